@@ -1,6 +1,7 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -8,7 +9,9 @@ use crate::{
     durable::MessageDurableConfig,
     endpoint::EndpointAddr,
     interest::{Interest, Subject},
-    message::{Message, MessageAckExpectKind, MessageHeader, MessageId, MessageTargetKind},
+    message::{
+        CompressionKind, Message, MessageAckExpectKind, MessageHeader, MessageId, MessageTargetKind,
+    },
     proposal::{EndpointInterest, SetState},
     topic::{TopicCode, WaitAckError, WaitAckSuccess},
     util::MaybeBase64Bytes,
@@ -164,9 +167,33 @@ pub struct EdgeMessageHeader {
     pub durability: Option<MessageDurableConfig>,
     pub subjects: Vec<Subject>,
     pub topic: TopicCode,
+    /// see [`MessageHeader::expire_at`]
+    #[serde(default)]
+    pub expire_at: Option<DateTime<Utc>>,
+    /// see [`MessageHeader::partition_key`]
+    #[serde(default)]
+    pub partition_key: Option<MaybeBase64Bytes>,
+    /// see [`MessageHeader::dedup_key`]
+    #[serde(default)]
+    pub dedup_key: Option<MaybeBase64Bytes>,
+    /// see [`MessageHeader::deliver_at`]
+    #[serde(default)]
+    pub deliver_at: Option<DateTime<Utc>>,
+    /// see [`MessageHeader::priority`]
+    #[serde(default)]
+    pub priority: u8,
+    /// see [`MessageHeader::attributes`]
+    #[serde(default)]
+    pub attributes: HashMap<String, MaybeBase64Bytes>,
 }
 
 impl EdgeMessageHeader {
+    /// the [`MessageHeader::MIRROR_CREDENTIAL`] attribute of a message forwarded by a topic mirror
+    pub fn mirror_credential(&self) -> Option<&Bytes> {
+        self.attributes
+            .get(MessageHeader::MIRROR_CREDENTIAL)
+            .map(|value| &value.0)
+    }
     pub fn into_message_header(self) -> (MessageHeader, TopicCode) {
         (
             MessageHeader {
@@ -175,6 +202,16 @@ impl EdgeMessageHeader {
                 target_kind: self.target_kind,
                 durability: self.durability,
                 subjects: self.subjects.into(),
+                sequence: None,
+                expire_at: self.expire_at,
+                dead_letter: None,
+                partition_key: self.partition_key,
+                dedup_key: self.dedup_key,
+                deliver_at: self.deliver_at,
+                priority: self.priority,
+                compression: CompressionKind::None,
+                checksum: None,
+                attributes: Arc::new(self.attributes),
             },
             self.topic,
         )
@@ -248,6 +285,12 @@ impl EdgeMessageBuilder {
                 durability: self.durability,
                 subjects: self.subjects,
                 topic: self.topic,
+                expire_at: None,
+                partition_key: None,
+                dedup_key: None,
+                deliver_at: None,
+                priority: 0,
+                attributes: HashMap::new(),
             },
             payload: MaybeBase64Bytes(self.payload),
         }
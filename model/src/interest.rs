@@ -61,6 +61,22 @@ impl Subject {
     pub const fn const_new(bytes: &'static str) -> Self {
         Self(Bytes::from_static(bytes.as_bytes()))
     }
+    /// Validate a subject, trimming leading and trailing `/`.
+    ///
+    /// Rejects subjects that aren't utf-8, are empty, have an empty segment or contain a wildcard
+    /// character, which only has a meaning in interests.
+    pub fn parse<B: Into<Bytes>>(bytes: B) -> Result<Self, MalformedSubject> {
+        let segments = parse_segments(bytes.into())?;
+        for segment in segments.split(|c| *c == b'/') {
+            if segment.contains(&b'*') || segment.contains(&b'>') {
+                return Err(MalformedSubject::new(
+                    &segments,
+                    MalformedReason::MisplacedWildcard,
+                ));
+            }
+        }
+        Ok(Self(segments))
+    }
     pub fn segments(&self) -> SubjectSegments<'_> {
         SubjectSegments {
             inner: self.0.as_ref(),
@@ -112,7 +128,9 @@ impl<'a> Iterator for SubjectSegments<'a> {
 
 /// # Interest
 /// ## Glob Match Interest
-/// (/)?(<path>|<*>|<**>)/*
+/// (/)?(<path>|<*>|<**>)/*(/<>>)?
+///
+/// `>` matches one or more remaining segments, and is only valid as the last segment.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[typeshare(serialized_as = "String")]
 pub struct Interest(Bytes);
@@ -137,10 +155,144 @@ impl<'de> Deserialize<'de> for Interest {
     }
 }
 
+impl FromStr for Interest {
+    type Err = InvalidInterest;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Interest::try_new(s.to_owned())
+    }
+}
+
+/// An interest with a `>` that isn't the last segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidInterest {
+    pub interest: Interest,
+}
+
+impl Display for InvalidInterest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = String::from_utf8_lossy(self.interest.0.as_ref());
+        write!(f, "`>` must be the last segment of interest {string}")
+    }
+}
+
+impl std::error::Error for InvalidInterest {}
+
+/// A subject or interest rejected by [`Subject::parse`] or [`Interest::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedSubject {
+    pub input: String,
+    pub reason: MalformedReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedReason {
+    NotUtf8,
+    Empty,
+    /// two `/` in a row, like `orders//created`
+    EmptySegment,
+    /// a wildcard in a subject, or mixed with other characters in an interest segment
+    MisplacedWildcard,
+    /// `>` before the last segment of an interest
+    MisplacedTail,
+}
+
+impl MalformedSubject {
+    fn new(input: &[u8], reason: MalformedReason) -> Self {
+        Self {
+            input: String::from_utf8_lossy(input).into_owned(),
+            reason,
+        }
+    }
+}
+
+impl Display for MalformedSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.reason {
+            MalformedReason::NotUtf8 => "is not utf-8",
+            MalformedReason::Empty => "is empty",
+            MalformedReason::EmptySegment => "has an empty segment",
+            MalformedReason::MisplacedWildcard => "has a wildcard where a literal is expected",
+            MalformedReason::MisplacedTail => "has `>` before its last segment",
+        };
+        write!(f, "`{}` {reason}", self.input)
+    }
+}
+
+impl std::error::Error for MalformedSubject {}
+
+/// checks shared by subjects and interests, returns `bytes` without leading and trailing `/`
+fn parse_segments(bytes: Bytes) -> Result<Bytes, MalformedSubject> {
+    if std::str::from_utf8(&bytes).is_err() {
+        return Err(MalformedSubject::new(&bytes, MalformedReason::NotUtf8));
+    }
+    let start = bytes.iter().position(|c| *c != b'/').unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|c| *c != b'/')
+        .map_or(start, |last| last + 1);
+    let trimmed = bytes.slice(start..end);
+    if trimmed.is_empty() {
+        return Err(MalformedSubject::new(&bytes, MalformedReason::Empty));
+    }
+    if trimmed
+        .split(|c| *c == b'/')
+        .any(|segment| segment.is_empty())
+    {
+        return Err(MalformedSubject::new(&bytes, MalformedReason::EmptySegment));
+    }
+    Ok(trimmed)
+}
+
 impl Interest {
     pub fn new<B: Into<Bytes>>(bytes: B) -> Self {
         Self(bytes.into())
     }
+    /// like [`Interest::new`], but rejects a `>` before the last segment
+    pub fn try_new<B: Into<Bytes>>(bytes: B) -> Result<Self, InvalidInterest> {
+        let interest = Self(bytes.into());
+        interest.validate()?;
+        Ok(interest)
+    }
+    /// Validate an interest like [`Subject::parse`], trimming leading and trailing `/`.
+    ///
+    /// Wildcards must be whole segments, `*`, `**` or `>`, and `>` only the last one.
+    pub fn parse<B: Into<Bytes>>(bytes: B) -> Result<Self, MalformedSubject> {
+        let segments = parse_segments(bytes.into())?;
+        let mut rest = segments.split(|c| *c == b'/').peekable();
+        while let Some(segment) = rest.next() {
+            match segment {
+                b">" if rest.peek().is_some() => {
+                    return Err(MalformedSubject::new(
+                        &segments,
+                        MalformedReason::MisplacedTail,
+                    ));
+                }
+                b"*" | b"**" | b">" => {}
+                literal if literal.contains(&b'*') || literal.contains(&b'>') => {
+                    return Err(MalformedSubject::new(
+                        &segments,
+                        MalformedReason::MisplacedWildcard,
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(Self(segments))
+    }
+    pub fn validate(&self) -> Result<(), InvalidInterest> {
+        let mut segments = self.as_segments().peekable();
+        while let Some(segment) = segments.next() {
+            if matches!(segment, InterestSegment::Tail) && segments.peek().is_some() {
+                return Err(InvalidInterest {
+                    interest: self.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
     pub fn as_segments(&self) -> impl Iterator<Item = InterestSegment<'_>> + Clone {
         self.0.split(|c| *c == b'/').filter_map(|seg| {
             if seg.is_empty() {
@@ -149,6 +301,7 @@ impl Interest {
                 Some(match seg.trim_ascii() {
                     b"*" => InterestSegment::Any,
                     b"**" => InterestSegment::RecursiveAny,
+                    b">" => InterestSegment::Tail,
                     specific => InterestSegment::Specific(specific),
                 })
             }
@@ -160,6 +313,8 @@ pub enum InterestSegment<'a> {
     Specific(&'a [u8]),
     Any,
     RecursiveAny,
+    /// `>`, one or more remaining segments
+    Tail,
 }
 impl InterestSegment<'_> {
     pub fn to_owned(&self) -> OwnedInterestSegment {
@@ -167,6 +322,7 @@ impl InterestSegment<'_> {
             InterestSegment::Specific(s) => OwnedInterestSegment::Specific(s.to_vec()),
             InterestSegment::Any => OwnedInterestSegment::Any,
             InterestSegment::RecursiveAny => OwnedInterestSegment::RecursiveAny,
+            InterestSegment::Tail => OwnedInterestSegment::Tail,
         }
     }
 }
@@ -174,4 +330,5 @@ pub enum OwnedInterestSegment {
     Specific(Vec<u8>),
     Any,
     RecursiveAny,
+    Tail,
 }
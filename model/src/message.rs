@@ -1,9 +1,13 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    durable::MessageDurableConfig, interest::Subject, topic::TopicCode, util::MaybeBase64Bytes,
+    durable::MessageDurableConfig,
+    interest::Subject,
+    topic::{TopicCode, WaitAckErrorException},
+    util::MaybeBase64Bytes,
 };
 use bytes::Bytes;
+use chrono::{DateTime, SubsecRound, Utc};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -43,12 +47,15 @@ pub enum MessageAckExpectKind {
     Sent = 0x00,
     Received = 0x01,
     Processed = 0x02,
+    /// fire and forget, the producer's handle resolves once the message is enqueued,
+    /// the cluster still tracks it like [`MessageAckExpectKind::Sent`]
+    None = 0x03,
 }
 
 impl From<MessageAckExpectKind> for MessageStatusKind {
     fn from(kind: MessageAckExpectKind) -> MessageStatusKind {
         match kind {
-            MessageAckExpectKind::Sent => MessageStatusKind::Sent,
+            MessageAckExpectKind::Sent | MessageAckExpectKind::None => MessageStatusKind::Sent,
             MessageAckExpectKind::Received => MessageStatusKind::Received,
             MessageAckExpectKind::Processed => MessageStatusKind::Processed,
         }
@@ -60,6 +67,7 @@ impl MessageAckExpectKind {
             0x00 => Some(MessageAckExpectKind::Sent),
             0x01 => Some(MessageAckExpectKind::Received),
             0x02 => Some(MessageAckExpectKind::Processed),
+            0x03 => Some(MessageAckExpectKind::None),
             _ => None,
         }
     }
@@ -71,6 +79,7 @@ impl std::fmt::Display for MessageAckExpectKind {
             MessageAckExpectKind::Sent => write!(f, "Sent"),
             MessageAckExpectKind::Received => write!(f, "Received"),
             MessageAckExpectKind::Processed => write!(f, "Processed"),
+            MessageAckExpectKind::None => write!(f, "None"),
         }
     }
 }
@@ -94,7 +103,7 @@ impl MessageStatusKind {
     }
     pub fn is_reached(&self, condition: MessageAckExpectKind) -> bool {
         match condition {
-            MessageAckExpectKind::Sent => {
+            MessageAckExpectKind::Sent | MessageAckExpectKind::None => {
                 *self == MessageStatusKind::Sent
                     || *self == MessageStatusKind::Received
                     || *self == MessageStatusKind::Processed
@@ -189,7 +198,7 @@ impl MessageId {
         thread_local! {
             static COUNTER: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
         }
-        let timestamp = crate::util::timestamp_sec();
+        let timestamp = crate::util::timestamp_ms();
         let counter = COUNTER.with(|c| {
             let v = c.get();
             c.set(v.wrapping_add(1));
@@ -202,8 +211,23 @@ impl MessageId {
         bytes[12..16].copy_from_slice(&counter.to_be_bytes());
         Self { bytes }
     }
+    /// Like [`MessageId::new_snowflake`], but the counter is given by the caller.
+    ///
+    /// Ids sharing an `origin` sort by the millisecond they were built in, then by `sequence`, so
+    /// ids built from one monotonic counter keep the order they were built in, even within a
+    /// millisecond.
+    pub fn new_sequenced(origin: u32, sequence: u32) -> Self {
+        let timestamp = crate::util::timestamp_ms();
+        let mut bytes = [0; 16];
+        bytes[0..4].copy_from_slice(&origin.to_be_bytes());
+        bytes[4..12].copy_from_slice(&timestamp.to_be_bytes());
+        bytes[12..16].copy_from_slice(&sequence.to_be_bytes());
+        Self { bytes }
+    }
 }
 
+/// Cloning a message is shallow, the payload and the header's subjects and attributes are
+/// reference counted, so fanning it out to many endpoints doesn't copy them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[typeshare]
 pub struct Message {
@@ -221,6 +245,31 @@ impl Message {
     pub fn subjects(&self) -> &[Subject] {
         &self.header.subjects
     }
+    /// value of the header attribute `key`, see [`MessageHeaderBuilder::attribute`]
+    pub fn attribute(&self, key: &str) -> Option<&Bytes> {
+        self.header.attributes.get(key).map(|value| &value.0)
+    }
+    /// the [`MessageHeader::CORRELATION_ID`] attribute of a request or its reply
+    pub fn correlation_id(&self) -> Option<&Bytes> {
+        self.attribute(MessageHeader::CORRELATION_ID)
+    }
+    /// subject a reply to this request goes to, from the [`MessageHeader::REPLY_TO`] attribute
+    pub fn reply_to(&self) -> Option<Subject> {
+        self.attribute(MessageHeader::REPLY_TO)
+            .map(|subject| Subject::new(subject.clone()))
+    }
+    /// topic this message was delivered from, from the [`MessageHeader::TOPIC`] attribute
+    pub fn topic(&self) -> Option<TopicCode> {
+        self.attribute(MessageHeader::TOPIC)
+            .map(|code| TopicCode::from(&code[..]))
+    }
+    /// which delivery of the message this is, from the [`MessageHeader::DELIVERY_ATTEMPT`]
+    /// attribute, `1` for the first one
+    pub fn delivery_attempt(&self) -> u32 {
+        self.attribute(MessageHeader::DELIVERY_ATTEMPT)
+            .and_then(|attempt| std::str::from_utf8(attempt).ok()?.parse().ok())
+            .unwrap_or(1)
+    }
 }
 
 impl Message {
@@ -239,9 +288,77 @@ pub struct MessageHeader {
     pub target_kind: MessageTargetKind,
     pub durability: Option<MessageDurableConfig>,
     pub subjects: Arc<[Subject]>,
+    /// per-topic sequence number, assigned by the cluster when the message is held
+    #[serde(default)]
+    pub sequence: Option<u64>,
+    /// drop the message if it's still waiting for acks at this time
+    #[serde(default)]
+    pub expire_at: Option<DateTime<Utc>>,
+    /// set on messages re-published into a dead-letter topic
+    #[serde(default)]
+    pub dead_letter: Option<DeadLetter>,
+    /// push messages with the same key go to the same endpoint while the endpoint set is unchanged
+    #[serde(default)]
+    pub partition_key: Option<MaybeBase64Bytes>,
+    /// idempotency key, a topic with a dedup window holds only the first message with a given key
+    #[serde(default)]
+    pub dedup_key: Option<MaybeBase64Bytes>,
+    /// held back by the topic and not delivered before this time
+    #[serde(default)]
+    pub deliver_at: Option<DateTime<Utc>>,
+    /// messages with a higher priority are dispatched first, and evicted last on overflow
+    #[serde(default)]
+    pub priority: u8,
+    /// compression of the payload as it's replicated and stored, endpoints always get it decompressed
+    #[serde(default)]
+    pub compression: CompressionKind,
+    /// payload checksum with the topic's checksum algorithm, over the payload as it's replicated
+    ///
+    /// Computed when the message is published, unless the producer already set it, and verified
+    /// when the cluster holds the message and when it loads it back from durable storage.
+    #[serde(default)]
+    pub checksum: Option<u32>,
+    /// metadata set by the producer, like a content type or a correlation id, left untouched by the cluster
+    ///
+    /// Shared by the clones of a message, `Arc::make_mut` copies it before a change.
+    #[serde(default)]
+    pub attributes: Arc<HashMap<String, MaybeBase64Bytes>>,
+}
+
+/// Compression applied to a message payload.
+///
+/// Requested by the publisher, the node holding the message only applies it to payloads above its
+/// threshold and resets it to `None` otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[typeshare]
+pub enum CompressionKind {
+    #[default]
+    None = 0,
+    Lz4 = 1,
+    Zstd = 2,
+}
+
+/// Where a dead-lettered message came from, and why it failed there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[typeshare]
+pub struct DeadLetter {
+    pub topic: TopicCode,
+    /// `None` when the message failed because endpoints acked it as failed
+    pub exception: Option<WaitAckErrorException>,
 }
 
 impl MessageHeader {
+    /// attribute pairing a request with its reply
+    pub const CORRELATION_ID: &'static str = "correlation-id";
+    /// attribute naming the subject a request expects its reply on
+    pub const REPLY_TO: &'static str = "reply-to";
+    /// attribute naming the topic a message was delivered from, set for endpoints in more than one topic
+    pub const TOPIC: &'static str = "topic";
+    /// attribute counting the deliveries of a redelivered message, set by the cluster
+    pub const DELIVERY_ATTEMPT: &'static str = "delivery-attempt";
+    /// attribute carrying a topic mirror's credential to the remote cluster's edge auth, removed
+    /// before the remote publishes the message
+    pub const MIRROR_CREDENTIAL: &'static str = "mirror-credential";
     #[inline(always)]
     pub(crate) fn ack(
         &self,
@@ -256,6 +373,13 @@ impl MessageHeader {
             topic_code,
         }
     }
+    /// bytes taken by attribute keys and values
+    pub fn attributes_size(&self) -> usize {
+        self.attributes
+            .iter()
+            .map(|(key, value)| key.len() + value.0.len())
+            .sum()
+    }
     #[inline(always)]
     pub fn ack_received(&self, topic_code: TopicCode, from: EndpointAddr) -> MessageAck {
         self.ack(topic_code, from, MessageStatusKind::Received)
@@ -268,6 +392,19 @@ impl MessageHeader {
     pub fn ack_failed(&self, topic_code: TopicCode, from: EndpointAddr) -> MessageAck {
         self.ack(topic_code, from, MessageStatusKind::Failed)
     }
+    /// give the message back, it's offered to another endpoint if there is one
+    #[inline(always)]
+    pub fn nack(&self, topic_code: TopicCode, from: EndpointAddr) -> MessageAck {
+        self.ack(topic_code, from, MessageStatusKind::Unsent)
+    }
+}
+
+/// `now + duration`, saturating at the latest representable time
+fn after_now(duration: std::time::Duration) -> DateTime<Utc> {
+    chrono::TimeDelta::from_std(duration)
+        .ok()
+        .and_then(|delta| Utc::now().checked_add_signed(delta))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
 }
 
 pub struct MessageHeaderBuilder {
@@ -275,6 +412,14 @@ pub struct MessageHeaderBuilder {
     target_kind: MessageTargetKind,
     durability: Option<MessageDurableConfig>,
     pub subjects: Vec<Subject>,
+    expire_at: Option<DateTime<Utc>>,
+    partition_key: Option<MaybeBase64Bytes>,
+    dedup_key: Option<MaybeBase64Bytes>,
+    deliver_at: Option<DateTime<Utc>>,
+    priority: u8,
+    compression: CompressionKind,
+    attributes: HashMap<String, MaybeBase64Bytes>,
+    message_id: Option<MessageId>,
 }
 
 impl MessageHeader {
@@ -291,8 +436,21 @@ impl MessageHeaderBuilder {
             target_kind: MessageTargetKind::default(),
             durability: None,
             subjects: subjects.into_iter().collect(),
+            expire_at: None,
+            partition_key: None,
+            dedup_key: None,
+            deliver_at: None,
+            priority: 0,
+            compression: CompressionKind::None,
+            attributes: HashMap::new(),
+            message_id: None,
         }
     }
+    /// use this id instead of a new snowflake one
+    pub fn message_id(mut self, message_id: MessageId) -> Self {
+        self.message_id = Some(message_id);
+        self
+    }
     #[inline(always)]
     pub fn ack_kind(mut self, ack_kind: MessageAckExpectKind) -> Self {
         self.ack_kind = ack_kind;
@@ -311,13 +469,66 @@ impl MessageHeaderBuilder {
         self.target_kind = MessageTargetKind::Push;
         self
     }
+    pub fn mode_available(mut self) -> Self {
+        self.target_kind = MessageTargetKind::Available;
+        self
+    }
+    /// kept at millisecond precision, like every time the topic compares it with
+    pub fn expire_at(mut self, expire_at: DateTime<Utc>) -> Self {
+        self.expire_at = Some(expire_at.trunc_subsecs(3));
+        self
+    }
+    pub fn partition_key(mut self, key: impl Into<Bytes>) -> Self {
+        self.partition_key = Some(MaybeBase64Bytes(key.into()));
+        self
+    }
+    pub fn dedup_key(mut self, key: impl Into<Bytes>) -> Self {
+        self.dedup_key = Some(MaybeBase64Bytes(key.into()));
+        self
+    }
+    /// kept at millisecond precision, like every time the topic compares it with
+    pub fn deliver_at(mut self, deliver_at: DateTime<Utc>) -> Self {
+        self.deliver_at = Some(deliver_at.trunc_subsecs(3));
+        self
+    }
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+    pub fn compression(mut self, compression: CompressionKind) -> Self {
+        self.compression = compression;
+        self
+    }
+    pub fn attribute(mut self, key: impl Into<String>, value: impl Into<Bytes>) -> Self {
+        self.attributes
+            .insert(key.into(), MaybeBase64Bytes(value.into()));
+        self
+    }
+    /// deliver the message `delay` after it's built
+    pub fn delay(self, delay: std::time::Duration) -> Self {
+        self.deliver_at(after_now(delay))
+    }
+    /// expire the message `ttl` after it's built
+    pub fn ttl(self, ttl: std::time::Duration) -> Self {
+        self.expire_at(after_now(ttl))
+    }
     pub fn build(self) -> MessageHeader {
         MessageHeader {
-            message_id: MessageId::new_snowflake(),
+            message_id: self.message_id.unwrap_or_else(MessageId::new_snowflake),
             ack_kind: self.ack_kind,
             target_kind: self.target_kind,
             durability: self.durability,
             subjects: self.subjects.into(),
+            sequence: None,
+            expire_at: self.expire_at,
+            dead_letter: None,
+            partition_key: self.partition_key,
+            dedup_key: self.dedup_key,
+            deliver_at: self.deliver_at,
+            priority: self.priority,
+            compression: self.compression,
+            checksum: None,
+            attributes: Arc::new(self.attributes),
         }
     }
 }
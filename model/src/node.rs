@@ -92,6 +92,20 @@ impl NodeId {
         bytes[1..16].copy_from_slice(&dg.as_slice()[0..15]);
         NodeId { bytes }
     }
+    /// Derive a stable id from a host identity, such as a hostname or a machine uuid.
+    ///
+    /// The identity is trimmed and lowercased, prefixed with `asteroid-mq/host/`, and hashed with
+    /// [`NodeId::sha256`], so the same identity always yields the same id and a restarted node
+    /// rejoins with its raft history.
+    ///
+    /// The id keeps 120 bits of the digest, accidental collisions are negligible even for large
+    /// clusters. The identity itself must be unique per node though: don't share one between
+    /// nodes, and don't use anything that changes across restarts, like a container id.
+    pub fn from_host_identity(identity: &str) -> Self {
+        const DOMAIN: &str = "asteroid-mq/host/";
+        let identity = identity.trim().to_lowercase();
+        Self::sha256(format!("{DOMAIN}{identity}").as_bytes())
+    }
     pub fn snowflake() -> NodeId {
         static INSTANCE_ID: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
         let dg = crate::util::executor_digest();
@@ -8,17 +8,79 @@ use crate::{endpoint::EndpointAddr, message::MessageStatusKind};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[typeshare(serialized_as = "String")]
-/// code are expect to be a valid utf8 string
+/// Name of a topic.
+///
+/// A valid code is a non-empty utf-8 string of at most [`TopicCode::MAX_LEN`] bytes without
+/// control characters. [`TopicCode::try_new`] checks it, [`TopicCode::new`] and
+/// [`TopicCode::const_new`] trust the caller and are meant for literals.
 pub struct TopicCode(Bytes);
 impl TopicCode {
+    /// longest code in bytes
+    pub const MAX_LEN: usize = 255;
     pub fn new<B: Into<String>>(code: B) -> Self {
         Self(Bytes::from(code.into()))
     }
     pub const fn const_new(code: &'static str) -> Self {
         Self(Bytes::from_static(code.as_bytes()))
     }
+    /// A code that is checked to be valid, see [`TopicCode`].
+    pub fn try_new<B: Into<String>>(code: B) -> Result<Self, TopicCodeError> {
+        let code = code.into();
+        let reason = if code.is_empty() {
+            Some(TopicCodeErrorReason::Empty)
+        } else if code.len() > Self::MAX_LEN {
+            Some(TopicCodeErrorReason::TooLong)
+        } else if code.chars().any(char::is_control) {
+            Some(TopicCodeErrorReason::NonPrintable)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => Err(TopicCodeError {
+                input: code,
+                reason,
+            }),
+            None => Ok(Self(Bytes::from(code))),
+        }
+    }
+}
+
+/// A topic code rejected by [`TopicCode::try_new`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicCodeError {
+    pub input: String,
+    pub reason: TopicCodeErrorReason,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicCodeErrorReason {
+    Empty,
+    /// longer than [`TopicCode::MAX_LEN`] bytes
+    TooLong,
+    /// contains a control character, like `\0` or `\n`
+    NonPrintable,
+}
+
+impl std::fmt::Display for TopicCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reason {
+            TopicCodeErrorReason::Empty => write!(f, "topic code is empty"),
+            TopicCodeErrorReason::TooLong => {
+                write!(f, "topic code is longer than {} bytes", TopicCode::MAX_LEN)
+            }
+            TopicCodeErrorReason::NonPrintable => {
+                write!(
+                    f,
+                    "topic code {:?} contains a control character",
+                    self.input
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopicCodeError {}
+
 impl From<&'_ str> for TopicCode {
     fn from(val: &'_ str) -> Self {
         TopicCode::new(val)
@@ -78,8 +140,22 @@ pub struct WaitAckError {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[typeshare]
+/// At least one endpoint took the message, `status` is the outcome of every endpoint it was sent
+/// to, failed ones included.
 pub struct WaitAckSuccess {
     pub status: HashMap<EndpointAddr, MessageStatusKind>,
+    /// Endpoints that took the message, `None` when it resolved without being counted.
+    ///
+    /// `Some(0)` tells the message reached no endpoint at all.
+    #[serde(default)]
+    pub delivered: Option<u32>,
+}
+
+impl WaitAckSuccess {
+    /// no endpoint failed or was unreachable
+    pub fn is_fully_acked(&self) -> bool {
+        !self.status.values().any(MessageStatusKind::is_failed)
+    }
 }
 
 impl WaitAckError {
@@ -92,12 +168,31 @@ impl WaitAckError {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[typeshare]
 pub enum WaitAckErrorException {
     MessageDropped = 0,
     Overflow = 1,
     NoAvailableTarget = 2,
+    PayloadCorrupted = 3,
+    /// not resolved before the message's `expire_at`
+    Expired = 4,
+    /// rejected by the topic's rate limit
+    RateLimited = 5,
+    /// the publisher stopped waiting, see `WaitAckHandle::with_timeout`
+    Timeout = 6,
+    /// the publisher's node shut down before the message resolved, see `Node::shutdown`
+    Shutdown = 7,
+    /// carries more subjects than the topic's `max_subjects_per_message`
+    TooManySubjects = 8,
+    /// the topic was unloaded or deleted before the message resolved
+    TopicNotFound = 9,
+    /// larger than the topic's or the publishing node's `max_message_bytes`
+    MessageTooLarge = 10,
+    /// nacked or redelivered `max_delivery_attempts` times, it's quarantined
+    PoisonMessage = 11,
+    /// rejected by the topic's payload validator on the publishing node
+    ValidationFailed = 12,
 }
 
 pub enum AckWaitErrorKind {
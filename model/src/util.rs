@@ -114,3 +114,10 @@ pub fn timestamp_sec() -> u64 {
         .expect("time never goes backward")
         .as_secs()
 }
+
+pub fn timestamp_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("time never goes backward")
+        .as_millis() as u64
+}
@@ -62,6 +62,17 @@ impl ClientReceivedMessage {
             .ack_processed(self.topic_code.clone(), self.ep_addr);
         node.send_single_ack(ack).await
     }
+    /// give the message back, it's offered to another endpoint if there is one
+    pub async fn nack(&self) -> Result<(), ClientNodeError> {
+        let Some(node) = self.node.upgrade() else {
+            return Err(ClientNodeError::disconnected());
+        };
+        let ack = self
+            .message
+            .header
+            .nack(self.topic_code.clone(), self.ep_addr);
+        node.send_single_ack(ack).await
+    }
     pub async fn ack_received(&self) -> Result<(), ClientNodeError> {
         let Some(node) = self.node.upgrade() else {
             return Err(ClientNodeError::disconnected());
@@ -50,6 +50,10 @@ export class Endpoint {
     public async ackFailed(message: Message) {
         await this.node.ackMessage(this, message.header.message_id, MessageStatusKind.Failed);
     }
+    /** give the message back, it's offered to another endpoint if there is one */
+    public async nack(message: Message) {
+        await this.node.ackMessage(this, message.header.message_id, MessageStatusKind.Unsent);
+    }
     public async *messages() {
         while (this.node.isAlive()) {
             if (this.messageQueue.length > 0) {
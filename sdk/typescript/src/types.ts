@@ -7,7 +7,9 @@ export type EndpointAddr = string;
 /**
  * # Interest
  * ## Glob Match Interest
- * (/)?(<path>|<*>|<**>)/*
+ * (/)?(<path>|<*>|<**>)/*(/<>>)?
+ * 
+ * `>` matches one or more remaining segments, and is only valid as the last segment.
  */
 export type Interest = string;
 
@@ -48,6 +50,11 @@ export enum MessageAckExpectKind {
 	Sent = "Sent",
 	Received = "Received",
 	Processed = "Processed",
+	/**
+	 * fire and forget, the producer's handle resolves once the message is enqueued,
+	 * the cluster still tracks it like `MessageAckExpectKind.Sent`
+	 */
+	None = "None",
 }
 
 export enum MessageTargetKind {
@@ -68,6 +75,18 @@ export interface EdgeMessageHeader {
 	durability?: MessageDurableConfig;
 	subjects: Subject[];
 	topic: TopicCode;
+	/** see [`MessageHeader::expire_at`] */
+	expire_at?: Date;
+	/** see [`MessageHeader::partition_key`] */
+	partition_key?: string;
+	/** see [`MessageHeader::dedup_key`] */
+	dedup_key?: string;
+	/** see [`MessageHeader::deliver_at`] */
+	deliver_at?: Date;
+	/** see [`MessageHeader::priority`] */
+	priority?: number;
+	/** see [`MessageHeader::attributes`] */
+	attributes?: Record<string, string>;
 }
 
 export interface EdgeMessage {
@@ -109,12 +128,56 @@ export interface EndpointInterest {
 	interests: Interest[];
 }
 
+/** Where a dead-lettered message came from, and why it failed there. */
+export interface DeadLetter {
+	topic: TopicCode;
+	/** `None` when the message failed because endpoints acked it as failed */
+	exception?: WaitAckErrorException;
+}
+
+/**
+ * Compression applied to a message payload.
+ * 
+ * Requested by the publisher, the node holding the message only applies it to payloads above its
+ * threshold and resets it to `None` otherwise.
+ */
+export enum CompressionKind {
+	None = "None",
+	Lz4 = "Lz4",
+	Zstd = "Zstd",
+}
+
 export interface MessageHeader {
 	message_id: MessageId;
 	ack_kind: MessageAckExpectKind;
 	target_kind: MessageTargetKind;
 	durability?: MessageDurableConfig;
 	subjects: Subject[];
+	/** per-topic sequence number, assigned by the cluster when the message is held */
+	sequence?: bigint;
+	/** drop the message if it's still waiting for acks at this time */
+	expire_at?: Date;
+	/** set on messages re-published into a dead-letter topic */
+	dead_letter?: DeadLetter;
+	/** push messages with the same key go to the same endpoint while the endpoint set is unchanged */
+	partition_key?: string;
+	/** idempotency key, a topic with a dedup window holds only the first message with a given key */
+	dedup_key?: string;
+	/** held back by the topic and not delivered before this time */
+	deliver_at?: Date;
+	/** messages with a higher priority are dispatched first, and evicted last on overflow */
+	priority?: number;
+	/** compression of the payload as it's replicated and stored, endpoints always get it decompressed */
+	compression?: CompressionKind;
+	/**
+	 * payload checksum with the topic's checksum algorithm, over the payload as it's replicated
+	 * 
+	 * Computed when the message is published, unless the producer already set it, and verified
+	 * when the cluster holds the message and when it loads it back from durable storage.
+	 */
+	checksum?: number;
+	/** metadata set by the producer, like a content type or a correlation id, left untouched by the cluster */
+	attributes?: Record<string, string>;
 }
 
 export interface Message {
@@ -146,6 +209,25 @@ export enum WaitAckErrorException {
 	MessageDropped = "MessageDropped",
 	Overflow = "Overflow",
 	NoAvailableTarget = "NoAvailableTarget",
+	PayloadCorrupted = "PayloadCorrupted",
+	/** not resolved before the message's `expire_at` */
+	Expired = "Expired",
+	/** rejected by the topic's rate limit */
+	RateLimited = "RateLimited",
+	/** the publisher stopped waiting, see `WaitAckHandle::with_timeout` */
+	Timeout = "Timeout",
+	/** the publisher's node shut down before the message resolved, see `Node::shutdown` */
+	Shutdown = "Shutdown",
+	/** carries more subjects than the topic's `max_subjects_per_message` */
+	TooManySubjects = "TooManySubjects",
+	/** the topic was unloaded or deleted before the message resolved */
+	TopicNotFound = "TopicNotFound",
+	/** larger than the topic's or the publishing node's `max_message_bytes` */
+	MessageTooLarge = "MessageTooLarge",
+	/** nacked or redelivered `max_delivery_attempts` times, it's quarantined */
+	PoisonMessage = "PoisonMessage",
+	/** rejected by the topic's payload validator on the publishing node */
+	ValidationFailed = "ValidationFailed",
 }
 
 export interface WaitAckError {
@@ -153,8 +235,18 @@ export interface WaitAckError {
 	exception?: WaitAckErrorException;
 }
 
+/**
+ * At least one endpoint took the message, `status` is the outcome of every endpoint it was sent
+ * to, failed ones included.
+ */
 export interface WaitAckSuccess {
 	status: Record<EndpointAddr, MessageStatusKind>;
+	/**
+	 * Endpoints that took the message, `None` when it resolved without being counted.
+	 * 
+	 * `Some(0)` tells the message reached no endpoint at all.
+	 */
+	delivered?: number;
 }
 
 export type EdgePayload = 
@@ -0,0 +1,55 @@
+//! Time to fan one message out to many local endpoints, for a few `dispatch_concurrency` values.
+//!
+//! Run with `cargo bench --bench dispatch_fanout`.
+use std::time::{Duration, Instant};
+
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+const ENDPOINTS: usize = 2000;
+const ROUNDS: u32 = 20;
+
+async fn fanout(port: u16, concurrency: usize) -> Result<Duration, Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("dispatch-fanout-bench");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: format!("127.0.0.1:{port}").parse()?,
+        dispatch_concurrency: concurrency,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoints = topic
+        .create_endpoints(vec![vec![Interest::new("bench/*")]; ENDPOINTS])
+        .await?;
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("bench/fanout")])
+                .mode_online()
+                .build(),
+            "payload",
+        );
+        let start = Instant::now();
+        let result = topic.send_message(message).await?.await;
+        total += start.elapsed();
+        assert!(result.is_ok());
+        for endpoint in &endpoints {
+            endpoint.try_recv();
+        }
+    }
+    node.shutdown(Duration::from_secs(1)).await?;
+    Ok(total / ROUNDS)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    for (port, concurrency) in [(19901, 1), (19902, 8), (19903, 32)] {
+        let elapsed = fanout(port, concurrency).await?;
+        println!("{ENDPOINTS} endpoints, concurrency {concurrency:>2}: {elapsed:?} per message");
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Insert and find times of both `InterestMapKind`s over 100k interests, mostly literal ones.
+//!
+//! Run with `cargo bench --bench interest_map`.
+use std::time::{Duration, Instant};
+
+use asteroid_mq::protocol::interest::{Interest, InterestMap, InterestMapKind, Subject};
+
+const INTERESTS: usize = 100_000;
+const WILDCARDS: usize = 100;
+
+fn measure(kind: InterestMapKind) -> (Duration, Duration) {
+    let mut map = InterestMap::with_kind(kind);
+    let start = Instant::now();
+    for index in 0..INTERESTS {
+        map.insert(Interest::new(format!("device/{index}/telemetry")), index);
+    }
+    for index in 0..WILDCARDS {
+        map.insert(
+            Interest::new(format!("device/*/alert-{index}")),
+            INTERESTS + index,
+        );
+    }
+    let insert = start.elapsed();
+
+    let subjects = (0..INTERESTS)
+        .map(|index| Subject::new(format!("device/{index}/telemetry")))
+        .collect::<Vec<_>>();
+    let start = Instant::now();
+    let mut found = 0;
+    for subject in &subjects {
+        found += map.find(subject).len();
+    }
+    let find = start.elapsed();
+    assert_eq!(found, INTERESTS);
+    (insert, find)
+}
+
+fn main() {
+    for kind in [InterestMapKind::Trie, InterestMapKind::Flat] {
+        let (insert, find) = measure(kind);
+        println!(
+            "{kind:?}: {INTERESTS} literal and {WILDCARDS} wildcard interests inserted in {insert:?}, \
+             {INTERESTS} subjects found in {find:?}"
+        );
+    }
+}
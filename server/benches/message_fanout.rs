@@ -0,0 +1,90 @@
+//! Cost of fanning one message with header attributes out to 1000 local endpoints.
+//!
+//! The clone part compares the shared attributes with copying them for every endpoint, which is
+//! what every delivery did before they were reference counted.
+//!
+//! Run with `cargo bench --bench message_fanout`.
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode,
+};
+use asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider;
+
+const ENDPOINTS: usize = 1000;
+const ATTRIBUTES: usize = 16;
+const ROUNDS: u32 = 20;
+
+fn message() -> Message {
+    let header = (0..ATTRIBUTES).fold(
+        MessageHeader::builder([Subject::new("bench/fanout")]).mode_online(),
+        |builder, index| builder.attribute(format!("attribute-{index}"), format!("value-{index}")),
+    );
+    Message::new(header.build(), "payload")
+}
+
+fn clone_fanout(copy_attributes: bool) -> Duration {
+    let message = message();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let copies = (0..ENDPOINTS)
+            .map(|_| {
+                let mut copy = message.clone();
+                if copy_attributes {
+                    copy.header.attributes = Arc::new(HashMap::clone(&copy.header.attributes));
+                }
+                copy
+            })
+            .collect::<Vec<_>>();
+        std::hint::black_box(copies);
+    }
+    start.elapsed() / ROUNDS
+}
+
+async fn dispatch_fanout() -> Result<Duration, Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("message-fanout-bench");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19904".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoints = topic
+        .create_endpoints(vec![vec![Interest::new("bench/*")]; ENDPOINTS])
+        .await?;
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let result = topic.send_message(message()).await?.await;
+        total += start.elapsed();
+        assert!(result.is_ok());
+        for endpoint in &endpoints {
+            endpoint.try_recv();
+        }
+    }
+    node.shutdown(Duration::from_secs(1)).await?;
+    Ok(total / ROUNDS)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!(
+        "clone for {ENDPOINTS} endpoints, shared attributes: {:?}",
+        clone_fanout(false)
+    );
+    println!(
+        "clone for {ENDPOINTS} endpoints, copied attributes: {:?}",
+        clone_fanout(true)
+    );
+    println!(
+        "dispatch to {ENDPOINTS} endpoints: {:?} per message",
+        dispatch_fanout().await?
+    );
+    Ok(())
+}
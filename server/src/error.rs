@@ -28,6 +28,10 @@ impl Display for Error {
 impl std::error::Error for Error {}
 
 impl Error {
+    /// what went wrong, for callers that handle errors programmatically
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
     pub const fn contextual<T: Into<ErrorKind>>(
         context: impl Into<Cow<'static, str>>,
     ) -> impl FnOnce(T) -> Self {
@@ -70,12 +74,12 @@ impl Error {
 macro_rules! error_kind {
     (
         pub enum $ErrorKind: ident {
-            $($Kind: ident$(: $InnerType: ty)?),*
+            $($(#[$meta: meta])* $Kind: ident$(: $InnerType: ty)?),*
         }
     ) => {
         #[derive(Debug)]
         pub enum ErrorKind {
-            $($Kind$(($InnerType))?,)*
+            $($(#[$meta])* $Kind$(($InnerType))?,)*
         }
         $(
             $(
@@ -91,12 +95,41 @@ macro_rules! error_kind {
 error_kind! {
     pub enum ErrorKind {
         Durability: DurableError,
+        /// the node, endpoint or peer needed isn't reachable
         Offline,
+        /// the topic isn't loaded and can't be reloaded from the durable service
+        TopicNotFound,
         TopicAlreadyExists,
-        NotLeader,
+        MessageNotFound,
+        Unauthorized,
+        HeaderTooLarge,
+        Timeout,
+        /// proposed to a node that isn't the leader, or no leader is elected
+        NotLeader: Box<openraft::error::ForwardToLeader<NodeId, BasicNode>>,
         Io: std::io::Error,
         Ack: WaitAckError,
+        /// a typed payload doesn't fit the topic's codec or the type asked for
+        Codec: crate::protocol::node::edge::codec::CodecError,
         Custom: Box<dyn std::error::Error + Send + Sync>,
-        RaftClient: openraft::error::RaftError<NodeId, openraft::error::ClientWriteError<NodeId, BasicNode>>
+        RaftClient: Box<openraft::error::RaftError<NodeId, openraft::error::ClientWriteError<NodeId, BasicNode>>>
+    }
+}
+
+impl From<openraft::error::RaftError<NodeId, openraft::error::ClientWriteError<NodeId, BasicNode>>>
+    for ErrorKind
+{
+    fn from(
+        error: openraft::error::RaftError<
+            NodeId,
+            openraft::error::ClientWriteError<NodeId, BasicNode>,
+        >,
+    ) -> Self {
+        ErrorKind::RaftClient(Box::new(error))
+    }
+}
+
+impl From<openraft::error::ForwardToLeader<NodeId, BasicNode>> for ErrorKind {
+    fn from(forward: openraft::error::ForwardToLeader<NodeId, BasicNode>) -> Self {
+        ErrorKind::NotLeader(Box::new(forward))
     }
 }
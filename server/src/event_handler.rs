@@ -1,5 +1,5 @@
 pub mod json;
-use std::{collections::HashMap, future::Future, marker::PhantomData, pin::Pin};
+use std::{collections::HashMap, future::Future, marker::PhantomData, pin::Pin, time::Duration};
 
 use bytes::Bytes;
 
@@ -13,8 +13,79 @@ type InnerEventHandler =
 
 pub struct HandleEventLoop {
     ep: LocalEndpoint,
+    config: ServeConfig,
     handlers: HashMap<Subject, Box<InnerEventHandler>>,
 }
+
+/// How the event loop retries a handler that returned a [`Transient`] error.
+///
+/// Attempts are counted from the [`delivery attempt`](Message::delivery_attempt) of the message,
+/// so a message redelivered by the topic continues the count of the deliveries before it. A
+/// message that still fails after the last attempt is acked as failed, which sends it to the
+/// topic's dead letter topic, if it has one. Without retries (the default) a failed message is
+/// only acked as received, like before retries existed.
+#[derive(Debug, Clone, Copy)]
+pub struct ServeConfig {
+    /// total attempts for one message, including the first one
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled for every retry after it
+    pub backoff: Duration,
+}
+
+impl Default for ServeConfig {
+    /// no retry
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl ServeConfig {
+    fn backoff_before(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(2)))
+    }
+    fn retries(&self) -> bool {
+        self.max_attempts > 1
+    }
+}
+
+/// Marks a handler error as transient, only errors that are, or are caused by, a `Transient` are
+/// retried, see [`ServeConfig`].
+#[derive(Debug)]
+pub struct Transient(Box<dyn std::error::Error + Send + Sync>);
+
+impl Transient {
+    pub fn new(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self(error.into())
+    }
+    /// whether `error` or one of its sources is a `Transient`
+    pub fn is_cause_of(error: &(dyn std::error::Error + 'static)) -> bool {
+        let mut cause = Some(error);
+        while let Some(error) = cause {
+            if error.is::<Transient>() {
+                return true;
+            }
+            cause = error.source();
+        }
+        false
+    }
+}
+
+impl std::fmt::Display for Transient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transient: {}", self.0)
+    }
+}
+
+impl std::error::Error for Transient {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
 pub trait EventCodec: Sized {
     fn from_bytes(bytes: Bytes) -> Option<Self>;
     fn to_bytes(&self) -> Bytes;
@@ -29,7 +100,7 @@ pub trait Event: EventAttribute + EventCodec + Send {}
 
 impl<E> Event for E where E: EventAttribute + EventCodec + Send {}
 pub trait Handler<A>: Clone + Sync + Send + 'static {
-    type Error: std::error::Error + Send;
+    type Error: std::error::Error + Send + 'static;
     type Event: Event;
     fn handle(self, event: Self::Event) -> impl Future<Output = Result<(), Self::Error>> + Send;
 }
@@ -39,7 +110,7 @@ pub trait Handler<A>: Clone + Sync + Send + 'static {
 pub struct PlainFnAdapter<M, E>(PhantomData<*const fn(M) -> E>);
 impl<M, F, Fut, E> Handler<PlainFnAdapter<M, E>> for F
 where
-    E: std::error::Error + Send,
+    E: std::error::Error + Send + 'static,
     M: Event,
     F: Fn(M) -> Fut + Clone + Send + Sync + 'static,
     Fut: Future<Output = Result<(), E>> + Send,
@@ -53,8 +124,12 @@ where
 
 impl HandleEventLoop {
     pub fn new(ep: LocalEndpoint) -> Self {
+        Self::new_with_config(ep, ServeConfig::default())
+    }
+    pub fn new_with_config(ep: LocalEndpoint, config: ServeConfig) -> Self {
         Self {
             ep,
+            config,
             handlers: Default::default(),
         }
     }
@@ -69,6 +144,7 @@ impl HandleEventLoop {
     {
         let subject = H::Event::SUBJECT;
         let ep = self.ep.clone();
+        let config = self.config;
         let inner_handler = Box::new(move |message: Message| {
             let handler = handler.clone();
             let ep = ep.clone();
@@ -78,20 +154,35 @@ impl HandleEventLoop {
                 {
                     ep.ack_received(&message.header).await?;
                 }
-                let event = match H::Event::from_bytes(message.payload.into_inner()) {
-                    Some(msg) => msg,
-                    None => {
-                        if H::Event::EXPECT_ACK_KIND == MessageAckExpectKind::Processed {
-                            ep.ack_failed(&message.header).await?;
+                let mut attempt = message.delivery_attempt();
+                let payload = message.payload.into_inner();
+                let handle_result = loop {
+                    let event = match H::Event::from_bytes(payload.clone()) {
+                        Some(msg) => msg,
+                        None => {
+                            if H::Event::EXPECT_ACK_KIND == MessageAckExpectKind::Processed {
+                                ep.ack_failed(&message.header).await?;
+                            }
+                            return Ok(());
+                        }
+                    };
+                    match handler.clone().handle(event).await {
+                        Err(e) if attempt < config.max_attempts && Transient::is_cause_of(&e) => {
+                            attempt += 1;
+                            tracing::debug!(?e, attempt, "retry event handler");
+                            tokio::time::sleep(config.backoff_before(attempt)).await;
                         }
-                        return Ok(());
+                        result => break result,
                     }
                 };
-                let handle_result = handler.handle(event).await;
                 if H::Event::EXPECT_ACK_KIND == MessageAckExpectKind::Processed {
                     if let Err(e) = handle_result {
-                        tracing::warn!("failed to handle event: {:?}", e);
-                        ep.ack_received(&message.header).await?;
+                        tracing::warn!(attempt, "failed to handle event: {:?}", e);
+                        if config.retries() {
+                            ep.ack_failed(&message.header).await?;
+                        } else {
+                            ep.ack_received(&message.header).await?;
+                        }
                     } else {
                         ep.ack_processed(&message.header).await?;
                     }
@@ -127,6 +218,9 @@ impl LocalEndpoint {
     pub fn create_event_loop(&self) -> HandleEventLoop {
         HandleEventLoop::new(self.clone())
     }
+    pub fn create_event_loop_with_config(&self, config: ServeConfig) -> HandleEventLoop {
+        HandleEventLoop::new_with_config(self.clone(), config)
+    }
 }
 
 impl Topic {
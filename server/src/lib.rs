@@ -1,5 +1,8 @@
 pub mod error;
 pub mod event_handler;
+pub mod lifecycle;
+#[cfg(feature = "metrics")]
+mod metrics;
 pub mod protocol;
 pub(crate) mod util;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
@@ -11,17 +14,35 @@ use serde::{Deserialize, Serialize};
 pub type Result<T> = std::result::Result<T, Error>;
 pub mod prelude {
     pub use crate::error::Error;
-    pub use crate::event_handler::{Event, EventAttribute, EventCodec, HandleEventLoop, Handler};
-    pub use crate::protocol::endpoint::{EndpointAddr, LocalEndpoint, LocalEndpointRef};
-    pub use crate::protocol::interest::{Interest, Subject};
+    pub use crate::event_handler::{
+        Event, EventAttribute, EventCodec, HandleEventLoop, Handler, ServeConfig, Transient,
+    };
+    pub use crate::lifecycle::LifecycleEvent;
+    pub use crate::protocol::endpoint::{
+        EndpointAddr, GroupId, LocalEndpoint, LocalEndpointRef, ReplayPolicy, SeekTarget,
+    };
+    pub use crate::protocol::interest::{Interest, InterestMapKind, Subject};
     pub use crate::protocol::message::*;
-    pub use crate::protocol::node::raft::state_machine::topic::config::*;
-    pub use crate::protocol::node::{Node, NodeConfig, NodeId};
+    pub use crate::protocol::node::clock::{Clock, ClockService, ManualClock, SystemClock};
+    pub use crate::protocol::node::edge::codec::CodecKind;
+    pub use crate::protocol::node::raft::state_machine::topic::{
+        config::*, MessageHeaderView, RoutingExplanation, SubjectRouting, TopicSnapshot, TopicStats,
+    };
+    #[cfg(feature = "tls")]
+    pub use crate::protocol::node::raft::tls::TlsConfig;
+    pub use crate::protocol::node::{health::NodeHealth, Node, NodeConfig, NodeId};
+    #[cfg(feature = "rocksdb")]
+    pub use crate::protocol::topic::durable_message::RocksDbDurability;
+    #[cfg(feature = "json-schema")]
+    pub use crate::protocol::topic::validator::JsonSchemaValidator;
     pub use crate::protocol::topic::{
         durable_message::{
-            Durable, DurableError, DurableMessage, DurableService, MessageDurableConfig,
+            Durable, DurableError, DurableMessage, DurableService, MemoryDurability,
+            MessageDurableConfig,
         },
-        Topic, TopicCode,
+        mirror::{MirrorConfig, MirrorStats},
+        validator::{PayloadValidator, PayloadValidatorService},
+        EndpointOptions, Topic, TopicCode, TopicCodeError,
     };
     pub use crate::util::MaybeBase64Bytes;
 }
@@ -33,6 +54,43 @@ impl TimestampSec {
     pub fn now() -> Self {
         Self(crate::util::timestamp_sec())
     }
+    pub fn as_datetime(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(self.0 as i64, 0).unwrap_or_default()
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for TimestampSec {
+    fn from(time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(time.timestamp().max(0) as u64)
+    }
+}
+
+/// Millisecond precision timestamp, for ordering and deadlines where seconds are too coarse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TimestampMs(u64);
+
+impl TimestampMs {
+    pub fn now() -> Self {
+        Self(crate::util::timestamp_ms())
+    }
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+    pub fn as_datetime(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp_millis(self.0 as i64).unwrap_or_default()
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for TimestampMs {
+    fn from(time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(time.timestamp_millis().max(0) as u64)
+    }
+}
+
+impl From<TimestampSec> for TimestampMs {
+    fn from(time: TimestampSec) -> Self {
+        Self(time.0.saturating_mul(1000))
+    }
 }
 
 pub const DEFAULT_TCP_PORT: u16 = 9559;
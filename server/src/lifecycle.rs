@@ -0,0 +1,110 @@
+//! Message lifecycle events, see [`Node::subscribe_events`](crate::prelude::Node::subscribe_events).
+//!
+//! Like metrics, events follow what this node's state machine applies, so every node of a
+//! cluster sees the whole cluster's messages. `Delivered` is the exception, only the node that
+//! pushed the message to its endpoint reports it.
+use futures_util::Stream;
+use tokio::sync::broadcast;
+
+use crate::protocol::{
+    endpoint::EndpointAddr,
+    message::{MessageId, MessageStatusKind},
+    node::raft::state_machine::topic::wait_ack::WaitAckErrorException,
+    topic::TopicCode,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// held by the topic, or delayed until its `deliver_at`
+    Published {
+        topic: TopicCode,
+        message_id: MessageId,
+    },
+    /// pushed to the mailbox of an endpoint
+    Delivered {
+        topic: TopicCode,
+        message_id: MessageId,
+        endpoint: EndpointAddr,
+    },
+    /// an endpoint reported it received, processed or failed the message
+    Acked {
+        topic: TopicCode,
+        message_id: MessageId,
+        endpoint: EndpointAddr,
+        status: MessageStatusKind,
+    },
+    /// the message failed without being handled
+    Dropped {
+        topic: TopicCode,
+        message_id: MessageId,
+        reason: WaitAckErrorException,
+    },
+    EndpointOnline {
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+    },
+    EndpointOffline {
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+    },
+}
+
+impl LifecycleEvent {
+    pub fn topic(&self) -> &TopicCode {
+        match self {
+            LifecycleEvent::Published { topic, .. }
+            | LifecycleEvent::Delivered { topic, .. }
+            | LifecycleEvent::Acked { topic, .. }
+            | LifecycleEvent::Dropped { topic, .. }
+            | LifecycleEvent::EndpointOnline { topic, .. }
+            | LifecycleEvent::EndpointOffline { topic, .. } => topic,
+        }
+    }
+    /// `None` for endpoint events
+    pub fn message_id(&self) -> Option<MessageId> {
+        match self {
+            LifecycleEvent::Published { message_id, .. }
+            | LifecycleEvent::Delivered { message_id, .. }
+            | LifecycleEvent::Acked { message_id, .. }
+            | LifecycleEvent::Dropped { message_id, .. } => Some(*message_id),
+            LifecycleEvent::EndpointOnline { .. } | LifecycleEvent::EndpointOffline { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct LifecycleEvents {
+    sender: broadcast::Sender<LifecycleEvent>,
+}
+
+impl Default for LifecycleEvents {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(Self::CAPACITY).0,
+        }
+    }
+}
+
+impl LifecycleEvents {
+    /// events a subscriber may fall behind by before it misses the oldest ones
+    pub(crate) const CAPACITY: usize = 1024;
+    /// never waits, with no subscriber the event is discarded
+    pub(crate) fn emit(&self, event: LifecycleEvent) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(event);
+        }
+    }
+    pub(crate) fn subscribe(&self) -> impl Stream<Item = LifecycleEvent> + Send + 'static {
+        futures_util::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "lifecycle event subscriber lagged behind");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
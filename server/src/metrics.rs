@@ -0,0 +1,170 @@
+//! Node metrics in the Prometheus text format, see [`Node::metrics_text`](crate::prelude::Node::metrics_text).
+//!
+//! Counters count what this node's state machine applied, so every node of a cluster reports
+//! the whole cluster's traffic.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::protocol::{
+    node::raft::state_machine::topic::{wait_ack::WaitAckErrorException, TopicData},
+    topic::{mirror::MirrorStats, TopicCode},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    published: AtomicU64,
+    acked: AtomicU64,
+    dropped: Mutex<BTreeMap<&'static str, u64>>,
+    topics: Mutex<BTreeMap<String, TopicGauges>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TopicGauges {
+    queue_len: usize,
+    endpoints_online: usize,
+}
+
+fn drop_reason(exception: WaitAckErrorException) -> &'static str {
+    match exception {
+        WaitAckErrorException::MessageDropped => "message_dropped",
+        WaitAckErrorException::Overflow => "overflow",
+        WaitAckErrorException::NoAvailableTarget => "no_available_target",
+        WaitAckErrorException::PayloadCorrupted => "payload_corrupted",
+        WaitAckErrorException::Expired => "expired",
+        WaitAckErrorException::RateLimited => "rate_limited",
+        WaitAckErrorException::Timeout => "timeout",
+        WaitAckErrorException::Shutdown => "shutdown",
+        WaitAckErrorException::TooManySubjects => "too_many_subjects",
+        WaitAckErrorException::TopicNotFound => "topic_not_found",
+        WaitAckErrorException::MessageTooLarge => "message_too_large",
+        WaitAckErrorException::PoisonMessage => "poison_message",
+        WaitAckErrorException::ValidationFailed => "validation_failed",
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Metrics {
+    pub(crate) fn message_published(&self) {
+        self.published.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn message_acked(&self) {
+        self.acked.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn message_dropped(&self, exception: WaitAckErrorException) {
+        *self
+            .dropped
+            .lock()
+            .unwrap()
+            .entry(drop_reason(exception))
+            .or_default() += 1;
+    }
+    /// replace the topic gauges, unloaded topics disappear
+    pub(crate) fn update_topics(&self, topics: &HashMap<TopicCode, TopicData>) {
+        let gauges = topics
+            .iter()
+            .map(|(code, topic)| {
+                let gauges = TopicGauges {
+                    queue_len: topic.queue.len(),
+                    endpoints_online: topic.ep_routing_table.values().map(|eps| eps.len()).sum(),
+                };
+                (code.to_string(), gauges)
+            })
+            .collect();
+        *self.topics.lock().unwrap() = gauges;
+    }
+    /// `mirrors` are read at scrape time, they are local to this node's topics
+    pub(crate) fn render(&self, mirrors: &BTreeMap<String, MirrorStats>) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_messages_published_total Messages held by topics.\n\
+             # TYPE asteroid_messages_published_total counter\n\
+             asteroid_messages_published_total {}",
+            self.published.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_messages_acked_total Received and processed acks from endpoints.\n\
+             # TYPE asteroid_messages_acked_total counter\n\
+             asteroid_messages_acked_total {}",
+            self.acked.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_messages_dropped_total Messages resolved with an exception.\n\
+             # TYPE asteroid_messages_dropped_total counter"
+        );
+        for (reason, count) in self.dropped.lock().unwrap().iter() {
+            let _ = writeln!(
+                text,
+                "asteroid_messages_dropped_total{{reason=\"{reason}\"}} {count}"
+            );
+        }
+        let topics = self.topics.lock().unwrap();
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_topic_queue_len Messages waiting for acks.\n\
+             # TYPE asteroid_topic_queue_len gauge"
+        );
+        for (topic, gauges) in topics.iter() {
+            let _ = writeln!(
+                text,
+                "asteroid_topic_queue_len{{topic=\"{}\"}} {}",
+                escape_label(topic),
+                gauges.queue_len
+            );
+        }
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_endpoints_online Endpoints online in a topic.\n\
+             # TYPE asteroid_endpoints_online gauge"
+        );
+        for (topic, gauges) in topics.iter() {
+            let _ = writeln!(
+                text,
+                "asteroid_endpoints_online{{topic=\"{}\"}} {}",
+                escape_label(topic),
+                gauges.endpoints_online
+            );
+        }
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_mirror_buffered Messages waiting to be forwarded to the remote cluster.\n\
+             # TYPE asteroid_mirror_buffered gauge"
+        );
+        for (topic, stats) in mirrors.iter() {
+            let _ = writeln!(
+                text,
+                "asteroid_mirror_buffered{{topic=\"{}\"}} {}",
+                escape_label(topic),
+                stats.buffered
+            );
+        }
+        let _ = writeln!(
+            text,
+            "# HELP asteroid_mirror_lag_seconds Age of the oldest message not yet forwarded.\n\
+             # TYPE asteroid_mirror_lag_seconds gauge"
+        );
+        for (topic, stats) in mirrors.iter() {
+            let _ = writeln!(
+                text,
+                "asteroid_mirror_lag_seconds{{topic=\"{}\"}} {}",
+                escape_label(topic),
+                stats.lag.num_milliseconds() as f64 / 1000.0
+            );
+        }
+        text
+    }
+}
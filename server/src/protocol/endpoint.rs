@@ -1,7 +1,11 @@
 use super::{
     message::*,
     node::{
-        raft::proposal::{EndpointInterest, EndpointOffline, Proposal},
+        authorizer::AuthorizeAction,
+        edge::codec::decode_value,
+        raft::proposal::{
+            EndpointHeartbeat, EndpointInterest, EndpointOffline, EndpointOnline, Proposal,
+        },
         Node, NodeRef,
     },
     topic::{Topic, TopicCode, TopicRef},
@@ -11,7 +15,92 @@ use std::{
     sync::{Arc, Weak},
 };
 
-use crate::protocol::interest::Interest;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::NodeId, protocol::interest::Interest, TimestampMs};
+
+/// What the cluster currently knows about one endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpInfo {
+    /// node the endpoint is attached to
+    pub host: NodeId,
+    pub interests: Vec<Interest>,
+    /// last time the endpoint came online, changed interests or acked a message
+    pub latest_active: DateTime<Utc>,
+    /// share of push messages it gets relative to other endpoints
+    pub weight: u32,
+    pub group: Option<GroupId>,
+    /// attribute filter the endpoint was created with, see [`MessageFilter`]
+    pub filter: Option<MessageFilter>,
+    /// max messages sent to it and not acked yet, see [`EndpointOptions::prefetch`](crate::prelude::EndpointOptions::prefetch)
+    pub prefetch: Option<u32>,
+}
+
+impl EpInfo {
+    pub const DEFAULT_WEIGHT: u32 = 1;
+}
+
+/// Consumer group of an endpoint.
+///
+/// An online message goes to one endpoint of every group, picked like a push message, while
+/// endpoints without a group all get it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GroupId(pub String);
+
+impl GroupId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<&str> for GroupId {
+    fn from(id: &str) -> Self {
+        Self(id.to_owned())
+    }
+}
+
+/// Durable messages a new endpoint gets before the live ones, see [`EndpointOptions::replay`](crate::prelude::EndpointOptions::replay).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplayPolicy {
+    /// only messages still held by the topic
+    #[default]
+    None,
+    /// every message kept by the durable service
+    FromBeginning,
+    /// messages kept by the durable service, saved at or after this time
+    Since(TimestampMs),
+}
+
+impl ReplayPolicy {
+    pub(crate) fn since(&self) -> Option<DateTime<Utc>> {
+        match self {
+            ReplayPolicy::None => None,
+            ReplayPolicy::FromBeginning => Some(DateTime::<Utc>::MIN_UTC),
+            ReplayPolicy::Since(time) => Some(time.as_datetime()),
+        }
+    }
+}
+
+/// Where [`LocalEndpoint::seek`] starts redelivering from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekTarget {
+    /// the first message the topic still retains or keeps durable
+    Earliest,
+    /// only messages published from now on, nothing is redelivered
+    Latest,
+    /// the message with this sequence number, see [`MessageHeader::sequence`](crate::prelude::MessageHeader)
+    Sequence(u64),
+    /// messages kept by the durable service, saved at or after this time
+    Time(TimestampMs),
+}
+
+impl From<u64> for SeekTarget {
+    fn from(sequence: u64) -> Self {
+        SeekTarget::Sequence(sequence)
+    }
+}
 #[derive(Clone, Debug)]
 pub struct LocalEndpoint {
     pub(crate) inner: Arc<LocalEndpointInner>,
@@ -28,17 +117,31 @@ impl Deref for LocalEndpoint {
 pub struct LocalEndpointInner {
     pub(crate) attached_node: NodeRef,
     pub(crate) attached_topic: TopicRef,
-    pub(crate) topic_code: TopicCode,
+    /// topics besides `attached_topic`, see [`Node::create_multi_topic_endpoint`]
+    pub(crate) other_topics: Vec<TopicRef>,
     pub(crate) interest: Vec<Interest>,
     pub(crate) address: EndpointAddr,
     pub(crate) mail_box: flume::Receiver<Message>,
     pub(crate) mail_addr: flume::Sender<Message>,
+    /// cancelled when the endpoint is deleted or its topic is unloaded
+    pub(crate) closed: tokio_util::sync::CancellationToken,
+    /// ack every message as processed once it's in the mailbox
+    pub(crate) auto_ack: bool,
+    /// see [`EndpointOptions::endpoint_key`](crate::prelude::EndpointOptions::endpoint_key)
+    pub(crate) key: Option<Bytes>,
+    /// see [`EndpointOptions::exactly_once`](crate::prelude::EndpointOptions::exactly_once)
+    pub(crate) exactly_once: bool,
 }
 
 impl Drop for LocalEndpointInner {
     fn drop(&mut self) {
+        if self.key.is_some() {
+            // kept for the consumer to reclaim when it comes back
+            return;
+        }
         let endpoint = self.address;
-        if let Some(topic) = self.attached_topic.upgrade() {
+        let topics = std::iter::once(&self.attached_topic).chain(&self.other_topics);
+        for topic in topics.filter_map(TopicRef::upgrade) {
             tokio::spawn(async move {
                 let node = topic.node();
                 let result = node
@@ -46,6 +149,7 @@ impl Drop for LocalEndpointInner {
                         topic_code: topic.code().clone(),
                         endpoint,
                         host: node.id(),
+                        now: node.now(),
                     }))
                     .await;
                 if let Err(err) = result {
@@ -56,6 +160,24 @@ impl Drop for LocalEndpointInner {
     }
 }
 
+/// A message didn't fit in the bounded mailbox of an endpoint, see [`EndpointOptions::mailbox_capacity`](crate::prelude::EndpointOptions::mailbox_capacity).
+#[derive(Debug, Clone, Copy)]
+pub struct MailboxFull {
+    pub message_id: MessageId,
+}
+
+impl std::fmt::Display for MailboxFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mailbox is full, message {} is not delivered",
+            self.message_id
+        )
+    }
+}
+
+impl std::error::Error for MailboxFull {}
+
 #[derive(Clone, Debug)]
 pub struct LocalEndpointRef {
     pub inner: Weak<LocalEndpointInner>,
@@ -76,74 +198,317 @@ impl LocalEndpoint {
     pub fn topic(&self) -> Option<Topic> {
         self.attached_topic.upgrade()
     }
+    #[inline]
+    pub fn address(&self) -> EndpointAddr {
+        self.address
+    }
     pub fn reference(&self) -> LocalEndpointRef {
         LocalEndpointRef {
             inner: Arc::downgrade(&self.inner),
         }
     }
+    /// every topic this endpoint is in that is still loaded
+    fn topics(&self) -> impl Iterator<Item = Topic> + '_ {
+        std::iter::once(&self.attached_topic)
+            .chain(&self.other_topics)
+            .filter_map(TopicRef::upgrade)
+    }
+    /// [`LocalEndpoint::topics`], failing with `TopicNotFound` if none is loaded
+    fn loaded_topics(&self) -> Result<Vec<Topic>, crate::Error> {
+        let topics = self.topics().collect::<Vec<_>>();
+        if topics.is_empty() {
+            return Err(crate::Error::new(
+                "topic not found",
+                crate::error::ErrorKind::TopicNotFound,
+            ));
+        }
+        Ok(topics)
+    }
+    /// topic a message with this header was delivered from
+    fn topic_of(&self, header: &MessageHeader) -> Option<Topic> {
+        match header.attributes.get(MessageHeader::TOPIC) {
+            Some(code) if !self.other_topics.is_empty() => {
+                let code = TopicCode::from(&code.0[..]);
+                self.topics().find(|topic| *topic.code() == code)
+            }
+            _ => self.topic(),
+        }
+    }
 
     pub async fn ack_processed(&self, header: &MessageHeader) -> Result<(), crate::Error> {
-        if let Some(topic) = self.topic() {
+        if let Some(topic) = self.topic_of(header) {
+            if self.exactly_once {
+                // the ack still goes out, this message is just not guarded against redelivery
+                if let Err(err) = topic.record_ack(self.address, header.message_id).await {
+                    tracing::warn!(?err, endpoint = ?self.address, "failed to record ack");
+                }
+            }
             topic
-                .single_ack(header.ack_processed(self.topic_code.clone(), self.address))
+                .single_ack(header.ack_processed(topic.code().clone(), self.address))
                 .await
         } else {
             Err(crate::Error::new(
                 "topic not found",
-                crate::error::ErrorKind::Offline,
+                crate::error::ErrorKind::TopicNotFound,
             ))
         }
     }
     pub async fn ack_received(&self, header: &MessageHeader) -> Result<(), crate::Error> {
-        if let Some(topic) = self.topic() {
+        if let Some(topic) = self.topic_of(header) {
             topic
-                .single_ack(header.ack_received(self.topic_code.clone(), self.address))
+                .single_ack(header.ack_received(topic.code().clone(), self.address))
                 .await
         } else {
             Err(crate::Error::new(
                 "topic not found",
-                crate::error::ErrorKind::Offline,
+                crate::error::ErrorKind::TopicNotFound,
             ))
         }
     }
     pub async fn ack_failed(&self, header: &MessageHeader) -> Result<(), crate::Error> {
-        if let Some(topic) = self.topic() {
+        if let Some(topic) = self.topic_of(header) {
+            topic
+                .single_ack(header.ack_failed(topic.code().clone(), self.address))
+                .await
+        } else {
+            Err(crate::Error::new(
+                "topic not found",
+                crate::error::ErrorKind::TopicNotFound,
+            ))
+        }
+    }
+    /// Give the message back without handling it.
+    ///
+    /// A push or available message is offered to another interested endpoint that hasn't
+    /// nacked it yet. When there is none, this counts as [`LocalEndpoint::ack_failed`].
+    /// A nack after the message reached its expected ack kind is ignored.
+    pub async fn nack(&self, header: &MessageHeader) -> Result<(), crate::Error> {
+        if let Some(topic) = self.topic_of(header) {
             topic
-                .single_ack(header.ack_failed(self.topic_code.clone(), self.address))
+                .single_ack(header.nack(topic.code().clone(), self.address))
                 .await
         } else {
             Err(crate::Error::new(
                 "topic not found",
-                crate::error::ErrorKind::Offline,
+                crate::error::ErrorKind::TopicNotFound,
             ))
         }
     }
-    pub(crate) fn push_message(&self, message: Message) {
-        self.mail_addr
-            .send(message)
-            .expect("ep self hold the receiver");
+    /// Put `message` in the mailbox without waiting, fails if a bounded mailbox is full.
+    pub(crate) fn push_message(&self, message: Message) -> Result<(), MailboxFull> {
+        let header = self.auto_ack.then(|| message.header.clone());
+        match self.mail_addr.try_send(message) {
+            Ok(()) => {
+                if let Some(header) = header {
+                    // called while applying the dispatch, the ack is a proposal of its own
+                    let endpoint = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = endpoint.ack_processed(&header).await {
+                            tracing::warn!(?err, endpoint = ?endpoint.address, "auto ack failed");
+                        }
+                    });
+                }
+                Ok(())
+            }
+            Err(flume::TrySendError::Full(message)) => Err(MailboxFull {
+                message_id: message.id(),
+            }),
+            Err(flume::TrySendError::Disconnected(_)) => unreachable!("ep self hold the receiver"),
+        }
     }
+    /// The next message, `None` once the endpoint is deleted or its topic is unloaded and the
+    /// messages already delivered are taken.
     pub async fn next_message(&self) -> Option<Message> {
-        self.mail_box.recv_async().await.ok()
+        Self::recv(&self.mail_box, &self.closed).await
     }
-    pub async fn update_interest(&self, interests: Vec<Interest>) -> Result<(), crate::Error> {
-        if let Some(topic) = self.topic() {
+    async fn recv(
+        mail_box: &flume::Receiver<Message>,
+        closed: &tokio_util::sync::CancellationToken,
+    ) -> Option<Message> {
+        tokio::select! {
+            biased;
+            message = mail_box.recv_async() => message.ok(),
+            _ = closed.cancelled() => mail_box.try_recv().ok(),
+        }
+    }
+    /// [`LocalEndpoint::next_message`] with the payload deserialized by the topic's
+    /// `payload_codec`, see [`Topic::send_typed`].
+    ///
+    /// A payload that doesn't decode as `T` is an error of kind `Codec`, its header is still
+    /// returned so the message can be acked or nacked.
+    pub async fn recv_typed<T: serde::de::DeserializeOwned>(
+        &self,
+    ) -> Option<(MessageHeader, Result<T, crate::Error>)> {
+        let message = self.next_message().await?;
+        let value = match self.topic_of(&message.header) {
+            Some(topic) => decode_value(topic.payload_codec, &message.payload.0)
+                .map_err(crate::Error::contextual("decode typed payload")),
+            None => Err(crate::Error::new(
+                "topic not found",
+                crate::error::ErrorKind::TopicNotFound,
+            )),
+        };
+        Some((message.header, value))
+    }
+    /// a message already delivered, without waiting
+    pub fn try_recv(&self) -> Option<Message> {
+        self.mail_box.try_recv().ok()
+    }
+    /// [`LocalEndpoint::next_message`], giving up after `timeout`
+    pub async fn recv_timeout(&self, timeout: std::time::Duration) -> Option<Message> {
+        tokio::time::timeout(timeout, self.next_message())
+            .await
+            .ok()
+            .flatten()
+    }
+    /// Messages of this endpoint as a stream, it ends like [`LocalEndpoint::next_message`].
+    ///
+    /// Streams and `next_message` share the mailbox, every message goes to only one of them.
+    pub fn stream(&self) -> impl futures_util::Stream<Item = Message> + Send + 'static {
+        futures_util::stream::unfold(
+            (self.mail_box.clone(), self.closed.clone()),
+            |(mail_box, closed)| async move {
+                let message = Self::recv(&mail_box, &closed).await?;
+                Some((message, (mail_box, closed)))
+            },
+        )
+    }
+    pub(crate) fn close(&self) {
+        self.closed.cancel();
+    }
+    /// Redeliver the messages of the topic from `target` on, a plain `u64` seeks to that sequence number.
+    ///
+    /// Messages are looked up in the topic's `retention` buffer and the durable service, and delivered once
+    /// each in sequence order. Seeking by time only sees the durable service. Only messages this endpoint
+    /// is currently interested in are delivered, positions neither of them keeps anymore are silently skipped.
+    pub async fn seek(&self, target: impl Into<SeekTarget>) -> Result<(), crate::Error> {
+        let Some(topic) = self.topic() else {
+            return Err(crate::Error::new(
+                "topic not found",
+                crate::error::ErrorKind::TopicNotFound,
+            ));
+        };
+        topic.seek_for(self, target.into()).await
+    }
+    /// Report the endpoint alive, so it isn't taken offline after
+    /// [`NodeConfig::endpoint_ttl`](crate::prelude::NodeConfig::endpoint_ttl) without activity.
+    ///
+    /// Acks and interest updates count as activity too, only an endpoint that may stay quiet longer
+    /// than the ttl needs heartbeats.
+    pub async fn heartbeat(&self) -> Result<(), crate::Error> {
+        let topics = self.loaded_topics()?;
+        for topic in topics {
+            let node = topic.node();
+            node.propose(Proposal::EpHeartbeat(EndpointHeartbeat {
+                topic_code: topic.code().clone(),
+                endpoint: self.address,
+                now: node.now(),
+            }))
+            .await?;
+        }
+        Ok(())
+    }
+    /// Replace the interests of this endpoint.
+    ///
+    /// Held durable messages it now matches are delivered to it, like to an endpoint coming online.
+    /// Held messages it no longer matches stop waiting for it, unless they were already sent to it.
+    pub async fn update_interests(&self, interests: Vec<Interest>) -> Result<(), crate::Error> {
+        for interest in &interests {
+            interest
+                .validate()
+                .map_err(crate::Error::contextual_custom("update interest"))?;
+        }
+        let topics = self.loaded_topics()?;
+        for topic in &topics {
+            topic.check_interests(&interests).await?;
+        }
+        for topic in topics {
             let node = topic.node();
             node.propose(Proposal::EpInterest(EndpointInterest {
                 topic_code: topic.code().clone(),
                 endpoint: self.address,
-                interests,
+                interests: interests.clone(),
+                now: node.now(),
             }))
-            .await
-        } else {
-            Err(crate::Error::new(
-                "topic not found",
-                crate::error::ErrorKind::Offline,
-            ))
+            .await?;
         }
+        Ok(())
     }
 }
 
 pub use asteroid_mq_model::EndpointAddr;
 
-impl Node {}
+impl Node {
+    /// One endpoint in each topic of `codes`, sharing a mailbox, an address and `interests`.
+    ///
+    /// Each topic sees it as an endpoint created with [`Topic::create_endpoint`]. Its messages carry
+    /// the code of the topic they were delivered from, see [`Message::topic`], and acks go back to
+    /// that topic. It goes offline in every topic once dropped, and it's closed as soon as any of
+    /// them is unloaded.
+    pub async fn create_multi_topic_endpoint(
+        &self,
+        codes: Vec<TopicCode>,
+        interests: impl IntoIterator<Item = Interest>,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        let interests = interests.into_iter().collect::<Vec<_>>();
+        for interest in &interests {
+            interest
+                .validate()
+                .map_err(crate::Error::contextual_custom(
+                    "create multi-topic endpoint",
+                ))?;
+        }
+        let mut topics = Vec::with_capacity(codes.len());
+        for code in &codes {
+            self.authorize(code, AuthorizeAction::CreateEndpoint, None)
+                .await?;
+            let topic = self.get_or_reload_topic(code).await?;
+            topic.check_interests(&interests).await?;
+            topics.push(topic);
+        }
+        let Some((first, others)) = topics.split_first() else {
+            return Err(crate::Error::new(
+                "no topic to create the endpoint in",
+                crate::error::ErrorKind::TopicNotFound,
+            ));
+        };
+        let (mail_addr, mail_box) = flume::unbounded();
+        let ep = LocalEndpoint {
+            inner: Arc::new(LocalEndpointInner {
+                attached_node: self.node_ref(),
+                attached_topic: first.reference(),
+                other_topics: others.iter().map(Topic::reference).collect(),
+                interest: interests,
+                address: EndpointAddr::new_snowflake(),
+                mail_box,
+                mail_addr,
+                closed: Default::default(),
+                auto_ack: false,
+                key: None,
+                exactly_once: false,
+            }),
+        };
+        for topic in &topics {
+            topic
+                .local_endpoints
+                .write()
+                .unwrap()
+                .insert(ep.address, ep.reference());
+            self.propose(Proposal::EpOnline(EndpointOnline {
+                topic_code: topic.code().clone(),
+                endpoint: ep.address,
+                interests: ep.interest.clone(),
+                host: self.id(),
+                weight: EpInfo::DEFAULT_WEIGHT,
+                group: None,
+                filter: None,
+                prefetch: None,
+                key: None,
+                now: self.now(),
+            }))
+            .await
+            .inspect_err(|_| topic.forget_local_endpoints([ep.address]))?;
+        }
+        Ok(ep)
+    }
+}
@@ -1,36 +1,56 @@
 //! # Interest
 //! ## Match Interest
-//! (/)?(<path>|<*>|<**>)/*
+//! (/)?(<path>|<*>|<**>)/*(/<>>)?
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     hash::Hash,
 };
 
 pub use asteroid_mq_model::{
-    Interest, InterestSegment, OwnedInterestSegment, Subject, SubjectSegments,
+    Interest, InterestSegment, InvalidInterest, MalformedReason, MalformedSubject,
+    OwnedInterestSegment, Subject, SubjectSegments,
 };
 use serde::{Deserialize, Serialize};
+
+/// How an [`InterestMap`] stores its interests, both find the same values for every subject.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InterestMapKind {
+    /// A tree of segments, finding costs the same however many interests there are.
+    #[default]
+    Trie,
+    /// Literal interests in a hash map and wildcard ones checked one by one, for topics with
+    /// many literal interests and few wildcards.
+    Flat,
+}
+
 #[derive(Debug, Clone)]
 pub struct InterestMap<T> {
-    root: InterestRadixTreeNode<T>,
+    index: InterestIndex<T>,
     pub(crate) raw: HashMap<T, HashSet<Interest>>,
 }
 
 impl<T> Default for InterestMap<T> {
     fn default() -> Self {
         Self {
-            root: Default::default(),
+            index: InterestIndex::Trie(Default::default()),
             raw: HashMap::default(),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+enum InterestIndex<T> {
+    Trie(InterestRadixTreeNode<T>),
+    Flat(InterestFlatIndex<T>),
+}
+
 #[derive(Debug, Clone)]
 pub struct InterestRadixTreeNode<T> {
     value: HashSet<T>,
     children: BTreeMap<Vec<u8>, InterestRadixTreeNode<T>>,
     any_child: Option<Box<InterestRadixTreeNode<T>>>,
     recursive_any_child: Option<Box<InterestRadixTreeNode<T>>>,
+    tail_child: Option<Box<InterestRadixTreeNode<T>>>,
 }
 
 impl<T> Default for InterestRadixTreeNode<T> {
@@ -40,6 +60,7 @@ impl<T> Default for InterestRadixTreeNode<T> {
             children: BTreeMap::new(),
             any_child: None,
             recursive_any_child: None,
+            tail_child: None,
         }
     }
 }
@@ -73,6 +94,10 @@ where
                     .get_or_insert_with(Default::default);
                 child.insert_recursive(path, value)
             }
+            Some(InterestSegment::Tail) => {
+                let child = self.tail_child.get_or_insert_with(Default::default);
+                child.insert_recursive(path, value)
+            }
             None => {
                 self.value.insert(value);
             }
@@ -99,6 +124,11 @@ where
                     child.delete_recursive(path, value)
                 }
             }
+            Some(InterestSegment::Tail) => {
+                if let Some(ref mut child) = self.tail_child {
+                    child.delete_recursive(path, value)
+                }
+            }
             None => {
                 self.value.remove(value);
             }
@@ -110,6 +140,10 @@ where
         collector: &mut HashSet<&'a T>,
     ) {
         if let Some(seg) = path.next() {
+            // at least one segment left, whatever its depth
+            if let Some(ref tail) = self.tail_child {
+                collector.extend(&tail.value);
+            }
             if let Some(ref rac) = self.recursive_any_child {
                 let mut rest_path = path.clone();
                 collector.extend(&rac.value);
@@ -129,47 +163,279 @@ where
             collector.extend(&self.value)
         }
     }
+    /// `path` is the interest leading to this node, wildcards written as `*` and `**`
+    fn collect_by_prefix(
+        &self,
+        path: &mut Vec<u8>,
+        prefix: &[u8],
+        collector: &mut Vec<(Interest, HashSet<T>)>,
+    ) where
+        T: Clone,
+    {
+        let common = path.len().min(prefix.len());
+        if path[..common] != prefix[..common] {
+            return;
+        }
+        if !self.value.is_empty() && path.len() >= prefix.len() {
+            collector.push((Interest::new(path.clone()), self.value.clone()));
+        }
+        let children = self
+            .children
+            .iter()
+            .map(|(seg, child)| (seg.as_slice(), child))
+            .chain(
+                self.any_child
+                    .as_deref()
+                    .map(|child| (b"*".as_slice(), child)),
+            )
+            .chain(
+                self.recursive_any_child
+                    .as_deref()
+                    .map(|child| (b"**".as_slice(), child)),
+            )
+            .chain(
+                self.tail_child
+                    .as_deref()
+                    .map(|child| (b">".as_slice(), child)),
+            );
+        for (seg, child) in children {
+            let len = path.len();
+            if len > 0 {
+                path.push(b'/');
+            }
+            path.extend_from_slice(seg);
+            child.collect_by_prefix(path, prefix, collector);
+            path.truncate(len);
+        }
+    }
+}
+/// Interests without wildcards by their segments, each behind a `/`, so an interest without
+/// segments and one with an empty segment don't collide.
+fn literal_key(segments: impl Iterator<Item = impl AsRef<[u8]>>) -> Vec<u8> {
+    let mut key = Vec::new();
+    for seg in segments {
+        key.push(b'/');
+        key.extend_from_slice(seg.as_ref());
+    }
+    key
 }
+
+/// The backing structure of [`InterestMapKind::Flat`].
+#[derive(Debug, Clone)]
+struct InterestFlatIndex<T> {
+    literal: HashMap<Vec<u8>, HashSet<T>>,
+    wildcard: HashMap<Interest, HashSet<T>>,
+}
+
+impl<T> Default for InterestFlatIndex<T> {
+    fn default() -> Self {
+        Self {
+            literal: HashMap::new(),
+            wildcard: HashMap::new(),
+        }
+    }
+}
+
+impl<T> InterestFlatIndex<T>
+where
+    T: Hash + Eq + PartialEq,
+{
+    /// the interest as a `literal` key, `None` if it has a wildcard
+    fn literal_key_of(interest: &Interest) -> Option<Vec<u8>> {
+        let segments = interest
+            .as_segments()
+            .map(|seg| match seg {
+                InterestSegment::Specific(seg) => Some(seg),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(literal_key(segments.into_iter()))
+    }
+    /// the interest written as the trie spells it, wildcards as `*`, `**` and `>`
+    fn canonical(interest: &Interest) -> Interest {
+        let segments = interest
+            .as_segments()
+            .map(|seg| match seg {
+                InterestSegment::Specific(seg) => seg,
+                InterestSegment::Any => b"*".as_slice(),
+                InterestSegment::RecursiveAny => b"**".as_slice(),
+                InterestSegment::Tail => b">".as_slice(),
+            })
+            .collect::<Vec<_>>();
+        Interest::new(segments.join(&b'/'))
+    }
+    fn insert(&mut self, interest: &Interest, value: T) {
+        match Self::literal_key_of(interest) {
+            Some(key) => self.literal.entry(key).or_default().insert(value),
+            None => self
+                .wildcard
+                .entry(Self::canonical(interest))
+                .or_default()
+                .insert(value),
+        };
+    }
+    fn delete(&mut self, interest: &Interest, value: &T) {
+        match Self::literal_key_of(interest) {
+            Some(key) => {
+                if let Some(values) = self.literal.get_mut(&key) {
+                    values.remove(value);
+                    if values.is_empty() {
+                        self.literal.remove(&key);
+                    }
+                }
+            }
+            None => {
+                let interest = Self::canonical(interest);
+                if let Some(values) = self.wildcard.get_mut(&interest) {
+                    values.remove(value);
+                    if values.is_empty() {
+                        self.wildcard.remove(&interest);
+                    }
+                }
+            }
+        }
+    }
+    fn find_all<'a>(&'a self, subject: &Subject, collector: &mut HashSet<&'a T>) {
+        let segments = subject.segments().collect::<Vec<_>>();
+        if let Some(values) = self.literal.get(&literal_key(segments.iter())) {
+            collector.extend(values);
+        }
+        for (interest, values) in &self.wildcard {
+            let interest = interest.as_segments().collect::<Vec<_>>();
+            if wildcard_matches(&interest, &segments) {
+                collector.extend(values);
+            }
+        }
+    }
+    fn collect_by_prefix(&self, prefix: &[u8], collector: &mut Vec<(Interest, HashSet<T>)>)
+    where
+        T: Clone,
+    {
+        let literal = self.literal.iter().map(|(key, values)| {
+            (
+                Interest::new(key.get(1..).unwrap_or_default().to_vec()),
+                values,
+            )
+        });
+        for (interest, values) in literal.chain(
+            self.wildcard
+                .iter()
+                .map(|(interest, values)| (interest.clone(), values)),
+        ) {
+            if interest.as_bytes().starts_with(prefix) {
+                collector.push((interest, values.clone()));
+            }
+        }
+    }
+}
+
+/// Whether the trie would find `interest` for `subject`, both given by their segments.
+///
+/// `**` takes at least one segment and may only be followed by a literal segment, and `>` takes
+/// at least one and ends the interest, as they are stored in the trie.
+fn wildcard_matches(interest: &[InterestSegment], subject: &[&[u8]]) -> bool {
+    let Some((first, rest)) = interest.split_first() else {
+        return subject.is_empty();
+    };
+    let Some((seg, subject_rest)) = subject.split_first() else {
+        return false;
+    };
+    match first {
+        InterestSegment::Specific(specific) => {
+            specific == seg && wildcard_matches(rest, subject_rest)
+        }
+        InterestSegment::Any => wildcard_matches(rest, subject_rest),
+        InterestSegment::Tail => rest.is_empty(),
+        InterestSegment::RecursiveAny => match rest.split_first() {
+            None => true,
+            Some((InterestSegment::Specific(next), after)) => {
+                subject_rest.iter().enumerate().any(|(index, seg)| {
+                    seg == next && wildcard_matches(after, &subject_rest[index + 1..])
+                })
+            }
+            Some(_) => false,
+        },
+    }
+}
+
 impl<T> InterestMap<T>
 where
     T: Hash + Eq + PartialEq + Clone,
 {
     pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_kind(kind: InterestMapKind) -> Self {
+        let index = match kind {
+            InterestMapKind::Trie => InterestIndex::Trie(Default::default()),
+            InterestMapKind::Flat => InterestIndex::Flat(Default::default()),
+        };
         Self {
-            root: InterestRadixTreeNode::default(),
+            index,
             raw: HashMap::default(),
         }
     }
+    pub fn kind(&self) -> InterestMapKind {
+        match self.index {
+            InterestIndex::Trie(_) => InterestMapKind::Trie,
+            InterestIndex::Flat(_) => InterestMapKind::Flat,
+        }
+    }
+    /// Move the interests to a `kind` backing structure, nothing changes if it's already one.
+    pub fn set_kind(&mut self, kind: InterestMapKind) {
+        if self.kind() != kind {
+            let mut map = Self::with_kind(kind);
+            for (value, interests) in std::mem::take(&mut self.raw) {
+                map.insert_all(value, interests);
+            }
+            *self = map;
+        }
+    }
     pub fn from_raw(raw: HashMap<T, HashSet<Interest>>) -> Self {
         let mut map = Self::new();
         for (value, interests) in raw {
-            for interest in &interests {
-                map.root
-                    .insert_recursive(interest.as_segments(), value.clone());
-            }
-            map.raw.insert(value, interests);
+            map.insert_all(value, interests);
         }
         map
     }
+    fn insert_all(&mut self, value: T, interests: HashSet<Interest>) {
+        for interest in &interests {
+            self.index_insert(interest, value.clone());
+        }
+        self.raw.insert(value, interests);
+    }
+    fn index_insert(&mut self, interest: &Interest, value: T) {
+        match &mut self.index {
+            InterestIndex::Trie(root) => root.insert_recursive(interest.as_segments(), value),
+            InterestIndex::Flat(flat) => flat.insert(interest, value),
+        }
+    }
 
     pub fn insert(&mut self, interest: Interest, value: T) {
-        self.root
-            .insert_recursive(interest.as_segments(), value.clone());
+        self.index_insert(&interest, value.clone());
         self.raw.entry(value).or_default().insert(interest);
     }
 
     pub fn find(&self, subject: &Subject) -> HashSet<&T> {
         let mut collector = HashSet::new();
-        self.root
-            .find_all_recursive(subject.segments(), &mut collector);
+        match &self.index {
+            InterestIndex::Trie(root) => {
+                root.find_all_recursive(subject.segments(), &mut collector)
+            }
+            InterestIndex::Flat(flat) => flat.find_all(subject, &mut collector),
+        }
         collector
     }
 
     pub fn delete(&mut self, value: &T) {
         if let Some(interests) = self.raw.remove(value) {
             for interest in interests {
-                let mut path = interest.as_segments();
-                self.root.delete_recursive(&mut path, value);
+                match &mut self.index {
+                    InterestIndex::Trie(root) => {
+                        root.delete_recursive(&mut interest.as_segments(), value)
+                    }
+                    InterestIndex::Flat(flat) => flat.delete(&interest, value),
+                }
             }
         }
     }
@@ -177,6 +443,38 @@ where
     pub fn interest_of(&self, value: &T) -> Option<&HashSet<Interest>> {
         self.raw.get(value)
     }
+
+    /// Every registered interest starting with `prefix`, with the values holding it.
+    ///
+    /// Interests are compared in their canonical form, segments joined by a single `/` and
+    /// wildcards written as `*` and `**`, so a wildcard never matches a literal prefix.
+    pub fn find_by_prefix(&self, prefix: &[u8]) -> Vec<(Interest, HashSet<T>)> {
+        let mut collector = Vec::new();
+        match &self.index {
+            InterestIndex::Trie(root) => {
+                root.collect_by_prefix(&mut Vec::new(), prefix, &mut collector)
+            }
+            InterestIndex::Flat(flat) => flat.collect_by_prefix(prefix, &mut collector),
+        }
+        collector
+    }
+
+    /// Every interest matching `subject`, paired with the value holding it.
+    ///
+    /// A value with several matching interests appears once for each of them.
+    pub fn interests_matching(&self, subject: &Subject) -> Vec<(Interest, T)> {
+        let mut collector = Vec::new();
+        for value in self.find(subject) {
+            for interest in self.raw.get(value).into_iter().flatten() {
+                let mut probe = InterestMap::new();
+                probe.insert(interest.clone(), ());
+                if !probe.find(subject).is_empty() {
+                    collector.push((interest.clone(), value.clone()));
+                }
+            }
+        }
+        collector
+    }
 }
 
 impl<T> Serialize for InterestMap<T>
@@ -203,6 +501,124 @@ where
         Ok(Self::from_raw(raw))
     }
 }
+/// Lowercase every segment and drop empty ones, which collapses duplicate `/` and trims
+/// leading and trailing `/`, so `orders/EU/` and `orders/eu` become the same subject.
+pub fn normalize_subject(subject: &Subject) -> Subject {
+    let segments = subject
+        .segments()
+        .filter(|seg| !seg.is_empty())
+        .map(|seg| String::from_utf8_lossy(seg).to_lowercase())
+        .collect::<Vec<_>>();
+    Subject::new(segments.join("/"))
+}
+
+/// The interest counterpart of [`normalize_subject`], wildcards are kept as they are.
+pub fn normalize_interest(interest: &Interest) -> Interest {
+    let segments = interest
+        .as_segments()
+        .map(|seg| match seg {
+            InterestSegment::Specific(seg) => String::from_utf8_lossy(seg).to_lowercase(),
+            InterestSegment::Any => "*".to_string(),
+            InterestSegment::RecursiveAny => "**".to_string(),
+            InterestSegment::Tail => ">".to_string(),
+        })
+        .collect::<Vec<_>>();
+    Interest::new(segments.join("/"))
+}
+
+/// Whether every subject `requested` matches is also matched by `allowed`.
+///
+/// Conservative: a requested wildcard only counts as covered by an allowed wildcard at least as wide.
+pub fn interest_covers(allowed: &Interest, requested: &Interest) -> bool {
+    let allowed = allowed.as_segments().collect::<Vec<_>>();
+    let requested = requested.as_segments().collect::<Vec<_>>();
+    covers_recursive(&allowed, &requested)
+}
+
+fn covers_recursive(allowed: &[InterestSegment], requested: &[InterestSegment]) -> bool {
+    let Some((first, rest)) = allowed.split_first() else {
+        return requested.is_empty();
+    };
+    match first {
+        InterestSegment::RecursiveAny => {
+            covers_recursive(rest, requested)
+                || (!requested.is_empty() && covers_recursive(allowed, &requested[1..]))
+        }
+        // matches anything with at least one segment
+        InterestSegment::Tail => requested
+            .iter()
+            .any(|seg| !matches!(seg, InterestSegment::RecursiveAny)),
+        InterestSegment::Any => match requested.split_first() {
+            Some((InterestSegment::Specific(_) | InterestSegment::Any, requested_rest)) => {
+                covers_recursive(rest, requested_rest)
+            }
+            _ => false,
+        },
+        InterestSegment::Specific(allowed_seg) => match requested.split_first() {
+            Some((InterestSegment::Specific(seg), requested_rest)) if seg == allowed_seg => {
+                covers_recursive(rest, requested_rest)
+            }
+            _ => false,
+        },
+    }
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(
+        normalize_subject(&Subject::new("/orders//EU/")).as_str(),
+        "orders/eu"
+    );
+    assert_eq!(
+        normalize_interest(&Interest::new("Orders//*/**/")),
+        Interest::new("orders/*/**")
+    );
+}
+#[test]
+fn test_parse() {
+    let reason = |result: Result<Subject, MalformedSubject>| result.unwrap_err().reason;
+    assert_eq!(
+        reason(Subject::parse("orders//created")),
+        MalformedReason::EmptySegment
+    );
+    assert_eq!(reason(Subject::parse("")), MalformedReason::Empty);
+    assert_eq!(reason(Subject::parse("//")), MalformedReason::Empty);
+    assert_eq!(
+        reason(Subject::parse("orders/*/created")),
+        MalformedReason::MisplacedWildcard
+    );
+    assert_eq!(
+        reason(Subject::parse("orders/eu>")),
+        MalformedReason::MisplacedWildcard
+    );
+    assert_eq!(
+        reason(Subject::parse(vec![b'o', 0xff])),
+        MalformedReason::NotUtf8
+    );
+    assert_eq!(
+        Subject::parse("/orders/eu/created/").unwrap().as_str(),
+        "orders/eu/created"
+    );
+
+    let reason = |result: Result<Interest, MalformedSubject>| result.unwrap_err().reason;
+    assert_eq!(
+        reason(Interest::parse("orders//*")),
+        MalformedReason::EmptySegment
+    );
+    assert_eq!(reason(Interest::parse("/")), MalformedReason::Empty);
+    assert_eq!(
+        reason(Interest::parse("orders/eu*")),
+        MalformedReason::MisplacedWildcard
+    );
+    assert_eq!(
+        reason(Interest::parse("orders/>/created")),
+        MalformedReason::MisplacedTail
+    );
+    assert_eq!(
+        Interest::parse("/orders/*/**/>/").unwrap(),
+        Interest::new("orders/*/**/>")
+    );
+}
 #[test]
 fn test_interest_map() {
     let mut map = InterestMap::new();
@@ -214,3 +630,158 @@ fn test_interest_map() {
     assert!(values.contains(&1));
     assert!(values.contains(&2));
 }
+
+#[test]
+fn test_find_by_prefix() {
+    let mut map = InterestMap::new();
+    map.insert(Interest::new("orders/created"), 1);
+    map.insert(Interest::new("orders/*/shipped"), 2);
+    map.insert(Interest::new("orders/*/shipped"), 3);
+    map.insert(Interest::new("users/login"), 4);
+    map.insert(Interest::new("*/created"), 5);
+
+    let mut found = map.find_by_prefix(b"orders/");
+    found.sort_by_key(|(interest, _)| interest.as_segments().count());
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, Interest::new("orders/created"));
+    assert_eq!(found[0].1, HashSet::from([1]));
+    assert_eq!(found[1].0, Interest::new("orders/*/shipped"));
+    assert_eq!(found[1].1, HashSet::from([2, 3]));
+
+    assert_eq!(map.find_by_prefix(b"").len(), 4);
+    assert!(map.find_by_prefix(b"payments/").is_empty());
+}
+
+#[test]
+fn test_interests_matching() {
+    let mut map = InterestMap::new();
+    map.insert(Interest::new("event/*"), 1);
+    map.insert(Interest::new("event/created"), 1);
+    map.insert(Interest::new("event/**"), 2);
+    map.insert(Interest::new("user/*"), 2);
+
+    let found = map.interests_matching(&Subject::new("event/created"));
+    assert_eq!(found.len(), 3);
+    assert!(found.contains(&(Interest::new("event/*"), 1)));
+    assert!(found.contains(&(Interest::new("event/created"), 1)));
+    assert!(found.contains(&(Interest::new("event/**"), 2)));
+    assert!(map
+        .interests_matching(&Subject::new("order/created"))
+        .is_empty());
+}
+
+#[test]
+fn test_tail_wildcard() {
+    let mut map = InterestMap::new();
+    map.insert(Interest::new("region/us/>"), 1);
+    map.insert(Interest::new("region/*/warehouse"), 2);
+
+    // any depth below the prefix
+    let values = map.find(&Subject::new("region/us/warehouse/42/scan"));
+    assert_eq!(values, HashSet::from([&1]));
+    // one segment below the prefix
+    let values = map.find(&Subject::new("region/us/warehouse"));
+    assert_eq!(values, HashSet::from([&1, &2]));
+    // `>` needs at least one segment
+    assert!(map.find(&Subject::new("region/us")).is_empty());
+    // single level wildcard is unchanged
+    assert_eq!(
+        map.find(&Subject::new("region/eu/warehouse")),
+        HashSet::from([&2])
+    );
+    assert!(map.find(&Subject::new("region/eu/warehouse/42")).is_empty());
+
+    assert!("region/us/>".parse::<Interest>().is_ok());
+    assert!("region/>/scan".parse::<Interest>().is_err());
+    assert!(Interest::try_new("region/>/scan").is_err());
+}
+
+#[test]
+fn test_interest_covers() {
+    let covers = |allowed: &'static str, requested: &'static str| {
+        interest_covers(&Interest::new(allowed), &Interest::new(requested))
+    };
+    assert!(covers("orders/eu", "orders/eu"));
+    assert!(!covers("orders/eu", "orders/us"));
+    assert!(covers("orders/*", "orders/eu"));
+    assert!(covers("orders/*", "orders/*"));
+    assert!(!covers("orders/*", "orders/eu/created"));
+    assert!(!covers("orders/eu", "orders/*"));
+    assert!(covers("orders/**", "orders"));
+    assert!(covers("orders/**", "orders/eu/*/created"));
+    assert!(covers("orders/**", "orders/**"));
+    assert!(covers("orders/**", "orders/eu/>"));
+    assert!(!covers("orders/*/created", "orders/**/created"));
+    assert!(covers("orders/>", "orders/eu/**"));
+    assert!(!covers("orders/>", "orders/**"));
+    assert!(!covers("orders/>", "orders"));
+    assert!(!covers("users/**", "orders/eu"));
+}
+
+#[test]
+fn test_interest_map_kinds_agree() {
+    let interests = [
+        "orders/created",
+        "orders/eu/created",
+        "orders/*",
+        "orders/*/created",
+        "orders/**",
+        "orders/**/created",
+        "orders/**/*",
+        "orders/>",
+        "orders/eu/>",
+        "*/created",
+        "**",
+        ">",
+        "users/login",
+        "users//login",
+        "",
+    ];
+    let subjects = [
+        "orders/created",
+        "orders/eu/created",
+        "orders/eu/us/created",
+        "orders/eu",
+        "orders",
+        "users/login",
+        "users/logout",
+        "payments/created",
+        "a/b/c/d",
+        "",
+    ];
+    let mut trie = InterestMap::with_kind(InterestMapKind::Trie);
+    let mut flat = InterestMap::with_kind(InterestMapKind::Flat);
+    for (value, interest) in interests.iter().enumerate() {
+        trie.insert(Interest::new(*interest), value);
+        flat.insert(Interest::new(*interest), value);
+    }
+    let agree = |trie: &InterestMap<usize>, flat: &InterestMap<usize>| {
+        for subject in subjects {
+            let subject = Subject::new(subject);
+            assert_eq!(trie.find(&subject), flat.find(&subject), "{subject:?}");
+        }
+        for prefix in [&b""[..], b"orders/", b"orders/*", b"users"] {
+            let sorted = |mut found: Vec<(Interest, HashSet<usize>)>| {
+                found.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+                found
+            };
+            assert_eq!(
+                sorted(trie.find_by_prefix(prefix)),
+                sorted(flat.find_by_prefix(prefix))
+            );
+        }
+    };
+    agree(&trie, &flat);
+
+    for value in [0, 3, 7] {
+        trie.delete(&value);
+        flat.delete(&value);
+    }
+    agree(&trie, &flat);
+
+    // moving to the other kind keeps every interest
+    trie.set_kind(InterestMapKind::Flat);
+    assert_eq!(trie.kind(), InterestMapKind::Flat);
+    flat.set_kind(InterestMapKind::Trie);
+    agree(&flat, &trie);
+}
@@ -1,3 +1,8 @@
+pub mod compression;
+pub mod filter;
+
 pub use asteroid_mq_model::{
-    Message, MessageAckExpectKind, MessageHeader, MessageId, MessageStatusKind, MessageTargetKind,
+    CompressionKind, DeadLetter, Message, MessageAckExpectKind, MessageHeader, MessageId,
+    MessageStatusKind, MessageTargetKind,
 };
+pub use filter::MessageFilter;
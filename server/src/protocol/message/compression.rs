@@ -0,0 +1,155 @@
+//! Payload compression, see [`CompressionKind`].
+//!
+//! Payloads are compressed once by the node that publishes them, so the raft log, the network
+//! between nodes and the durable service all carry the compressed form. They are decompressed
+//! right before they are handed to an endpoint.
+use std::borrow::Cow;
+
+use bytes::Bytes;
+
+use crate::util::MaybeBase64Bytes;
+
+use super::{CompressionKind, Message};
+
+#[derive(Debug)]
+pub struct CompressionError {
+    reason: Cow<'static, str>,
+}
+
+impl std::fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "compression error: {}", self.reason)
+    }
+}
+
+impl std::error::Error for CompressionError {}
+
+impl CompressionError {
+    pub fn decompress_error<E: std::fmt::Display>(e: E) -> Self {
+        Self {
+            reason: format!("decompress error: {}", e).into(),
+        }
+    }
+    pub fn unsupported(kind: CompressionKind) -> Self {
+        Self {
+            reason: format!("unsupported compression: {:?}", kind).into(),
+        }
+    }
+}
+
+/// `None` if this build doesn't support `kind`.
+#[cfg_attr(not(any(feature = "lz4", feature = "zstd")), allow(unused_variables))]
+fn compress_bytes(kind: CompressionKind, bytes: &[u8]) -> Option<Vec<u8>> {
+    match kind {
+        CompressionKind::None => None,
+        #[cfg(feature = "lz4")]
+        CompressionKind::Lz4 => Some(lz4_flex::compress_prepend_size(bytes)),
+        #[cfg(feature = "zstd")]
+        CompressionKind::Zstd => zstd::encode_all(bytes, 0).ok(),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+fn decompress_bytes(kind: CompressionKind, bytes: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    match kind {
+        CompressionKind::None => Ok(bytes.to_vec()),
+        #[cfg(feature = "lz4")]
+        CompressionKind::Lz4 => {
+            lz4_flex::decompress_size_prepended(bytes).map_err(CompressionError::decompress_error)
+        }
+        #[cfg(feature = "zstd")]
+        CompressionKind::Zstd => {
+            zstd::decode_all(bytes).map_err(CompressionError::decompress_error)
+        }
+        #[allow(unreachable_patterns)]
+        kind => Err(CompressionError::unsupported(kind)),
+    }
+}
+
+/// Apply the compression requested in the header if the payload is at least `threshold` bytes.
+///
+/// The header is reset to [`CompressionKind::None`] whenever the payload is left as it was,
+/// including when this build doesn't support the requested kind.
+pub fn compress(mut message: Message, threshold: usize) -> Message {
+    let kind = message.header.compression;
+    if kind == CompressionKind::None {
+        return message;
+    }
+    let compressed = if message.payload.0.len() >= threshold {
+        compress_bytes(kind, &message.payload.0)
+    } else {
+        None
+    };
+    match compressed {
+        Some(compressed) => message.payload = MaybeBase64Bytes(Bytes::from(compressed)),
+        None => message.header.compression = CompressionKind::None,
+    }
+    message
+}
+
+/// Reverse [`compress`], the message comes back with its original payload.
+pub fn decompress(mut message: Message) -> Result<Message, CompressionError> {
+    let kind = message.header.compression;
+    if kind == CompressionKind::None {
+        return Ok(message);
+    }
+    let payload = decompress_bytes(kind, &message.payload.0)?;
+    message.payload = MaybeBase64Bytes(Bytes::from(payload));
+    message.header.compression = CompressionKind::None;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::{MessageHeader, Subject};
+
+    const THRESHOLD: usize = 1024;
+
+    fn message(kind: CompressionKind, payload: impl Into<Bytes>) -> Message {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/compressed")])
+                .compression(kind)
+                .build(),
+            payload,
+        )
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    fn large_payload() -> String {
+        r#"{"event":"order-created","items":[1,2,3]}"#.repeat(256)
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    fn assert_round_trip(kind: CompressionKind) {
+        let compressed = compress(message(kind, large_payload()), THRESHOLD);
+        assert_eq!(compressed.header.compression, kind);
+        assert!(compressed.payload.0.len() < large_payload().len());
+        let decompressed = decompress(compressed).expect("decompressible");
+        assert_eq!(decompressed.header.compression, CompressionKind::None);
+        assert_eq!(&decompressed.payload.0[..], large_payload().as_bytes());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_round_trip() {
+        assert_round_trip(CompressionKind::Lz4);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_round_trip() {
+        assert_round_trip(CompressionKind::Zstd);
+    }
+
+    #[test]
+    fn test_small_payload_uncompressed() {
+        for kind in [CompressionKind::Lz4, CompressionKind::Zstd] {
+            let small = compress(message(kind, "tiny"), THRESHOLD);
+            assert_eq!(small.header.compression, CompressionKind::None);
+            assert_eq!(&small.payload.0[..], b"tiny");
+            assert_eq!(&decompress(small).unwrap().payload.0[..], b"tiny");
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Endpoint-side message filters, see [`MessageFilter`].
+//!
+//! A filter narrows down what an endpoint receives beyond its interests, by looking at the
+//! message's header attributes. It's evaluated when the topic picks the targets of a message, so
+//! an endpoint filtering a message out is never waited for.
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use super::Message;
+
+/// A small expression over the header attributes of a message.
+///
+/// ```
+/// # use asteroid_mq::prelude::MessageFilter;
+/// // `region=us` messages that aren't marked as test traffic
+/// let filter = MessageFilter::eq("region", "us").and(MessageFilter::exists("test").not());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MessageFilter {
+    /// the attribute is set, whatever its value
+    Exists(String),
+    /// the attribute is set to exactly this value
+    Eq(String, Bytes),
+    Not(Box<MessageFilter>),
+    /// every filter matches, true if there is none
+    All(Vec<MessageFilter>),
+    /// at least one filter matches, false if there is none
+    Any(Vec<MessageFilter>),
+}
+
+impl MessageFilter {
+    pub fn exists(key: impl Into<String>) -> Self {
+        Self::Exists(key.into())
+    }
+    pub fn eq(key: impl Into<String>, value: impl Into<Bytes>) -> Self {
+        Self::Eq(key.into(), value.into())
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::All(mut filters) => {
+                filters.push(other);
+                Self::All(filters)
+            }
+            filter => Self::All(vec![filter, other]),
+        }
+    }
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Any(mut filters) => {
+                filters.push(other);
+                Self::Any(filters)
+            }
+            filter => Self::Any(vec![filter, other]),
+        }
+    }
+    pub fn matches(&self, message: &Message) -> bool {
+        match self {
+            Self::Exists(key) => message.attribute(key).is_some(),
+            Self::Eq(key, value) => message.attribute(key) == Some(value),
+            Self::Not(filter) => !filter.matches(message),
+            Self::All(filters) => filters.iter().all(|filter| filter.matches(message)),
+            Self::Any(filters) => filters.iter().any(|filter| filter.matches(message)),
+        }
+    }
+}
+
+#[test]
+fn test_message_filter() {
+    use crate::prelude::{MessageHeader, Subject};
+    let message = |region: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/created")])
+                .attribute("region", region)
+                .build(),
+            "{}",
+        )
+    };
+    let us = MessageFilter::eq("region", "us");
+    assert!(us.matches(&message("us")));
+    assert!(!us.matches(&message("eu")));
+    assert!(us.clone().not().matches(&message("eu")));
+    assert!(us
+        .clone()
+        .or(MessageFilter::eq("region", "ca"))
+        .matches(&message("ca")));
+    assert!(!us
+        .and(MessageFilter::exists("test"))
+        .matches(&message("us")));
+    assert!(MessageFilter::All(Vec::new()).matches(&message("us")));
+    assert!(!MessageFilter::Any(Vec::new()).matches(&message("us")));
+}
@@ -1,20 +1,30 @@
+pub mod authorizer;
+pub mod clock;
 pub mod edge;
+pub mod health;
 pub mod raft;
+pub(crate) mod scheduler;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     net::SocketAddr,
     ops::Deref,
     sync::{self, Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 use super::{
-    endpoint::EndpointAddr,
+    endpoint::{EndpointAddr, EpInfo},
     topic::{
         durable_message::{DurableCommand, DurableMessageQuery},
+        validator::PayloadValidatorService,
         Topic, TopicCode,
     },
 };
 pub use asteroid_mq_model::NodeId;
+use authorizer::{AuthorizeAction, AuthorizerService};
+use bytes::Bytes;
+use chrono::{DateTime, TimeDelta, Utc};
+use clock::ClockService;
 use edge::{
     auth::EdgeAuthService,
     codec::CodecRegistry,
@@ -29,21 +39,39 @@ use edge::{
     EdgeError, EdgeErrorKind,
 };
 use futures_util::TryFutureExt;
-use openraft::{BasicNode, ChangeMembers, Raft};
+use health::NodeHealth;
+use openraft::{
+    error::{CheckIsLeaderError, ForwardToLeader, RaftError},
+    raft::ClientWriteResponse,
+    BasicNode, ChangeMembers, Raft,
+};
 use raft::{
     cluster::ClusterProvider,
     log_storage::LogStorage,
-    network_factory::TcpNetworkService,
-    proposal::{EndpointOffline, EndpointOnline, LoadTopic, Proposal},
-    state_machine::{topic::config::TopicConfig, StateMachineStore},
+    network_factory::{RaftTcpConnection, TcpNetworkService},
+    proposal::{
+        DelegateMessage, DelegateMessages, EndpointInterest, EndpointOffline, EndpointOnline,
+        ExpireMessages, LoadTopic, NodeOffline, Proposal, RedeliverMessages, ReleaseDelayed,
+        SetState, UnloadTopic, UpdateTopicConfig,
+    },
+    state_machine::{
+        topic::{
+            config::{TopicConfig, TopicConfigPatch},
+            wait_ack::{WaitAckError, WaitAckErrorException, WaitAckHandle, WaitAckResult},
+            TopicData, TopicStats,
+        },
+        LogEntrySummary, StateMachineStore,
+    },
     MaybeLoadingRaft, TypeConfig,
 };
+use scheduler::DispatchScheduler;
 use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 
 use crate::{
-    prelude::{DurableMessage, DurableService},
+    lifecycle::LifecycleEvent,
+    prelude::{DurableMessage, DurableService, MemoryDurability, MessageHeader},
     DEFAULT_TCP_SOCKET_ADDR,
 };
 
@@ -52,8 +80,60 @@ pub struct NodeConfig {
     pub id: NodeId,
     pub addr: SocketAddr,
     pub raft: openraft::Config,
+    /// defaults to a [`MemoryDurability`](crate::prelude::MemoryDurability), `None` turns durability off
     pub durable: Option<DurableService>,
     pub edge_auth: Option<EdgeAuthService>,
+    /// checks callers loading topics, creating endpoints and publishing, `None` allows everyone
+    pub authorizer: Option<AuthorizerService>,
+    /// max dispatch jobs run for one topic before the next topic gets its turn
+    pub dispatch_budget: usize,
+    /// Max endpoints a topic's dispatch jobs are delivered to at the same time.
+    ///
+    /// Deliveries to one endpoint still run one by one in order, 1 delivers to one endpoint
+    /// after another.
+    pub dispatch_concurrency: usize,
+    /// Max bytes of one segment of held messages in a snapshot.
+    ///
+    /// Only bounds how much is encoded or decoded at a time, the chunks a snapshot is sent to
+    /// followers in are sized by `raft.snapshot_max_chunk_size`.
+    pub snapshot_segment_size: usize,
+    /// endpoints with no activity for this long are taken offline, `None` keeps them until they
+    /// go offline themselves, see [`LocalEndpoint::heartbeat`](crate::prelude::LocalEndpoint::heartbeat)
+    pub endpoint_ttl: Option<Duration>,
+    /// payloads smaller than this are never compressed, whatever their header asks for
+    pub compression_threshold: usize,
+    /// max bytes of header attribute keys and values, larger messages are rejected at publish
+    pub max_attributes_size: usize,
+    /// max bytes of a message published through this node, header included, applies on top of
+    /// the topic's `max_message_bytes`, `None` allows any size
+    pub max_message_bytes: Option<usize>,
+    /// time source of delays, expiry, redelivery and endpoint freshness, read when this node
+    /// proposes as the leader, the wall clock by default, see [`ManualClock`](clock::ManualClock)
+    /// for tests
+    pub clock: ClockService,
+    /// Acks of a topic arriving within this window are committed together as one log entry,
+    /// `None` commits every ack on its own.
+    ///
+    /// An ack that resolves its message flushes the window right away, so producers aren't
+    /// kept waiting for it.
+    pub ack_flush_interval: Option<Duration>,
+    /// Max producers of one topic on this node waiting for their messages to resolve, `None`
+    /// lets them pile up.
+    ///
+    /// Past it the handles waiting the longest fail with `Overflow`, their messages are still
+    /// held and delivered.
+    pub max_waiting_handles: Option<usize>,
+    /// encrypt the links to other cluster members, `None` keeps them plain tcp
+    #[cfg(feature = "tls")]
+    pub tls: Option<raft::tls::TlsConfig>,
+}
+
+impl NodeConfig {
+    pub const DEFAULT_DISPATCH_BUDGET: usize = 32;
+    pub const DEFAULT_DISPATCH_CONCURRENCY: usize = 8;
+    pub const DEFAULT_SNAPSHOT_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+    pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+    pub const DEFAULT_MAX_ATTRIBUTES_SIZE: usize = 16 * 1024;
 }
 
 impl Default for NodeConfig {
@@ -62,8 +142,21 @@ impl Default for NodeConfig {
             id: NodeId::default(),
             addr: DEFAULT_TCP_SOCKET_ADDR,
             raft: openraft::Config::default(),
-            durable: None,
+            durable: Some(DurableService::new(MemoryDurability::new())),
             edge_auth: None,
+            authorizer: None,
+            dispatch_budget: Self::DEFAULT_DISPATCH_BUDGET,
+            dispatch_concurrency: Self::DEFAULT_DISPATCH_CONCURRENCY,
+            snapshot_segment_size: Self::DEFAULT_SNAPSHOT_SEGMENT_SIZE,
+            endpoint_ttl: None,
+            compression_threshold: Self::DEFAULT_COMPRESSION_THRESHOLD,
+            max_attributes_size: Self::DEFAULT_MAX_ATTRIBUTES_SIZE,
+            max_message_bytes: None,
+            clock: ClockService::default(),
+            ack_flush_interval: None,
+            max_waiting_handles: None,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 }
@@ -77,9 +170,16 @@ pub struct NodeInner {
     edge_routing: RwLock<HashMap<EndpointAddr, (NodeId, TopicCode)>>,
     codec_registry: Arc<CodecRegistry>,
     topics: RwLock<HashMap<TopicCode, Topic>>,
+    /// node-local, see `TopicConfig::validator`
+    payload_validators: RwLock<HashMap<TopicCode, PayloadValidatorService>>,
+    state_machine: sync::OnceLock<Arc<StateMachineStore>>,
+    pub(crate) dispatch_scheduler: DispatchScheduler,
     durable_commands_queue: std::sync::RwLock<VecDeque<DurableCommand>>,
     ct: CancellationToken,
     pub(crate) durable_syncs: tokio::sync::Mutex<HashMap<TopicCode, Arc<tokio::sync::Mutex<()>>>>,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: crate::metrics::Metrics,
+    pub(crate) lifecycle: crate::lifecycle::LifecycleEvents,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -89,13 +189,18 @@ pub struct NodeRef {
 
 impl NodeRef {
     pub(crate) fn upgrade(&self) -> Option<Node> {
-        self.inner.upgrade().map(|inner| Node { inner })
+        self.inner.upgrade().map(|inner| Node {
+            inner,
+            credential: None,
+        })
     }
 }
 
 #[derive(Clone)]
 pub struct Node {
     pub(crate) inner: Arc<NodeInner>,
+    /// presented to the [`Authorizer`](authorizer::Authorizer), see [`Node::with_credential`]
+    credential: Option<Bytes>,
 }
 
 impl std::fmt::Debug for Node {
@@ -121,25 +226,48 @@ impl Node {
     pub fn config(&self) -> &NodeConfig {
         &self.config
     }
+    /// the time as told by [`NodeConfig::clock`]
+    pub fn now(&self) -> DateTime<Utc> {
+        self.config.clock.now()
+    }
     pub fn new(config: NodeConfig) -> Self {
         let ct = CancellationToken::new();
         let raft = MaybeLoadingRaft::new();
-        let network =
-            raft.net_work_service(config.id, BasicNode::new(config.addr), ct.child_token());
-        let inner = NodeInner {
-            edge_connections: RwLock::new(HashMap::new()),
-            edge_routing: RwLock::new(HashMap::new()),
-            topics: RwLock::new(HashMap::new()),
-            config,
-            raft,
-            codec_registry: Arc::new(CodecRegistry::new_preloaded()),
-            network,
-            durable_commands_queue: Default::default(),
-            durable_syncs: Default::default(),
-            ct,
-        };
+        // the network service serves messages forwarded by followers, so it refers back to the node
+        let inner = Arc::new_cyclic(|inner| {
+            let network = raft.net_work_service(
+                config.id,
+                BasicNode::new(config.addr),
+                NodeRef {
+                    inner: inner.clone(),
+                },
+                ct.child_token(),
+            );
+            NodeInner {
+                edge_connections: RwLock::new(HashMap::new()),
+                edge_routing: RwLock::new(HashMap::new()),
+                topics: RwLock::new(HashMap::new()),
+                payload_validators: RwLock::new(HashMap::new()),
+                state_machine: sync::OnceLock::new(),
+                dispatch_scheduler: DispatchScheduler::new(
+                    config.dispatch_budget,
+                    config.dispatch_concurrency,
+                ),
+                config,
+                raft,
+                codec_registry: Arc::new(CodecRegistry::new_preloaded()),
+                network,
+                durable_commands_queue: Default::default(),
+                durable_syncs: Default::default(),
+                ct,
+                #[cfg(feature = "metrics")]
+                metrics: Default::default(),
+                lifecycle: Default::default(),
+            }
+        });
         Self {
-            inner: Arc::new(inner),
+            inner,
+            credential: None,
         }
     }
     pub async fn init_raft<C: ClusterProvider>(
@@ -150,11 +278,12 @@ impl Node {
             return Ok(());
         }
         let membership_change_listener_task_ct = self.ct.child_token();
+        let listener_node_ref = self.node_ref();
         let node_ref = self.node_ref();
         let id = self.id();
         let maybe_loading_raft = self.raft.clone();
         let tcp_service = self.network.clone();
-        let state_machine_store = StateMachineStore::new(node_ref);
+        let state_machine_store = Arc::new(StateMachineStore::new(node_ref));
         let raft_config = self
             .config
             .raft
@@ -167,7 +296,7 @@ impl Node {
             Arc::new(raft_config),
             tcp_service.clone(),
             LogStorage::default(),
-            Arc::new(state_machine_store),
+            state_machine_store.clone(),
         )
         .await
         .map_err(crate::Error::contextual_custom("create raft node"))?;
@@ -180,10 +309,34 @@ impl Node {
                 (id, node)
             })
             .collect::<BTreeMap<_, _>>();
-        raft.initialize(members.clone())
-            .await
-            .map_err(crate::Error::contextual_custom("init raft node"))?;
+        if members.contains_key(&id) {
+            raft.initialize(members.clone())
+                .await
+                .map_err(crate::Error::contextual_custom("init raft node"))?;
+        } else {
+            // joins an existing cluster once the leader adds it, see `Node::add_learner`
+            tracing::info!(?id, "not a pristine member, wait to be added");
+        }
+        let _ = self.state_machine.set(state_machine_store);
         maybe_loading_raft.set(raft.clone());
+        tokio::spawn(Self::idle_unload_task(
+            self.node_ref(),
+            self.ct.child_token(),
+        ));
+        tokio::spawn(Self::expiry_task(self.node_ref(), self.ct.child_token()));
+        if let Some(ttl) = self.config.endpoint_ttl {
+            tokio::spawn(Self::endpoint_ttl_task(
+                self.node_ref(),
+                ttl,
+                self.ct.child_token(),
+            ));
+        }
+        tokio::spawn(
+            self.dispatch_scheduler
+                .clone()
+                .run(self.ct.child_token())
+                .instrument(tracing::info_span!("dispatch scheduler", id = ?id)),
+        );
         let _membership_change_listener_task = {
             let mut prev_members = members.keys().cloned().collect::<BTreeSet<_>>();
             let ct = membership_change_listener_task_ct;
@@ -250,6 +403,19 @@ impl Node {
                                     });
                             }
                             if !removed_nodes.is_empty() {
+                                if let Some(node) = listener_node_ref.upgrade() {
+                                    for removed in &removed_nodes {
+                                        let _ = node
+                                            .propose(Proposal::NodeOffline(NodeOffline {
+                                                node: *removed,
+                                                now: node.now(),
+                                            }))
+                                            .await
+                                            .inspect_err(|e| {
+                                                tracing::error!(?e, "node offline error");
+                                            });
+                                    }
+                                }
                                 let _ = raft
                                     .change_membership(
                                         ChangeMembers::RemoveVoters(removed_nodes.clone()),
@@ -281,9 +447,198 @@ impl Node {
         };
         Ok(())
     }
+    /// Leave the cluster for good and stop this node.
+    ///
+    /// Endpoints hosted here are taken offline first, so their durable messages are handed off.
+    /// If the node is the leader, it then hands its leadership over to an up-to-date voter, see
+    /// [`Node::transfer_leadership`], and the node removes itself from the membership through the
+    /// new leader. Producers on this node still waiting for acks get
+    /// [`WaitAckErrorException::Shutdown`], their messages stay held by the cluster.
+    ///
+    /// Returns once the membership change is committed, or with an error if that takes longer
+    /// than `timeout`. The node is stopped either way.
+    pub async fn shutdown(&self, timeout: Duration) -> Result<(), crate::Error> {
+        let leave = async {
+            self.propose(Proposal::NodeOffline(NodeOffline {
+                node: self.id(),
+                now: self.now(),
+            }))
+            .await?;
+            self.flush_durable_commands().await;
+            if self.is_leader() {
+                self.transfer_leadership().await;
+            }
+            self.leave_membership().await
+        };
+        let result = tokio::time::timeout(timeout, leave)
+            .await
+            .unwrap_or_else(|e| Err(crate::Error::custom("leave cluster", e)));
+        let topics = self
+            .topics
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for topic in topics {
+            topic.abandon_waiting(WaitAckErrorException::Shutdown).await;
+            topic.close_local_endpoints();
+        }
+        if let Some(raft) = self.raft_opt() {
+            if let Err(e) = raft.shutdown().await {
+                tracing::error!(?e, "raft shutdown error");
+            }
+        }
+        self.ct.cancel();
+        tracing::info!(id = %self.id(), "node shut down");
+        result
+    }
+    /// Hand the leadership over to the voter that replicated the most of the log.
+    ///
+    /// openraft has no transfer of its own, so the leader stops sending heartbeats, waits for the
+    /// followers' leader lease to run out and asks the chosen voter to start an election. Returns
+    /// once another node leads, or after a few election timeouts, the followers elect a leader
+    /// themselves without heartbeats anyway. Does nothing without another voter.
+    async fn transfer_leadership(&self) {
+        let raft = self.raft().await;
+        let target = {
+            let metrics = raft.metrics();
+            let metrics = metrics.borrow();
+            let voters = metrics
+                .membership_config
+                .membership()
+                .voter_ids()
+                .collect::<BTreeSet<_>>();
+            metrics
+                .replication
+                .iter()
+                .flatten()
+                .filter(|(id, _)| **id != self.id() && voters.contains(*id))
+                .max_by_key(|(_, matched)| **matched)
+                .map(|(id, _)| *id)
+        };
+        let Some(target) = target else {
+            return;
+        };
+        tracing::info!(%target, "transfer leadership");
+        raft.runtime_config().heartbeat(false);
+        let lease = Duration::from_millis(raft.config().election_timeout_max);
+        tokio::time::sleep(lease).await;
+        let connection = self.network.connections.read().await.get(&target).cloned();
+        if let Some(connection) = connection {
+            if let Err(e) = connection.elect().await {
+                tracing::warn!(?e, %target, "failed to ask for an election");
+            }
+        }
+        let id = self.id();
+        let stepped_down = raft
+            .wait(Some(lease * 4))
+            .metrics(
+                |metrics| metrics.current_leader.is_some_and(|leader| leader != id),
+                "wait for another leader",
+            )
+            .await;
+        if let Err(e) = stepped_down {
+            tracing::warn!(?e, "leadership not transferred, leave as the leader");
+            raft.runtime_config().heartbeat(true);
+        }
+    }
+    /// wait for the durable commands being written, only the leader writes them
+    async fn flush_durable_commands(&self) {
+        let syncs = self
+            .durable_syncs
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for sync in syncs {
+            drop(sync.lock().await);
+        }
+    }
+    /// remove this node from the membership, through the leader if it's not the leader
+    async fn leave_membership(&self) -> Result<(), crate::Error> {
+        let leader = self.leader().await?;
+        if leader == self.id() {
+            self.raft()
+                .await
+                .change_membership(
+                    ChangeMembers::RemoveVoters(BTreeSet::from([self.id()])),
+                    false,
+                )
+                .await
+                .map_err(crate::Error::contextual_custom(
+                    "remove self from membership",
+                ))?;
+        } else {
+            self.leader_connection(leader)
+                .await?
+                .leave(self.id())
+                .await?;
+        }
+        Ok(())
+    }
+    /// Add the node `node_id` listening on `addr` as a learner, it replicates the log but doesn't
+    /// vote.
+    ///
+    /// Only the leader changes the membership. Returns once the learner caught up with the log and
+    /// the change is committed. The address is kept in the membership, where every node looks its
+    /// peers up, so the cluster provider doesn't need to list it.
+    pub async fn add_learner(&self, node_id: NodeId, addr: SocketAddr) -> Result<(), crate::Error> {
+        self.ensure_leader().await?;
+        self.raft()
+            .await
+            .add_learner(node_id, BasicNode::new(addr), true)
+            .await
+            .map_err(crate::Error::contextual_custom("add learner"))?;
+        tracing::info!(?node_id, %addr, "learner added");
+        Ok(())
+    }
+    /// Make the learner `node_id` a voter, see [`Node::add_learner`].
+    ///
+    /// Returns once the change is committed.
+    pub async fn promote_to_voter(&self, node_id: NodeId) -> Result<(), crate::Error> {
+        self.ensure_leader().await?;
+        self.raft()
+            .await
+            .change_membership(ChangeMembers::AddVoterIds(BTreeSet::from([node_id])), false)
+            .await
+            .map_err(crate::Error::contextual_custom("promote to voter"))?;
+        tracing::info!(?node_id, "learner promoted to voter");
+        Ok(())
+    }
+    /// Remove the voter or learner `node_id` from the cluster.
+    ///
+    /// Its endpoints are taken offline first, like for a node leaving with [`Node::shutdown`].
+    /// Returns once the change is committed.
+    pub async fn remove_node(&self, node_id: NodeId) -> Result<(), crate::Error> {
+        self.ensure_leader().await?;
+        self.propose(Proposal::NodeOffline(NodeOffline {
+            node: node_id,
+            now: self.now(),
+        }))
+        .await?;
+        let raft = self.raft().await;
+        let is_voter = raft
+            .metrics()
+            .borrow()
+            .membership_config
+            .membership()
+            .voter_ids()
+            .any(|voter| voter == node_id);
+        let changes = if is_voter {
+            ChangeMembers::RemoveVoters(BTreeSet::from([node_id]))
+        } else {
+            ChangeMembers::RemoveNodes(BTreeSet::from([node_id]))
+        };
+        raft.change_membership(changes, false)
+            .await
+            .map_err(crate::Error::contextual_custom("remove node"))?;
+        tracing::info!(?node_id, "node removed");
+        Ok(())
+    }
     #[tracing::instrument(skip_all)]
     pub async fn load_from_durable_service(&self) -> Result<(), crate::Error> {
-        const PAGE_SIZE: u32 = 100;
         let Some(durable) = self.config.durable.as_ref().cloned() else {
             return Ok(());
         };
@@ -294,32 +649,13 @@ impl Node {
         let mut task_set = tokio::task::JoinSet::new();
         for topic in topics {
             let node = self.clone();
-            let durable = durable.clone();
             let task = async move {
-                let mut query = DurableMessageQuery {
-                    limit: PAGE_SIZE,
-                    offset: 0,
-                };
-                let mut queue = Vec::new();
                 let code = topic.code.clone();
-                loop {
-                    let page = durable
-                        .batch_retrieve(code.clone(), query)
-                        .await
-                        .map_err(crate::Error::contextual_custom("batch retrieve"))?;
-                    let page_len = page.len();
-                    queue.extend(page);
-                    if page_len < PAGE_SIZE as usize {
-                        break;
-                    } else {
-                        query = query.next_page()
-                    }
-                }
-                let result = node.load_topic(topic, queue).await;
+                let result = node.load_topic_from_durable(topic).await;
                 if let Err(e) = result {
                     match e.kind {
                         crate::error::ErrorKind::TopicAlreadyExists
-                        | crate::error::ErrorKind::NotLeader => {
+                        | crate::error::ErrorKind::NotLeader(_) => {
                             // do nothing
                         }
                         _ => {
@@ -338,33 +674,318 @@ impl Node {
         }
         Ok(())
     }
-    pub(crate) async fn propose(&self, proposal: Proposal) -> Result<(), crate::Error> {
-        let raft = self.raft().await;
-        let metric = raft
+    /// load a topic with all its messages from the durable service
+    pub(crate) async fn load_topic_from_durable(
+        &self,
+        config: TopicConfig,
+    ) -> Result<Topic, crate::Error> {
+        const PAGE_SIZE: u32 = 100;
+        let Some(durable) = self.config.durable.as_ref().cloned() else {
+            return Err(crate::Error::unknown("durable service not configured"));
+        };
+        let mut query = DurableMessageQuery {
+            limit: PAGE_SIZE,
+            offset: 0,
+        };
+        let mut queue = Vec::new();
+        loop {
+            let page = durable
+                .batch_retrieve(config.code.clone(), query)
+                .await
+                .map_err(crate::Error::contextual_custom("batch retrieve"))?;
+            let page_len = page.len();
+            queue.extend(page);
+            if page_len < PAGE_SIZE as usize {
+                break;
+            } else {
+                query = query.next_page()
+            }
+        }
+        self.load_topic_unchecked(config, queue).await
+    }
+    /// Get a loaded topic, or reload it from the durable service if it was unloaded while idle.
+    pub async fn get_or_reload_topic(&self, code: &TopicCode) -> Result<Topic, crate::Error> {
+        if let Some(topic) = self.get_topic(code) {
+            return Ok(topic);
+        }
+        let not_found =
+            || crate::Error::new("topic not found", crate::error::ErrorKind::TopicNotFound);
+        let Some(durable) = self.config.durable.as_ref().cloned() else {
+            return Err(not_found());
+        };
+        let config = durable
+            .topic_list()
+            .await
+            .map_err(crate::Error::contextual("load topic list"))?
+            .into_iter()
+            .find(|config| &config.code == code)
+            .ok_or_else(not_found)?;
+        tracing::info!(topic = %code, "reload idle topic");
+        match self.load_topic_from_durable(config).await {
+            Err(e) if matches!(e.kind, crate::error::ErrorKind::TopicAlreadyExists) => {
+                self.get_topic(code).ok_or_else(not_found)
+            }
+            result => result,
+        }
+    }
+    /// expire messages of topics that see no traffic, busy topics expire them on every update too,
+    /// release delayed messages once they are due and redeliver messages not acked in time
+    async fn expiry_task(node_ref: NodeRef, ct: CancellationToken) {
+        const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+        loop {
+            tokio::select! {
+                _ = ct.cancelled() => break,
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            }
+            let Some(node) = node_ref.upgrade() else {
+                break;
+            };
+            if node.ensure_leader().await.is_err() {
+                continue;
+            }
+            let Some(state_machine) = node.state_machine.get() else {
+                continue;
+            };
+            let now = node.now();
+            let (due_topics, release_topics, redeliver_topics) = {
+                let state_machine = state_machine.state_machine.read().await;
+                let topics = &state_machine.node.topics;
+                (
+                    topics
+                        .iter()
+                        .filter(|(_, topic)| topic.queue.has_expired(now))
+                        .map(|(code, _)| code.clone())
+                        .collect::<Vec<_>>(),
+                    topics
+                        .iter()
+                        .filter(|(_, topic)| topic.queue.has_delayed_due(now))
+                        .map(|(code, _)| code.clone())
+                        .collect::<Vec<_>>(),
+                    topics
+                        .iter()
+                        .filter(|(_, topic)| topic.queue.has_redelivery_due(now))
+                        .map(|(code, _)| code.clone())
+                        .collect::<Vec<_>>(),
+                )
+            };
+            for topic in release_topics {
+                if let Err(e) = node
+                    .propose(Proposal::ReleaseDelayed(ReleaseDelayed {
+                        topic: topic.clone(),
+                        now,
+                    }))
+                    .await
+                {
+                    tracing::warn!(?e, %topic, "release delayed messages failed");
+                }
+            }
+            for topic in due_topics {
+                if let Err(e) = node
+                    .propose(Proposal::ExpireMessages(ExpireMessages {
+                        topic: topic.clone(),
+                        now,
+                    }))
+                    .await
+                {
+                    tracing::warn!(?e, %topic, "expire messages failed");
+                }
+            }
+            for topic in redeliver_topics {
+                if let Err(e) = node
+                    .propose(Proposal::RedeliverMessages(RedeliverMessages {
+                        topic: topic.clone(),
+                        now,
+                    }))
+                    .await
+                {
+                    tracing::warn!(?e, %topic, "redeliver messages failed");
+                }
+            }
+        }
+    }
+    /// take endpoints offline once they have been silent for `ttl`, their hosts may be gone
+    /// without ever reporting them offline
+    async fn endpoint_ttl_task(node_ref: NodeRef, ttl: Duration, ct: CancellationToken) {
+        let check_interval = (ttl / 2).min(Duration::from_secs(1));
+        let ttl = TimeDelta::from_std(ttl).unwrap_or(TimeDelta::max_value());
+        loop {
+            tokio::select! {
+                _ = ct.cancelled() => break,
+                _ = tokio::time::sleep(check_interval) => {}
+            }
+            let Some(node) = node_ref.upgrade() else {
+                break;
+            };
+            if node.ensure_leader().await.is_err() {
+                continue;
+            }
+            let Some(state_machine) = node.state_machine.get() else {
+                continue;
+            };
+            let now = node.now();
+            let stale = state_machine
+                .state_machine
+                .read()
+                .await
+                .node
+                .topics
+                .iter()
+                .flat_map(|(code, topic)| {
+                    topic
+                        .stale_eps(now, ttl)
+                        .into_iter()
+                        .map(move |(host, endpoint)| (code.clone(), host, endpoint))
+                })
+                .collect::<Vec<_>>();
+            for (topic_code, host, endpoint) in stale {
+                tracing::info!(topic = %topic_code, ?endpoint, "endpoint is stale, take it offline");
+                if let Err(e) = node
+                    .propose(Proposal::EpOffline(EndpointOffline {
+                        topic_code,
+                        endpoint,
+                        host,
+                        now: node.now(),
+                    }))
+                    .await
+                {
+                    tracing::warn!(?e, "take stale endpoint offline failed");
+                }
+            }
+        }
+    }
+    async fn idle_unload_task(node_ref: NodeRef, ct: CancellationToken) {
+        const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+        let mut idle_since = HashMap::<TopicCode, Instant>::new();
+        loop {
+            tokio::select! {
+                _ = ct.cancelled() => break,
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            }
+            let Some(node) = node_ref.upgrade() else {
+                break;
+            };
+            // only the leader unloads, and only topics it can reload from durable storage
+            let Some(durable) = node.config.durable.clone() else {
+                break;
+            };
+            if node.ensure_leader().await.is_err() {
+                idle_since.clear();
+                continue;
+            }
+            let Some(state_machine) = node.state_machine.get() else {
+                continue;
+            };
+            let idle_topics = state_machine
+                .state_machine
+                .read()
+                .await
+                .node
+                .topics
+                .values()
+                .filter(|topic| topic.config.idle_unload_after.is_some() && topic.is_idle())
+                .map(|topic| topic.config.clone())
+                .collect::<Vec<_>>();
+            idle_since.retain(|code, _| idle_topics.iter().any(|config| &config.code == code));
+            for config in idle_topics {
+                let since = *idle_since
+                    .entry(config.code.clone())
+                    .or_insert_with(Instant::now);
+                let Some(idle_unload_after) = config.idle_unload_after else {
+                    continue;
+                };
+                if since.elapsed() < idle_unload_after {
+                    continue;
+                }
+                let code = config.code.clone();
+                idle_since.remove(&code);
+                // persist the config, so the topic can be reloaded on demand
+                if let Err(e) = durable.create_topic(config).await {
+                    tracing::error!(?e, topic = %code, "persist idle topic failed");
+                    continue;
+                }
+                tracing::info!(topic = %code, "unload idle topic");
+                if let Err(e) = node
+                    .propose(Proposal::UnloadTopic(UnloadTopic::if_idle(
+                        code,
+                        node.now(),
+                    )))
+                    .await
+                {
+                    tracing::error!(?e, "unload idle topic failed");
+                }
+            }
+        }
+    }
+    /// the current leader, waiting for one to be elected
+    pub(crate) async fn leader(&self) -> Result<NodeId, crate::Error> {
+        let metric = self
+            .raft()
+            .await
             .wait(None)
             .metrics(
                 |rm| rm.current_leader.is_some(),
                 "wait for leader to be elected",
             )
             .await
-            .map_err(crate::Error::contextual_custom(
-                "wait for leader when proposal",
-            ))?;
-        let leader = metric.current_leader.expect("leader should be elected");
-        let this = self.id();
-        let client_write_result = if this == leader {
-            raft.client_write(proposal)
-                .await
-                .map_err(crate::Error::contextual_custom("client write"))?
-        } else {
-            let Some(connection) = self.network.connections.read().await.get(&leader).cloned()
-            else {
-                return Err(crate::Error::new(
-                    "no connection to leader",
-                    crate::error::ErrorKind::Offline,
-                ));
-            };
-            connection.propose(proposal).await?
+            .map_err(|err| {
+                tracing::debug!(?err, "no leader elected");
+                crate::Error::new(
+                    "wait for leader",
+                    ForwardToLeader::<NodeId, BasicNode>::empty(),
+                )
+            })?;
+        Ok(metric.current_leader.expect("leader should be elected"))
+    }
+    /// Whether this node is the leader as far as it knows, `false` before raft is initialized.
+    pub fn is_leader(&self) -> bool {
+        self.leader_id() == Some(self.id())
+    }
+    /// The leader as last seen by this node, without waiting for an election.
+    pub fn leader_id(&self) -> Option<NodeId> {
+        self.raft.get_opt()?.metrics().borrow().current_leader
+    }
+    /// Address of [`Node::leader_id`] in the cluster membership, as given by the cluster provider.
+    pub fn leader_addr(&self) -> Option<SocketAddr> {
+        let raft = self.raft.get_opt()?;
+        let metrics = raft.metrics();
+        let metrics = metrics.borrow();
+        let leader = metrics.current_leader?;
+        let node = metrics.membership_config.membership().get_node(&leader)?;
+        node.addr.parse().ok()
+    }
+    async fn leader_connection(
+        &self,
+        leader: NodeId,
+    ) -> Result<Arc<RaftTcpConnection>, crate::Error> {
+        self.network
+            .connections
+            .read()
+            .await
+            .get(&leader)
+            .cloned()
+            .ok_or_else(|| {
+                crate::Error::new("no connection to leader", crate::error::ErrorKind::Offline)
+            })
+    }
+    /// Commit `proposal` through the leader, forwarding it when this node is a follower.
+    ///
+    /// If the leader is unknown or changes before the proposal reaches it, it's retried a few
+    /// times against the new leader.
+    pub(crate) async fn propose(&self, proposal: Proposal) -> Result<(), crate::Error> {
+        const ATTEMPTS: u32 = 3;
+        const BACKOFF: Duration = Duration::from_millis(100);
+        let raft = self.raft().await;
+        let mut attempt = 1;
+        let client_write_result = loop {
+            match self.propose_to_leader(&raft, proposal.clone()).await {
+                Ok(response) => break response,
+                Err(err) if attempt < ATTEMPTS && Self::is_leader_missed(&err) => {
+                    tracing::debug!(?err, attempt, "leader missed, retry proposal");
+                    tokio::time::sleep(BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
         };
         let id = client_write_result.log_id();
         raft.wait(None)
@@ -374,6 +995,110 @@ impl Node {
         Ok(())
     }
 
+    async fn propose_to_leader(
+        &self,
+        raft: &Raft<TypeConfig>,
+        mut proposal: Proposal,
+    ) -> Result<ClientWriteResponse<TypeConfig>, crate::Error> {
+        let leader = self.leader().await?;
+        if self.id() == leader {
+            proposal.stamp(self.now());
+            raft.client_write(proposal)
+                .await
+                .map_err(crate::Error::contextual("client write"))
+        } else {
+            self.leader_connection(leader)
+                .await?
+                .propose(proposal)
+                .await
+        }
+    }
+    /// the proposal didn't reach a leader, so it wasn't committed and can be retried
+    fn is_leader_missed(err: &crate::Error) -> bool {
+        match &err.kind {
+            crate::error::ErrorKind::NotLeader(_) | crate::error::ErrorKind::Offline => true,
+            crate::error::ErrorKind::RaftClient(err) => err.forward_to_leader().is_some(),
+            _ => false,
+        }
+    }
+    /// Hold `message` on `leader`, the handle resolves with the ack result reported back from it.
+    ///
+    /// The handle fails with `MessageDropped` if the leader can't be reached before that, even
+    /// though the message may still be delivered.
+    pub(crate) async fn forward_message(
+        &self,
+        leader: NodeId,
+        message: DelegateMessage,
+    ) -> Result<WaitAckHandle, crate::Error> {
+        let (sender, handle) = WaitAckHandle::new(message.message.id());
+        let result = self
+            .leader_connection(leader)
+            .await?
+            .send_message(message)
+            .await?;
+        tokio::spawn(async move {
+            let result = result.await.unwrap_or_else(|e| {
+                tracing::warn!(?e, "lost the result of a forwarded message");
+                Err(WaitAckError::exception(
+                    WaitAckErrorException::MessageDropped,
+                ))
+            });
+            let _ = sender.send(result);
+        });
+        Ok(handle)
+    }
+    /// [`Node::forward_message`] for a batch, the leader holds it as one proposal
+    pub(crate) async fn forward_messages(
+        &self,
+        leader: NodeId,
+        messages: DelegateMessages,
+    ) -> Result<Vec<WaitAckHandle>, crate::Error> {
+        let (senders, handles): (Vec<_>, Vec<_>) = messages
+            .messages
+            .iter()
+            .map(|message| WaitAckHandle::new(message.id()))
+            .unzip();
+        let results = self
+            .leader_connection(leader)
+            .await?
+            .send_messages(messages)
+            .await?;
+        tokio::spawn(async move {
+            let mut results = results
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(?e, "lost the results of forwarded messages");
+                    Vec::new()
+                })
+                .into_iter();
+            for sender in senders {
+                let result = results.next().unwrap_or(Err(WaitAckError::exception(
+                    WaitAckErrorException::MessageDropped,
+                )));
+                let _ = sender.send(result);
+            }
+        });
+        Ok(handles)
+    }
+    /// serve a batch forwarded by a follower, see [`Node::forward_messages`]
+    pub(crate) async fn hold_forwarded_messages(
+        &self,
+        DelegateMessages {
+            topic, messages, ..
+        }: DelegateMessages,
+    ) -> Result<Vec<WaitAckResult>, crate::Error> {
+        let topic = self.get_or_reload_topic(&topic).await?;
+        let handles = topic.send_compressed_messages(messages).await?;
+        Ok(futures_util::future::join_all(handles).await)
+    }
+    /// serve a message forwarded by a follower, see [`Node::forward_message`]
+    pub(crate) async fn hold_forwarded_message(
+        &self,
+        DelegateMessage { topic, message, .. }: DelegateMessage,
+    ) -> Result<WaitAckResult, crate::Error> {
+        let topic = self.get_or_reload_topic(&topic).await?;
+        Ok(topic.send_compressed_message(message).await?.await)
+    }
     pub fn raft_opt(&self) -> Option<Raft<TypeConfig>> {
         self.raft.get_opt()
     }
@@ -460,6 +1185,7 @@ impl Node {
                 endpoint: addr,
                 host,
                 topic_code,
+                now: self.now(),
             };
             let node = self.clone();
             tokio::spawn(async move {
@@ -501,7 +1227,12 @@ impl Node {
             }
         }
         match edge_request_kind {
-            edge::EdgeRequestEnum::SendMessage(edge_message) => {
+            edge::EdgeRequestEnum::SendMessage(mut edge_message) => {
+                // checked above, consumers never see it
+                edge_message
+                    .header
+                    .attributes
+                    .remove(MessageHeader::MIRROR_CREDENTIAL);
                 let (message, topic_code) = edge_message.into_message();
                 let Some(topic) = self.get_topic(&topic_code) else {
                     return Err(EdgeError::new(
@@ -509,8 +1240,9 @@ impl Node {
                         EdgeErrorKind::TopicNotFound,
                     ));
                 };
+                // edge connections are checked by edge auth, not the authorizer
                 let handle = topic
-                    .send_message(message)
+                    .publish(message)
                     .map_err(|e| {
                         EdgeError::with_message(
                             "send message",
@@ -539,6 +1271,12 @@ impl Node {
                     interests: online.interests,
                     endpoint,
                     host: node.id(),
+                    weight: EpInfo::DEFAULT_WEIGHT,
+                    group: None,
+                    filter: None,
+                    prefetch: None,
+                    key: None,
+                    now: node.now(),
                 }))
                 .await
                 .map_err(|e| {
@@ -565,6 +1303,7 @@ impl Node {
                     topic_code: topic_code.clone(),
                     endpoint: offline.endpoint,
                     host: from,
+                    now: node.now(),
                 }))
                 .await
                 .map_err(|e| {
@@ -588,15 +1327,20 @@ impl Node {
                 })?;
                 let node = topic.node();
                 self.check_ep_auth(&interest.endpoint, &from)?;
-                node.propose(Proposal::EpInterest(interest.clone()))
-                    .await
-                    .map_err(|e| {
-                        EdgeError::with_message(
-                            "endpoint interest",
-                            e.to_string(),
-                            EdgeErrorKind::Internal,
-                        )
-                    })?;
+                node.propose(Proposal::EpInterest(EndpointInterest {
+                    topic_code: interest.topic_code.clone(),
+                    endpoint: interest.endpoint,
+                    interests: interest.interests.clone(),
+                    now: node.now(),
+                }))
+                .await
+                .map_err(|e| {
+                    EdgeError::with_message(
+                        "endpoint interest",
+                        e.to_string(),
+                        EdgeErrorKind::Internal,
+                    )
+                })?;
                 Ok(edge::EdgeResponseEnum::EndpointInterest)
             }
             edge::EdgeRequestEnum::SetState(set_state) => {
@@ -611,23 +1355,219 @@ impl Node {
                 for ep in set_state.update.status.keys() {
                     self.check_ep_auth(ep, &from)?;
                 }
-                node.propose(Proposal::SetState(set_state.clone()))
-                    .await
-                    .map_err(|e| {
-                        EdgeError::with_message("set state", e.to_string(), EdgeErrorKind::Internal)
-                    })?;
+                node.propose(Proposal::SetState(SetState {
+                    topic: set_state.topic.clone(),
+                    update: set_state.update.clone(),
+                    now: node.now(),
+                }))
+                .await
+                .map_err(|e| {
+                    EdgeError::with_message("set state", e.to_string(), EdgeErrorKind::Internal)
+                })?;
                 Ok(edge::EdgeResponseEnum::SetState)
             }
         }
     }
 
+    /// whether `code` is loaded on this node, see [`Node::get_or_reload_topic`] for unloaded topics
+    pub fn has_topic(&self, code: &TopicCode) -> bool {
+        self.topics.read().unwrap().contains_key(code)
+    }
+    pub(crate) fn payload_validator(&self, code: &TopicCode) -> Option<PayloadValidatorService> {
+        self.payload_validators.read().unwrap().get(code).cloned()
+    }
     pub fn get_topic(&self, code: &TopicCode) -> Option<Topic> {
         let topics = self.topics.read().unwrap();
-        topics.get(code).cloned()
+        topics
+            .get(code)
+            .cloned()
+            .map(|topic| topic.presenting(self.credential.clone()))
+    }
+    /// A handle presenting `credential` to the node's [`Authorizer`](authorizer::Authorizer),
+    /// topics got from it present it too.
+    pub fn with_credential(&self, credential: impl Into<Bytes>) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            credential: Some(credential.into()),
+        }
+    }
+    /// Fails with [`ErrorKind::Unauthorized`](crate::error::ErrorKind::Unauthorized) if the
+    /// authorizer denies `action` for `credential`.
+    pub(crate) async fn authorize(
+        &self,
+        topic: &TopicCode,
+        action: AuthorizeAction,
+        credential: Option<&Bytes>,
+    ) -> Result<(), crate::Error> {
+        let Some(authorizer) = &self.config.authorizer else {
+            return Ok(());
+        };
+        if authorizer.authorize(topic, action, credential).await {
+            Ok(())
+        } else {
+            tracing::debug!(%topic, ?action, "unauthorized");
+            Err(crate::Error::new(
+                format!("{action:?} on topic {topic}"),
+                crate::error::ErrorKind::Unauthorized,
+            ))
+        }
+    }
+    /// Counters and gauges of this node in the Prometheus text format, to be served by any HTTP handler.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_text(&self) -> String {
+        let mirrors = self
+            .topics
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(code, topic)| Some((code.to_string(), topic.mirror_stats()?)))
+            .collect();
+        self.metrics.render(&mirrors)
+    }
+    /// Lifecycle events of messages and endpoints from now on, see [`LifecycleEvent`].
+    ///
+    /// The stream is bounded, a subscriber that falls more than 1024 events behind misses the
+    /// oldest ones instead of slowing down the message path.
+    pub fn subscribe_events(
+        &self,
+    ) -> impl futures_util::Stream<Item = LifecycleEvent> + Send + 'static {
+        self.lifecycle.subscribe()
+    }
+    /// Unload a topic from the cluster, its held messages stay in the durable service.
+    ///
+    /// The config is persisted first, so the topic can be brought back with
+    /// [`Node::get_or_reload_topic`].
+    pub async fn unload_topic(&self, code: &TopicCode) -> Result<(), crate::Error> {
+        let config = self
+            .with_topic_data(code, |topic| topic.config.clone())
+            .await
+            .ok_or_else(|| {
+                crate::Error::new("topic not found", crate::error::ErrorKind::TopicNotFound)
+            })?;
+        if let Some(durable) = self.config.durable.as_ref() {
+            durable
+                .create_topic(config)
+                .await
+                .map_err(crate::Error::contextual("persist topic config"))?;
+        }
+        self.propose(Proposal::UnloadTopic(UnloadTopic::new(
+            code.clone(),
+            self.now(),
+        )))
+        .await
+    }
+    /// Change the mutable settings of a loaded topic, see [`TopicConfigPatch`].
+    ///
+    /// Held messages and endpoints are kept. The change is committed like any other proposal, so
+    /// every node applies it, and it's persisted with the config when the topic is unloaded.
+    pub async fn update_topic_config(
+        &self,
+        code: &TopicCode,
+        patch: TopicConfigPatch,
+    ) -> Result<(), crate::Error> {
+        if !self.has_topic(code) {
+            return Err(crate::Error::new(
+                "topic not found",
+                crate::error::ErrorKind::TopicNotFound,
+            ));
+        }
+        self.propose(Proposal::UpdateTopicConfig(UpdateTopicConfig {
+            topic: code.clone(),
+            patch,
+            now: self.now(),
+        }))
+        .await
+    }
+    /// Codes of the topics loaded on this node, in no particular order.
+    ///
+    /// Unloaded topics kept by the durable service aren't listed.
+    pub fn list_topics(&self) -> Vec<TopicCode> {
+        self.topics.read().unwrap().keys().cloned().collect()
+    }
+    /// Stats of every topic loaded on this node.
+    ///
+    /// Each topic is read under its own short lock, so the numbers of different topics may be
+    /// taken at slightly different moments.
+    pub async fn topic_stats(&self) -> HashMap<TopicCode, TopicStats> {
+        let topics = self.topics.read().unwrap().clone();
+        let mut stats = HashMap::with_capacity(topics.len());
+        for (code, topic) in topics {
+            let Some(mut topic_stats) = self.with_topic_data(&code, TopicData::stats).await else {
+                continue;
+            };
+            topic_stats.waiting_handles = topic.ack_waiting_pool.read().await.len();
+            stats.insert(code, topic_stats);
+        }
+        stats
     }
-    pub async fn is_leader(&self) -> bool {
+    /// Kinds, indices and terms of the latest `n` log entries applied on this node, oldest first.
+    ///
+    /// Only the latest [`StateMachineStore::RECENT_ENTRIES_CAPACITY`] entries are retained.
+    pub fn recent_log_entries(&self, n: usize) -> Vec<LogEntrySummary> {
+        self.state_machine
+            .get()
+            .map(|state_machine| state_machine.recent_entries(n))
+            .unwrap_or_default()
+    }
+    /// Health of this node as of the latest raft metrics, see [`NodeHealth`].
+    ///
+    /// Nothing is awaited, so it's cheap enough for liveness and readiness probes.
+    pub fn health(&self) -> NodeHealth {
+        let topics = self.topics.read().unwrap().len();
+        let connected_peers = self
+            .network
+            .live_connections
+            .load(std::sync::atomic::Ordering::Relaxed);
+        let Some(raft) = self.raft.get_opt() else {
+            return NodeHealth {
+                role: None,
+                leader: None,
+                connected_peers,
+                member_peers: 0,
+                applied_lag: 0,
+                topics,
+            };
+        };
+        let metrics = raft.metrics();
+        let metrics = metrics.borrow();
+        let member_peers = metrics
+            .membership_config
+            .membership()
+            .nodes()
+            .filter(|(id, _)| **id != self.id())
+            .count();
+        let applied = metrics.last_applied.map_or(0, |log_id| log_id.index);
+        NodeHealth {
+            role: Some(metrics.state),
+            leader: metrics.current_leader,
+            connected_peers,
+            member_peers,
+            applied_lag: metrics.last_log_index.unwrap_or(0).saturating_sub(applied),
+            topics,
+        }
+    }
+    /// read the applied state of a topic on this node
+    pub(crate) async fn with_topic_data<T>(
+        &self,
+        code: &TopicCode,
+        f: impl FnOnce(&TopicData) -> T,
+    ) -> Option<T> {
+        let state_machine = self.state_machine.get()?;
+        let state_machine = state_machine.state_machine.read().await;
+        state_machine.node.topics.get(code).map(f)
+    }
+    /// Guard for leader-only operations.
+    ///
+    /// Returns [`ErrorKind::NotLeader`](crate::error::ErrorKind::NotLeader) with the current leader hint if this node is not the leader.
+    pub async fn ensure_leader(&self) -> Result<(), crate::Error> {
         let raft = self.raft().await;
-        raft.ensure_linearizable().await.is_ok()
+        match raft.ensure_linearizable().await {
+            Ok(_) => Ok(()),
+            Err(RaftError::APIError(CheckIsLeaderError::ForwardToLeader(forward))) => {
+                Err(crate::Error::new("this node is not the leader", forward))
+            }
+            Err(e) => Err(crate::Error::custom("ensure leader", e)),
+        }
     }
     pub async fn load_topic<C: Into<TopicConfig>>(
         &self,
@@ -635,6 +1575,20 @@ impl Node {
         queue: Vec<DurableMessage>,
     ) -> Result<Topic, crate::Error> {
         let config: TopicConfig = config.into();
+        self.authorize(
+            &config.code,
+            AuthorizeAction::LoadTopic,
+            self.credential.as_ref(),
+        )
+        .await?;
+        let topic = self.load_topic_unchecked(config, queue).await?;
+        Ok(topic.presenting(self.credential.clone()))
+    }
+    pub(crate) async fn load_topic_unchecked(
+        &self,
+        config: TopicConfig,
+        queue: Vec<DurableMessage>,
+    ) -> Result<Topic, crate::Error> {
         let config_code = config.code.clone();
         // check if topic already exists
         {
@@ -646,9 +1600,19 @@ impl Node {
                 ));
             }
         }
+        if let Some(validator) = &config.validator {
+            self.payload_validators
+                .write()
+                .unwrap()
+                .insert(config_code.clone(), validator.clone());
+        }
         tracing::info!(?config, "load_topic");
-        self.propose(Proposal::LoadTopic(LoadTopic { config, queue }))
-            .await?;
+        self.propose(Proposal::LoadTopic(LoadTopic {
+            config,
+            queue,
+            now: self.now(),
+        }))
+        .await?;
         let topics = self.topics.read().unwrap();
         let topic = topics
             .get(&config_code)
@@ -0,0 +1,118 @@
+//! Authorization of in-process callers, see [`Authorizer`].
+//!
+//! Callers present a credential by taking a handle with [`Node::with_credential`](crate::prelude::Node::with_credential),
+//! topics got from that handle carry it too. Edge connections are checked by
+//! [`EdgeAuth`](super::edge::auth::EdgeAuth) instead.
+use std::{borrow::Cow, collections::HashSet, future::Future, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::prelude::TopicCode;
+
+/// What a caller is about to do on a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthorizeAction {
+    LoadTopic,
+    CreateEndpoint,
+    SendMessage,
+}
+
+#[derive(Clone)]
+pub struct AuthorizerService {
+    inner: Arc<dyn sealed::BoxedAuthorizer>,
+    source: Cow<'static, str>,
+}
+
+impl std::fmt::Debug for AuthorizerService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthorizerService")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl AuthorizerService {
+    pub fn new<T>(inner: T) -> Self
+    where
+        T: Authorizer,
+    {
+        Self {
+            inner: Arc::new(inner),
+            source: std::any::type_name::<T>().into(),
+        }
+    }
+    pub async fn authorize(
+        &self,
+        topic: &TopicCode,
+        action: AuthorizeAction,
+        credential: Option<&Bytes>,
+    ) -> bool {
+        self.inner.authorize(topic, action, credential).await
+    }
+}
+
+pub trait Authorizer: Send + Sync + 'static {
+    /// `credential` is `None` for a caller that didn't present any
+    fn authorize<'r>(
+        &'r self,
+        topic: &'r TopicCode,
+        action: AuthorizeAction,
+        credential: Option<&'r Bytes>,
+    ) -> impl Future<Output = bool> + Send + 'r;
+}
+
+/// Allow every action to callers presenting one of a fixed set of tokens.
+#[derive(Debug, Clone, Default)]
+pub struct StaticTokenAuthorizer {
+    tokens: HashSet<Bytes>,
+}
+
+impl StaticTokenAuthorizer {
+    pub fn new<T: Into<Bytes>>(tokens: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Authorizer for StaticTokenAuthorizer {
+    fn authorize<'r>(
+        &'r self,
+        _topic: &'r TopicCode,
+        _action: AuthorizeAction,
+        credential: Option<&'r Bytes>,
+    ) -> impl Future<Output = bool> + Send + 'r {
+        std::future::ready(credential.is_some_and(|token| self.tokens.contains(token)))
+    }
+}
+
+mod sealed {
+    use bytes::Bytes;
+    use futures_util::future::BoxFuture;
+
+    use super::{AuthorizeAction, Authorizer};
+    use crate::prelude::TopicCode;
+
+    pub(super) trait BoxedAuthorizer: Send + Sync {
+        fn authorize<'r>(
+            &'r self,
+            topic: &'r TopicCode,
+            action: AuthorizeAction,
+            credential: Option<&'r Bytes>,
+        ) -> BoxFuture<'r, bool>;
+    }
+
+    impl<T> BoxedAuthorizer for T
+    where
+        T: Authorizer,
+    {
+        fn authorize<'r>(
+            &'r self,
+            topic: &'r TopicCode,
+            action: AuthorizeAction,
+            credential: Option<&'r Bytes>,
+        ) -> BoxFuture<'r, bool> {
+            Box::pin(async move { self.authorize(topic, action, credential).await })
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Time source of a node, see [`Clock`].
+//!
+//! The leader reads the time from its clock when it proposes, and stamps it into the proposal.
+//! Delays, expiry, redelivery deadlines and endpoint freshness are applied as of that time, so
+//! followers never consult their own clock. [`SystemClock`] is the wall clock, [`ManualClock`]
+//! only moves when told to, so tests can age messages and endpoints without sleeping.
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that stands still until it's advanced, clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+    pub fn advance(&self, duration: Duration) {
+        let delta = TimeDelta::from_std(duration).unwrap_or(TimeDelta::max_value());
+        let mut now = self.now.lock().unwrap();
+        *now += delta;
+    }
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.now.lock().unwrap() = time;
+    }
+}
+
+impl Default for ManualClock {
+    /// starts at the current wall clock time
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[derive(Clone)]
+pub struct ClockService {
+    inner: Arc<dyn Clock>,
+    source: Cow<'static, str>,
+}
+
+impl std::fmt::Debug for ClockService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClockService")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl ClockService {
+    pub fn new<T>(inner: T) -> Self
+    where
+        T: Clock,
+    {
+        Self {
+            inner: Arc::new(inner),
+            source: std::any::type_name::<T>().into(),
+        }
+    }
+    pub fn now(&self) -> DateTime<Utc> {
+        self.inner.now()
+    }
+}
+
+impl Default for ClockService {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
@@ -11,8 +11,10 @@ pub use bincode::*;
 pub(crate) mod cbor;
 #[cfg(feature = "cbor")]
 pub use cbor::*;
+pub(crate) mod framing;
 pub(crate) mod json;
 pub use asteroid_mq_model::CodecKind;
+pub use framing::*;
 pub use json::*;
 
 #[derive(Debug)]
@@ -32,6 +34,11 @@ impl CodecError {
             reason: format!("decode error: {}", e).into(),
         }
     }
+    pub fn encode_error<E: std::fmt::Display>(e: E) -> Self {
+        Self {
+            reason: format!("encode error: {}", e).into(),
+        }
+    }
     pub fn unregistered_codec(codec: CodecKind) -> Self {
         Self {
             reason: format!("unregistered codec: {}", codec).into(),
@@ -39,6 +46,73 @@ impl CodecError {
     }
 }
 
+/// Serialize a typed message payload, see [`Topic::send_typed`](crate::prelude::Topic::send_typed).
+pub fn encode_value<T: serde::Serialize>(
+    kind: CodecKind,
+    value: &T,
+) -> Result<Vec<u8>, CodecError> {
+    match kind {
+        #[cfg(feature = "cbor")]
+        CodecKind::CBOR => {
+            let mut buffer = Vec::new();
+            ciborium::into_writer(value, &mut buffer).map_err(CodecError::encode_error)?;
+            Ok(buffer)
+        }
+        CodecKind::BINCODE => ::bincode::serialize(value).map_err(CodecError::encode_error),
+        CodecKind::JSON => serde_json::to_vec(value).map_err(CodecError::encode_error),
+        _ => Err(CodecError::unregistered_codec(kind)),
+    }
+}
+
+/// Byte length of `value` encoded with `kind`, counted without allocating the encoded buffer.
+pub fn encoded_len<T: serde::Serialize>(kind: CodecKind, value: &T) -> Result<usize, CodecError> {
+    match kind {
+        #[cfg(feature = "cbor")]
+        CodecKind::CBOR => {
+            let mut counter = ByteCounter::default();
+            ciborium::into_writer(value, &mut counter).map_err(CodecError::encode_error)?;
+            Ok(counter.0)
+        }
+        CodecKind::BINCODE => ::bincode::serialized_size(value)
+            .map(|size| size as usize)
+            .map_err(CodecError::encode_error),
+        CodecKind::JSON => {
+            let mut counter = ByteCounter::default();
+            serde_json::to_writer(&mut counter, value).map_err(CodecError::encode_error)?;
+            Ok(counter.0)
+        }
+        _ => Err(CodecError::unregistered_codec(kind)),
+    }
+}
+
+/// A sink that only counts the bytes written to it.
+#[derive(Debug, Default)]
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Deserialize a typed message payload, see [`LocalEndpoint::recv_typed`](crate::prelude::LocalEndpoint::recv_typed).
+pub fn decode_value<T: serde::de::DeserializeOwned>(
+    kind: CodecKind,
+    bytes: &[u8],
+) -> Result<T, CodecError> {
+    match kind {
+        #[cfg(feature = "cbor")]
+        CodecKind::CBOR => ciborium::from_reader(bytes).map_err(CodecError::decode_error),
+        CodecKind::BINCODE => ::bincode::deserialize(bytes).map_err(CodecError::decode_error),
+        CodecKind::JSON => serde_json::from_slice(bytes).map_err(CodecError::decode_error),
+        _ => Err(CodecError::unregistered_codec(kind)),
+    }
+}
+
 pub trait Codec: Send + Sync + 'static {
     fn encode(&self, value: &EdgePayload) -> Vec<u8>;
     fn decode(&self, bytes: &[u8]) -> Result<EdgePayload, CodecError>;
@@ -92,3 +166,46 @@ impl CodecRegistry {
             .and_then(|codec| codec.decode(bytes))
     }
 }
+
+#[cfg(test)]
+fn sample_payload() -> EdgePayload {
+    use asteroid_mq_model::{
+        EdgeEndpointOnline, EdgeRequest, EdgeRequestEnum, Interest, TopicCode,
+    };
+    EdgePayload::Request(EdgeRequest {
+        seq_id: 42,
+        request: EdgeRequestEnum::EndpointOnline(EdgeEndpointOnline {
+            topic_code: TopicCode::const_new("codec-test"),
+            interests: vec![Interest::new("region/us/>")],
+        }),
+    })
+}
+
+#[test]
+fn test_codec_round_trip() {
+    let registry = CodecRegistry::new_preloaded();
+    // bincode can't decode the adjacently tagged edge enums, only self-describing codecs round trip
+    let kinds = [
+        Some(CodecKind::JSON),
+        cfg!(feature = "cbor").then_some(CodecKind::CBOR),
+    ];
+    for kind in kinds.into_iter().flatten() {
+        let bytes = registry.encode(kind, &sample_payload()).unwrap();
+        let decoded = registry.decode(kind, &bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(sample_payload()).unwrap(),
+            "round trip through codec {kind}"
+        );
+    }
+}
+
+#[test]
+fn test_codec_kind_mismatch() {
+    let registry = CodecRegistry::new_preloaded();
+    let bytes = registry
+        .encode(CodecKind::BINCODE, &sample_payload())
+        .unwrap();
+    assert!(registry.decode(CodecKind::JSON, &bytes).is_err());
+    assert!(registry.decode(CodecKind(0xff), &bytes).is_err());
+}
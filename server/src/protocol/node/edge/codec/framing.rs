@@ -0,0 +1,104 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::CodecError;
+
+const LENGTH_SIZE: usize = std::mem::size_of::<u32>();
+
+/// A frame larger than this is rejected by [`FramedDecoder::default`].
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Write `value` into `buffer` behind its length, as a 4-byte big-endian `u32`.
+pub fn encode_framed(value: &[u8], buffer: &mut BytesMut) {
+    buffer.reserve(LENGTH_SIZE + value.len());
+    buffer.put_u32(value.len() as u32);
+    buffer.put_slice(value);
+}
+
+/// Take one frame written by [`encode_framed`] from the front of `buffer`.
+///
+/// Returns `Ok(None)` and leaves `buffer` untouched when the frame isn't complete yet, so the
+/// caller can append more bytes from the stream and try again.
+pub fn decode_framed(
+    buffer: &mut BytesMut,
+    max_frame_size: usize,
+) -> Result<Option<Bytes>, CodecError> {
+    if buffer.len() < LENGTH_SIZE {
+        return Ok(None);
+    }
+    let length =
+        u32::from_be_bytes(buffer[..LENGTH_SIZE].try_into().expect("have enough bytes")) as usize;
+    if length > max_frame_size {
+        return Err(CodecError::decode_error(format!(
+            "frame of {length} bytes exceeds the limit of {max_frame_size} bytes"
+        )));
+    }
+    if buffer.len() < LENGTH_SIZE + length {
+        buffer.reserve(LENGTH_SIZE + length - buffer.len());
+        return Ok(None);
+    }
+    buffer.advance(LENGTH_SIZE);
+    Ok(Some(buffer.split_to(length).freeze()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramedEncoder;
+
+impl FramedEncoder {
+    pub fn encode(&self, value: &[u8], buffer: &mut BytesMut) {
+        encode_framed(value, buffer)
+    }
+}
+
+/// Buffers bytes read from a stream and yields whole frames.
+#[derive(Debug)]
+pub struct FramedDecoder {
+    buffer: BytesMut,
+    max_frame_size: usize,
+}
+
+impl Default for FramedDecoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl FramedDecoder {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            max_frame_size,
+        }
+    }
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+    /// the next complete frame, `None` if more bytes are needed
+    pub fn next_frame(&mut self) -> Result<Option<Bytes>, CodecError> {
+        decode_framed(&mut self.buffer, self.max_frame_size)
+    }
+}
+
+#[test]
+fn test_frame_across_chunks() {
+    let mut encoded = BytesMut::new();
+    encode_framed(b"hello", &mut encoded);
+    encode_framed(b"world!", &mut encoded);
+    let (first, second) = encoded.split_at(7);
+
+    let mut decoder = FramedDecoder::default();
+    decoder.extend(first);
+    assert_eq!(decoder.next_frame().unwrap(), None);
+    decoder.extend(second);
+    assert_eq!(decoder.next_frame().unwrap().unwrap().as_ref(), b"hello");
+    assert_eq!(decoder.next_frame().unwrap().unwrap().as_ref(), b"world!");
+    assert_eq!(decoder.next_frame().unwrap(), None);
+}
+
+#[test]
+fn test_frame_too_large() {
+    let mut encoded = BytesMut::new();
+    encode_framed(&[0; 32], &mut encoded);
+    let mut decoder = FramedDecoder::new(16);
+    decoder.extend(&encoded[..LENGTH_SIZE]);
+    assert!(decoder.next_frame().is_err());
+}
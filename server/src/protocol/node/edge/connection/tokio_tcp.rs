@@ -89,7 +89,7 @@ impl Sink<EdgePacket> for TokioTcp {
         let header = item.header;
         this.write_header_buf[0..16].copy_from_slice(&header.id.bytes);
         this.write_header_buf[16] = header.codec.0;
-        this.write_header_buf[17..21].copy_from_slice(&item.payload.len().to_be_bytes());
+        this.write_header_buf[17..21].copy_from_slice(&(item.payload.len() as u32).to_be_bytes());
         *this.write_payload_buf = item.payload;
         Ok(())
     }
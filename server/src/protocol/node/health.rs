@@ -0,0 +1,33 @@
+//! Health of a node for orchestration probes, see [`Node::health`](crate::prelude::Node::health).
+//!
+//! Everything is read from the raft metrics and the node's own bookkeeping as last published, so
+//! taking it never waits on the cluster.
+use openraft::ServerState;
+
+use super::NodeId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+    /// raft role of this node, `None` before raft is initialized
+    pub role: Option<ServerState>,
+    /// the leader as last seen by this node
+    pub leader: Option<NodeId>,
+    /// peers with a live raft connection to this node
+    pub connected_peers: usize,
+    /// voters and learners in the cluster membership, this node excluded
+    pub member_peers: usize,
+    /// log entries stored on this node and not applied to its state machine yet
+    pub applied_lag: u64,
+    /// topics loaded on this node
+    pub topics: usize,
+}
+
+impl NodeHealth {
+    pub fn has_leader(&self) -> bool {
+        self.leader.is_some()
+    }
+    /// A leader is known and every stored log entry is applied.
+    pub fn is_ready(&self) -> bool {
+        self.has_leader() && self.applied_lag == 0
+    }
+}
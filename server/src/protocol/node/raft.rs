@@ -8,13 +8,15 @@ pub mod proposal;
 pub mod raft_node;
 pub mod response;
 pub mod state_machine;
+#[cfg(feature = "tls")]
+pub mod tls;
 use network_factory::{RaftNodeInfo, TcpNetworkService};
 use openraft::{BasicNode, Raft};
 use proposal::Proposal;
 use response::RaftResponse;
 use tokio_util::sync::CancellationToken;
 
-use super::NodeId;
+use super::{NodeId, NodeRef};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Copy)]
 pub struct TypeConfig {
@@ -80,8 +82,9 @@ impl MaybeLoadingRaft {
         &self,
         id: NodeId,
         node: BasicNode,
+        node_ref: NodeRef,
         ct: CancellationToken,
     ) -> TcpNetworkService {
-        TcpNetworkService::new(RaftNodeInfo { id, node }, self.clone(), ct)
+        TcpNetworkService::new(RaftNodeInfo { id, node }, self.clone(), node_ref, ct)
     }
 }
@@ -2,7 +2,8 @@ use std::sync::Arc;
 
 use openraft::{
     error::{
-        ClientWriteError, InstallSnapshotError, RPCError, RaftError, RemoteError, Unreachable,
+        ClientWriteError, Fatal, InstallSnapshotError, RPCError, RaftError, RemoteError,
+        Unreachable,
     },
     raft::{
         AppendEntriesRequest, AppendEntriesResponse, ClientWriteResponse, InstallSnapshotRequest,
@@ -11,13 +12,14 @@ use openraft::{
     BasicNode, RaftNetwork,
 };
 use serde::{Deserialize, Serialize};
-use tokio::{net::TcpStream, sync::oneshot::Receiver};
+use tokio::sync::oneshot::Receiver;
 
 use crate::prelude::NodeId;
 
 use super::{
     network_factory::{RaftNodeInfo, RaftTcpConnection, TcpNetworkService},
-    proposal::Proposal,
+    proposal::{DelegateMessage, DelegateMessages, Proposal},
+    state_machine::topic::wait_ack::WaitAckResult,
     TypeConfig,
 };
 
@@ -32,6 +34,14 @@ pub(super) enum Request {
     AppendEntries(AppendEntriesRequest<TypeConfig>),
     InstallSnapshot(InstallSnapshotRequest<TypeConfig>),
     Proposal(Proposal),
+    /// hold a message on the leader and answer with its ack result
+    SendMessage(DelegateMessage),
+    /// hold a batch on the leader as one proposal and answer with every message's ack result
+    SendMessages(DelegateMessages),
+    /// remove a node from the membership, asked by the node itself
+    Leave(NodeId),
+    /// start an election at once, asked by a leader handing its leadership over
+    Elect,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,6 +57,17 @@ pub(super) enum Response {
             RaftError<NodeId, ClientWriteError<NodeId, BasicNode>>,
         >,
     ),
+    /// the error is why the message couldn't be held
+    SendMessage(Result<WaitAckResult, String>),
+    /// in the order of the batch, the error is why it couldn't be held
+    SendMessages(Result<Vec<WaitAckResult>, String>),
+    Leave(
+        Result<
+            ClientWriteResponse<TypeConfig>,
+            RaftError<NodeId, ClientWriteError<NodeId, BasicNode>>,
+        >,
+    ),
+    Elect(Result<(), Fatal<NodeId>>),
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) enum Payload {
@@ -74,13 +95,10 @@ impl TcpNetwork {
         Self { peer, source }
     }
     async fn create_connection(&self) -> Result<RaftTcpConnection, Unreachable> {
-        let stream = TcpStream::connect(self.peer.node.addr.clone())
-            .await
-            .map_err(|e| Unreachable::new(&e))?;
-        let connection = RaftTcpConnection::from_tokio_tcp_stream(stream, self.source.clone())
+        self.source
+            .connect(&self.peer.node.addr)
             .await
-            .map_err(|e| Unreachable::new(&e))?;
-        Ok(connection)
+            .map_err(|e| Unreachable::new(&e))
     }
     #[tracing::instrument(skip_all, fields(peer_id = ?self.peer.id))]
     async fn send_request(&mut self, req: Request) -> Result<Receiver<Response>, Unreachable> {
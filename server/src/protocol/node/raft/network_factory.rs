@@ -1,18 +1,19 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     ops::Deref,
     sync::{
-        atomic::{self, AtomicBool, AtomicU64},
+        atomic::{self, AtomicBool, AtomicU64, AtomicUsize},
         Arc, OnceLock,
     },
 };
 
 use openraft::{
-    error::Unreachable, raft::ClientWriteResponse, BasicNode, Raft, RaftNetworkFactory,
+    error::Unreachable, raft::ClientWriteResponse, BasicNode, ChangeMembers, Raft,
+    RaftNetworkFactory,
 };
 use serde::{Deserialize, Serialize};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::{TcpListener, TcpStream},
     sync::oneshot,
 };
@@ -21,19 +22,27 @@ use tracing::Instrument;
 
 use crate::{
     prelude::NodeId,
-    protocol::node::raft::{network::TcpNetwork, TypeConfig},
+    protocol::node::{
+        raft::{network::TcpNetwork, TypeConfig},
+        NodeRef,
+    },
 };
 
 use super::{
     network::{Packet, Payload, Request, Response},
-    proposal::Proposal,
+    proposal::{DelegateMessage, DelegateMessages, Proposal},
+    state_machine::topic::wait_ack::WaitAckResult,
     MaybeLoadingRaft,
 };
 #[derive(Clone, Debug)]
 pub struct TcpNetworkService {
     pub info: RaftNodeInfo,
     pub raft: MaybeLoadingRaft,
+    /// the node served, for requests beyond raft itself
+    pub node: NodeRef,
     pub connections: RaftTcpConnectionMap,
+    /// connections still alive, read without locking `connections`
+    pub live_connections: Arc<AtomicUsize>,
     pub service_task: Arc<OnceLock<tokio::task::JoinHandle<()>>>,
     pub ct: CancellationToken,
 }
@@ -51,6 +60,9 @@ impl TcpNetworkService {
                     async move {
                         let inner_task = async move {
                             let tcp_listener = TcpListener::bind(info.node.addr.clone()).await?;
+                            #[cfg(feature = "tls")]
+                            let acceptor =
+                                tcp_service.tls().map(|tls| tls.acceptor()).transpose()?;
                             loop {
                                 let accepted = tokio::select! {
                                     _ = ct.cancelled() => {
@@ -63,12 +75,21 @@ impl TcpNetworkService {
                                 let Ok((stream, _)) = accepted else {
                                     continue;
                                 };
-                                if let Ok(connection) = RaftTcpConnection::from_tokio_tcp_stream(
-                                    stream,
-                                    tcp_service.clone(),
-                                )
-                                .await
-                                {
+                                let connection = async {
+                                    #[cfg(feature = "tls")]
+                                    if let Some(acceptor) = &acceptor {
+                                        let stream = acceptor.accept(stream).await?;
+                                        return RaftTcpConnection::from_stream(
+                                            stream,
+                                            tcp_service.clone(),
+                                        )
+                                        .await;
+                                    }
+                                    RaftTcpConnection::from_stream(stream, tcp_service.clone())
+                                        .await
+                                }
+                                .await;
+                                if let Ok(connection) = connection {
                                     let peer_id = connection.peer_id();
                                     // we only accept node with smaller id
                                     if peer_id >= info.id {
@@ -126,6 +147,7 @@ pub struct RaftTcpConnection {
     wait_poll: Arc<tokio::sync::Mutex<HashMap<u64, oneshot::Sender<Response>>>>,
     local_seq: Arc<AtomicU64>,
     alive: Arc<AtomicBool>,
+    live_connections: Arc<AtomicUsize>,
     read_task: tokio::task::JoinHandle<()>,
     write_task: tokio::task::JoinHandle<()>,
 }
@@ -135,7 +157,14 @@ impl Drop for RaftTcpConnection {
         tracing::info!("connection dropped");
         self.read_task.abort();
         self.write_task.abort();
-        self.alive.store(false, atomic::Ordering::Relaxed);
+        mark_dead(&self.alive, &self.live_connections);
+    }
+}
+
+/// turn `alive` off, counting the connection out of `live_connections` the first time only
+fn mark_dead(alive: &AtomicBool, live_connections: &AtomicUsize) {
+    if alive.swap(false, atomic::Ordering::Relaxed) {
+        live_connections.fetch_sub(1, atomic::Ordering::Relaxed);
     }
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -176,6 +205,88 @@ impl RaftTcpConnection {
         let resp = resp.map_err(crate::Error::contextual("remote proposal"))?;
         Ok(resp)
     }
+    /// Ask the peer, the leader, to remove `node` from the membership.
+    pub(crate) async fn leave(
+        &self,
+        node: NodeId,
+    ) -> crate::Result<ClientWriteResponse<TypeConfig>> {
+        let resp = self
+            .send_request(Request::Leave(node))
+            .await
+            .map_err(crate::Error::contextual_custom("sending leave to remote"))?;
+        let resp = resp.await.map_err(crate::Error::contextual_custom(
+            "waiting for leave response",
+        ))?;
+        let Response::Leave(resp) = resp else {
+            return Err(crate::Error::unknown("unexpected response"));
+        };
+        let resp = resp.map_err(crate::Error::contextual("remote leave"))?;
+        Ok(resp)
+    }
+    /// Ask the peer to start an election at once, see `Node::transfer_leadership`.
+    pub(crate) async fn elect(&self) -> crate::Result<()> {
+        let resp = self
+            .send_request(Request::Elect)
+            .await
+            .map_err(crate::Error::contextual_custom("sending elect to remote"))?;
+        let resp = resp.await.map_err(crate::Error::contextual_custom(
+            "waiting for elect response",
+        ))?;
+        let Response::Elect(resp) = resp else {
+            return Err(crate::Error::unknown("unexpected response"));
+        };
+        resp.map_err(crate::Error::contextual_custom("remote elect"))
+    }
+    /// Hold a message on the peer, the returned future resolves with its ack result there.
+    pub(crate) async fn send_message(
+        &self,
+        message: DelegateMessage,
+    ) -> crate::Result<impl std::future::Future<Output = crate::Result<WaitAckResult>>> {
+        let resp = self
+            .send_request(Request::SendMessage(message))
+            .await
+            .map_err(crate::Error::contextual_custom("sending message to remote"))?;
+        Ok(async move {
+            let resp = resp.await.map_err(crate::Error::contextual_custom(
+                "waiting for remote message result",
+            ))?;
+            let Response::SendMessage(result) = resp else {
+                return Err(crate::Error::unknown("unexpected response"));
+            };
+            result.map_err(|reason| {
+                crate::Error::new(
+                    "remote send message",
+                    crate::error::ErrorKind::Custom(reason.into()),
+                )
+            })
+        })
+    }
+    /// Hold a batch on the peer, the returned future resolves with the ack results of its messages.
+    pub(crate) async fn send_messages(
+        &self,
+        messages: DelegateMessages,
+    ) -> crate::Result<impl std::future::Future<Output = crate::Result<Vec<WaitAckResult>>>> {
+        let resp = self
+            .send_request(Request::SendMessages(messages))
+            .await
+            .map_err(crate::Error::contextual_custom(
+                "sending messages to remote",
+            ))?;
+        Ok(async move {
+            let resp = resp.await.map_err(crate::Error::contextual_custom(
+                "waiting for remote messages results",
+            ))?;
+            let Response::SendMessages(results) = resp else {
+                return Err(crate::Error::unknown("unexpected response"));
+            };
+            results.map_err(|reason| {
+                crate::Error::new(
+                    "remote send messages",
+                    crate::error::ErrorKind::Custom(reason.into()),
+                )
+            })
+        })
+    }
     pub(super) async fn send_request(
         &self,
         req: Request,
@@ -199,10 +310,18 @@ impl RaftTcpConnection {
         Ok(receiver)
     }
     pub async fn from_tokio_tcp_stream(
-        mut stream: TcpStream,
+        stream: TcpStream,
         service: TcpNetworkService,
     ) -> std::io::Result<Self> {
+        Self::from_stream(stream, service).await
+    }
+    /// Set up a connection over any stream, a plain tcp stream or a TLS one wrapping it.
+    pub async fn from_stream<S>(mut stream: S, service: TcpNetworkService) -> std::io::Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         let raft = service.raft.get().await;
+        let node_ref = service.node.clone();
         let info = service.info.clone();
         let packet = bincode::serialize(&info).map_err(|_| std::io::ErrorKind::InvalidData)?;
         stream.write_u32(packet.len() as u32).await?;
@@ -213,7 +332,7 @@ impl RaftTcpConnection {
         let peer: RaftNodeInfo =
             bincode::deserialize(&hello_data).map_err(|_| std::io::ErrorKind::InvalidData)?;
         tracing::debug!(?peer, "hello received");
-        let (mut read, mut write) = stream.into_split();
+        let (mut read, mut write) = tokio::io::split(stream);
         let wait_pool = Arc::new(tokio::sync::Mutex::new(HashMap::<
             u64,
             oneshot::Sender<Response>,
@@ -257,10 +376,13 @@ impl RaftTcpConnection {
             )),
         );
         let alive = Arc::new(AtomicBool::new(true));
+        let live_connections = service.live_connections.clone();
+        live_connections.fetch_add(1, atomic::Ordering::Relaxed);
         let read_task_ct = service.ct.child_token();
         let read_task = {
             let packet_tx = packet_tx.clone();
             let alive = alive.clone();
+            let live_connections = live_connections.clone();
             let inner_task = async move {
                 let mut buffer = Vec::with_capacity(BUFFER_CAPACITY);
                 loop {
@@ -292,6 +414,7 @@ impl RaftTcpConnection {
                     match payload {
                         Payload::Request(req) => {
                             let raft = raft.clone();
+                            let node_ref = node_ref.clone();
                             let packet_tx = packet_tx.clone();
                             tokio::spawn(async move {
                                 let resp = match req {
@@ -302,9 +425,38 @@ impl RaftTcpConnection {
                                     Request::InstallSnapshot(install) => Response::InstallSnapshot(
                                         raft.install_snapshot(install).await,
                                     ),
-                                    Request::Proposal(proposal) => {
+                                    Request::Proposal(mut proposal) => {
+                                        if let Some(node) = node_ref.upgrade() {
+                                            proposal.stamp(node.now());
+                                        }
                                         Response::Proposal(raft.client_write(proposal).await)
                                     }
+                                    Request::SendMessage(message) => {
+                                        let result = match node_ref.upgrade() {
+                                            Some(node) => {
+                                                node.hold_forwarded_message(message).await
+                                            }
+                                            None => Err(crate::Error::unknown("node dropped")),
+                                        };
+                                        Response::SendMessage(result.map_err(|e| e.to_string()))
+                                    }
+                                    Request::SendMessages(messages) => {
+                                        let results = match node_ref.upgrade() {
+                                            Some(node) => {
+                                                node.hold_forwarded_messages(messages).await
+                                            }
+                                            None => Err(crate::Error::unknown("node dropped")),
+                                        };
+                                        Response::SendMessages(results.map_err(|e| e.to_string()))
+                                    }
+                                    Request::Leave(node) => Response::Leave(
+                                        raft.change_membership(
+                                            ChangeMembers::RemoveVoters(BTreeSet::from([node])),
+                                            false,
+                                        )
+                                        .await,
+                                    ),
+                                    Request::Elect => Response::Elect(raft.trigger().elect().await),
                                 };
                                 let payload = Payload::Response(resp);
                                 let _ = packet_tx.send_async(Packet { seq_id, payload }).await;
@@ -326,7 +478,7 @@ impl RaftTcpConnection {
                 if let Err(e) = result {
                     tracing::error!(%e, "read task error");
                 }
-                alive.store(false, atomic::Ordering::Relaxed);
+                mark_dead(&alive, &live_connections);
             })
         };
         Ok(Self {
@@ -335,6 +487,7 @@ impl RaftTcpConnection {
             peer,
             local_seq: Arc::new(AtomicU64::new(0)),
             alive,
+            live_connections,
             read_task,
             write_task,
         })
@@ -342,11 +495,18 @@ impl RaftTcpConnection {
 }
 
 impl TcpNetworkService {
-    pub fn new(info: RaftNodeInfo, raft: MaybeLoadingRaft, ct: CancellationToken) -> Self {
+    pub fn new(
+        info: RaftNodeInfo,
+        raft: MaybeLoadingRaft,
+        node: NodeRef,
+        ct: CancellationToken,
+    ) -> Self {
         Self {
             info,
             raft,
+            node,
             connections: RaftTcpConnectionMap::default(),
+            live_connections: Default::default(),
             service_task: Arc::new(OnceLock::new()),
             ct,
         }
@@ -354,6 +514,23 @@ impl TcpNetworkService {
     pub fn set_raft(&self, raft: Raft<TypeConfig>) {
         self.raft.set(raft);
     }
+    #[cfg(feature = "tls")]
+    fn tls(&self) -> Option<super::tls::TlsConfig> {
+        self.node
+            .upgrade()
+            .and_then(|node| node.config().tls.clone())
+    }
+    /// Open a connection to the peer at `addr`, over TLS if this node is configured with it.
+    pub(super) async fn connect(&self, addr: &str) -> std::io::Result<RaftTcpConnection> {
+        let stream = TcpStream::connect(addr).await?;
+        #[cfg(feature = "tls")]
+        if let Some(tls) = self.tls() {
+            let server_name = tls.server_name(stream.peer_addr()?)?;
+            let stream = tls.connector()?.connect(server_name, stream).await?;
+            return RaftTcpConnection::from_stream(stream, self.clone()).await;
+        }
+        RaftTcpConnection::from_stream(stream, self.clone()).await
+    }
 }
 
 impl RaftNetworkFactory<TypeConfig> for TcpNetworkService {
@@ -3,16 +3,20 @@ use std::{
     sync::Arc,
 };
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::Instrument;
 
 use crate::{
-    prelude::{DurableMessage, DurableService, MessageId, Node, TopicCode},
+    lifecycle::LifecycleEvent,
+    prelude::{DurableService, MessageId, Node, TopicCode},
     protocol::{endpoint::EndpointAddr, message::*, topic::durable_message::DurableCommand},
 };
 
-use super::state_machine::topic::wait_ack::WaitAckResult;
+use super::state_machine::topic::{
+    config::TopicChecksum,
+    wait_ack::{WaitAckError, WaitAckErrorException, WaitAckResult},
+};
 pub(crate) mod ep_online;
 pub use ep_online::EndpointOnline;
 pub(crate) mod ep_offline;
@@ -27,6 +31,28 @@ pub(crate) mod unload_topic;
 pub use unload_topic::UnloadTopic;
 pub(crate) mod delegate_message;
 pub use delegate_message::DelegateMessage;
+pub(crate) mod force_resolve;
+pub use force_resolve::{ForceResolve, ForceResolveOutcome};
+pub(crate) mod node_offline;
+pub use node_offline::NodeOffline;
+pub(crate) mod expire_messages;
+pub use expire_messages::ExpireMessages;
+pub(crate) mod release_delayed;
+pub use release_delayed::ReleaseDelayed;
+pub(crate) mod delegate_messages;
+pub use delegate_messages::DelegateMessages;
+pub(crate) mod ep_heartbeat;
+pub use ep_heartbeat::EndpointHeartbeat;
+pub(crate) mod redeliver_messages;
+pub use redeliver_messages::RedeliverMessages;
+pub(crate) mod update_topic_config;
+pub use update_topic_config::UpdateTopicConfig;
+pub(crate) mod eps_online;
+pub use eps_online::EndpointsOnline;
+pub(crate) mod import_snapshot;
+pub use import_snapshot::ImportSnapshot;
+pub(crate) mod set_states;
+pub use set_states::SetStates;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Proposal {
@@ -43,21 +69,139 @@ pub enum Proposal {
     EpOffline(EndpointOffline),
     /// En Interest: set endpoint's interests.
     EpInterest(EndpointInterest),
+    /// Force Resolve: break-glass resolution of a stuck message.
+    ForceResolve(ForceResolve),
+    /// Node Offline: report all endpoints hosted by a departed node offline.
+    NodeOffline(NodeOffline),
+    /// Expire Messages: drop held messages past their `expire_at`.
+    ExpireMessages(ExpireMessages),
+    /// Release Delayed: hold delayed messages past their `deliver_at`.
+    ReleaseDelayed(ReleaseDelayed),
+    /// Hold Messages: hold a batch of messages of one topic.
+    DelegateMessages(DelegateMessages),
+    /// En Heartbeat: refresh endpoint's latest activity.
+    EpHeartbeat(EndpointHeartbeat),
+    /// Redeliver Messages: offer messages again to endpoints past their ack deadline.
+    RedeliverMessages(RedeliverMessages),
+    /// Update Topic Config: change the mutable settings of a loaded topic.
+    UpdateTopicConfig(UpdateTopicConfig),
+    /// Eps Online: a batch of endpoints of one topic come online.
+    EpsOnline(EndpointsOnline),
+    /// Import Snapshot: merge an exported topic snapshot into a loaded topic.
+    ImportSnapshot(ImportSnapshot),
+    /// Set States: set the ack states collected during one flush window.
+    SetStates(SetStates),
+}
+impl Proposal {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Proposal::DelegateMessage(_) => "DelegateMessage",
+            Proposal::SetState(_) => "SetState",
+            Proposal::LoadTopic(_) => "LoadTopic",
+            Proposal::UnloadTopic(_) => "UnloadTopic",
+            Proposal::EpOnline(_) => "EpOnline",
+            Proposal::EpOffline(_) => "EpOffline",
+            Proposal::EpInterest(_) => "EpInterest",
+            Proposal::ForceResolve(_) => "ForceResolve",
+            Proposal::NodeOffline(_) => "NodeOffline",
+            Proposal::ExpireMessages(_) => "ExpireMessages",
+            Proposal::ReleaseDelayed(_) => "ReleaseDelayed",
+            Proposal::DelegateMessages(_) => "DelegateMessages",
+            Proposal::EpHeartbeat(_) => "EpHeartbeat",
+            Proposal::RedeliverMessages(_) => "RedeliverMessages",
+            Proposal::UpdateTopicConfig(_) => "UpdateTopicConfig",
+            Proposal::EpsOnline(_) => "EpsOnline",
+            Proposal::ImportSnapshot(_) => "ImportSnapshot",
+            Proposal::SetStates(_) => "SetStates",
+        }
+    }
+    /// the leader's time stamped into the proposal
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Proposal::DelegateMessage(proposal) => proposal.now,
+            Proposal::SetState(proposal) => proposal.now,
+            Proposal::LoadTopic(proposal) => proposal.now,
+            Proposal::UnloadTopic(proposal) => proposal.now,
+            Proposal::EpOnline(proposal) => proposal.now,
+            Proposal::EpOffline(proposal) => proposal.now,
+            Proposal::EpInterest(proposal) => proposal.now,
+            Proposal::ForceResolve(proposal) => proposal.now,
+            Proposal::NodeOffline(proposal) => proposal.now,
+            Proposal::ExpireMessages(proposal) => proposal.now,
+            Proposal::ReleaseDelayed(proposal) => proposal.now,
+            Proposal::DelegateMessages(proposal) => proposal.now,
+            Proposal::EpHeartbeat(proposal) => proposal.now,
+            Proposal::RedeliverMessages(proposal) => proposal.now,
+            Proposal::UpdateTopicConfig(proposal) => proposal.now,
+            Proposal::EpsOnline(proposal) => proposal.now,
+            Proposal::ImportSnapshot(proposal) => proposal.now,
+            Proposal::SetStates(proposal) => proposal.now,
+        }
+    }
+    /// Stamp the leader's time into the proposal before it's written to the log.
+    ///
+    /// Applying a proposal reads the time from it, so every node ends up in the same state no
+    /// matter how its own clock runs.
+    pub(crate) fn stamp(&mut self, now: DateTime<Utc>) {
+        match self {
+            Proposal::DelegateMessage(proposal) => proposal.now = now,
+            Proposal::SetState(proposal) => proposal.now = now,
+            Proposal::LoadTopic(proposal) => proposal.now = now,
+            Proposal::UnloadTopic(proposal) => proposal.now = now,
+            Proposal::EpOnline(proposal) => proposal.now = now,
+            Proposal::EpOffline(proposal) => proposal.now = now,
+            Proposal::EpInterest(proposal) => proposal.now = now,
+            Proposal::ForceResolve(proposal) => proposal.now = now,
+            Proposal::NodeOffline(proposal) => proposal.now = now,
+            Proposal::ExpireMessages(proposal) => proposal.now = now,
+            Proposal::ReleaseDelayed(proposal) => proposal.now = now,
+            Proposal::DelegateMessages(proposal) => proposal.now = now,
+            Proposal::EpHeartbeat(proposal) => proposal.now = now,
+            Proposal::RedeliverMessages(proposal) => proposal.now = now,
+            Proposal::UpdateTopicConfig(proposal) => proposal.now = now,
+            Proposal::EpsOnline(proposal) => {
+                proposal.now = now;
+                for online in &mut proposal.endpoints {
+                    online.now = now;
+                }
+            }
+            Proposal::ImportSnapshot(proposal) => proposal.now = now,
+            Proposal::SetStates(proposal) => proposal.now = now,
+        }
+    }
 }
 #[derive(Debug, Clone)]
 pub struct ProposalContext {
     pub node: Node,
     pub topic_code: Option<TopicCode>,
+    now: DateTime<Utc>,
 }
 
 impl ProposalContext {
-    pub fn new(node: Node) -> Self {
+    /// `now` is the time stamped into the proposal being applied
+    pub fn new(node: Node, now: DateTime<Utc>) -> Self {
         Self {
             node,
             topic_code: None,
+            now,
         }
     }
+    /// the time the proposal being applied was stamped with
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+    pub(crate) fn emit_event(&self, event: LifecycleEvent) {
+        self.node.lifecycle.emit(event);
+    }
     pub fn push_durable_command(&mut self, command: DurableCommand) {
+        let is_ephemeral = self
+            .topic_code
+            .as_ref()
+            .and_then(|code| self.node.get_topic(code))
+            .is_some_and(|topic| topic.ephemeral);
+        if is_ephemeral {
+            return;
+        }
         self.node.push_durable_commands(Some(command));
     }
     pub fn commit_durable_commands(&mut self) {
@@ -75,7 +219,7 @@ impl ProposalContext {
                     let sync_lock = node.get_durable_lock(topic_code.clone()).await;
                     let _sync_guard = sync_lock.lock().await;
                     let commands = node.swap_out_durable_commands();
-                    if node.raft().await.ensure_linearizable().await.is_err() {
+                    if node.ensure_leader().await.is_err() {
                         tracing::trace!("raft not leader, skip durable commands");
                         return;
                     } else {
@@ -84,18 +228,7 @@ impl ProposalContext {
                     for command in commands {
                         let topic = topic_code.clone();
                         let result = match command {
-                            DurableCommand::Create(command) => {
-                                service
-                                    .save(
-                                        topic,
-                                        DurableMessage {
-                                            message: command,
-                                            status: Default::default(),
-                                            time: Utc::now(),
-                                        },
-                                    )
-                                    .await
-                            }
+                            DurableCommand::Create(command) => service.save(topic, *command).await,
                             DurableCommand::UpdateStatus(command) => {
                                 service.update_status(topic, command).await
                             }
@@ -119,9 +252,28 @@ impl ProposalContext {
         self.topic_code = Some(code);
     }
     pub fn resolve_ack(&self, id: MessageId, result: WaitAckResult) {
+        #[cfg(feature = "metrics")]
+        if let Err(super::state_machine::topic::wait_ack::WaitAckError {
+            exception: Some(exception),
+            ..
+        }) = &result
+        {
+            self.node.metrics.message_dropped(*exception);
+        }
         let Some(ref code) = self.topic_code else {
             return;
         };
+        if let Err(WaitAckError {
+            exception: Some(reason),
+            ..
+        }) = &result
+        {
+            self.emit_event(LifecycleEvent::Dropped {
+                topic: code.clone(),
+                message_id: id,
+                reason: *reason,
+            });
+        }
         let Some(topic) = self.node.get_topic(code) else {
             return;
         };
@@ -131,8 +283,138 @@ impl ProposalContext {
             }
         });
     }
-    #[tracing::instrument(skip(self))]
+    pub fn report_corrupted_message(&self, id: MessageId) {
+        let Some(ref code) = self.topic_code else {
+            return;
+        };
+        tracing::error!(topic = %code, message_id = %id, "payload checksum mismatch");
+        if let Some(topic) = self.node.get_topic(code) {
+            topic
+                .corrupted_messages
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+    /// hand a newly held message to the topic mirror, only the leader forwards
+    pub fn mirror_message(&self, message: &Message) {
+        let Some(ref code) = self.topic_code else {
+            return;
+        };
+        let Some(mirror) = self
+            .node
+            .get_topic(code)
+            .and_then(|topic| topic.mirror.clone())
+        else {
+            return;
+        };
+        let is_leader = self
+            .node
+            .raft_opt()
+            .is_some_and(|raft| raft.metrics().borrow().current_leader == Some(self.node.id()));
+        if is_leader {
+            mirror.push(message.clone());
+        }
+    }
+    /// re-publish a failed message into the topic's dead-letter topic, only the leader does
+    pub fn dead_letter(
+        &self,
+        message: &Message,
+        offloaded: bool,
+        exception: Option<WaitAckErrorException>,
+    ) {
+        let Some(ref code) = self.topic_code else {
+            return;
+        };
+        let Some(topic) = self.node.get_topic(code) else {
+            return;
+        };
+        let Some(dead_letter) = topic.dead_letter.read().unwrap().clone() else {
+            return;
+        };
+        // never loop, a failing dead-letter topic just loses the message
+        if message.header.dead_letter.is_some() || &dead_letter == code {
+            return;
+        }
+        let is_leader = self
+            .node
+            .raft_opt()
+            .is_some_and(|raft| raft.metrics().borrow().current_leader == Some(self.node.id()));
+        if !is_leader {
+            return;
+        }
+        tracing::debug!(message_id = %message.id(), from = %code, to = %dead_letter, ?exception, "dead letter");
+        let mut message = message.clone();
+        let node = self.node.clone();
+        let from = code.clone();
+        tokio::spawn(async move {
+            if offloaded {
+                // deliver it for inspection even if the stored payload doesn't verify
+                match topic.load_payload(message, TopicChecksum::None).await {
+                    Some(loaded) => message = loaded,
+                    None => {
+                        tracing::warn!(topic = %from, "dead letter payload is gone");
+                        return;
+                    }
+                }
+            }
+            message.header.sequence = None;
+            message.header.expire_at = None;
+            message.header.dead_letter = Some(DeadLetter {
+                topic: from,
+                exception,
+            });
+            let result = match node.get_or_reload_topic(&dead_letter).await {
+                Ok(_) => {
+                    node.propose(Proposal::DelegateMessage(DelegateMessage {
+                        topic: dead_letter.clone(),
+                        message,
+                        now: node.now(),
+                    }))
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::error!(?e, topic = %dead_letter, "dead letter failed");
+            }
+        });
+    }
+    /// wake producers waiting for room in the topic's queue
+    pub fn notify_space(&self) {
+        let Some(ref code) = self.topic_code else {
+            return;
+        };
+        if let Some(topic) = self.node.get_topic(code) {
+            topic.space_notify.notify_waiters();
+        }
+    }
+    /// keep a newly held payload at hand for a topic with lazy payloads
+    pub fn cache_payload(&self, message: &Message) {
+        let Some(ref code) = self.topic_code else {
+            return;
+        };
+        if let Some(topic) = self.node.get_topic(code) {
+            topic.cache_payload(message);
+        }
+    }
     pub fn dispatch_message(&self, message: &Message, endpoint: EndpointAddr) {
+        self.schedule_dispatch(message, endpoint, None)
+    }
+    /// dispatch a message whose payload was offloaded, it's loaded and verified with `checksum` first
+    pub fn dispatch_offloaded_message(
+        &self,
+        message: &Message,
+        checksum: TopicChecksum,
+        endpoint: EndpointAddr,
+    ) {
+        self.schedule_dispatch(message, endpoint, Some(checksum))
+    }
+    #[tracing::instrument(skip(self))]
+    pub(crate) fn schedule_dispatch(
+        &self,
+        message: &Message,
+        endpoint: EndpointAddr,
+        offloaded: Option<TopicChecksum>,
+    ) {
         let Some(ref code) = self.topic_code else {
             // topic code is not set
             tracing::warn!("topic code is not set");
@@ -143,28 +425,53 @@ impl ProposalContext {
             tracing::warn!(?code, "topic not found");
             return;
         };
+        if topic.park_if_paused(message, endpoint, offloaded) {
+            tracing::debug!(?code, ?endpoint, "topic paused, delivery kept back");
+            return;
+        }
         let message = message.clone();
-        let code = code.clone();
         let node = self.node.clone();
-        tokio::spawn(async move {
+        let topic_code = code.clone();
+        // dispatch runs on the fair scheduler, so a hot topic can't starve the others
+        let job = async move {
             let message_id = message.id();
-            let status = topic
-                .dispatch_message(message, &endpoint)
-                .await
-                .unwrap_or(MessageStatusKind::Unreachable);
-            let proposal_result = node
-                .propose(Proposal::SetState(SetState {
-                    topic: code,
-                    update: MessageStateUpdate::new(
-                        message_id,
-                        HashMap::from([(endpoint, status)]),
-                    ),
-                }))
-                .await;
-            if let Err(err) = proposal_result {
-                tracing::error!(?err, "set state failed");
+            let message = match offloaded {
+                Some(checksum) => topic.load_payload(message, checksum).await,
+                None => Some(message),
+            };
+            let status = match message {
+                Some(message) => topic.dispatch_message(message, &endpoint).await,
+                None => None,
             }
-        });
+            .unwrap_or(MessageStatusKind::Unreachable);
+            if status == MessageStatusKind::Sent {
+                node.lifecycle.emit(LifecycleEvent::Delivered {
+                    topic: topic_code.clone(),
+                    message_id,
+                    endpoint,
+                });
+            }
+            tokio::spawn(async move {
+                let proposal_result = node
+                    .propose(Proposal::SetState(SetState {
+                        topic: topic_code,
+                        update: MessageStateUpdate::new(
+                            message_id,
+                            HashMap::from([(endpoint, status)]),
+                        ),
+                        now: node.now(),
+                    }))
+                    .await;
+                if let Err(err) = proposal_result {
+                    tracing::error!(?err, "set state failed");
+                }
+            });
+        };
+        self.node.dispatch_scheduler.schedule(
+            code.clone(),
+            endpoint,
+            Box::pin(job.in_current_span()),
+        );
     }
 }
 
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::prelude::{Message, TopicCode};
@@ -6,4 +7,6 @@ use crate::prelude::{Message, TopicCode};
 pub struct DelegateMessage {
     pub topic: TopicCode,
     pub message: Message,
+    /// the leader's time when it proposed the message, targets are picked as of it
+    pub now: DateTime<Utc>,
 }
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{Message, TopicCode};
+
+/// Messages published together, held in order as one log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateMessages {
+    pub topic: TopicCode,
+    pub messages: Vec<Message>,
+    /// the leader's time when it proposed the messages, targets are picked as of it
+    pub now: DateTime<Utc>,
+}
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{EndpointAddr, TopicCode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct EndpointHeartbeat {
+    pub topic_code: TopicCode,
+    pub endpoint: EndpointAddr,
+    /// the leader's time when it proposed the heartbeat, the endpoint counts as active as of it
+    pub now: DateTime<Utc>,
+}
@@ -1 +1,15 @@
-pub use asteroid_mq_model::EndpointInterest;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{EndpointAddr, Interest, TopicCode};
+
+/// New interests of an endpoint, the edge's
+/// [`EndpointInterest`](asteroid_mq_model::EndpointInterest) as written to the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointInterest {
+    pub topic_code: TopicCode,
+    pub endpoint: EndpointAddr,
+    pub interests: Vec<Interest>,
+    /// the leader's time when it proposed the update, the endpoint counts as active as of it
+    pub now: DateTime<Utc>,
+}
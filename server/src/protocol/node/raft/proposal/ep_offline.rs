@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::prelude::{EndpointAddr, NodeId, TopicCode};
@@ -8,4 +9,6 @@ pub struct EndpointOffline {
     pub topic_code: TopicCode,
     pub endpoint: EndpointAddr,
     pub host: NodeId,
+    /// the leader's time when it proposed the endpoint going offline
+    pub now: DateTime<Utc>,
 }
@@ -1,6 +1,8 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::prelude::{EndpointAddr, Interest, NodeId, TopicCode};
+use crate::prelude::{EndpointAddr, GroupId, Interest, MessageFilter, NodeId, TopicCode};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 
@@ -9,4 +11,20 @@ pub struct EndpointOnline {
     pub endpoint: EndpointAddr,
     pub interests: Vec<Interest>,
     pub host: NodeId,
+    /// share of push messages relative to other endpoints, 0 counts as 1
+    #[serde(default)]
+    pub weight: u32,
+    #[serde(default)]
+    pub group: Option<GroupId>,
+    /// messages not matching it are never sent to the endpoint
+    #[serde(default)]
+    pub filter: Option<MessageFilter>,
+    /// max messages sent to the endpoint and not acked yet, `None` sends without limit
+    #[serde(default)]
+    pub prefetch: Option<u32>,
+    /// stable key a restarting consumer reclaims the endpoint with
+    #[serde(default)]
+    pub key: Option<Bytes>,
+    /// the leader's time when it proposed the endpoint, it counts as active as of it
+    pub now: DateTime<Utc>,
 }
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+use super::EndpointOnline;
+
+/// Endpoints of one topic coming online together, applied as one log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointsOnline {
+    pub topic_code: TopicCode,
+    pub endpoints: Vec<EndpointOnline>,
+    /// the leader's time when it proposed the endpoints, they count as active as of it
+    pub now: DateTime<Utc>,
+}
@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct ExpireMessages {
+    pub topic: TopicCode,
+    /// the leader's time when it proposed the expiry, messages expired by then are dropped
+    pub now: DateTime<Utc>,
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{MessageId, TopicCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForceResolveOutcome {
+    /// report success to the producer, with whatever status was collected so far
+    Success,
+    /// report failure to the producer, with whatever status was collected so far
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct ForceResolve {
+    pub topic: TopicCode,
+    pub message_id: MessageId,
+    pub outcome: ForceResolveOutcome,
+    /// the leader's time when it proposed the resolution
+    pub now: DateTime<Utc>,
+}
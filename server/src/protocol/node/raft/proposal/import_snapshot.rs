@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{TopicCode, TopicSnapshot};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSnapshot {
+    pub topic: TopicCode,
+    /// boxed, or every [`Proposal`](super::Proposal) would be as large as a topic snapshot
+    pub snapshot: Box<TopicSnapshot>,
+    /// the leader's time when it proposed the import, imported messages are held as of it
+    pub now: DateTime<Utc>,
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -8,13 +9,16 @@ use crate::{
 pub struct LoadTopic {
     pub config: TopicConfig,
     pub queue: Vec<DurableMessage>,
+    /// the leader's time when it proposed the load, loaded messages are held as of it
+    pub now: DateTime<Utc>,
 }
 
 impl LoadTopic {
-    pub fn from_config<C: Into<TopicConfig>>(config: C) -> Self {
+    pub fn from_config<C: Into<TopicConfig>>(config: C, now: DateTime<Utc>) -> Self {
         Self {
             config: config.into(),
             queue: Vec::new(),
+            now,
         }
     }
 }
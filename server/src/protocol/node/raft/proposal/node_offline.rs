@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::NodeId;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct NodeOffline {
+    pub node: NodeId,
+    /// the leader's time when it proposed the node going offline
+    pub now: DateTime<Utc>,
+}
@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct RedeliverMessages {
+    pub topic: TopicCode,
+    /// the leader's time when it proposed the redelivery, deliveries unacked by then are sent again
+    pub now: DateTime<Utc>,
+}
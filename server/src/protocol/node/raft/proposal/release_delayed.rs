@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct ReleaseDelayed {
+    pub topic: TopicCode,
+    /// the leader's time when it proposed the release, messages due by then are released
+    pub now: DateTime<Utc>,
+}
@@ -1 +1,16 @@
-pub use asteroid_mq_model::{MessageStateUpdate, SetState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+pub use asteroid_mq_model::MessageStateUpdate;
+
+/// An ack of one message, the edge's [`SetState`](asteroid_mq_model::SetState) as written to the
+/// log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetState {
+    pub topic: TopicCode,
+    pub update: MessageStateUpdate,
+    /// the leader's time when it proposed the ack, redelivery deadlines count from it
+    pub now: DateTime<Utc>,
+}
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+use super::MessageStateUpdate;
+
+/// Acks of one topic collected during a flush window, applied in one log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetStates {
+    pub topic: TopicCode,
+    pub updates: Vec<MessageStateUpdate>,
+    /// the leader's time when it proposed the acks, redelivery deadlines count from it
+    pub now: DateTime<Utc>,
+}
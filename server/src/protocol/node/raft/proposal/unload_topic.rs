@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::prelude::TopicCode;
@@ -6,10 +7,27 @@ use crate::prelude::TopicCode;
 
 pub struct UnloadTopic {
     pub code: TopicCode,
+    /// skip the unload if the topic is no longer idle when it's applied
+    #[serde(default)]
+    pub if_idle: bool,
+    /// the leader's time when it proposed the unload
+    pub now: DateTime<Utc>,
 }
 
 impl UnloadTopic {
-    pub fn new(code: TopicCode) -> Self {
-        Self { code }
+    pub fn new(code: TopicCode, now: DateTime<Utc>) -> Self {
+        Self {
+            code,
+            if_idle: false,
+            now,
+        }
+    }
+    /// unload a topic found idle, unless something reached it in the meantime
+    pub fn if_idle(code: TopicCode, now: DateTime<Utc>) -> Self {
+        Self {
+            code,
+            if_idle: true,
+            now,
+        }
     }
 }
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{TopicCode, TopicConfigPatch};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub struct UpdateTopicConfig {
+    pub topic: TopicCode,
+    pub patch: TopicConfigPatch,
+    /// the leader's time when it proposed the update
+    pub now: DateTime<Utc>,
+}
@@ -1,7 +1,9 @@
 pub mod node;
+mod snapshot;
 pub mod topic;
 
 use std::{
+    collections::VecDeque,
     io::{self, Cursor},
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -9,6 +11,7 @@ use std::{
     },
 };
 
+use chrono::{DateTime, Utc};
 use node::NodeData;
 use openraft::{
     storage::RaftStateMachine, BasicNode, EntryPayload, LogId, RaftSnapshotBuilder, RaftTypeConfig,
@@ -17,11 +20,8 @@ use openraft::{
 use tokio::sync::RwLock;
 
 use crate::{
-    prelude::{NodeId, Topic},
-    protocol::{
-        node::{raft::proposal::ProposalContext, NodeRef},
-        topic::TopicInner,
-    },
+    prelude::{NodeConfig, NodeId, Topic},
+    protocol::node::{raft::proposal::ProposalContext, NodeRef},
 };
 
 use super::{response::RaftResponse, TypeConfig};
@@ -38,9 +38,21 @@ pub struct StateMachineData<C: RaftTypeConfig> {
 
     pub last_membership: StoredMembership<C::NodeId, C::Node>,
 
+    /// the time stamped into the latest applied proposal, `None` until one is applied
+    pub last_applied_time: Option<DateTime<Utc>>,
+
     pub node: NodeData,
 }
 
+/// Kind and position of an applied log entry, without its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntrySummary {
+    pub index: u64,
+    pub term: u64,
+    /// `Blank`, `Membership`, or the [`Proposal`](crate::protocol::node::raft::proposal::Proposal) kind
+    pub kind: &'static str,
+}
+
 /// Defines a state machine for the Raft cluster. This state machine represents a copy of the
 /// data for this node. Additionally, it is responsible for storing the last snapshot of the data.
 #[derive(Debug)]
@@ -57,15 +69,48 @@ pub struct StateMachineStore {
 
     /// The last received snapshot.
     current_snapshot: RwLock<Option<StoredSnapshot>>,
+    /// Summaries of the latest applied entries, for introspection.
+    recent_entries: std::sync::Mutex<VecDeque<LogEntrySummary>>,
     node_ref: NodeRef,
 }
 
 impl StateMachineStore {
+    pub const RECENT_ENTRIES_CAPACITY: usize = 256;
+    fn record_entry(&self, summary: LogEntrySummary) {
+        let mut recent_entries = self.recent_entries.lock().unwrap();
+        if recent_entries.len() >= Self::RECENT_ENTRIES_CAPACITY {
+            recent_entries.pop_front();
+        }
+        recent_entries.push_back(summary);
+    }
+    /// the latest `n` applied entries, oldest first
+    pub fn recent_entries(&self, n: usize) -> Vec<LogEntrySummary> {
+        let recent_entries = self.recent_entries.lock().unwrap();
+        let skip = recent_entries.len().saturating_sub(n);
+        recent_entries.iter().skip(skip).cloned().collect()
+    }
+    fn snapshot_segment_size(&self) -> usize {
+        self.node_ref
+            .upgrade()
+            .map(|node| node.config.snapshot_segment_size)
+            .unwrap_or(NodeConfig::DEFAULT_SNAPSHOT_SEGMENT_SIZE)
+    }
+    fn encode_snapshot(&self, data: &mut NodeData) -> io::Result<Vec<u8>> {
+        snapshot::encode(data, self.snapshot_segment_size()).map_err(io::Error::other)
+    }
+    fn snapshot_write_error(error: io::Error) -> StorageError<NodeId> {
+        StorageError::from_io_error(
+            openraft::ErrorSubject::Snapshot(None),
+            openraft::ErrorVerb::Write,
+            error,
+        )
+    }
     pub fn new(node_ref: NodeRef) -> Self {
         Self {
             state_machine: RwLock::new(StateMachineData::default()),
             snapshot_idx: AtomicU64::new(0),
             current_snapshot: RwLock::new(None),
+            recent_entries: Default::default(),
             node_ref,
         }
     }
@@ -75,6 +120,7 @@ impl StateMachineStore {
             state_machine: RwLock::new(StateMachineData::default()),
             snapshot_idx: AtomicU64::new(0),
             current_snapshot: RwLock::new(None),
+            recent_entries: Default::default(),
             node_ref: NodeRef::default(),
         }
     }
@@ -84,7 +130,11 @@ impl RaftSnapshotBuilder<TypeConfig> for Arc<StateMachineStore> {
     async fn build_snapshot(&mut self) -> Result<Snapshot<TypeConfig>, StorageError<NodeId>> {
         // Serialize the data of the state machine.
         let state_machine = self.state_machine.read().await;
-        let snapshot = state_machine.node.clone();
+        // as of the latest applied proposal, nothing is dropped by its age before one is applied
+        let now = state_machine
+            .last_applied_time
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let mut snapshot = state_machine.node.snapshot(now);
 
         let last_applied_log = state_machine.last_applied_log;
         let last_membership = state_machine.last_membership.clone();
@@ -106,7 +156,9 @@ impl RaftSnapshotBuilder<TypeConfig> for Arc<StateMachineStore> {
             last_membership,
             snapshot_id,
         };
-        let bytes = bincode::serialize(&snapshot).unwrap();
+        let bytes = self
+            .encode_snapshot(&mut snapshot)
+            .map_err(StateMachineStore::snapshot_write_error)?;
         let stored = StoredSnapshot {
             meta: meta.clone(),
             data: snapshot,
@@ -155,6 +207,15 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
         let mut res = Vec::new(); //No `with_capacity`; do not know `len` of iterator
         for entry in entries {
             sm.last_applied_log = Some(entry.log_id);
+            self.record_entry(LogEntrySummary {
+                index: entry.log_id.index,
+                term: entry.log_id.leader_id.term,
+                kind: match entry.payload {
+                    EntryPayload::Blank => "Blank",
+                    EntryPayload::Normal(ref proposal) => proposal.kind(),
+                    EntryPayload::Membership(_) => "Membership",
+                },
+            });
             match entry.payload {
                 EntryPayload::Blank => res.push(RaftResponse { result: Ok(()) }),
                 EntryPayload::Normal(ref proposal) => {
@@ -163,7 +224,8 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
                         res.push(RaftResponse { result: Err(()) });
                         continue;
                     };
-                    let context = ProposalContext::new(node);
+                    sm.last_applied_time = Some(proposal.now());
+                    let context = ProposalContext::new(node, proposal.now());
                     match proposal {
                         crate::protocol::node::raft::proposal::Proposal::DelegateMessage(
                             delegate_message,
@@ -184,7 +246,7 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
                         crate::protocol::node::raft::proposal::Proposal::UnloadTopic(
                             unload_topic,
                         ) => {
-                            sm.node.apply_unload_topic(unload_topic.clone());
+                            sm.node.apply_unload_topic(unload_topic.clone(), context);
                             res.push(RaftResponse { result: Ok(()) })
                         }
                         crate::protocol::node::raft::proposal::Proposal::EpOnline(ep_online) => {
@@ -201,6 +263,74 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
                             sm.node.apply_ep_interest(ep_interest.clone(), context);
                             res.push(RaftResponse { result: Ok(()) })
                         }
+                        crate::protocol::node::raft::proposal::Proposal::ForceResolve(
+                            force_resolve,
+                        ) => {
+                            sm.node.apply_force_resolve(force_resolve.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::NodeOffline(
+                            node_offline,
+                        ) => {
+                            sm.node.apply_node_offline(node_offline.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::ExpireMessages(
+                            expire_messages,
+                        ) => {
+                            sm.node
+                                .apply_expire_messages(expire_messages.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::ReleaseDelayed(
+                            release_delayed,
+                        ) => {
+                            sm.node
+                                .apply_release_delayed(release_delayed.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::DelegateMessages(
+                            delegate_messages,
+                        ) => {
+                            sm.node
+                                .apply_delegate_messages(delegate_messages.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::EpHeartbeat(
+                            ep_heartbeat,
+                        ) => {
+                            sm.node.apply_ep_heartbeat(ep_heartbeat.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::RedeliverMessages(
+                            redeliver_messages,
+                        ) => {
+                            sm.node
+                                .apply_redeliver_messages(redeliver_messages.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::UpdateTopicConfig(
+                            update_topic_config,
+                        ) => {
+                            sm.node
+                                .apply_update_topic_config(update_topic_config.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::EpsOnline(eps_online) => {
+                            sm.node.apply_eps_online(eps_online.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::ImportSnapshot(
+                            import_snapshot,
+                        ) => {
+                            sm.node
+                                .apply_import_snapshot(import_snapshot.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
+                        crate::protocol::node::raft::proposal::Proposal::SetStates(set_states) => {
+                            sm.node.apply_set_states(set_states.clone(), context);
+                            res.push(RaftResponse { result: Ok(()) })
+                        }
                     }
                 }
                 EntryPayload::Membership(ref mem) => {
@@ -209,6 +339,10 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
                 }
             };
         }
+        #[cfg(feature = "metrics")]
+        if let Some(node) = self.node_ref.upgrade() {
+            node.metrics.update_topics(&sm.node.topics);
+        }
         Ok(res)
     }
 
@@ -225,9 +359,11 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
         &mut self,
     ) -> Result<Option<Snapshot<TypeConfig>>, StorageError<<TypeConfig as RaftTypeConfig>::NodeId>>
     {
-        match &*self.current_snapshot.read().await {
+        match &mut *self.current_snapshot.write().await {
             Some(snapshot) => {
-                let bytes = bincode::serialize(&snapshot.data).unwrap();
+                let bytes = self
+                    .encode_snapshot(&mut snapshot.data)
+                    .map_err(StateMachineStore::snapshot_write_error)?;
                 Ok(Some(Snapshot {
                     meta: snapshot.meta.clone(),
                     snapshot: Box::new(Cursor::new(bytes)),
@@ -253,7 +389,8 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
             { snapshot_size = snapshot.get_ref().len(), meta= ?meta },
             "decoding snapshot for installation"
         );
-        let new_data: NodeData = bincode::deserialize_from(&mut snapshot).map_err(|e| {
+        // decoded in full before anything is replaced, a broken transfer leaves the state as it was
+        let new_data = snapshot::decode(&mut snapshot).map_err(|e| {
             StorageError::from_io_error(
                 openraft::ErrorSubject::Snapshot(None),
                 openraft::ErrorVerb::Read,
@@ -270,6 +407,7 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
         let mut state_machine = self.state_machine.write().await;
         state_machine.last_membership = new_snapshot.meta.last_membership.clone();
         state_machine.last_applied_log = new_snapshot.meta.last_log_id;
+        state_machine.last_applied_time = None;
         state_machine.node = new_data;
 
         // Apply the side effects
@@ -291,18 +429,8 @@ impl StateMachineStore {
             return;
         };
         let mut topic_write_wg = node.topics.write().unwrap();
-        for code in data.topics.keys() {
-            topic_write_wg.insert(
-                code.clone(),
-                Topic {
-                    inner: Arc::new(TopicInner {
-                        code: code.clone(),
-                        node: node.clone(),
-                        ack_waiting_pool: Default::default(),
-                        local_endpoints: Default::default(),
-                    }),
-                },
-            );
+        for (code, topic) in data.topics.iter() {
+            topic_write_wg.insert(code.clone(), Topic::new(&topic.config, node.clone()));
         }
     }
 }
@@ -1,44 +1,102 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
 use crate::{
-    prelude::{Topic, TopicCode},
-    protocol::{
-        node::raft::proposal::{
-            DelegateMessage, EndpointInterest, EndpointOffline, EndpointOnline, LoadTopic,
-            ProposalContext, SetState, UnloadTopic,
-        },
-        topic::TopicInner,
+    prelude::{DurableMessage, Topic, TopicCode},
+    protocol::node::raft::proposal::{
+        DelegateMessage, DelegateMessages, EndpointHeartbeat, EndpointInterest, EndpointOffline,
+        EndpointOnline, EndpointsOnline, ExpireMessages, ForceResolve, ImportSnapshot, LoadTopic,
+        NodeOffline, ProposalContext, RedeliverMessages, ReleaseDelayed, SetState, SetStates,
+        UnloadTopic, UpdateTopicConfig,
     },
 };
 
-use super::topic::TopicData;
+use super::topic::{wait_ack::WaitAckErrorException, TopicData};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NodeData {
     pub(crate) topics: HashMap<TopicCode, TopicData>,
+    /// endpoints that came online in a topic before it was loaded, they join it once it is
+    #[serde(default)]
+    pub(crate) pending_endpoints: HashMap<TopicCode, Vec<EndpointOnline>>,
 }
 
 impl NodeData {
+    /// most endpoints kept waiting for one topic, later ones are dropped
+    pub(crate) const MAX_PENDING_ENDPOINTS: usize = 1024;
+    /// most unloaded topics endpoints may wait for, endpoints of further ones are dropped
+    pub(crate) const MAX_PENDING_TOPICS: usize = 1024;
+    pub(crate) fn snapshot(&self, now: DateTime<Utc>) -> Self {
+        Self {
+            topics: self
+                .topics
+                .iter()
+                .map(|(code, topic)| (code.clone(), topic.snapshot(now)))
+                .collect(),
+            pending_endpoints: self.pending_endpoints.clone(),
+        }
+    }
+    /// keep `online` until its topic is loaded
+    fn defer_ep_online(&mut self, online: EndpointOnline) {
+        if !self.pending_endpoints.contains_key(&online.topic_code)
+            && self.pending_endpoints.len() >= Self::MAX_PENDING_TOPICS
+        {
+            tracing::warn!(topic = %online.topic_code, endpoint = ?online.endpoint, "too many unknown topics, endpoint dropped");
+            return;
+        }
+        let pending = self
+            .pending_endpoints
+            .entry(online.topic_code.clone())
+            .or_default();
+        if pending.len() >= Self::MAX_PENDING_ENDPOINTS {
+            tracing::warn!(topic = %online.topic_code, endpoint = ?online.endpoint, "endpoint of unknown topic dropped");
+            return;
+        }
+        tracing::debug!(topic = %online.topic_code, endpoint = ?online.endpoint, "endpoint waits for its topic");
+        pending.push(online);
+    }
     #[instrument(skip_all, fields(node_id=%ctx.node.id(), topic=%topic, message_id = %message.id()))]
     pub(crate) fn apply_delegate_message(
         &mut self,
-        DelegateMessage { topic, message }: DelegateMessage,
+        DelegateMessage { topic, message, .. }: DelegateMessage,
         mut ctx: ProposalContext,
     ) {
         ctx.set_topic_code(topic.clone());
         if let Some(topic) = self.topics.get_mut(&topic) {
             topic.hold_new_message(message.clone(), &mut ctx);
         } else {
-            tracing::error!(?topic, "topic not found");
+            // unloaded after the message was proposed, its producer is failed by the proposing node
+            tracing::warn!(?topic, "message for unknown topic dropped");
+        }
+        ctx.commit_durable_commands();
+    }
+    #[instrument(skip_all, fields(node_id=%ctx.node.id(), topic=%topic, count = messages.len()))]
+    pub(crate) fn apply_delegate_messages(
+        &mut self,
+        DelegateMessages {
+            topic, messages, ..
+        }: DelegateMessages,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            // one at a time, so overflow is handled for every message on its own
+            for message in messages {
+                topic.hold_new_message(message, &mut ctx);
+            }
+        } else {
+            tracing::warn!(?topic, "messages for unknown topic dropped");
         }
         ctx.commit_durable_commands();
     }
     pub(crate) fn apply_load_topic(
         &mut self,
-        LoadTopic { config, mut queue }: LoadTopic,
+        LoadTopic {
+            config, mut queue, ..
+        }: LoadTopic,
         mut ctx: ProposalContext,
     ) {
         use std::collections::hash_map::Entry;
@@ -46,32 +104,27 @@ impl NodeData {
         let entry = self.topics.entry(code.clone());
         match entry {
             Entry::Vacant(entry) => {
-                queue.sort_by_key(|m| m.time);
+                queue.sort_by_key(DurableMessage::hold_order);
                 ctx.set_topic_code(code.clone());
-                let topic = TopicData::from_durable(config, queue);
-                entry.insert(topic);
+                let node = ctx.node.clone();
+                let topic = Topic::new(&config, node.clone());
+                node.topics.write().unwrap().insert(code.clone(), topic);
+                let topic = entry.insert(TopicData::from_durable(config, queue, &ctx));
+                for online in self.pending_endpoints.remove(&code).unwrap_or_default() {
+                    topic.ep_online(online, &mut ctx);
+                }
             }
             _ => {
                 tracing::warn!(?code, "topic already loaded");
                 return;
             }
         }
-        let node = ctx.node.clone();
-        let topic = Topic {
-            inner: Arc::new(TopicInner {
-                code: code.clone(),
-                node: node.clone(),
-                ack_waiting_pool: Default::default(),
-                local_endpoints: Default::default(),
-            }),
-        };
-        node.topics.write().unwrap().insert(code.clone(), topic);
         ctx.commit_durable_commands();
     }
     #[instrument(skip_all, fields(node_id=%ctx.node.id(), topic=%topic, message_id=%update.message_id))]
     pub(crate) fn apply_set_state(
         &mut self,
-        SetState { topic, update }: SetState,
+        SetState { topic, update, .. }: SetState,
         mut ctx: ProposalContext,
     ) {
         ctx.set_topic_code(topic.clone());
@@ -82,24 +135,51 @@ impl NodeData {
         }
         ctx.commit_durable_commands();
     }
-    pub(crate) fn apply_unload_topic(&mut self, UnloadTopic { code }: UnloadTopic) {
-        self.topics.remove(&code);
-    }
-    pub(crate) fn apply_ep_online(
+    #[instrument(skip_all, fields(node_id=%ctx.node.id(), topic=%topic, count=updates.len()))]
+    pub(crate) fn apply_set_states(
         &mut self,
-        EndpointOnline {
-            topic_code,
-            endpoint,
-            interests,
-            host,
-        }: EndpointOnline,
+        SetStates { topic, updates, .. }: SetStates,
         mut ctx: ProposalContext,
     ) {
-        let Some(topic) = self.topics.get_mut(&topic_code) else {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            for update in updates {
+                topic.update_and_flush(update, &mut ctx);
+            }
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_unload_topic(
+        &mut self,
+        UnloadTopic { code, if_idle, .. }: UnloadTopic,
+        ctx: ProposalContext,
+    ) {
+        // a publish or an endpoint may have raced the idle check
+        if if_idle && self.topics.get(&code).is_some_and(|topic| !topic.is_idle()) {
+            tracing::debug!(topic = %code, "topic is busy again, unload skipped");
+            return;
+        }
+        self.topics.remove(&code);
+        let topic = ctx.node.topics.write().unwrap().remove(&code);
+        if let Some(topic) = topic {
+            topic.close_local_endpoints();
+            // nothing resolves them once the topic is gone
+            tokio::spawn(async move {
+                topic
+                    .abandon_waiting(WaitAckErrorException::TopicNotFound)
+                    .await
+            });
+        }
+    }
+    pub(crate) fn apply_ep_online(&mut self, online: EndpointOnline, mut ctx: ProposalContext) {
+        let Some(topic) = self.topics.get_mut(&online.topic_code) else {
+            self.defer_ep_online(online);
             return;
         };
-        ctx.set_topic_code(topic_code);
-        topic.ep_online(endpoint, interests, host, &mut ctx);
+        ctx.set_topic_code(online.topic_code.clone());
+        topic.ep_online(online, &mut ctx);
         ctx.commit_durable_commands();
     }
     pub(crate) fn apply_ep_offline(
@@ -108,22 +188,50 @@ impl NodeData {
             topic_code,
             endpoint,
             host,
+            ..
         }: EndpointOffline,
         mut ctx: ProposalContext,
     ) {
         let Some(topic) = self.topics.get_mut(&topic_code) else {
+            // never joined its topic
+            if let Some(pending) = self.pending_endpoints.get_mut(&topic_code) {
+                pending.retain(|online| online.endpoint != endpoint);
+                if pending.is_empty() {
+                    self.pending_endpoints.remove(&topic_code);
+                }
+            }
             return;
         };
-        ctx.set_topic_code(topic_code);
+        ctx.set_topic_code(topic_code.clone());
         topic.ep_offline(host, &endpoint, &mut ctx);
+        // the endpoint may have been taken offline by others, e.g. for missing heartbeats
+        if host == ctx.node.id() {
+            if let Some(topic) = ctx.node.get_topic(&topic_code) {
+                topic.close_local_endpoint(&endpoint);
+            }
+        }
         ctx.commit_durable_commands();
     }
+    pub(crate) fn apply_ep_heartbeat(
+        &mut self,
+        EndpointHeartbeat {
+            topic_code,
+            endpoint,
+            ..
+        }: EndpointHeartbeat,
+        ctx: ProposalContext,
+    ) {
+        if let Some(topic) = self.topics.get_mut(&topic_code) {
+            topic.ep_heartbeat(&endpoint, ctx.now());
+        }
+    }
     pub(crate) fn apply_ep_interest(
         &mut self,
         EndpointInterest {
             topic_code,
             endpoint,
             interests,
+            ..
         }: EndpointInterest,
         mut ctx: ProposalContext,
     ) {
@@ -134,4 +242,280 @@ impl NodeData {
         topic.update_ep_interest(&endpoint, interests, &mut ctx);
         ctx.commit_durable_commands();
     }
+    #[instrument(skip_all, fields(node_id=%ctx.node.id(), topic=%topic, message_id=%message_id))]
+    pub(crate) fn apply_force_resolve(
+        &mut self,
+        ForceResolve {
+            topic,
+            message_id,
+            outcome,
+            ..
+        }: ForceResolve,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            topic.force_resolve(message_id, outcome, &mut ctx);
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    #[instrument(skip_all, fields(node_id=%ctx.node.id(), offline_node=%node))]
+    pub(crate) fn apply_node_offline(
+        &mut self,
+        NodeOffline { node, .. }: NodeOffline,
+        ctx: ProposalContext,
+    ) {
+        self.pending_endpoints.retain(|_, pending| {
+            pending.retain(|online| online.host != node);
+            !pending.is_empty()
+        });
+        for (code, topic) in self.topics.iter_mut() {
+            let mut ctx = ctx.clone();
+            ctx.set_topic_code(code.clone());
+            topic.node_offline(node, &mut ctx);
+            ctx.commit_durable_commands();
+        }
+    }
+    pub(crate) fn apply_expire_messages(
+        &mut self,
+        ExpireMessages { topic, .. }: ExpireMessages,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            topic.expire_due(&mut ctx);
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_release_delayed(
+        &mut self,
+        ReleaseDelayed { topic, .. }: ReleaseDelayed,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            topic.release_due(&mut ctx);
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_redeliver_messages(
+        &mut self,
+        RedeliverMessages { topic, .. }: RedeliverMessages,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            topic.redeliver_due(&mut ctx);
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_update_topic_config(
+        &mut self,
+        UpdateTopicConfig { topic, patch, .. }: UpdateTopicConfig,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic_data) = self.topics.get_mut(&topic) {
+            topic_data.update_config(&patch, &mut ctx);
+            // the node-local handle keeps its own copy
+            if let Some(local) = ctx.node.get_topic(&topic) {
+                *local.dead_letter.write().unwrap() = topic_data.config.dead_letter.clone();
+            }
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_eps_online(
+        &mut self,
+        EndpointsOnline {
+            topic_code,
+            endpoints,
+            ..
+        }: EndpointsOnline,
+        mut ctx: ProposalContext,
+    ) {
+        let Some(topic) = self.topics.get_mut(&topic_code) else {
+            for online in endpoints {
+                self.defer_ep_online(online);
+            }
+            return;
+        };
+        ctx.set_topic_code(topic_code);
+        for endpoint in endpoints {
+            topic.ep_online(endpoint, &mut ctx);
+        }
+        ctx.commit_durable_commands();
+    }
+    pub(crate) fn apply_import_snapshot(
+        &mut self,
+        ImportSnapshot {
+            topic, snapshot, ..
+        }: ImportSnapshot,
+        mut ctx: ProposalContext,
+    ) {
+        ctx.set_topic_code(topic.clone());
+        if let Some(topic) = self.topics.get_mut(&topic) {
+            topic.import_snapshot(snapshot.data, &mut ctx);
+        } else {
+            tracing::error!(?topic, "topic not found");
+        }
+        ctx.commit_durable_commands();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::{
+        EndpointAddr, Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject,
+    };
+
+    #[tokio::test]
+    async fn test_ep_online_before_load_topic() {
+        const CODE: TopicCode = TopicCode::const_new("pending-endpoint-test");
+        let node = Node::new(NodeConfig::default());
+        let mut data = NodeData::default();
+        let online = |endpoint| EndpointOnline {
+            topic_code: CODE,
+            endpoint,
+            interests: vec![Interest::new("event/*")],
+            host: node.id(),
+            weight: 1,
+            group: None,
+            filter: None,
+            prefetch: None,
+            key: None,
+            now: node.now(),
+        };
+        let (kept, gone) = (EndpointAddr::new_snowflake(), EndpointAddr::new_snowflake());
+        data.apply_ep_online(online(kept), ProposalContext::new(node.clone(), node.now()));
+        data.apply_ep_online(online(gone), ProposalContext::new(node.clone(), node.now()));
+        data.apply_ep_offline(
+            EndpointOffline {
+                topic_code: CODE,
+                endpoint: gone,
+                host: node.id(),
+                now: node.now(),
+            },
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert!(data.topics.is_empty());
+        assert_eq!(data.pending_endpoints[&CODE].len(), 1);
+
+        data.apply_load_topic(
+            LoadTopic::from_config(CODE, node.now()),
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert!(data.pending_endpoints.is_empty());
+        let topic = &data.topics[&CODE];
+        assert!(topic.ep_info(&kept).is_some());
+        assert!(topic.ep_info(&gone).is_none());
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/created")])
+                .mode_online()
+                .build(),
+            "{}",
+        );
+        let routing = topic.explain_routing(&message, node.now());
+        assert_eq!(routing.targets, [kept].into_iter().collect());
+    }
+
+    #[tokio::test]
+    async fn test_pending_endpoints_bounded() {
+        let node = Node::new(NodeConfig::default());
+        let mut data = NodeData::default();
+        let online = |topic_code, host| EndpointOnline {
+            topic_code,
+            endpoint: EndpointAddr::new_snowflake(),
+            interests: vec![Interest::new("event/*")],
+            host,
+            weight: 1,
+            group: None,
+            filter: None,
+            prefetch: None,
+            key: None,
+            now: node.now(),
+        };
+        let departed = NodeId::new_indexed(2);
+        for index in 0..NodeData::MAX_PENDING_TOPICS {
+            let code = TopicCode::new(format!("pending-topic-{index}"));
+            data.apply_ep_online(
+                online(code.clone(), node.id()),
+                ProposalContext::new(node.clone(), node.now()),
+            );
+            data.apply_ep_online(
+                online(code, departed),
+                ProposalContext::new(node.clone(), node.now()),
+            );
+        }
+        data.apply_ep_online(
+            online(TopicCode::const_new("one-topic-too-many"), node.id()),
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert_eq!(data.pending_endpoints.len(), NodeData::MAX_PENDING_TOPICS);
+
+        data.apply_node_offline(
+            NodeOffline {
+                node: departed,
+                now: node.now(),
+            },
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert_eq!(data.pending_endpoints.len(), NodeData::MAX_PENDING_TOPICS);
+        assert!(data
+            .pending_endpoints
+            .values()
+            .flatten()
+            .all(|online| online.host == node.id()));
+    }
+
+    #[tokio::test]
+    async fn test_idle_unload_raced_by_publish() {
+        const CODE: TopicCode = TopicCode::const_new("idle-unload-race-test");
+        let node = Node::new(NodeConfig::default());
+        let mut data = NodeData::default();
+        data.apply_load_topic(
+            LoadTopic::from_config(CODE, node.now()),
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert!(data.topics[&CODE].is_idle());
+        // the idle check passed, then a message got in before the unload was applied
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/created")])
+                .deliver_at(node.now() + chrono::TimeDelta::hours(1))
+                .build(),
+            "{}",
+        );
+        data.apply_delegate_message(
+            DelegateMessage {
+                topic: CODE,
+                message,
+                now: node.now(),
+            },
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        data.apply_unload_topic(
+            UnloadTopic::if_idle(CODE, node.now()),
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert!(data.topics.contains_key(&CODE));
+        assert!(node.get_topic(&CODE).is_some());
+
+        // an explicit unload doesn't wait for the topic to be idle
+        data.apply_unload_topic(
+            UnloadTopic::new(CODE, node.now()),
+            ProposalContext::new(node.clone(), node.now()),
+        );
+        assert!(!data.topics.contains_key(&CODE));
+        assert!(node.get_topic(&CODE).is_none());
+    }
 }
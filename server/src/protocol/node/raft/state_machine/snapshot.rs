@@ -0,0 +1,261 @@
+//! Snapshot encoding.
+//!
+//! Held messages are the bulk of a snapshot, so they are left out of the topic state and
+//! written after it in segments of bounded size. Segments are decoded one by one on install.
+use std::{collections::HashMap, io::Read};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::TopicCode;
+
+use super::{node::NodeData, topic::message_queue::HoldMessage};
+
+/// Segments as they are read back, variants in the same order as [`SegmentRef`].
+#[derive(Deserialize)]
+enum Segment {
+    /// every topic, with no held messages
+    Base(NodeData),
+    Messages {
+        topic: TopicCode,
+        messages: Vec<HoldMessage>,
+    },
+    End,
+}
+
+/// Segments as they are written, borrowing from the node data.
+#[derive(Serialize)]
+enum SegmentRef<'a> {
+    Base(&'a NodeData),
+    Messages {
+        topic: &'a TopicCode,
+        messages: Vec<&'a HoldMessage>,
+    },
+    End,
+}
+
+fn invalid(reason: &'static str) -> bincode::Error {
+    Box::new(bincode::ErrorKind::Custom(reason.to_string()))
+}
+
+/// Encode `data`, held messages in segments of at most `segment_size` bytes unless a single
+/// message is larger. `data` is left as it was.
+pub(crate) fn encode(data: &mut NodeData, segment_size: usize) -> bincode::Result<Vec<u8>> {
+    let held = data
+        .topics
+        .iter_mut()
+        .map(|(code, topic)| (code.clone(), std::mem::take(&mut topic.queue.hold_messages)))
+        .collect::<HashMap<_, _>>();
+    let encoded = encode_segments(data, &held, segment_size);
+    for (code, hold_messages) in held {
+        if let Some(topic) = data.topics.get_mut(&code) {
+            topic.queue.hold_messages = hold_messages;
+        }
+    }
+    encoded
+}
+
+fn encode_segments(
+    base: &NodeData,
+    held: &HashMap<TopicCode, HashMap<crate::prelude::MessageId, HoldMessage>>,
+    segment_size: usize,
+) -> bincode::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    bincode::serialize_into(&mut buffer, &SegmentRef::Base(base))?;
+    for (topic, hold_messages) in held {
+        let mut messages = Vec::new();
+        let mut size = 0;
+        for message in hold_messages.values() {
+            let message_size = bincode::serialized_size(message)? as usize;
+            if !messages.is_empty() && size + message_size > segment_size {
+                bincode::serialize_into(
+                    &mut buffer,
+                    &SegmentRef::Messages {
+                        topic,
+                        messages: std::mem::take(&mut messages),
+                    },
+                )?;
+                size = 0;
+            }
+            size += message_size;
+            messages.push(message);
+        }
+        if !messages.is_empty() {
+            bincode::serialize_into(&mut buffer, &SegmentRef::Messages { topic, messages })?;
+        }
+    }
+    bincode::serialize_into(&mut buffer, &SegmentRef::End)?;
+    Ok(buffer)
+}
+
+/// Rebuild node data segment by segment, nothing is returned unless every segment decodes.
+pub(crate) fn decode(mut reader: impl Read) -> bincode::Result<NodeData> {
+    let Segment::Base(mut data) = bincode::deserialize_from(&mut reader)? else {
+        return Err(invalid("snapshot doesn't start with its base segment"));
+    };
+    loop {
+        match bincode::deserialize_from(&mut reader)? {
+            Segment::Messages { topic, messages } => {
+                let Some(topic) = data.topics.get_mut(&topic) else {
+                    return Err(invalid("held messages of an unknown topic"));
+                };
+                topic.queue.hold_messages.extend(
+                    messages
+                        .into_iter()
+                        .map(|message| (message.message.id(), message)),
+                );
+            }
+            Segment::End => {
+                // the interest map is written without its kind, the in-flight counts not at all
+                for topic in data.topics.values_mut() {
+                    topic.ep_interest_map.set_kind(topic.config.interest_map);
+                    topic.queue.recount_in_flight();
+                }
+                return Ok(data);
+            }
+            Segment::Base(_) => return Err(invalid("more than one base segment")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        prelude::{
+            DurableMessage, EndpointAddr, Message, MessageAckExpectKind, MessageHeader,
+            MessageStatusKind, Subject, TopicConfig,
+        },
+        protocol::{
+            interest::InterestMap,
+            node::raft::state_machine::topic::{message_queue::MessageQueue, TopicData},
+        },
+    };
+
+    fn topic_with_held_messages(code: TopicCode, count: usize) -> TopicData {
+        let mut queue = MessageQueue::new(false, count);
+        for index in 0..count {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/snapshot")]).build(),
+                format!("message {index}"),
+            );
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: Default::default(),
+                time: chrono::Utc::now(),
+            });
+        }
+        TopicData {
+            config: TopicConfig::from(code),
+            ep_routing_table: HashMap::new(),
+            ep_interest_map: InterestMap::new(),
+            ep_latest_active: HashMap::new(),
+            ep_weights: HashMap::new(),
+            ep_groups: HashMap::new(),
+            ep_filters: HashMap::new(),
+            ep_prefetch: HashMap::new(),
+            ep_keys: HashMap::new(),
+            dedup: Default::default(),
+            queue,
+        }
+    }
+
+    #[test]
+    fn test_segmented_round_trip() {
+        const CODE: TopicCode = TopicCode::const_new("snapshot-test");
+        let mut data = NodeData::default();
+        data.topics
+            .insert(CODE, topic_with_held_messages(CODE, 10_000));
+        data.topics.insert(
+            TopicCode::const_new("empty"),
+            topic_with_held_messages(TopicCode::const_new("empty"), 0),
+        );
+        let bytes = encode(&mut data, 64 * 1024).expect("encodable");
+        // left as it was
+        let original = &data.topics[&CODE].queue;
+        assert_eq!(original.hold_messages.len(), 10_000);
+
+        let decoded = decode(bytes.as_slice()).expect("decodable");
+        let reconstructed = &decoded.topics[&CODE].queue;
+        assert_eq!(reconstructed.len(), original.len());
+        assert_eq!(reconstructed.time_id, original.time_id);
+        assert_eq!(
+            reconstructed.hold_messages.keys().collect::<HashSet<_>>(),
+            original.hold_messages.keys().collect::<HashSet<_>>()
+        );
+        for (id, message) in &original.hold_messages {
+            assert_eq!(
+                bincode::serialize(&reconstructed.hold_messages[id]).unwrap(),
+                bincode::serialize(message).unwrap()
+            );
+        }
+        assert!(decoded.topics[&TopicCode::const_new("empty")]
+            .queue
+            .hold_messages
+            .is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_drops_resolved_messages() {
+        const CODE: TopicCode = TopicCode::const_new("snapshot-test");
+        let (ep_a, ep_b) = (EndpointAddr::new_snowflake(), EndpointAddr::new_snowflake());
+        let statuses = [
+            // resolved, not flushed yet
+            vec![(ep_a, MessageStatusKind::Processed)],
+            vec![(ep_a, MessageStatusKind::Failed)],
+            // still waiting for an endpoint
+            vec![(ep_a, MessageStatusKind::Sent)],
+            vec![
+                (ep_a, MessageStatusKind::Processed),
+                (ep_b, MessageStatusKind::Unsent),
+            ],
+        ];
+        let mut topic = topic_with_held_messages(CODE, 0);
+        let mut pending = HashSet::new();
+        for (index, status) in statuses.into_iter().enumerate() {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/snapshot")])
+                    .mode_online()
+                    .ack_kind(MessageAckExpectKind::Processed)
+                    .build(),
+                format!("message {index}"),
+            );
+            if index >= 2 {
+                pending.insert(message.id());
+            }
+            topic.queue.push_durable_message(DurableMessage {
+                message,
+                status: status.into_iter().collect(),
+                time: chrono::Utc::now(),
+            });
+        }
+        let mut data = NodeData::default();
+        data.topics.insert(CODE, topic);
+        let mut snapshot = data.snapshot(chrono::Utc::now());
+        // the live state is untouched
+        assert_eq!(data.topics[&CODE].queue.len(), 4);
+
+        let bytes = encode(&mut snapshot, 64 * 1024).expect("encodable");
+        let decoded = decode(bytes.as_slice()).expect("decodable");
+        let queue = &decoded.topics[&CODE].queue;
+        assert_eq!(queue.len(), 2);
+        assert_eq!(
+            queue.hold_messages.keys().copied().collect::<HashSet<_>>(),
+            pending
+        );
+        assert_eq!(queue.in_dispatch_order().collect::<HashSet<_>>(), pending);
+        assert_eq!(queue.time_id.len(), 2);
+    }
+
+    #[test]
+    fn test_truncated_snapshot_fails() {
+        const CODE: TopicCode = TopicCode::const_new("snapshot-test");
+        let mut data = NodeData::default();
+        data.topics
+            .insert(CODE, topic_with_held_messages(CODE, 100));
+        let bytes = encode(&mut data, 1024).expect("encodable");
+        assert!(decode(&bytes[..bytes.len() / 2]).is_err());
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
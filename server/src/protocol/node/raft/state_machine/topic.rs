@@ -1,36 +1,156 @@
 pub mod config;
+mod dedup;
+mod hash_ring;
 pub mod message_queue;
 pub mod wait_ack;
 use crate::{
-    prelude::{DurableMessage, Interest, NodeId, Subject},
+    lifecycle::LifecycleEvent,
+    prelude::{DurableMessage, Interest, NodeId, Subject, TopicCode},
     protocol::{
-        endpoint::EndpointAddr,
-        interest::InterestMap,
+        endpoint::{EndpointAddr, EpInfo, GroupId},
+        interest::{interest_covers, normalize_interest, normalize_subject, InterestMap},
         message::*,
-        node::raft::proposal::{MessageStateUpdate, ProposalContext},
+        node::raft::proposal::{
+            EndpointOnline, ForceResolveOutcome, MessageStateUpdate, ProposalContext,
+        },
         topic::durable_message::DurableCommand,
     },
+    util::{MaybeBase64Bytes, Timed},
+    TimestampMs,
 };
-use config::TopicConfig;
+use asteroid_mq_model::MessageAck;
+use chrono::{DateTime, TimeDelta, Utc};
+use config::{TopicConfig, TopicConfigPatch};
+use dedup::DedupWindow;
+use hash_ring::HashRing;
 use message_queue::{HoldMessage, MessageQueue};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
+    sync::Arc,
     task::Poll,
 };
-use wait_ack::{WaitAck, WaitAckError, WaitAckErrorException};
+use wait_ack::{WaitAck, WaitAckError, WaitAckErrorException, WaitAckSuccess};
+
+/// A point-in-time view of one topic, see [`Node::topic_stats`](crate::prelude::Node::topic_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicStats {
+    /// messages waiting for acks
+    pub held_messages: usize,
+    /// messages waiting for space in a full queue
+    pub parked_messages: usize,
+    /// messages waiting for their `deliver_at`
+    pub delayed_messages: usize,
+    /// producers on this node waiting for their messages to resolve
+    pub waiting_handles: usize,
+    pub endpoints: usize,
+    /// distinct interests over all endpoints
+    pub interests: usize,
+    /// endpoint status over all held messages
+    pub status: HashMap<MessageStatusKind, usize>,
+}
+
+/// How a message would be routed in a topic right now, see
+/// [`Topic::explain_routing`](crate::prelude::Topic::explain_routing).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingExplanation {
+    pub target_kind: MessageTargetKind,
+    /// the message's subjects in order, as they are routed
+    pub subjects: Vec<SubjectRouting>,
+    /// endpoints interested in any of the subjects
+    pub collected: HashSet<EndpointAddr>,
+    /// endpoints the target kind picks among the collected ones, empty if it finds none
+    pub targets: HashSet<EndpointAddr>,
+}
+
+/// The interests one subject matched, with the endpoint holding each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubjectRouting {
+    pub subject: Subject,
+    pub matches: Vec<(Interest, EndpointAddr)>,
+}
+
+/// What a held message is, without its payload, see [`Topic::peek`](crate::prelude::Topic::peek).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHeaderView {
+    pub id: MessageId,
+    /// when it was held
+    pub time: DateTime<Utc>,
+    pub target_kind: MessageTargetKind,
+    pub subjects: Arc<[Subject]>,
+    /// status of every endpoint it waits for
+    pub status: HashMap<EndpointAddr, MessageStatusKind>,
+    /// decompressed payload, only set by
+    /// [`Topic::peek_with_payload`](crate::prelude::Topic::peek_with_payload)
+    pub payload: Option<bytes::Bytes>,
+}
+
+/// A point-in-time backup of a topic, its held messages and endpoints, see
+/// [`Topic::export_snapshot`](crate::prelude::Topic::export_snapshot).
+///
+/// Written with serde, `version` is the format it was exported in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicSnapshot {
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    pub(crate) data: TopicData,
+}
+
+impl TopicSnapshot {
+    pub const VERSION: u32 = 1;
+    /// the topic it was exported from
+    pub fn code(&self) -> &TopicCode {
+        &self.data.config.code
+    }
+    /// ids of the held messages, in dispatch order
+    pub fn held_messages(&self) -> Vec<MessageId> {
+        self.data.queue.in_dispatch_order().collect()
+    }
+    /// endpoints online when it was exported, as [`Topic::endpoints`](crate::prelude::Topic::endpoints) lists them
+    pub fn endpoints(&self) -> Vec<(EndpointAddr, EpInfo)> {
+        self.data.ep_infos()
+    }
+}
+
+/// where a push message lands on the hash ring
+fn push_hash(message: &Message) -> u64 {
+    match &message.header.partition_key {
+        Some(key) => crate::util::hash64(&key.0),
+        None => crate::util::hash64(&message.id()),
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TopicData {
     pub(crate) config: TopicConfig,
     pub(crate) ep_routing_table: HashMap<NodeId, HashSet<EndpointAddr>>,
     pub(crate) ep_interest_map: InterestMap<EndpointAddr>,
+    pub(crate) ep_latest_active: HashMap<EndpointAddr, DateTime<Utc>>,
+    /// virtual node weight of endpoints on the push hash ring
+    pub(crate) ep_weights: HashMap<EndpointAddr, u32>,
+    #[serde(default)]
+    pub(crate) ep_groups: HashMap<EndpointAddr, GroupId>,
+    #[serde(default)]
+    pub(crate) ep_filters: HashMap<EndpointAddr, MessageFilter>,
+    /// max messages sent to an endpoint and not acked by it yet
+    #[serde(default)]
+    pub(crate) ep_prefetch: HashMap<EndpointAddr, u32>,
+    /// endpoints created with a key, a restarting consumer reclaims the endpoint by it
+    #[serde(default)]
+    pub(crate) ep_keys: HashMap<bytes::Bytes, EndpointAddr>,
+    /// recent dedup keys, kept in snapshots of ephemeral topics too
+    #[serde(default)]
+    pub(crate) dedup: DedupWindow,
     pub(crate) queue: MessageQueue,
 }
 
 impl TopicData {
-    pub(crate) fn from_durable(config: TopicConfig, mut messages: Vec<DurableMessage>) -> Self {
-        messages.sort_by_key(|f| f.time);
+    pub(crate) fn from_durable(
+        config: TopicConfig,
+        mut messages: Vec<DurableMessage>,
+        ctx: &ProposalContext,
+    ) -> Self {
+        messages.sort_by_key(DurableMessage::hold_order);
         let mut queue = MessageQueue::new(
             config.blocking,
             config
@@ -39,16 +159,214 @@ impl TopicData {
                 .map(|x| x.size())
                 .unwrap_or(MessageQueue::DEFAULT_CAPACITY),
         );
+        queue.checksum = config.checksum;
+        queue.retention = config.retention as usize;
+        queue.lazy_payloads = config.lazy_payloads && !config.ephemeral;
+        queue.redelivery = config.redelivery;
+        queue.ordered_partitions = config.ordered_partitions;
         for message in messages {
+            if !config
+                .checksum
+                .verify(&message.message.payload.0, message.message.header.checksum)
+            {
+                // keep it in durable storage for inspection, but never deliver it
+                ctx.report_corrupted_message(message.message.id());
+                continue;
+            }
             queue.push_durable_message(message);
         }
         Self {
+            ep_interest_map: InterestMap::with_kind(config.interest_map),
             config,
             ep_routing_table: HashMap::new(),
-            ep_interest_map: InterestMap::new(),
+            ep_latest_active: HashMap::new(),
+            ep_weights: HashMap::new(),
+            ep_groups: HashMap::new(),
+            ep_filters: HashMap::new(),
+            ep_prefetch: HashMap::new(),
+            ep_keys: HashMap::new(),
+            dedup: DedupWindow::default(),
             queue,
         }
     }
+    /// Up to `n` held messages from the head of the queue, in dispatch order, nothing is changed.
+    pub(crate) fn peek(&self, n: usize) -> Vec<(MessageHeaderView, &HoldMessage)> {
+        self.queue
+            .in_dispatch_order()
+            .take(n)
+            .filter_map(|id| {
+                let hold_message = self.queue.hold_messages.get(&id)?;
+                let header = &hold_message.message.header;
+                let view = MessageHeaderView {
+                    id,
+                    time: self.queue.id_time.get(&id).copied()?,
+                    target_kind: header.target_kind,
+                    subjects: header.subjects.clone(),
+                    status: hold_message.wait_ack.status.clone(),
+                    payload: None,
+                };
+                Some((view, hold_message))
+            })
+            .collect()
+    }
+    /// everything but `waiting_handles`, which is node-local
+    pub(crate) fn stats(&self) -> TopicStats {
+        let mut status = HashMap::new();
+        for message in self.queue.hold_messages.values() {
+            for kind in message.wait_ack.status.values() {
+                *status.entry(*kind).or_default() += 1;
+            }
+        }
+        TopicStats {
+            held_messages: self.queue.len(),
+            parked_messages: self.queue.parked.len(),
+            delayed_messages: self.queue.delayed.len(),
+            waiting_handles: 0,
+            endpoints: self.ep_routing_table.values().map(HashSet::len).sum(),
+            interests: self
+                .ep_interest_map
+                .raw
+                .values()
+                .flatten()
+                .collect::<HashSet<_>>()
+                .len(),
+            status,
+        }
+    }
+    /// room for another message without overflowing or jumping parked ones
+    pub(crate) fn has_space(&self) -> bool {
+        match &self.config.overflow_config {
+            Some(overflow_config) => {
+                self.queue.len() < overflow_config.size() && self.queue.parked.is_empty()
+            }
+            None => true,
+        }
+    }
+    /// no endpoints and nothing held
+    pub(crate) fn is_idle(&self) -> bool {
+        self.ep_routing_table.values().all(HashSet::is_empty)
+            && self.queue.len() == 0
+            && self.queue.parked.is_empty()
+            && self.queue.delayed.is_empty()
+    }
+    /// the state written into raft snapshots at `now`, ephemeral topics leave their messages out
+    pub(crate) fn snapshot(&self, now: DateTime<Utc>) -> Self {
+        if self.config.ephemeral {
+            Self {
+                config: self.config.clone(),
+                ep_routing_table: self.ep_routing_table.clone(),
+                ep_interest_map: self.ep_interest_map.clone(),
+                ep_latest_active: self.ep_latest_active.clone(),
+                ep_weights: self.ep_weights.clone(),
+                ep_groups: self.ep_groups.clone(),
+                ep_filters: self.ep_filters.clone(),
+                ep_prefetch: self.ep_prefetch.clone(),
+                ep_keys: self.ep_keys.clone(),
+                dedup: self.dedup.clone(),
+                queue: self.queue.emptied(),
+            }
+        } else {
+            // resolved messages are never dispatched again, the node installing it can do without
+            let mut snapshot = self.clone();
+            snapshot.queue.compact(now);
+            snapshot
+        }
+    }
+    /// Merge `snapshot`, exported from this or another topic, into this one.
+    ///
+    /// Endpoints are added, one online here already takes the snapshot's host, interests and
+    /// settings. Messages not held here yet are held and dispatched again, with their ack
+    /// deadlines starting over. The config and the messages held here stay as they are.
+    pub(crate) fn import_snapshot(&mut self, snapshot: TopicData, ctx: &mut ProposalContext) {
+        for (host, eps) in snapshot.ep_routing_table {
+            for ep in eps {
+                for hosted in self.ep_routing_table.values_mut() {
+                    hosted.remove(&ep);
+                }
+                self.ep_routing_table.entry(host).or_default().insert(ep);
+            }
+        }
+        for (ep, latest_active) in snapshot.ep_latest_active {
+            self.ep_interest_map.delete(&ep);
+            for interest in snapshot
+                .ep_interest_map
+                .interest_of(&ep)
+                .into_iter()
+                .flatten()
+            {
+                self.ep_interest_map.insert(interest.clone(), ep);
+            }
+            self.ep_latest_active.insert(ep, latest_active);
+            self.ep_weights.insert(
+                ep,
+                snapshot
+                    .ep_weights
+                    .get(&ep)
+                    .copied()
+                    .unwrap_or(EpInfo::DEFAULT_WEIGHT),
+            );
+            match snapshot.ep_groups.get(&ep) {
+                Some(group) => self.ep_groups.insert(ep, group.clone()),
+                None => self.ep_groups.remove(&ep),
+            };
+            match snapshot.ep_filters.get(&ep) {
+                Some(filter) => self.ep_filters.insert(ep, filter.clone()),
+                None => self.ep_filters.remove(&ep),
+            };
+            match snapshot.ep_prefetch.get(&ep) {
+                Some(prefetch) => self.ep_prefetch.insert(ep, *prefetch),
+                None => self.ep_prefetch.remove(&ep),
+            };
+        }
+        self.ep_keys.extend(snapshot.ep_keys);
+        let mut queue = snapshot.queue;
+        let mut imported = HashSet::new();
+        for Timed {
+            time,
+            data: (_, id),
+        } in std::mem::take(&mut queue.time_id)
+        {
+            if self.queue.hold_messages.contains_key(&id) {
+                continue;
+            }
+            let Some(mut hold_message) = queue.hold_messages.remove(&id) else {
+                continue;
+            };
+            hold_message.redeliver_at = None;
+            let message = hold_message.message.clone();
+            let durable = DurableMessage {
+                message: message.clone(),
+                status: hold_message.wait_ack.status.clone(),
+                time,
+            };
+            if self.queue.lazy_payloads {
+                ctx.cache_payload(&message);
+            }
+            self.queue.push(hold_message, time);
+            ctx.push_durable_command(DurableCommand::Create(Box::new(durable)));
+            imported.insert(id);
+        }
+        for hold_message in queue.parked {
+            let id = hold_message.message.id();
+            if !self.queue.hold_messages.contains_key(&id)
+                && !self
+                    .queue
+                    .parked
+                    .iter()
+                    .any(|parked| parked.message.id() == id)
+            {
+                self.queue.parked.push_back(hold_message);
+            }
+        }
+        for message in queue.delayed_messages.into_values() {
+            if !self.queue.delayed_messages.contains_key(&message.id()) {
+                self.queue.delay(message);
+            }
+        }
+        tracing::info!(topic = %self.config.code, imported = imported.len(), "snapshot imported");
+        self.admit_parked(ctx);
+        self.poll_in_dispatch_order(imported, ctx);
+    }
     pub(crate) fn collect_addr_by_subjects<'i>(
         &self,
         subjects: impl Iterator<Item = &'i Subject>,
@@ -59,73 +377,472 @@ impl TopicData {
         }
         ep_collect
     }
-    pub fn hold_new_message(&mut self, message: Message, ctx: &mut ProposalContext) {
-        let ep_collect = match message.header.target_kind {
-            MessageTargetKind::Durable | MessageTargetKind::Online => {
-                self.collect_addr_by_subjects(message.header.subjects.iter())
-                // just accept all
+    /// endpoints interested in `message` whose filter, if any, lets it through
+    pub(crate) fn collect_addr_by_message(&self, message: &Message) -> HashSet<EndpointAddr> {
+        self.collect_addr_by_subjects(message.header.subjects.iter())
+            .into_iter()
+            .filter(|ep| self.passes_filter(ep, message))
+            .collect()
+    }
+    fn passes_filter(&self, ep: &EndpointAddr, message: &Message) -> bool {
+        self.ep_filters
+            .get(ep)
+            .into_iter()
+            .all(|filter| filter.matches(message))
+    }
+    /// keep ungrouped endpoints, and pick one endpoint of every consumer group
+    fn pick_per_group(&self, eps: HashSet<EndpointAddr>, hash: u64) -> HashSet<EndpointAddr> {
+        let mut picked = HashSet::new();
+        let mut groups = HashMap::<&GroupId, HashSet<EndpointAddr>>::new();
+        for ep in eps {
+            match self.ep_groups.get(&ep) {
+                Some(group) => {
+                    groups.entry(group).or_default().insert(ep);
+                }
+                None => {
+                    picked.insert(ep);
+                }
+            }
+        }
+        for members in groups.values() {
+            picked.extend(HashRing::from_weights(members, &self.ep_weights).pick(hash));
+        }
+        picked
+    }
+    pub fn hold_new_message(&mut self, mut message: Message, ctx: &mut ProposalContext) {
+        if let Some(max) = self.config.max_subjects_per_message {
+            if message.header.subjects.len() > max as usize {
+                tracing::debug!(message_id = %message.id(), subjects = message.header.subjects.len(), "too many subjects");
+                ctx.resolve_ack(
+                    message.id(),
+                    Err(WaitAckError::exception(
+                        WaitAckErrorException::TooManySubjects,
+                    )),
+                );
+                return;
+            }
+        }
+        if let Some(max) = self.config.max_message_bytes {
+            // publishers check it too, unless they raced a config with a lower limit
+            if bincode::serialized_size(&message).is_ok_and(|size| size > u64::from(max)) {
+                tracing::debug!(message_id = %message.id(), "message too large");
+                ctx.resolve_ack(
+                    message.id(),
+                    Err(WaitAckError::exception(
+                        WaitAckErrorException::MessageTooLarge,
+                    )),
+                );
+                return;
+            }
+        }
+        if let Some(key) = &message.header.dedup_key {
+            if self.config.dedup_window > 0
+                && !self
+                    .dedup
+                    .insert(key.0.clone(), self.config.dedup_window as usize)
+            {
+                tracing::debug!(message_id = %message.id(), "duplicated message dropped");
+                ctx.resolve_ack(
+                    message.id(),
+                    Ok(WaitAckSuccess {
+                        status: HashMap::new(),
+                        delivered: None,
+                    }),
+                );
+                return;
             }
+        }
+        if !self
+            .config
+            .checksum
+            .verify(&message.payload.0, message.header.checksum)
+        {
+            // damaged on its way from the publisher, never hold bad data
+            ctx.report_corrupted_message(message.id());
+            ctx.dead_letter(
+                &message,
+                false,
+                Some(WaitAckErrorException::PayloadCorrupted),
+            );
+            ctx.resolve_ack(
+                message.id(),
+                Err(WaitAckError::exception(
+                    WaitAckErrorException::PayloadCorrupted,
+                )),
+            );
+            return;
+        }
+        #[cfg(feature = "metrics")]
+        ctx.node.metrics.message_published();
+        ctx.emit_event(LifecycleEvent::Published {
+            topic: self.config.code.clone(),
+            message_id: message.id(),
+        });
+        if self.config.normalize_subjects {
+            message.header.subjects = message
+                .header
+                .subjects
+                .iter()
+                .map(normalize_subject)
+                .collect();
+        }
+        if message
+            .header
+            .deliver_at
+            .is_some_and(|deliver_at| deliver_at > ctx.now())
+        {
+            tracing::debug!(message_id = %message.id(), "delay message");
+            self.queue.delay(message);
+            return;
+        }
+        self.route_message(message, ctx);
+    }
+    /// hold delayed messages past their `deliver_at`
+    pub(crate) fn release_due(&mut self, ctx: &mut ProposalContext) {
+        for message in self.queue.take_delayed_due(ctx.now()) {
+            tracing::debug!(message_id = %message.id(), "release delayed message");
+            self.route_message(message, ctx);
+        }
+    }
+    /// Endpoints the message goes to, picked by its target kind among the interested ones.
+    ///
+    /// `None` if an available or push message finds no endpoint, the others may wait for one.
+    pub(crate) fn select_targets(
+        &self,
+        message: &Message,
+        now: DateTime<Utc>,
+    ) -> Option<HashSet<EndpointAddr>> {
+        let ep_collect = self.collect_addr_by_message(message);
+        match message.header.target_kind {
+            // just accept all
+            MessageTargetKind::Durable => Some(ep_collect),
+            MessageTargetKind::Online => Some(self.pick_per_group(ep_collect, push_hash(message))),
             MessageTargetKind::Available => {
-                unimplemented!("available kind is not supported");
-                // unsupported
+                // every live endpoint gets it, fail fast if there is none
+                let ep_collect = ep_collect
+                    .into_iter()
+                    .filter(|ep| self.is_ep_fresh(ep, now))
+                    .collect::<HashSet<_>>();
+                (!ep_collect.is_empty()).then_some(ep_collect)
             }
             MessageTargetKind::Push => {
-                let message_hash = crate::util::hash64(&message.id());
-                let ep_collect = self.collect_addr_by_subjects(message.header.subjects.iter());
-
-                let mut hash_ring = ep_collect
-                    .iter()
-                    .map(|ep| (crate::util::hash64(ep), *ep))
-                    .collect::<Vec<_>>();
-                hash_ring.sort_by_key(|x| x.0);
-                if hash_ring.is_empty() {
-                    ctx.resolve_ack(
-                        message.id(),
-                        Err(WaitAckError::exception(
-                            WaitAckErrorException::NoAvailableTarget,
-                        )),
-                    );
-                    return;
-                } else {
-                    let ep = hash_ring[(message_hash as usize) % (hash_ring.len())].1;
-                    tracing::debug!(?ep, "select ep");
-                    HashSet::from([ep])
+                let mut ep_collect = ep_collect;
+                if let Some(hosted) = self
+                    .config
+                    .affinity
+                    .as_ref()
+                    .and_then(|affinity| self.ep_routing_table.get(affinity))
+                {
+                    let preferred = ep_collect
+                        .intersection(hosted)
+                        .copied()
+                        .collect::<HashSet<_>>();
+                    if !preferred.is_empty() {
+                        ep_collect = preferred;
+                    }
                 }
+
+                let hash_ring = HashRing::from_weights(&ep_collect, &self.ep_weights);
+                let ep = hash_ring.pick(push_hash(message))?;
+                tracing::debug!(?ep, "select ep");
+                Some(HashSet::from([ep]))
             }
+        }
+    }
+    /// Routing of `message` as if it were published at `now`, nothing is changed.
+    pub(crate) fn explain_routing(
+        &self,
+        message: &Message,
+        now: DateTime<Utc>,
+    ) -> RoutingExplanation {
+        let mut message = message.clone();
+        if self.config.normalize_subjects {
+            message.header.subjects = message
+                .header
+                .subjects
+                .iter()
+                .map(normalize_subject)
+                .collect();
+        }
+        let subjects = message
+            .header
+            .subjects
+            .iter()
+            .map(|subject| SubjectRouting {
+                subject: subject.clone(),
+                matches: self.ep_interest_map.interests_matching(subject),
+            })
+            .collect();
+        RoutingExplanation {
+            target_kind: message.header.target_kind,
+            subjects,
+            collected: self.collect_addr_by_message(&message),
+            targets: self.select_targets(&message, now).unwrap_or_default(),
+        }
+    }
+    /// pick the targets of an admitted message and hold it
+    fn route_message(&mut self, message: Message, ctx: &mut ProposalContext) {
+        let Some(ep_collect) = self.select_targets(&message, ctx.now()) else {
+            ctx.dead_letter(
+                &message,
+                false,
+                Some(WaitAckErrorException::NoAvailableTarget),
+            );
+            ctx.resolve_ack(
+                message.id(),
+                Err(WaitAckError::exception(
+                    WaitAckErrorException::NoAvailableTarget,
+                )),
+            );
+            return;
         };
+        if ep_collect.is_empty() && self.config.warn_on_no_subscribers {
+            tracing::warn!(
+                topic = %self.config.code,
+                message_id = %message.id(),
+                subjects = ?message.header.subjects,
+                "message published with no subscribers"
+            );
+            if message.header.target_kind == MessageTargetKind::Online {
+                ctx.resolve_ack(
+                    message.id(),
+                    Ok(WaitAckSuccess {
+                        status: HashMap::new(),
+                        delivered: Some(0),
+                    }),
+                );
+                return;
+            }
+        }
         let hold_message = HoldMessage {
             message: message.clone(),
             wait_ack: WaitAck::new(message.ack_kind(), ep_collect.clone()),
+            offloaded: false,
+            nacked: HashSet::new(),
+            delivery_attempts: 0,
+            redeliver_at: None,
         };
-        {
-            // put in queue
-            if let Some(overflow_config) = &self.config.overflow_config {
-                let size = u32::from(overflow_config.size) as usize;
-                let waiting_size = self.queue.len();
-                if waiting_size >= size {
-                    match overflow_config.policy {
-                        config::TopicOverflowPolicy::RejectNew => {
-                            ctx.resolve_ack(
-                                message.id(),
-                                Err(WaitAckError::exception(WaitAckErrorException::Overflow)),
-                            );
-                            return;
-                        }
-                        config::TopicOverflowPolicy::DropOld => {
-                            let old = self.queue.pop().expect("queue at least one element");
-                            ctx.resolve_ack(
-                                old.message.id(),
-                                Err(WaitAckError::exception(WaitAckErrorException::Overflow)),
-                            );
-                        }
+        if let Some(overflow_config) = &self.config.overflow_config {
+            let is_full = self.queue.len() >= overflow_config.size();
+            let waits_for_space = self.queue.blocking
+                || matches!(overflow_config.policy, config::TopicOverflowPolicy::Block);
+            if waits_for_space && (is_full || !self.queue.parked.is_empty()) {
+                if self.queue.parked.len() >= overflow_config.max_parked() {
+                    tracing::warn!(message_id = %message.id(), "too many messages wait for space");
+                    ctx.dead_letter(&message, false, Some(WaitAckErrorException::Overflow));
+                    ctx.resolve_ack(
+                        message.id(),
+                        Err(WaitAckError::exception(WaitAckErrorException::Overflow)),
+                    );
+                    return;
+                }
+                // blocking queue never drops or reorders, the publisher waits for space
+                // producers of a `Block` topic waited already, park the ones that raced past the check
+                tracing::debug!(message_id = %message.id(), "queue is full, wait for space");
+                self.queue.parked.push_back(hold_message);
+                return;
+            }
+            if is_full {
+                match overflow_config.policy {
+                    config::TopicOverflowPolicy::RejectNew => {
+                        ctx.dead_letter(&message, false, Some(WaitAckErrorException::Overflow));
+                        ctx.resolve_ack(
+                            message.id(),
+                            Err(WaitAckError::exception(WaitAckErrorException::Overflow)),
+                        );
+                        return;
+                    }
+                    config::TopicOverflowPolicy::Block => {
+                        unreachable!("full block topics park new messages")
+                    }
+                    config::TopicOverflowPolicy::DropOld => {
+                        let old = self
+                            .queue
+                            .select_victim(overflow_config.victim)
+                            .and_then(|id| self.queue.remove(id))
+                            .expect("queue at least one element");
+                        ctx.dead_letter(
+                            &old.message,
+                            old.offloaded,
+                            Some(WaitAckErrorException::Overflow),
+                        );
+                        ctx.resolve_ack(
+                            old.message.id(),
+                            Err(WaitAckError::exception(WaitAckErrorException::Overflow)),
+                        );
                     }
                 }
             }
-            self.queue.push(hold_message);
-            ctx.push_durable_command(DurableCommand::Create(message.clone()));
         }
-        self.update_and_flush(MessageStateUpdate::new_empty(message.id()), ctx);
         tracing::debug!(?ep_collect, "hold new message");
+        self.admit(hold_message, ctx);
+    }
+    fn admit(&mut self, mut hold_message: HoldMessage, ctx: &mut ProposalContext) {
+        hold_message.message.header.sequence = Some(self.queue.next_sequence());
+        let message = hold_message.message.clone();
+        if self.queue.lazy_payloads {
+            ctx.cache_payload(&message);
+        }
+        // held, stored and ordered at millisecond precision
+        let time = TimestampMs::from(ctx.now()).as_datetime();
+        self.queue.push(hold_message, time);
+        ctx.mirror_message(&message);
+        ctx.push_durable_command(DurableCommand::Create(Box::new(DurableMessage {
+            message: message.clone(),
+            status: Default::default(),
+            time,
+        })));
+        self.update_and_flush(MessageStateUpdate::new_empty(message.id()), ctx);
+    }
+    /// Apply `patch` to the config, held messages and endpoints are kept as they are.
+    ///
+    /// A larger overflow size admits parked messages right away, a smaller one only holds back new
+    /// ones, messages already held are never dropped for it.
+    pub(crate) fn update_config(&mut self, patch: &TopicConfigPatch, ctx: &mut ProposalContext) {
+        patch.apply(&mut self.config);
+        tracing::info!(topic = %self.config.code, ?patch, "topic config updated");
+        self.admit_parked(ctx);
+        ctx.notify_space();
+    }
+    /// move parked messages into the queue as long as there is space
+    fn admit_parked(&mut self, ctx: &mut ProposalContext) {
+        let Some(size) = self.config.overflow_config.as_ref().map(|c| c.size()) else {
+            return;
+        };
+        while self.queue.len() < size {
+            let Some(hold_message) = self.queue.parked.pop_front() else {
+                break;
+            };
+            self.admit(hold_message, ctx);
+        }
+    }
+    /// held messages dispatched to `ep` and not acked by it yet
+    pub(crate) fn pending_for(&self, ep: &EndpointAddr) -> Vec<MessageId> {
+        self.queue
+            .hold_messages
+            .iter()
+            .filter(|(_, message)| {
+                message
+                    .wait_ack
+                    .status
+                    .get(ep)
+                    .is_some_and(|status| !status.is_resolved(message.wait_ack.expect))
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    }
+    pub(crate) fn is_waiting(&self, message_id: &MessageId) -> bool {
+        self.queue.hold_messages.contains_key(message_id)
+            || self
+                .queue
+                .parked
+                .iter()
+                .any(|parked| parked.message.id() == *message_id)
+    }
+    /// whether committing `ack` resolves the message it acks
+    pub(crate) fn is_resolved_by(&self, ack: &MessageAck) -> bool {
+        let Some(message) = self.queue.hold_messages.get(&ack.ack_to) else {
+            return false;
+        };
+        if message.message.header.target_kind == MessageTargetKind::Durable {
+            return false;
+        }
+        let expect = message.wait_ack.expect;
+        message.wait_ack.status.iter().all(|(ep, status)| {
+            if *ep == ack.from {
+                ack.kind.is_resolved(expect)
+            } else {
+                status.is_resolved(expect)
+            }
+        })
+    }
+    /// drop a message from the queue without waiting for its acks, and report `outcome` to the producer
+    pub(crate) fn force_resolve(
+        &mut self,
+        message_id: MessageId,
+        outcome: ForceResolveOutcome,
+        ctx: &mut ProposalContext,
+    ) {
+        let hold_message = match self.queue.remove(message_id) {
+            Some(hold_message) => hold_message,
+            None => {
+                let Some(index) = self
+                    .queue
+                    .parked
+                    .iter()
+                    .position(|parked| parked.message.id() == message_id)
+                else {
+                    tracing::warn!(%message_id, "message to force resolve is not waiting");
+                    return;
+                };
+                self.queue.parked.remove(index).expect("index is in bounds")
+            }
+        };
+        tracing::warn!(%message_id, ?outcome, status = ?hold_message.wait_ack.status, "message force resolved");
+        let status = hold_message.wait_ack.status;
+        let result = match outcome {
+            ForceResolveOutcome::Success => Ok(WaitAckSuccess {
+                status,
+                delivered: None,
+            }),
+            ForceResolveOutcome::Failure => Err(WaitAckError {
+                status,
+                exception: None,
+            }),
+        };
+        ctx.resolve_ack(message_id, result);
+        ctx.push_durable_command(DurableCommand::Archive(message_id));
+        // a stuck front may have held back the rest of a blocking queue
+        let reachable_eps = self.dispatchable_eps(&ctx.node.id());
+        self.queue.flush(&reachable_eps, ctx);
+        ctx.notify_space();
+        self.admit_parked(ctx);
+    }
+    /// retained messages from `position` on, which `ep` is interested in
+    pub(crate) fn replay_for(&self, ep: &EndpointAddr, position: u64) -> Vec<Message> {
+        self.queue
+            .retained_since(position)
+            .filter(|message| self.is_interested(ep, message))
+            .cloned()
+            .collect()
+    }
+    /// the endpoint created with `key` and its interests, see `EndpointOptions::endpoint_key`
+    pub(crate) fn keyed_endpoint(
+        &self,
+        key: &bytes::Bytes,
+    ) -> Option<(EndpointAddr, Vec<Interest>)> {
+        let ep = *self.ep_keys.get(key)?;
+        let interests = self
+            .ep_interest_map
+            .interest_of(&ep)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        Some((ep, interests))
+    }
+    /// Messages sent to `ep` and not acked by it yet in dispatch order, with whether their
+    /// payload is offloaded.
+    pub(crate) fn unacked_for(&self, ep: &EndpointAddr) -> Vec<(Message, bool)> {
+        self.queue
+            .in_dispatch_order()
+            .filter_map(|id| {
+                let hold_message = self.queue.hold_messages.get(&id)?;
+                let expect = hold_message.wait_ack.expect;
+                let status = hold_message.wait_ack.status.get(ep)?;
+                (!status.is_unsent() && !status.is_resolved(expect))
+                    .then(|| (hold_message.message.clone(), hold_message.offloaded))
+            })
+            .collect()
+    }
+    /// whether `ep` is currently interested in any subject of `message` and its filter lets it through
+    pub(crate) fn is_interested(&self, ep: &EndpointAddr, message: &Message) -> bool {
+        message
+            .header
+            .subjects
+            .iter()
+            .any(|subject| self.ep_interest_map.find(subject).contains(ep))
+            && self.passes_filter(ep, message)
     }
     pub(crate) fn reachable_eps(&self, node_id: &NodeId) -> HashSet<EndpointAddr> {
         self.ep_routing_table
@@ -133,71 +850,520 @@ impl TopicData {
             .cloned()
             .unwrap_or_default()
     }
+    /// endpoints hosted by `node_id` that may be sent another message, the ones at their
+    /// prefetch limit are left out
+    fn dispatchable_eps(&self, node_id: &NodeId) -> HashSet<EndpointAddr> {
+        let mut eps = self.reachable_eps(node_id);
+        if !self.ep_prefetch.is_empty() {
+            eps.retain(|ep| self.has_credit(ep));
+        }
+        eps
+    }
+    /// whether `ep` has fewer messages sent to it and not acked yet than its prefetch
+    fn has_credit(&self, ep: &EndpointAddr) -> bool {
+        let Some(prefetch) = self.ep_prefetch.get(ep) else {
+            return true;
+        };
+        let in_flight = self.queue.in_flight.get(ep).copied().unwrap_or_default();
+        in_flight < *prefetch as usize
+    }
+    /// poll the messages held back from `eps` while they were at their prefetch limit
+    fn resume_prefetch(&mut self, eps: &[EndpointAddr], ctx: &mut ProposalContext) {
+        if eps.is_empty() {
+            return;
+        }
+        let waiting = self
+            .queue
+            .hold_messages
+            .iter()
+            .filter(|(_, message)| {
+                eps.iter().any(|ep| {
+                    message
+                        .wait_ack
+                        .status
+                        .get(ep)
+                        .is_some_and(MessageStatusKind::is_unsent)
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        self.poll_in_dispatch_order(waiting, ctx);
+    }
+    /// poll the messages of `partition` held back while an earlier one waited for its endpoints
+    fn resume_partition(&mut self, partition: Option<MaybeBase64Bytes>, ctx: &mut ProposalContext) {
+        let Some(key) = partition else {
+            return;
+        };
+        let waiting = self.queue.unsent_in_partition(&key);
+        self.poll_in_dispatch_order(waiting, ctx);
+    }
+    /// drop held messages past their `expire_at`, their producers get `Expired`
+    pub(crate) fn expire_due(&mut self, ctx: &mut ProposalContext) {
+        let expired = self.queue.take_expired(ctx.now());
+        if expired.is_empty() {
+            return;
+        }
+        for hold_message in expired {
+            let id = hold_message.message.id();
+            tracing::debug!(%id, "message expired");
+            ctx.dead_letter(
+                &hold_message.message,
+                hold_message.offloaded,
+                Some(WaitAckErrorException::Expired),
+            );
+            ctx.resolve_ack(
+                id,
+                Err(WaitAckError {
+                    status: hold_message.wait_ack.status,
+                    exception: Some(WaitAckErrorException::Expired),
+                }),
+            );
+            ctx.push_durable_command(DurableCommand::Archive(id));
+        }
+        self.admit_parked(ctx);
+        ctx.notify_space();
+    }
     pub(crate) fn update_and_flush(
         &mut self,
         update: MessageStateUpdate,
         ctx: &mut ProposalContext,
     ) {
-        let reachable_eps = self.reachable_eps(&ctx.node.id());
+        self.expire_due(ctx);
+        let reachable_eps = self.dispatchable_eps(&ctx.node.id());
+        // an ack, nack or failure, or the message resolving, may give the endpoint credit back
+        let replenished = update
+            .status
+            .keys()
+            .filter(|ep| self.ep_prefetch.contains_key(ep))
+            .copied()
+            .collect::<Vec<_>>();
+        // so do they for the next message of the same partition
+        let partition = if self.queue.ordered_partitions && !update.status.is_empty() {
+            self.queue
+                .hold_messages
+                .get(&update.message_id)
+                .and_then(|message| message.message.header.partition_key.clone())
+        } else {
+            None
+        };
         ctx.push_durable_command(DurableCommand::UpdateStatus(update.clone()));
         let poll_result = {
             for (from, status) in update.status {
-                self.queue.update_ack(&update.message_id, from, status)
+                self.touch_ep(&from, ctx.now());
+                #[cfg(feature = "metrics")]
+                if matches!(
+                    status,
+                    MessageStatusKind::Received | MessageStatusKind::Processed
+                ) {
+                    ctx.node.metrics.message_acked();
+                }
+                if matches!(
+                    status,
+                    MessageStatusKind::Received
+                        | MessageStatusKind::Processed
+                        | MessageStatusKind::Failed
+                ) {
+                    ctx.emit_event(LifecycleEvent::Acked {
+                        topic: self.config.code.clone(),
+                        message_id: update.message_id,
+                        endpoint: from,
+                        status,
+                    });
+                }
+                if status.is_unsent() {
+                    // endpoints only report `Unsent` to nack
+                    self.requeue(&update.message_id, from, ctx.now());
+                } else {
+                    self.queue.update_ack(&update.message_id, from, status)
+                }
+            }
+            if self.quarantine_if_poisoned(update.message_id, ctx) {
+                self.resume_prefetch(&replenished, ctx);
+                self.resume_partition(partition, ctx);
+                return;
             }
             self.queue
                 .poll_message(update.message_id, &reachable_eps, ctx)
         };
         if let Some(Poll::Ready(())) = poll_result {
             self.queue.flush(&reachable_eps, ctx);
+            self.admit_parked(ctx);
+        }
+        self.resume_prefetch(&replenished, ctx);
+        self.resume_partition(partition, ctx);
+    }
+    /// `from` nacked the message, offer it to other interested endpoints that haven't nacked it
+    ///
+    /// Online and durable messages already went to every interested endpoint, so there a nack
+    /// is a failure, unless another member of the nacking endpoint's consumer group can take an
+    /// online one. So is a nack with nobody left to take the message, it must not spin.
+    fn requeue(&mut self, message_id: &MessageId, from: EndpointAddr, now: DateTime<Utc>) {
+        let Some(hold_message) = self.queue.hold_messages.get(message_id) else {
+            return;
+        };
+        let expect = hold_message.wait_ack.expect;
+        let pending = hold_message
+            .wait_ack
+            .status
+            .get(&from)
+            .is_some_and(|status| !status.is_resolved(expect));
+        if !pending {
+            return;
+        }
+        let next = self.next_targets(hold_message, from, now);
+        let Some(hold_message) = self.queue.hold_messages.get_mut(message_id) else {
+            return;
+        };
+        hold_message.nacked.insert(from);
+        let status = &mut hold_message.wait_ack.status;
+        let in_flight = &mut self.queue.in_flight;
+        if next.is_empty() {
+            tracing::debug!(%message_id, ?from, "nacked with no other target");
+            let before = status.insert(from, MessageStatusKind::Failed);
+            MessageQueue::track_in_flight(in_flight, from, before, Some(MessageStatusKind::Failed));
+        } else {
+            tracing::debug!(%message_id, ?from, ?next, "nacked, requeue");
+            MessageQueue::track_in_flight(in_flight, from, status.remove(&from), None);
+            status.extend(next.into_iter().map(|ep| (ep, MessageStatusKind::Unsent)));
+        }
+    }
+    /// interested endpoints that may take a message `from` gave up, instead of `from`
+    ///
+    /// Endpoints that nacked the message or already have it are never picked.
+    fn next_targets(
+        &self,
+        hold_message: &HoldMessage,
+        from: EndpointAddr,
+        now: DateTime<Utc>,
+    ) -> Vec<EndpointAddr> {
+        let message = &hold_message.message;
+        let candidates = self
+            .collect_addr_by_message(message)
+            .into_iter()
+            .filter(|ep| {
+                *ep != from
+                    && !hold_message.nacked.contains(ep)
+                    && !hold_message.wait_ack.status.contains_key(ep)
+            });
+        match message.header.target_kind {
+            MessageTargetKind::Push => {
+                let candidates = candidates.collect::<HashSet<_>>();
+                HashRing::from_weights(&candidates, &self.ep_weights)
+                    .pick(push_hash(message))
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            }
+            MessageTargetKind::Available => {
+                candidates.filter(|ep| self.is_ep_fresh(ep, now)).collect()
+            }
+            MessageTargetKind::Online => match self.ep_groups.get(&from) {
+                Some(group) => {
+                    let members = candidates
+                        .filter(|ep| self.ep_groups.get(ep) == Some(group))
+                        .collect::<HashSet<_>>();
+                    HashRing::from_weights(&members, &self.ep_weights)
+                        .pick(push_hash(message))
+                        .into_iter()
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+            MessageTargetKind::Durable => Vec::new(),
+        }
+    }
+    /// Offer messages again to the endpoints that didn't ack them before their ack deadline.
+    ///
+    /// A push message goes to another interested endpoint when there is one, any other message to
+    /// the same endpoints. Once the topic's `max_redeliveries` are used up, those endpoints fail.
+    pub(crate) fn redeliver_due(&mut self, ctx: &mut ProposalContext) {
+        let now = ctx.now();
+        let due = self.queue.take_redelivery_due(now);
+        let Some(redelivery) = self.queue.redelivery else {
+            return;
+        };
+        let mut message_need_poll = HashSet::new();
+        for id in due {
+            let Some(hold_message) = self.queue.hold_messages.get(&id) else {
+                continue;
+            };
+            let expect = hold_message.wait_ack.expect;
+            let overdue = hold_message
+                .wait_ack
+                .status
+                .iter()
+                .filter(|(_, status)| !status.is_unsent() && !status.is_resolved(expect))
+                .map(|(ep, _)| *ep)
+                .collect::<Vec<_>>();
+            if overdue.is_empty() {
+                continue;
+            }
+            let exhausted = hold_message.delivery_attempts >= redelivery.max_redeliveries;
+            let targets = if exhausted {
+                Vec::new()
+            } else {
+                overdue
+                    .iter()
+                    .map(|ep| (*ep, self.next_targets(hold_message, *ep, now)))
+                    .collect::<Vec<_>>()
+            };
+            let Some(hold_message) = self.queue.hold_messages.get_mut(&id) else {
+                continue;
+            };
+            let status = &mut hold_message.wait_ack.status;
+            let in_flight = &mut self.queue.in_flight;
+            if exhausted {
+                tracing::debug!(%id, ?overdue, "not acked after the last redelivery");
+                for ep in overdue {
+                    let before = status.insert(ep, MessageStatusKind::Failed);
+                    MessageQueue::track_in_flight(
+                        in_flight,
+                        ep,
+                        before,
+                        Some(MessageStatusKind::Failed),
+                    );
+                }
+            } else {
+                hold_message.delivery_attempts += 1;
+                // consumers continue counting their attempts from here
+                Arc::make_mut(&mut hold_message.message.header.attributes).insert(
+                    MessageHeader::DELIVERY_ATTEMPT.to_owned(),
+                    MaybeBase64Bytes::new((hold_message.delivery_attempts + 1).to_string().into()),
+                );
+                tracing::debug!(%id, attempts = hold_message.delivery_attempts, ?overdue, "redeliver");
+                for (ep, next) in targets {
+                    if next.is_empty() {
+                        let before = status.insert(ep, MessageStatusKind::Unsent);
+                        MessageQueue::track_in_flight(
+                            in_flight,
+                            ep,
+                            before,
+                            Some(MessageStatusKind::Unsent),
+                        );
+                    } else {
+                        MessageQueue::track_in_flight(in_flight, ep, status.remove(&ep), None);
+                        status.extend(next.into_iter().map(|ep| (ep, MessageStatusKind::Unsent)));
+                    }
+                }
+            }
+            message_need_poll.insert(id);
+        }
+        message_need_poll.retain(|id| !self.quarantine_if_poisoned(*id, ctx));
+        self.poll_in_dispatch_order(message_need_poll, ctx);
+    }
+    /// Drop a message that failed `max_delivery_attempts` times, its producer gets
+    /// `PoisonMessage`. Returns whether it was dropped.
+    fn quarantine_if_poisoned(&mut self, id: MessageId, ctx: &mut ProposalContext) -> bool {
+        let Some(max) = self.config.max_delivery_attempts else {
+            return false;
+        };
+        let poisoned = self
+            .queue
+            .hold_messages
+            .get(&id)
+            .is_some_and(|hold_message| hold_message.failed_attempts() >= max);
+        if !poisoned {
+            return false;
+        }
+        let Some(hold_message) = self.queue.remove(id) else {
+            return false;
+        };
+        tracing::warn!(%id, attempts = hold_message.failed_attempts(), "poison message quarantined");
+        ctx.dead_letter(
+            &hold_message.message,
+            hold_message.offloaded,
+            Some(WaitAckErrorException::PoisonMessage),
+        );
+        ctx.resolve_ack(
+            id,
+            Err(WaitAckError {
+                status: hold_message.wait_ack.status,
+                exception: Some(WaitAckErrorException::PoisonMessage),
+            }),
+        );
+        ctx.push_durable_command(DurableCommand::Archive(id));
+        self.admit_parked(ctx);
+        ctx.notify_space();
+        true
+    }
+    /// only endpoints still online are tracked
+    fn touch_ep(&mut self, ep: &EndpointAddr, now: DateTime<Utc>) {
+        if let Some(latest_active) = self.ep_latest_active.get_mut(ep) {
+            *latest_active = now;
+        }
+    }
+    pub(crate) fn ep_heartbeat(&mut self, ep: &EndpointAddr, now: DateTime<Utc>) {
+        self.touch_ep(ep, now);
+    }
+    /// endpoints with no activity for longer than `ttl`, with their hosts
+    pub(crate) fn stale_eps(
+        &self,
+        now: DateTime<Utc>,
+        ttl: TimeDelta,
+    ) -> Vec<(NodeId, EndpointAddr)> {
+        self.ep_routing_table
+            .iter()
+            .flat_map(|(host, eps)| eps.iter().map(move |ep| (*host, *ep)))
+            .filter(|(_, ep)| {
+                self.ep_latest_active
+                    .get(ep)
+                    .is_some_and(|latest_active| now - *latest_active > ttl)
+            })
+            .collect()
+    }
+    /// active within `available_freshness`
+    fn is_ep_fresh(&self, ep: &EndpointAddr, now: DateTime<Utc>) -> bool {
+        let Some(window) = self.config.available_freshness else {
+            return true;
+        };
+        let window = TimeDelta::from_std(window).unwrap_or(TimeDelta::max_value());
+        self.ep_latest_active
+            .get(ep)
+            .is_some_and(|latest_active| now - *latest_active <= window)
+    }
+    /// host, interests and latest activity of one endpoint
+    pub(crate) fn ep_info(&self, ep: &EndpointAddr) -> Option<EpInfo> {
+        let latest_active = *self.ep_latest_active.get(ep)?;
+        let host = self
+            .ep_routing_table
+            .iter()
+            .find_map(|(host, eps)| eps.contains(ep).then_some(*host))?;
+        let interests = self
+            .ep_interest_map
+            .interest_of(ep)
+            .map(|interests| interests.iter().cloned().collect())
+            .unwrap_or_default();
+        Some(EpInfo {
+            host,
+            interests,
+            latest_active,
+            weight: self.ep_weights.get(ep).copied().unwrap_or(1),
+            group: self.ep_groups.get(ep).cloned(),
+            filter: self.ep_filters.get(ep).cloned(),
+            prefetch: self.ep_prefetch.get(ep).copied(),
+        })
+    }
+    pub(crate) fn ep_infos(&self) -> Vec<(EndpointAddr, EpInfo)> {
+        self.ep_latest_active
+            .keys()
+            .filter_map(|ep| Some((*ep, self.ep_info(ep)?)))
+            .collect()
+    }
+    /// poll `ids`, so a higher priority message is dispatched before a lower priority one
+    fn poll_in_dispatch_order(&mut self, ids: HashSet<MessageId>, ctx: &mut ProposalContext) {
+        let ordered = self
+            .queue
+            .in_dispatch_order()
+            .filter(|id| ids.contains(id))
+            .collect::<Vec<_>>();
+        for id in ordered {
+            self.update_and_flush(MessageStateUpdate::new_empty(id), ctx);
         }
     }
+    fn normalize_interests(&self, interests: Vec<Interest>) -> Vec<Interest> {
+        if self.config.normalize_subjects {
+            interests.iter().map(normalize_interest).collect()
+        } else {
+            interests
+        }
+    }
+    /// the first of `interests` not covered by `allowed_interests`
+    pub(crate) fn uncovered_interest(&self, interests: &[Interest]) -> Option<Interest> {
+        let allowed = self.config.allowed_interests.as_ref()?;
+        let allowed = self.normalize_interests(allowed.clone());
+        self.normalize_interests(interests.to_vec())
+            .into_iter()
+            .find(|requested| {
+                !allowed
+                    .iter()
+                    .any(|allowed| interest_covers(allowed, requested))
+            })
+    }
     pub(crate) fn update_ep_interest(
         &mut self,
         ep: &EndpointAddr,
         interests: Vec<Interest>,
         ctx: &mut ProposalContext,
     ) {
+        let interests = self.normalize_interests(interests);
+        self.touch_ep(ep, ctx.now());
         self.ep_interest_map.delete(ep);
         for interest in interests {
             self.ep_interest_map.insert(interest, *ep);
         }
         let mut message_need_poll = HashSet::new();
         for (id, message) in &mut self.queue.hold_messages {
-            if message.message.header.target_kind == MessageTargetKind::Durable {
-                for subject in message.message.header.subjects.iter() {
-                    // if
-                    if self.ep_interest_map.find(subject).contains(ep) {
-                        if !message.wait_ack.status.contains_key(ep) {
-                            message
-                                .wait_ack
-                                .status
-                                .insert(*ep, MessageStatusKind::Unsent);
-                        }
-                        message_need_poll.insert(*id);
-                    }
+            let matched = message
+                .message
+                .header
+                .subjects
+                .iter()
+                .any(|subject| self.ep_interest_map.find(subject).contains(ep))
+                && self
+                    .ep_filters
+                    .get(ep)
+                    .into_iter()
+                    .all(|f| f.matches(&message.message));
+            let status = &mut message.wait_ack.status;
+            if matched {
+                if message.message.header.target_kind == MessageTargetKind::Durable {
+                    status.entry(*ep).or_insert(MessageStatusKind::Unsent);
+                    message_need_poll.insert(*id);
                 }
+            } else if status.get(ep) == Some(&MessageStatusKind::Unsent) {
+                // not sent yet and no longer wanted, the ack set doesn't wait for it
+                status.remove(ep);
+                message_need_poll.insert(*id);
             }
         }
-        for id in message_need_poll {
-            self.update_and_flush(MessageStateUpdate::new_empty(id), ctx);
-        }
+        self.poll_in_dispatch_order(message_need_poll, ctx);
     }
     pub(crate) fn ep_online(
         &mut self,
-        endpoint: EndpointAddr,
-        interests: Vec<Interest>,
-        host: NodeId,
+        EndpointOnline {
+            endpoint,
+            interests,
+            host,
+            weight,
+            group,
+            filter,
+            prefetch,
+            key,
+            ..
+        }: EndpointOnline,
         ctx: &mut ProposalContext,
     ) {
+        let interests = self.normalize_interests(interests);
         let mut message_need_poll = HashSet::new();
         {
+            if let Some(key) = key {
+                // reclaimed, maybe from another node
+                for hosted in self.ep_routing_table.values_mut() {
+                    hosted.remove(&endpoint);
+                }
+                self.ep_keys.insert(key, endpoint);
+            }
             self.ep_routing_table
                 .entry(host)
                 .or_default()
                 .insert(endpoint);
+            self.ep_latest_active.insert(endpoint, ctx.now());
+            self.ep_weights.insert(endpoint, weight.max(1));
+            if let Some(group) = group {
+                self.ep_groups.insert(endpoint, group);
+            }
+            if let Some(filter) = &filter {
+                self.ep_filters.insert(endpoint, filter.clone());
+            }
+            if let Some(prefetch) = prefetch {
+                self.ep_prefetch.insert(endpoint, prefetch.max(1));
+            }
             for interest in &interests {
                 self.ep_interest_map.insert(interest.clone(), endpoint);
             }
+            ctx.emit_event(LifecycleEvent::EndpointOnline {
+                topic: self.config.code.clone(),
+                endpoint,
+            });
             let queue = &mut self.queue;
             for (id, message) in &mut queue.hold_messages {
                 if message.message.header.target_kind == MessageTargetKind::Durable {
@@ -209,6 +1375,10 @@ impl TopicData {
                             .subjects
                             .iter()
                             .any(|s| self.ep_interest_map.find(s).contains(&endpoint))
+                        && filter
+                            .as_ref()
+                            .into_iter()
+                            .all(|f| f.matches(&message.message))
                     {
                         status.insert(endpoint, MessageStatusKind::Unsent);
                         message_need_poll.insert(*id);
@@ -216,9 +1386,73 @@ impl TopicData {
                 }
             }
         }
-        for id in message_need_poll {
-            self.update_and_flush(MessageStateUpdate::new_empty(id), ctx);
+        self.poll_in_dispatch_order(message_need_poll, ctx);
+    }
+
+    /// take all endpoints hosted by `host` offline in one pass
+    ///
+    /// Durable messages they hadn't acked yet are handed off: the departed endpoints give up their
+    /// slot, and the message is offered again to the remaining interested endpoints.
+    pub(crate) fn node_offline(&mut self, host: NodeId, ctx: &mut ProposalContext) {
+        let Some(endpoints) = self.ep_routing_table.remove(&host) else {
+            return;
+        };
+        tracing::info!(%host, count = endpoints.len(), "endpoints offline with their host");
+        for endpoint in &endpoints {
+            self.ep_interest_map.delete(endpoint);
+            self.ep_latest_active.remove(endpoint);
+            self.ep_weights.remove(endpoint);
+            self.ep_groups.remove(endpoint);
+            self.ep_filters.remove(endpoint);
+            self.ep_prefetch.remove(endpoint);
+            ctx.emit_event(LifecycleEvent::EndpointOffline {
+                topic: self.config.code.clone(),
+                endpoint: *endpoint,
+            });
+        }
+        self.ep_keys.retain(|_, ep| !endpoints.contains(ep));
+        let mut message_need_poll = HashSet::new();
+        for (id, message) in &mut self.queue.hold_messages {
+            let expect = message.wait_ack.expect;
+            let status = &mut message.wait_ack.status;
+            let mut handoff = false;
+            for endpoint in &endpoints {
+                let Some(ep_status) = status.get_mut(endpoint) else {
+                    continue;
+                };
+                let before = *ep_status;
+                if message.message.header.target_kind == MessageTargetKind::Durable
+                    && !ep_status.is_reached(expect)
+                {
+                    status.remove(endpoint);
+                    handoff = true;
+                } else {
+                    *ep_status = MessageStatusKind::Unreachable;
+                }
+                MessageQueue::track_in_flight(
+                    &mut self.queue.in_flight,
+                    *endpoint,
+                    Some(before),
+                    None,
+                );
+                message_need_poll.insert(*id);
+            }
+            if handoff {
+                for subject in message.message.header.subjects.iter() {
+                    for endpoint in self.ep_interest_map.find(subject) {
+                        if self
+                            .ep_filters
+                            .get(endpoint)
+                            .into_iter()
+                            .all(|f| f.matches(&message.message))
+                        {
+                            status.entry(*endpoint).or_insert(MessageStatusKind::Unsent);
+                        }
+                    }
+                }
+            }
         }
+        self.poll_in_dispatch_order(message_need_poll, ctx);
     }
 
     pub(crate) fn ep_offline(
@@ -232,16 +1466,30 @@ impl TopicData {
             .or_default()
             .remove(endpoint);
         self.ep_interest_map.delete(endpoint);
+        self.ep_latest_active.remove(endpoint);
+        self.ep_weights.remove(endpoint);
+        self.ep_groups.remove(endpoint);
+        self.ep_filters.remove(endpoint);
+        self.ep_prefetch.remove(endpoint);
+        self.ep_keys.retain(|_, ep| ep != endpoint);
+        ctx.emit_event(LifecycleEvent::EndpointOffline {
+            topic: self.config.code.clone(),
+            endpoint: *endpoint,
+        });
         let mut message_need_poll = HashSet::new();
         // update state
         for message in self.queue.hold_messages.values_mut() {
             if let Some(status) = message.wait_ack.status.get_mut(endpoint) {
-                *status = MessageStatusKind::Unreachable;
+                let before = std::mem::replace(status, MessageStatusKind::Unreachable);
+                MessageQueue::track_in_flight(
+                    &mut self.queue.in_flight,
+                    *endpoint,
+                    Some(before),
+                    Some(MessageStatusKind::Unreachable),
+                );
                 message_need_poll.insert(message.message.id());
             }
         }
-        for id in message_need_poll {
-            self.update_and_flush(MessageStateUpdate::new_empty(id), ctx);
-        }
+        self.poll_in_dispatch_order(message_need_poll, ctx);
     }
 }
@@ -1,8 +1,14 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
-use crate::prelude::TopicCode;
+use crate::{
+    prelude::{Interest, InterestMapKind, NodeId, TopicCode},
+    protocol::{
+        node::edge::codec::CodecKind,
+        topic::{mirror::MirrorConfig, validator::PayloadValidatorService},
+    },
+};
 
 #[derive(Debug, Clone, Default)]
 
@@ -12,6 +18,20 @@ pub enum TopicOverflowPolicy {
     #[default]
     RejectNew = 0,
     DropOld = 1,
+    /// the producer waits in `Topic::send_message` until the queue has room
+    Block = 2,
+}
+
+/// Which message is sacrificed when [`TopicOverflowPolicy::DropOld`] kicks in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TopicOverflowVictim {
+    Oldest = 0,
+    Newest = 1,
+    /// the oldest of the messages with the lowest priority, the same as `Oldest` when no
+    /// message sets a priority
+    #[default]
+    LowestPriority = 2,
+    NearestExpiry = 3,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,9 +39,23 @@ pub enum TopicOverflowPolicy {
 pub struct TopicOverflowConfig {
     pub policy: TopicOverflowPolicy,
     pub size: NonZeroU32,
+    /// only used by [`TopicOverflowPolicy::DropOld`]
+    #[serde(default)]
+    pub victim: TopicOverflowVictim,
+    /// only used by [`TopicOverflowPolicy::Block`], how long a producer waits before the message
+    /// fails with `Overflow`, defaults to [`TopicOverflowConfig::DEFAULT_BLOCK_TIMEOUT`]
+    #[serde(default)]
+    pub block_timeout: Option<Duration>,
+    /// most messages a full blocking or [`TopicOverflowPolicy::Block`] queue parks while they
+    /// wait for space, further ones fail with `Overflow`, defaults to
+    /// [`TopicOverflowConfig::DEFAULT_MAX_PARKED`]
+    #[serde(default)]
+    pub max_parked: Option<NonZeroU32>,
 }
 
 impl TopicOverflowConfig {
+    pub const DEFAULT_BLOCK_TIMEOUT: Duration = Duration::from_secs(30);
+    pub const DEFAULT_MAX_PARKED: usize = 1024;
     #[inline(always)]
     pub fn size(&self) -> usize {
         self.size.get() as usize
@@ -30,12 +64,108 @@ impl TopicOverflowConfig {
         Self {
             policy: TopicOverflowPolicy::RejectNew,
             size: NonZeroU32::new(size).unwrap_or(NonZeroU32::MAX),
+            victim: TopicOverflowVictim::default(),
+            block_timeout: None,
+            max_parked: None,
         }
     }
     pub fn new_drop_old(size: u32) -> Self {
+        Self::new_drop(size, TopicOverflowVictim::default())
+    }
+    pub fn new_drop(size: u32, victim: TopicOverflowVictim) -> Self {
         Self {
             policy: TopicOverflowPolicy::DropOld,
             size: NonZeroU32::new(size).unwrap_or(NonZeroU32::MAX),
+            victim,
+            block_timeout: None,
+            max_parked: None,
+        }
+    }
+    pub fn new_block(size: u32, timeout: Duration) -> Self {
+        Self {
+            policy: TopicOverflowPolicy::Block,
+            size: NonZeroU32::new(size).unwrap_or(NonZeroU32::MAX),
+            victim: TopicOverflowVictim::default(),
+            block_timeout: Some(timeout),
+            max_parked: None,
+        }
+    }
+    pub fn block_timeout(&self) -> Duration {
+        self.block_timeout.unwrap_or(Self::DEFAULT_BLOCK_TIMEOUT)
+    }
+    pub fn max_parked(&self) -> usize {
+        self.max_parked
+            .map_or(Self::DEFAULT_MAX_PARKED, |max_parked| {
+                max_parked.get() as usize
+            })
+    }
+}
+
+/// Token bucket limiting how fast a topic admits new messages.
+///
+/// The bucket starts full, every admitted message takes one token, and tokens refill at
+/// `per_second` up to `burst`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TopicRateLimit {
+    pub per_second: NonZeroU32,
+    pub burst: NonZeroU32,
+}
+
+impl TopicRateLimit {
+    pub fn new(per_second: u32, burst: u32) -> Self {
+        Self {
+            per_second: NonZeroU32::new(per_second).unwrap_or(NonZeroU32::MIN),
+            burst: NonZeroU32::new(burst).unwrap_or(NonZeroU32::MIN),
+        }
+    }
+}
+
+/// Redelivery of messages expecting `Processed` that an endpoint took but never acked.
+///
+/// An endpoint that hasn't acked within the deadline gets the message again, or another endpoint
+/// does when the message is a push one. Every redelivery doubles the deadline, and once
+/// `max_redeliveries` are used up the endpoints still not acking are counted as failed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TopicRedeliveryConfig {
+    /// deadline of the first delivery
+    pub ack_deadline: Duration,
+    pub max_redeliveries: u32,
+}
+
+impl TopicRedeliveryConfig {
+    pub fn new(ack_deadline: Duration, max_redeliveries: u32) -> Self {
+        Self {
+            ack_deadline,
+            max_redeliveries,
+        }
+    }
+    /// ack deadline after `attempts` redeliveries
+    pub fn deadline(&self, attempts: u32) -> Duration {
+        self.ack_deadline
+            .saturating_mul(1u32.checked_shl(attempts).unwrap_or(u32::MAX))
+    }
+}
+
+/// Checksum algorithm applied to message payloads of a topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TopicChecksum {
+    #[default]
+    None = 0,
+    Crc32 = 1,
+}
+
+impl TopicChecksum {
+    pub fn digest(&self, payload: &[u8]) -> Option<u32> {
+        match self {
+            TopicChecksum::None => None,
+            TopicChecksum::Crc32 => Some(crate::util::crc32(payload)),
+        }
+    }
+    /// Messages without a recorded checksum are always considered valid.
+    pub fn verify(&self, payload: &[u8], checksum: Option<u32>) -> bool {
+        match (self.digest(payload), checksum) {
+            (Some(digest), Some(checksum)) => digest == checksum,
+            _ => true,
         }
     }
 }
@@ -43,8 +173,165 @@ impl TopicOverflowConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TopicConfig {
     pub code: TopicCode,
+    /// Deliver and resolve messages strictly in publish order.
+    ///
+    /// When the queue is full, new messages wait for space instead of being rejected or dropping others,
+    /// the publisher's ack handle stays pending until its message is admitted and resolved.
     pub blocking: bool,
     pub overflow_config: Option<TopicOverflowConfig>,
+    #[serde(default)]
+    pub checksum: TopicChecksum,
+    #[serde(default)]
+    pub mirror: Option<MirrorConfig>,
+    /// number of latest messages kept for replay by `LocalEndpoint::seek`, 0 disables replay
+    #[serde(default)]
+    pub retention: u32,
+    /// lowercase subjects and interests and ignore duplicate or trailing `/` when routing, off by default
+    #[serde(default)]
+    pub normalize_subjects: bool,
+    /// Messages are never written to the durable service and are left out of raft snapshots.
+    ///
+    /// Endpoints and routing still replicate, but messages not yet delivered are lost when the leader changes.
+    #[serde(default)]
+    pub ephemeral: bool,
+    /// Unload the topic after it had no endpoints and an empty queue for this long.
+    ///
+    /// The config is persisted to the durable service, and the topic is reloaded from there on the
+    /// next publish or subscribe. Ignored when the node has no durable service.
+    #[serde(default)]
+    pub idle_unload_after: Option<Duration>,
+    /// Node preferred for this topic's dispatch work, usually the one hosting most of its consumers.
+    ///
+    /// Every node dispatches messages to the endpoints it hosts, so affinity works by choosing the
+    /// endpoint: a push message goes to an interested endpoint on this node whenever there is one,
+    /// and only falls back to endpoints on other nodes (or reached over edge connections) otherwise.
+    /// Online and durable messages reach all interested endpoints and are not affected. Raft
+    /// leadership is independent of this setting.
+    #[serde(default)]
+    pub affinity: Option<NodeId>,
+    /// Keep only headers of held messages in memory and load payloads from the durable service
+    /// when they are dispatched, trading dispatch latency for memory on large backlogs.
+    ///
+    /// Recently published and recently loaded payloads are served from a small cache. Needs a
+    /// durable service, and is ignored for ephemeral topics.
+    #[serde(default)]
+    pub lazy_payloads: bool,
+    /// An endpoint only counts as available for `Available` messages if it was active within this window.
+    ///
+    /// Activity is coming online, changing interests or acking a message. `None` counts every online endpoint.
+    #[serde(default)]
+    pub available_freshness: Option<Duration>,
+    /// Re-publish messages that fail here into this topic, tagged with [`DeadLetter`](crate::prelude::DeadLetter).
+    ///
+    /// A message fails when it's rejected or dropped on overflow, finds no target, expires, or is
+    /// acked as failed. Messages that are already dead letters are never dead-lettered again.
+    #[serde(default)]
+    pub dead_letter: Option<TopicCode>,
+    /// number of latest [`dedup_key`](crate::prelude::MessageHeader::dedup_key)s remembered, 0 disables deduplication
+    ///
+    /// A message whose key is still remembered is not held again, its publisher is resolved as succeeded.
+    #[serde(default)]
+    pub dedup_window: u32,
+    /// Reject messages with `RateLimited` once the topic admits them faster than this.
+    ///
+    /// Admission is done by the leader before a message is proposed, so every node agrees on it.
+    /// The bucket is never replicated or snapshotted, it starts full on a new leader.
+    #[serde(default)]
+    pub rate_limit: Option<TopicRateLimit>,
+    /// Endpoints may only subscribe to interests covered by one of these, `None` allows any interest.
+    ///
+    /// Wildcards cover narrower interests, `orders/**` allows `orders/eu/*` but `orders/*` doesn't
+    /// allow `orders/**`. Creating an endpoint or updating interests outside of it fails with `Unauthorized`.
+    #[serde(default)]
+    pub allowed_interests: Option<Vec<Interest>>,
+    /// Reject messages with more subjects than this with `TooManySubjects`, `None` allows any number.
+    ///
+    /// Checked before routing, so a message with thousands of subjects never reaches the interest map.
+    #[serde(default)]
+    pub max_subjects_per_message: Option<u32>,
+    /// Redeliver `Processed` messages endpoints don't ack in time, `None` waits for the ack forever.
+    #[serde(default)]
+    pub redelivery: Option<TopicRedeliveryConfig>,
+    /// Reject messages larger than this with `MessageTooLarge`, header and payload as they are
+    /// proposed, `None` allows any size. See also `NodeConfig::max_message_bytes`.
+    ///
+    /// Publishers check it before proposing, so an oversize message never enters the raft log.
+    #[serde(default)]
+    pub max_message_bytes: Option<u32>,
+    /// Quarantine a message once it was nacked or redelivered this many times in total, its
+    /// producer gets `PoisonMessage` and it goes to the dead letter topic if there is one.
+    ///
+    /// `None` lets it go round until every endpoint nacked it or `max_redeliveries` is used up.
+    #[serde(default)]
+    pub max_delivery_attempts: Option<u32>,
+    /// Send messages sharing a [`partition_key`](crate::prelude::MessageHeaderBuilder::partition_key)
+    /// to each endpoint one at a time, in the order they were published.
+    ///
+    /// A message waits for an endpoint until the previous message with the same key is acked or
+    /// failed by it, so redelivery and concurrent dispatch can't reorder them. Messages without a
+    /// key are not affected.
+    #[serde(default)]
+    pub ordered_partitions: bool,
+    /// How endpoint interests are stored, the same endpoints are found either way.
+    #[serde(default)]
+    pub interest_map: InterestMapKind,
+    /// Codec of typed payloads, see [`Topic::send_typed`](crate::prelude::Topic::send_typed),
+    /// `None` is json.
+    ///
+    /// Raw payloads are never touched by it.
+    #[serde(default)]
+    pub payload_codec: Option<CodecKind>,
+    /// Warn about messages published while no endpoint is interested in them.
+    ///
+    /// An online one is resolved right away with `delivered` of `Some(0)` instead of being held,
+    /// a durable one is still held for the endpoints coming later.
+    #[serde(default)]
+    pub warn_on_no_subscribers: bool,
+    /// Reject messages whose payload it refuses with `ValidationFailed`.
+    ///
+    /// It's node-local and never replicated or persisted: it's registered on the node the topic is
+    /// loaded from, kept there when the topic is unloaded and reloaded, and only checks messages
+    /// published on that node.
+    #[serde(skip)]
+    pub validator: Option<PayloadValidatorService>,
+}
+
+/// Changes to the settings of a loaded topic, see `Node::update_topic_config`.
+///
+/// Fields left `None` are kept and `Some(None)` clears a setting. Only settings that can change
+/// under held messages are here, the code and settings like `blocking` or `checksum` need the
+/// topic to be created again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopicConfigPatch {
+    pub overflow_config: Option<Option<TopicOverflowConfig>>,
+    pub rate_limit: Option<Option<TopicRateLimit>>,
+    pub dead_letter: Option<Option<TopicCode>>,
+}
+
+impl TopicConfigPatch {
+    pub fn overflow_config(mut self, overflow_config: Option<TopicOverflowConfig>) -> Self {
+        self.overflow_config = Some(overflow_config);
+        self
+    }
+    pub fn rate_limit(mut self, rate_limit: Option<TopicRateLimit>) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+    pub fn dead_letter(mut self, dead_letter: Option<TopicCode>) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+    pub fn apply(&self, config: &mut TopicConfig) {
+        if let Some(overflow_config) = &self.overflow_config {
+            config.overflow_config = overflow_config.clone();
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            config.rate_limit = rate_limit;
+        }
+        if let Some(dead_letter) = &self.dead_letter {
+            config.dead_letter = dead_letter.clone();
+        }
+    }
 }
 
 impl From<TopicCode> for TopicConfig {
@@ -53,6 +340,28 @@ impl From<TopicCode> for TopicConfig {
             code,
             blocking: false,
             overflow_config: None,
+            checksum: TopicChecksum::None,
+            mirror: None,
+            retention: 0,
+            normalize_subjects: false,
+            ephemeral: false,
+            idle_unload_after: None,
+            affinity: None,
+            lazy_payloads: false,
+            available_freshness: None,
+            dead_letter: None,
+            dedup_window: 0,
+            rate_limit: None,
+            allowed_interests: None,
+            max_subjects_per_message: None,
+            redelivery: None,
+            max_message_bytes: None,
+            max_delivery_attempts: None,
+            ordered_partitions: false,
+            interest_map: InterestMapKind::Trie,
+            payload_codec: None,
+            warn_on_no_subscribers: false,
+            validator: None,
         }
     }
 }
@@ -0,0 +1,46 @@
+use std::collections::{HashSet, VecDeque};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// The latest dedup keys seen by a topic, oldest evicted first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DedupWindow {
+    order: VecDeque<Bytes>,
+    seen: HashSet<Bytes>,
+}
+
+impl DedupWindow {
+    /// remember `key`, false if it's already in the window
+    pub(crate) fn insert(&mut self, key: Bytes, capacity: usize) -> bool {
+        if self.seen.contains(&key) {
+            return false;
+        }
+        while self.order.len() >= capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.seen.remove(&oldest);
+        }
+        self.seen.insert(key.clone());
+        self.order.push_back(key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_evict_oldest() {
+        let mut window = DedupWindow::default();
+        assert!(window.insert(Bytes::from_static(b"a"), 2));
+        assert!(window.insert(Bytes::from_static(b"b"), 2));
+        assert!(!window.insert(Bytes::from_static(b"a"), 2));
+        assert!(window.insert(Bytes::from_static(b"c"), 2));
+        // `a` was evicted by `c`
+        assert!(window.insert(Bytes::from_static(b"a"), 2));
+        assert!(!window.insert(Bytes::from_static(b"c"), 2));
+    }
+}
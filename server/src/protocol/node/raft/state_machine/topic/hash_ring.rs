@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use crate::{protocol::endpoint::EndpointAddr, util::hash64};
+
+/// Consistent hash ring over endpoints, each gets `weight * VIRTUAL_NODES_PER_WEIGHT` points.
+///
+/// Adding or removing one endpoint only remaps the keys that land on its points.
+#[derive(Debug, Clone)]
+pub(crate) struct HashRing {
+    points: Vec<(u64, EndpointAddr)>,
+}
+
+impl HashRing {
+    pub(crate) const VIRTUAL_NODES_PER_WEIGHT: u32 = 16;
+    /// a weight of 0 counts as 1
+    pub(crate) fn new(endpoints: impl IntoIterator<Item = (EndpointAddr, u32)>) -> Self {
+        let mut points = Vec::new();
+        for (ep, weight) in endpoints {
+            for replica in 0..weight.max(1).saturating_mul(Self::VIRTUAL_NODES_PER_WEIGHT) {
+                points.push((hash64(&(ep, replica)), ep));
+            }
+        }
+        // ties are broken by address, so the ring doesn't depend on iteration order
+        points.sort_unstable_by_key(|(point, ep)| (*point, ep.bytes));
+        Self { points }
+    }
+    /// first endpoint clockwise from the key's hash
+    pub(crate) fn pick(&self, key_hash: u64) -> Option<EndpointAddr> {
+        let index = self.points.partition_point(|(point, _)| *point < key_hash);
+        self.points
+            .get(index)
+            .or_else(|| self.points.first())
+            .map(|(_, ep)| *ep)
+    }
+    pub(crate) fn from_weights<'e>(
+        endpoints: impl IntoIterator<Item = &'e EndpointAddr>,
+        weights: &HashMap<EndpointAddr, u32>,
+    ) -> Self {
+        Self::new(
+            endpoints
+                .into_iter()
+                .map(|ep| (*ep, weights.get(ep).copied().unwrap_or(1))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocol::message::MessageId;
+
+    #[test]
+    fn test_remove_endpoint_keeps_most_mappings() {
+        let endpoints = [
+            EndpointAddr::new_snowflake(),
+            EndpointAddr::new_snowflake(),
+            EndpointAddr::new_snowflake(),
+        ];
+        let keys = (0..1000)
+            .map(|_| hash64(&MessageId::new_snowflake()))
+            .collect::<Vec<_>>();
+        let full = HashRing::new(endpoints.iter().map(|ep| (*ep, 1)));
+        let reduced = HashRing::new(endpoints[..2].iter().map(|ep| (*ep, 1)));
+        let unchanged = keys
+            .iter()
+            .filter(|key| full.pick(**key) == reduced.pick(**key))
+            .count();
+        // only keys of the removed endpoint move, about a third of them
+        assert!(unchanged > keys.len() / 2, "unchanged: {unchanged}");
+        for key in &keys {
+            if full.pick(*key) != Some(endpoints[2]) {
+                assert_eq!(full.pick(*key), reduced.pick(*key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_weight() {
+        let light = EndpointAddr::new_snowflake();
+        let heavy = EndpointAddr::new_snowflake();
+        let ring = HashRing::new([(light, 1), (heavy, 4)]);
+        let heavy_count = (0..1000)
+            .map(|_| hash64(&MessageId::new_snowflake()))
+            .filter(|key| ring.pick(*key) == Some(heavy))
+            .count();
+        assert!(heavy_count > 600, "heavy: {heavy_count}");
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
     task::Poll,
 };
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -13,11 +15,14 @@ use crate::{
         message::*,
         node::raft::{
             proposal::ProposalContext,
-            state_machine::topic::wait_ack::{WaitAckError, WaitAckSuccess},
+            state_machine::topic::{
+                config::{TopicChecksum, TopicOverflowVictim, TopicRedeliveryConfig},
+                wait_ack::{WaitAckError, WaitAckSuccess},
+            },
         },
         topic::durable_message::DurableCommand,
     },
-    util::Timed,
+    util::{MaybeBase64Bytes, Timed},
 };
 
 use super::wait_ack::{WaitAck, WaitAckResult};
@@ -26,9 +31,25 @@ use super::wait_ack::{WaitAck, WaitAckResult};
 pub(crate) struct HoldMessage {
     pub message: Message,
     pub wait_ack: WaitAck,
+    /// payload dropped from memory, it's loaded from the durable service on dispatch
+    #[serde(default)]
+    pub offloaded: bool,
+    /// endpoints that gave the message back, it's never offered to them again
+    #[serde(default)]
+    pub nacked: HashSet<EndpointAddr>,
+    /// times the message was redelivered to endpoints that didn't ack in time
+    #[serde(default)]
+    pub delivery_attempts: u32,
+    /// when endpoints that haven't acked yet get the message again, see `TopicRedeliveryConfig`
+    #[serde(default)]
+    pub redeliver_at: Option<DateTime<Utc>>,
 }
 
 impl HoldMessage {
+    /// endpoints that nacked it plus redeliveries, compared against `max_delivery_attempts`
+    pub(crate) fn failed_attempts(&self) -> u32 {
+        self.nacked.len() as u32 + self.delivery_attempts
+    }
     // pub(crate) fn as_durable(&self) -> DurableMessage {
     //     DurableMessage {
     //         message: self.message.clone(),
@@ -47,26 +68,38 @@ impl HoldMessage {
     //         wait_ack,
     //     }
     // }
+    fn offload(&mut self) {
+        self.message.payload = crate::util::MaybeBase64Bytes(Default::default());
+        self.offloaded = true;
+    }
     pub(crate) fn send_unsent(
         &mut self,
+        checksum: TopicChecksum,
         reachable_eps: &HashSet<EndpointAddr>,
+        in_flight: &mut HashMap<EndpointAddr, usize>,
         context: &ProposalContext,
     ) {
         for (ep, status) in self.wait_ack.status.iter_mut() {
             tracing::debug!(?ep, %status, ?reachable_eps, "send_unsent");
             if status.is_unsent() && reachable_eps.contains(ep) {
+                // payloads in memory were verified when the message was held, offloaded ones are
+                // verified when they are loaded
                 *status = MessageStatusKind::Sending;
-                context.dispatch_message(&self.message, *ep);
+                *in_flight.entry(*ep).or_default() += 1;
+                if self.offloaded {
+                    context.dispatch_offloaded_message(&self.message, checksum, *ep);
+                } else {
+                    context.dispatch_message(&self.message, *ep);
+                }
             }
         }
     }
-    pub(crate) fn is_resolved(&self) -> bool {
+    pub(crate) fn is_resolved(&self, now: DateTime<Utc>) -> bool {
         match self.message.header.target_kind {
             MessageTargetKind::Durable => {
                 let Some(durability_config) = self.message.header.durability.as_ref() else {
                     return true;
                 };
-                let now = Utc::now();
                 if now > durability_config.expire {
                     return true;
                 }
@@ -85,17 +118,21 @@ impl HoldMessage {
             }
         }
     }
-    pub(crate) fn resolve(self) -> WaitAckResult {
+    pub(crate) fn resolve(&mut self) -> WaitAckResult {
         tracing::trace!("resolved: {self:?}");
-        let wait_ack = self.wait_ack;
-        let status = wait_ack.status;
-        if status.iter().any(|(_, ack)| ack.is_failed()) {
+        let status = std::mem::take(&mut self.wait_ack.status);
+        if !status.is_empty() && status.values().all(MessageStatusKind::is_failed) {
+            // a partial failure is still a success, the status tells which endpoints failed
             Err(WaitAckError {
                 status,
                 exception: None,
             })
         } else {
-            Ok(WaitAckSuccess { status })
+            let delivered = status.values().filter(|status| !status.is_failed()).count();
+            Ok(WaitAckSuccess {
+                status,
+                delivered: Some(delivered as u32),
+            })
         }
     }
 }
@@ -104,14 +141,63 @@ pub enum MessageContainer {
     Durable(),
 }
 
+/// Orders held messages sharing a time by the sequence they were held in, then by id.
+pub(crate) type HoldKey = (Option<u64>, MessageId);
+
+fn hold_key(message: &Message) -> HoldKey {
+    (message.header.sequence, message.id())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct MessageQueue {
+    /// messages are resolved strictly in order,
+    /// and a full queue parks new messages instead of applying the overflow policy
     pub(crate) blocking: bool,
+    #[serde(default)]
+    pub(crate) checksum: TopicChecksum,
     pub(crate) hold_messages: HashMap<MessageId, HoldMessage>,
-    pub(crate) time_id: BTreeSet<Timed<MessageId>>,
+    pub(crate) time_id: BTreeSet<Timed<HoldKey>>,
+    /// held messages in dispatch order, higher priority first, then older first
+    pub(crate) dispatch_order: BTreeSet<(Reverse<u8>, Timed<HoldKey>)>,
     pub(crate) id_time: HashMap<MessageId, DateTime<Utc>>,
     pub(crate) resolved: HashSet<MessageId>,
     pub(crate) size: usize,
+    /// messages waiting for space in a full blocking queue, not counted in `size`
+    #[serde(default)]
+    pub(crate) parked: VecDeque<HoldMessage>,
+    #[serde(default)]
+    pub(crate) next_sequence: u64,
+    #[serde(default)]
+    pub(crate) retention: usize,
+    /// latest messages kept for replay, ordered by sequence
+    #[serde(default)]
+    pub(crate) retained: VecDeque<Message>,
+    /// held messages with an `expire_at`, by expiry time
+    #[serde(default)]
+    pub(crate) expirations: BTreeSet<Timed<MessageId>>,
+    /// offload payloads of held messages, see `TopicConfig::lazy_payloads`
+    #[serde(default)]
+    pub(crate) lazy_payloads: bool,
+    /// messages waiting for their `deliver_at`, by delivery time, not counted in `size`
+    #[serde(default)]
+    pub(crate) delayed: BTreeSet<Timed<MessageId>>,
+    #[serde(default)]
+    pub(crate) delayed_messages: HashMap<MessageId, Message>,
+    #[serde(default)]
+    pub(crate) redelivery: Option<TopicRedeliveryConfig>,
+    /// held messages with a `redeliver_at`, by redelivery time
+    #[serde(default)]
+    pub(crate) redeliveries: BTreeSet<Timed<MessageId>>,
+    /// see `TopicConfig::ordered_partitions`
+    #[serde(default)]
+    pub(crate) ordered_partitions: bool,
+    /// unresolved held messages by partition key, ordered by sequence
+    #[serde(default)]
+    pub(crate) partitions: HashMap<MaybeBase64Bytes, BTreeSet<(u64, MessageId)>>,
+    /// held messages sent to an endpoint and not acked by it yet, by endpoint, rebuilt from the
+    /// held messages' status by `recount_in_flight`
+    #[serde(skip)]
+    pub(crate) in_flight: HashMap<EndpointAddr, usize>,
 }
 
 impl MessageQueue {
@@ -119,18 +205,263 @@ impl MessageQueue {
     pub(crate) fn new(blocking: bool, capacity: usize) -> Self {
         Self {
             blocking,
+            checksum: TopicChecksum::None,
             hold_messages: HashMap::with_capacity(capacity),
             time_id: BTreeSet::new(),
+            dispatch_order: BTreeSet::new(),
             resolved: HashSet::with_capacity(capacity),
             id_time: HashMap::with_capacity(capacity),
             size: 0,
+            parked: VecDeque::new(),
+            next_sequence: 0,
+            retention: 0,
+            retained: VecDeque::new(),
+            expirations: BTreeSet::new(),
+            lazy_payloads: false,
+            delayed: BTreeSet::new(),
+            delayed_messages: HashMap::new(),
+            redelivery: None,
+            redeliveries: BTreeSet::new(),
+            ordered_partitions: false,
+            partitions: HashMap::new(),
+            in_flight: HashMap::new(),
+        }
+    }
+    /// an empty queue with the same settings and sequence counter
+    pub(crate) fn emptied(&self) -> Self {
+        let mut queue = Self::new(self.blocking, 0);
+        queue.checksum = self.checksum;
+        queue.next_sequence = self.next_sequence;
+        queue.retention = self.retention;
+        queue.lazy_payloads = self.lazy_payloads;
+        queue.redelivery = self.redelivery;
+        queue.ordered_partitions = self.ordered_partitions;
+        queue
+    }
+    /// Drop held messages that are resolved but not flushed yet, so snapshots don't carry them.
+    ///
+    /// Messages still waiting for any endpoint are kept, the indexes stay consistent.
+    pub(crate) fn compact(&mut self, now: DateTime<Utc>) {
+        let terminal = self
+            .hold_messages
+            .iter()
+            .filter(|(_, hold_message)| hold_message.is_resolved(now))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in terminal {
+            self.remove(id);
+        }
+    }
+    /// Move `ep` in or out of `in_flight` as its status goes from `before` to `after`, a status
+    /// counts while the message is sent and not acked yet.
+    pub(crate) fn track_in_flight(
+        in_flight: &mut HashMap<EndpointAddr, usize>,
+        ep: EndpointAddr,
+        before: Option<MessageStatusKind>,
+        after: Option<MessageStatusKind>,
+    ) {
+        let counts = |status: Option<MessageStatusKind>| {
+            matches!(
+                status,
+                Some(MessageStatusKind::Sending | MessageStatusKind::Sent)
+            )
+        };
+        match (counts(before), counts(after)) {
+            (false, true) => *in_flight.entry(ep).or_default() += 1,
+            (true, false) => {
+                if let Some(count) = in_flight.get_mut(&ep) {
+                    *count -= 1;
+                    if *count == 0 {
+                        in_flight.remove(&ep);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    /// rebuild `in_flight` after the held messages were decoded
+    pub(crate) fn recount_in_flight(&mut self) {
+        self.in_flight.clear();
+        for hold_message in self.hold_messages.values() {
+            for (ep, status) in &hold_message.wait_ack.status {
+                Self::track_in_flight(&mut self.in_flight, *ep, None, Some(*status));
+            }
+        }
+    }
+    fn index_partition(&mut self, message: &Message) {
+        if !self.ordered_partitions {
+            return;
+        }
+        let header = &message.header;
+        if let (Some(key), Some(sequence)) = (&header.partition_key, header.sequence) {
+            self.partitions
+                .entry(key.clone())
+                .or_default()
+                .insert((sequence, header.message_id));
+        }
+    }
+    fn unindex_partition(
+        partitions: &mut HashMap<MaybeBase64Bytes, BTreeSet<(u64, MessageId)>>,
+        header: &MessageHeader,
+    ) {
+        let (Some(key), Some(sequence)) = (&header.partition_key, header.sequence) else {
+            return;
+        };
+        if let Some(partition) = partitions.get_mut(key) {
+            partition.remove(&(sequence, header.message_id));
+            if partition.is_empty() {
+                partitions.remove(key);
+            }
+        }
+    }
+    pub(crate) fn next_sequence(&mut self) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        sequence
+    }
+    fn retain(&mut self, message: &Message) {
+        if self.retention == 0 {
+            return;
+        }
+        if let Some(sequence) = message.header.sequence {
+            self.next_sequence = self.next_sequence.max(sequence + 1);
+        }
+        self.retained.push_back(message.clone());
+        while self.retained.len() > self.retention {
+            self.retained.pop_front();
         }
     }
-    pub(crate) fn push(&mut self, message: HoldMessage) {
+    pub(crate) fn retained_since(&self, position: u64) -> impl Iterator<Item = &Message> {
+        self.retained
+            .iter()
+            .filter(move |message| message.header.sequence >= Some(position))
+    }
+    fn track_expiry(&mut self, message: &Message) {
+        if let Some(expire_at) = message.header.expire_at {
+            self.expirations.insert(Timed::new(expire_at, message.id()));
+        }
+    }
+    fn untrack_expiry(&mut self, message: &Message) {
+        if let Some(expire_at) = message.header.expire_at {
+            self.expirations
+                .remove(&Timed::new(expire_at, message.id()));
+        }
+    }
+    pub(crate) fn has_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expirations
+            .first()
+            .is_some_and(|timed| timed.time <= now)
+    }
+    /// remove and return held messages past their `expire_at`
+    pub(crate) fn take_expired(&mut self, now: DateTime<Utc>) -> Vec<HoldMessage> {
+        let mut expired = Vec::new();
+        while self.has_expired(now) {
+            let Some(timed) = self.expirations.pop_first() else {
+                break;
+            };
+            if let Some(hm) = self.remove(timed.data) {
+                expired.push(hm);
+            }
+        }
+        expired
+    }
+    fn untrack_in_flight(&mut self, hold_message: &HoldMessage) {
+        for (ep, status) in &hold_message.wait_ack.status {
+            Self::track_in_flight(&mut self.in_flight, *ep, Some(*status), None);
+        }
+    }
+    fn untrack_redelivery(&mut self, hold_message: &HoldMessage) {
+        if let Some(redeliver_at) = hold_message.redeliver_at {
+            self.redeliveries
+                .remove(&Timed::new(redeliver_at, hold_message.message.id()));
+        }
+    }
+    /// Start the ack deadline of a dispatched message that waits for `Processed` acks, unless
+    /// one is running already.
+    fn track_redelivery(&mut self, id: MessageId, now: DateTime<Utc>) {
+        let Some(redelivery) = self.redelivery else {
+            return;
+        };
+        let Some(hold_message) = self.hold_messages.get_mut(&id) else {
+            return;
+        };
+        let expect = hold_message.wait_ack.expect;
+        let pending = hold_message
+            .wait_ack
+            .status
+            .values()
+            .any(|status| !status.is_unsent() && !status.is_resolved(expect));
+        if expect != MessageAckExpectKind::Processed
+            || hold_message.redeliver_at.is_some()
+            || !pending
+        {
+            return;
+        }
+        let deadline = redelivery.deadline(hold_message.delivery_attempts);
+        let redeliver_at = now + TimeDelta::from_std(deadline).unwrap_or(TimeDelta::max_value());
+        hold_message.redeliver_at = Some(redeliver_at);
+        self.redeliveries.insert(Timed::new(redeliver_at, id));
+    }
+    pub(crate) fn has_redelivery_due(&self, now: DateTime<Utc>) -> bool {
+        self.redeliveries
+            .first()
+            .is_some_and(|timed| timed.time <= now)
+    }
+    /// ids of held messages past their `redeliver_at`, their deadlines are cleared
+    pub(crate) fn take_redelivery_due(&mut self, now: DateTime<Utc>) -> Vec<MessageId> {
+        let mut due = Vec::new();
+        while self.has_redelivery_due(now) {
+            let Some(timed) = self.redeliveries.pop_first() else {
+                break;
+            };
+            if let Some(hold_message) = self.hold_messages.get_mut(&timed.data) {
+                hold_message.redeliver_at = None;
+                due.push(timed.data);
+            }
+        }
+        due
+    }
+    /// keep a message back until its `deliver_at`
+    pub(crate) fn delay(&mut self, message: Message) {
+        let Some(deliver_at) = message.header.deliver_at else {
+            return;
+        };
+        self.delayed.insert(Timed::new(deliver_at, message.id()));
+        self.delayed_messages.insert(message.id(), message);
+    }
+    pub(crate) fn has_delayed_due(&self, now: DateTime<Utc>) -> bool {
+        self.delayed.first().is_some_and(|timed| timed.time <= now)
+    }
+    /// remove and return delayed messages past their `deliver_at`, in delivery order
+    pub(crate) fn take_delayed_due(&mut self, now: DateTime<Utc>) -> Vec<Message> {
+        let mut due = Vec::new();
+        while self.has_delayed_due(now) {
+            let Some(timed) = self.delayed.pop_first() else {
+                break;
+            };
+            if let Some(message) = self.delayed_messages.remove(&timed.data) {
+                due.push(message);
+            }
+        }
+        due
+    }
+    pub(crate) fn push(&mut self, mut message: HoldMessage, time: DateTime<Utc>) {
+        self.retain(&message.message);
+        self.track_expiry(&message.message);
+        self.index_partition(&message.message);
+        if self.lazy_payloads {
+            message.offload();
+        }
         let message_id = message.message.header.message_id;
-        let time = Utc::now();
+        let priority = message.message.header.priority;
+        let key = hold_key(&message.message);
+        for (ep, status) in &message.wait_ack.status {
+            Self::track_in_flight(&mut self.in_flight, *ep, None, Some(*status));
+        }
         self.hold_messages.insert(message_id, message);
-        self.time_id.insert(Timed::new(time, message_id));
+        self.time_id.insert(Timed::new(time, key));
+        self.dispatch_order
+            .insert((Reverse(priority), Timed::new(time, key)));
         self.id_time.insert(message_id, time);
         self.size += 1;
     }
@@ -142,42 +473,109 @@ impl MessageQueue {
             time,
         }: DurableMessage,
     ) {
+        if let Some(sequence) = message.header.sequence {
+            self.next_sequence = self.next_sequence.max(sequence + 1);
+        }
+        self.retain(&message);
+        self.track_expiry(&message);
+        self.index_partition(&message);
         let message_id = message.header.message_id;
-        self.hold_messages.insert(
-            message_id,
-            HoldMessage {
-                wait_ack: WaitAck {
-                    expect: message.header.ack_kind,
-                    status,
-                },
-                message,
+        let priority = message.header.priority;
+        let key = hold_key(&message);
+        let mut hold_message = HoldMessage {
+            wait_ack: WaitAck {
+                expect: message.header.ack_kind,
+                status,
             },
-        );
-        self.time_id.insert(Timed::new(time, message_id));
+            message,
+            offloaded: false,
+            nacked: HashSet::new(),
+            delivery_attempts: 0,
+            redeliver_at: None,
+        };
+        if self.lazy_payloads {
+            hold_message.offload();
+        }
+        for (ep, status) in &hold_message.wait_ack.status {
+            Self::track_in_flight(&mut self.in_flight, *ep, None, Some(*status));
+        }
+        self.hold_messages.insert(message_id, hold_message);
+        self.time_id.insert(Timed::new(time, key));
+        self.dispatch_order
+            .insert((Reverse(priority), Timed::new(time, key)));
         self.id_time.insert(message_id, time);
         self.size += 1;
     }
+    /// take the message to dispatch next
     pub(crate) fn pop(&mut self) -> Option<HoldMessage> {
-        if let Some(timed) = self.time_id.pop_first() {
-            self.id_time.remove(&timed.data);
-            self.resolved.remove(&timed.data);
+        if let Some((_, timed)) = self.dispatch_order.pop_first() {
+            self.time_id.remove(&timed);
+            let (_, id) = timed.data;
+            self.id_time.remove(&id);
+            self.resolved.remove(&id);
             self.size -= 1;
-            self.hold_messages.remove(&timed.data)
+            let hm = self.hold_messages.remove(&id)?;
+            self.untrack_in_flight(&hm);
+            self.untrack_expiry(&hm.message);
+            Self::unindex_partition(&mut self.partitions, &hm.message.header);
+            self.untrack_redelivery(&hm);
+            Some(hm)
         } else {
             None
         }
     }
+    pub(crate) fn select_victim(&self, victim: TopicOverflowVictim) -> Option<MessageId> {
+        match victim {
+            TopicOverflowVictim::Oldest => self.time_id.first().map(|timed| timed.data.1),
+            TopicOverflowVictim::Newest => self.time_id.last().map(|timed| timed.data.1),
+            // the oldest of the lowest priority
+            TopicOverflowVictim::LowestPriority => {
+                let (lowest, _) = self.dispatch_order.last()?;
+                self.dispatch_order
+                    .iter()
+                    .rev()
+                    .take_while(|(priority, _)| priority == lowest)
+                    .last()
+                    .map(|(_, timed)| timed.data.1)
+            }
+            TopicOverflowVictim::NearestExpiry => self
+                .time_id
+                .iter()
+                .min_by_key(|timed| {
+                    // the earlier of the message's own ttl and its durable expiry
+                    let expire = self.hold_messages.get(&timed.data.1).and_then(|hm| {
+                        let header = &hm.message.header;
+                        let durable = header.durability.as_ref().map(|config| config.expire);
+                        header.expire_at.into_iter().chain(durable).min()
+                    });
+                    // messages that never expire sort last, ties go to the oldest
+                    (expire.is_none(), expire)
+                })
+                .map(|timed| timed.data.1),
+        }
+    }
     pub(crate) fn get_front(&self) -> Option<&HoldMessage> {
-        self.time_id
+        self.dispatch_order
             .first()
-            .and_then(|timed| self.hold_messages.get(&timed.data))
+            .and_then(|(_, timed)| self.hold_messages.get(&timed.data.1))
+    }
+    /// ids of held messages, the one to dispatch first comes first
+    pub(crate) fn in_dispatch_order(&self) -> impl Iterator<Item = MessageId> + '_ {
+        self.dispatch_order.iter().map(|(_, timed)| timed.data.1)
     }
     pub(crate) fn remove(&mut self, message_id: MessageId) -> Option<HoldMessage> {
         if let Some(hm) = self.hold_messages.remove(&message_id) {
-            self.time_id
-                .remove(&Timed::new(self.id_time[&message_id], message_id));
+            let timed = Timed::new(self.id_time[&message_id], hold_key(&hm.message));
+            self.dispatch_order
+                .remove(&(Reverse(hm.message.header.priority), timed.clone()));
+            self.time_id.remove(&timed);
             self.id_time.remove(&message_id);
+            self.resolved.remove(&message_id);
             self.size -= 1;
+            self.untrack_in_flight(&hm);
+            self.untrack_expiry(&hm.message);
+            Self::unindex_partition(&mut self.partitions, &hm.message.header);
+            self.untrack_redelivery(&hm);
             Some(hm)
         } else {
             None
@@ -190,6 +588,7 @@ impl MessageQueue {
         kind: MessageStatusKind,
     ) {
         if let Some(hm) = self.hold_messages.get_mut(ack_to) {
+            let before = hm.wait_ack.status.get(&from).copied();
             if let Some(status) = hm.wait_ack.status.get_mut(&from) {
                 // resolved message should not be updated
                 if status.is_resolved(hm.wait_ack.expect) {
@@ -197,21 +596,20 @@ impl MessageQueue {
                 }
                 match status {
                     MessageStatusKind::Processed => return,
-                    MessageStatusKind::Received => {
-                        if kind == MessageStatusKind::Processed || kind == MessageStatusKind::Failed
-                        {
-                            *status = kind;
-                        }
+                    MessageStatusKind::Received
+                        if kind == MessageStatusKind::Processed
+                            || kind == MessageStatusKind::Failed =>
+                    {
+                        *status = kind;
                     }
-                    MessageStatusKind::Sent => {
-                        if kind != MessageStatusKind::Unsent || kind != MessageStatusKind::Sending {
-                            *status = kind;
-                        }
+                    MessageStatusKind::Sent
+                        if kind != MessageStatusKind::Unsent
+                            || kind != MessageStatusKind::Sending =>
+                    {
+                        *status = kind;
                     }
-                    MessageStatusKind::Sending => {
-                        if kind != MessageStatusKind::Unsent {
-                            *status = kind;
-                        }
+                    MessageStatusKind::Sending if kind != MessageStatusKind::Unsent => {
+                        *status = kind;
                     }
                     MessageStatusKind::Unsent => {
                         *status = kind;
@@ -220,8 +618,12 @@ impl MessageQueue {
                         // otherwise, it must be resolved
                     }
                 }
+            } else if hm.message.header.target_kind == MessageTargetKind::Durable {
+                // the slot was handed off when its host left, a late ack doesn't take it back
+                return;
             }
             hm.wait_ack.status.insert(from, kind);
+            Self::track_in_flight(&mut self.in_flight, from, before, Some(kind));
         }
     }
     // poll with resolved cache
@@ -237,6 +639,10 @@ impl MessageQueue {
             let resolved = self.poll_message_inner(id, reachable_eps, ctx)?;
             if resolved.is_ready() {
                 self.resolved.insert(id);
+                // acked by all its endpoints, it holds back no later message of its partition
+                if let Some(hm) = self.hold_messages.get(&id) {
+                    Self::unindex_partition(&mut self.partitions, &hm.message.header);
+                }
             }
             Some(resolved)
         }
@@ -254,15 +660,70 @@ impl MessageQueue {
                 return Some(Poll::Pending);
             }
         }
+        let checksum = self.checksum;
+        let blocked = self.partition_blocked(id);
+        let reachable_eps = if blocked.is_empty() {
+            Cow::Borrowed(reachable_eps)
+        } else {
+            Cow::Owned(reachable_eps - &blocked)
+        };
         let message = self.hold_messages.get_mut(&id)?;
-        message.send_unsent(reachable_eps, ctx);
+        message.send_unsent(checksum, &reachable_eps, &mut self.in_flight, ctx);
 
-        if message.is_resolved() {
+        if message.is_resolved(ctx.now()) {
             Some(Poll::Ready(()))
         } else {
+            self.track_redelivery(id, ctx.now());
             Some(Poll::Pending)
         }
     }
+    /// Endpoints an earlier message with the same partition key still waits for, the message
+    /// mustn't be sent to them yet, see `TopicConfig::ordered_partitions`.
+    fn partition_blocked(&self, id: MessageId) -> HashSet<EndpointAddr> {
+        if !self.ordered_partitions {
+            return HashSet::new();
+        }
+        let Some(header) = self.hold_messages.get(&id).map(|m| &m.message.header) else {
+            return HashSet::new();
+        };
+        let (Some(key), Some(sequence)) = (&header.partition_key, header.sequence) else {
+            return HashSet::new();
+        };
+        let Some(partition) = self.partitions.get(key) else {
+            return HashSet::new();
+        };
+        partition
+            .range(..(sequence, id))
+            .filter_map(|(_, earlier)| self.hold_messages.get(earlier))
+            .flat_map(|earlier| {
+                earlier
+                    .wait_ack
+                    .status
+                    .iter()
+                    .filter(|(_, status)| !status.is_resolved(earlier.wait_ack.expect))
+                    .map(|(ep, _)| *ep)
+            })
+            .collect()
+    }
+    /// held messages with this partition key waiting to be sent to some endpoint
+    pub(crate) fn unsent_in_partition(&self, key: &MaybeBase64Bytes) -> HashSet<MessageId> {
+        let Some(partition) = self.partitions.get(key) else {
+            return HashSet::new();
+        };
+        partition
+            .iter()
+            .map(|(_, id)| *id)
+            .filter(|id| {
+                self.hold_messages.get(id).is_some_and(|message| {
+                    message
+                        .wait_ack
+                        .status
+                        .values()
+                        .any(MessageStatusKind::is_unsent)
+                })
+            })
+            .collect()
+    }
 
     pub(crate) fn len(&self) -> usize {
         self.size
@@ -291,21 +752,309 @@ impl MessageQueue {
         context: &mut ProposalContext,
     ) {
         tracing::trace!(blocking = self.blocking, "flushing");
+        let len = self.len();
         if self.blocking {
-            while let Some(m) = self.blocking_pop(reachable_eps, context) {
+            while let Some(mut m) = self.blocking_pop(reachable_eps, context) {
                 let id = m.message.id();
                 let result = m.resolve();
+                if let Err(e) = &result {
+                    context.dead_letter(&m.message, m.offloaded, e.exception);
+                }
                 context.resolve_ack(id, result);
                 context.push_durable_command(DurableCommand::Archive(id));
             }
         } else {
             for id in self.swap_out_resolved() {
-                if let Some(m) = self.remove(id) {
+                if let Some(mut m) = self.remove(id) {
                     let result = m.resolve();
+                    if let Err(e) = &result {
+                        context.dead_letter(&m.message, m.offloaded, e.exception);
+                    }
                     context.resolve_ack(id, result);
                     context.push_durable_command(DurableCommand::Archive(id));
                 }
             }
         }
+        if self.len() < len {
+            context.notify_space();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeDelta, Utc};
+
+    use super::*;
+    use crate::{
+        prelude::{MessageDurableConfig, Subject},
+        TimestampMs,
+    };
+
+    fn mixed_queue() -> (MessageQueue, Vec<MessageId>) {
+        let now = Utc::now();
+        let expires = [
+            Some(now + TimeDelta::hours(2)),
+            None,
+            Some(now + TimeDelta::hours(1)),
+            None,
+        ];
+        let mut queue = MessageQueue::new(false, 4);
+        let mut ids = Vec::new();
+        for (index, expire) in expires.into_iter().enumerate() {
+            let builder = MessageHeader::builder([Subject::new("event/test")]);
+            let header = match expire {
+                Some(expire) => builder.mode_durable(MessageDurableConfig {
+                    expire,
+                    max_receiver: None,
+                }),
+                None => builder.mode_push(),
+            }
+            .build();
+            let message = Message::new(header, "payload");
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: HashMap::new(),
+                time: now + TimeDelta::seconds(index as i64),
+            });
+        }
+        (queue, ids)
+    }
+
+    #[test]
+    fn test_same_millisecond_ordering() {
+        let time = crate::TimestampMs::now().as_datetime();
+        let mut queue = MessageQueue::new(false, 4);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/test")]).build(),
+                "payload",
+            );
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: HashMap::new(),
+                time,
+            });
+        }
+        // messages held within the same millisecond are all kept, ordered by id
+        assert_eq!(queue.len(), 3);
+        ids.sort();
+        let popped = std::iter::from_fn(|| queue.pop().map(|m| m.message.id())).collect::<Vec<_>>();
+        assert_eq!(popped, ids);
+        assert_eq!(crate::TimestampMs::from(time).as_datetime(), time);
+    }
+
+    #[test]
+    fn test_millisecond_expiry() {
+        let now = TimestampMs::now().as_datetime();
+        let mut queue = MessageQueue::new(false, 2);
+        let mut ids = Vec::new();
+        for millis in [2, 1] {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/test")])
+                    .expire_at(now + TimeDelta::milliseconds(millis))
+                    .build(),
+                "payload",
+            );
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: HashMap::new(),
+                time: now - TimeDelta::milliseconds(millis),
+            });
+        }
+        // held a millisecond apart, the older one goes first
+        assert_eq!(queue.get_front().map(|m| m.message.id()), Some(ids[0]));
+        let expired = queue.take_expired(now + TimeDelta::milliseconds(1));
+        assert_eq!(
+            expired.iter().map(|m| m.message.id()).collect::<Vec<_>>(),
+            [ids[1]]
+        );
+        assert!(!queue.has_expired(now + TimeDelta::microseconds(1999)));
+        assert!(queue.has_expired(now + TimeDelta::milliseconds(2)));
+
+        // ids of one executor keep the millisecond they were built in
+        let earlier = MessageId::new_snowflake();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(earlier.bytes[0..4], MessageId::new_snowflake().bytes[0..4]);
+        assert!(earlier.bytes[4..12] < MessageId::new_snowflake().bytes[4..12]);
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let now = Utc::now();
+        let mut queue = MessageQueue::new(false, 4);
+        let mut ids = Vec::new();
+        for (index, priority) in [1, 3, 0, 3, 0].into_iter().enumerate() {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/test")])
+                    .priority(priority)
+                    .build(),
+                "payload",
+            );
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: HashMap::new(),
+                time: now + TimeDelta::seconds(index as i64),
+            });
+        }
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::LowestPriority),
+            Some(ids[2])
+        );
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::Oldest),
+            Some(ids[0])
+        );
+        queue.remove(ids[3]);
+        let popped = std::iter::from_fn(|| queue.pop().map(|m| m.message.id())).collect::<Vec<_>>();
+        assert_eq!(popped, [ids[1], ids[0], ids[2], ids[4]]);
+        assert!(queue.time_id.is_empty());
+    }
+
+    #[test]
+    fn test_in_flight_count() {
+        let now = Utc::now();
+        let mut queue = MessageQueue::new(false, 4);
+        let (ep_a, ep_b) = (EndpointAddr::new_snowflake(), EndpointAddr::new_snowflake());
+        let mut ids = Vec::new();
+        for status in [
+            [
+                (ep_a, MessageStatusKind::Sent),
+                (ep_b, MessageStatusKind::Unsent),
+            ],
+            [
+                (ep_a, MessageStatusKind::Sending),
+                (ep_b, MessageStatusKind::Sent),
+            ],
+        ] {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/test")])
+                    .mode_online()
+                    .ack_kind(MessageAckExpectKind::Processed)
+                    .build(),
+                "payload",
+            );
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: status.into_iter().collect(),
+                time: now,
+            });
+        }
+        assert_eq!(queue.in_flight, HashMap::from([(ep_a, 2), (ep_b, 1)]));
+        // sent on from sending stays in flight, an ack gives the credit back
+        queue.update_ack(&ids[1], ep_a, MessageStatusKind::Sent);
+        queue.update_ack(&ids[0], ep_a, MessageStatusKind::Received);
+        assert_eq!(queue.in_flight, HashMap::from([(ep_a, 1), (ep_b, 1)]));
+        queue.remove(ids[1]);
+        assert!(queue.in_flight.is_empty());
+        queue.recount_in_flight();
+        assert!(queue.in_flight.is_empty());
+    }
+
+    #[test]
+    fn test_partition_index() {
+        let now = Utc::now();
+        let mut queue = MessageQueue::new(false, 4);
+        queue.ordered_partitions = true;
+        let (slow, fast) = (EndpointAddr::new_snowflake(), EndpointAddr::new_snowflake());
+        let mut ids = Vec::new();
+        for (sequence, key) in ["a", "a", "b", "a"].into_iter().enumerate() {
+            let mut message = Message::new(
+                MessageHeader::builder([Subject::new("event/test")])
+                    .partition_key(key)
+                    .build(),
+                "payload",
+            );
+            message.header.sequence = Some(sequence as u64);
+            ids.push(message.id());
+            queue.push_durable_message(DurableMessage {
+                message,
+                status: HashMap::from([
+                    (slow, MessageStatusKind::Unsent),
+                    (fast, MessageStatusKind::Processed),
+                ]),
+                time: now + TimeDelta::seconds(sequence as i64),
+            });
+        }
+        let key = |key: &'static str| MaybeBase64Bytes(key.into());
+        assert_eq!(queue.partitions[&key("a")].len(), 3);
+        assert_eq!(queue.partition_blocked(ids[0]), HashSet::new());
+        assert_eq!(queue.partition_blocked(ids[3]), HashSet::from([slow]));
+        // other partitions don't hold it back
+        assert_eq!(queue.partition_blocked(ids[2]), HashSet::new());
+        assert_eq!(
+            queue.unsent_in_partition(&key("a")),
+            HashSet::from([ids[0], ids[1], ids[3]])
+        );
+
+        queue.remove(ids[0]);
+        queue.remove(ids[1]);
+        assert_eq!(queue.partition_blocked(ids[3]), HashSet::new());
+        assert_eq!(
+            queue.unsent_in_partition(&key("a")),
+            HashSet::from([ids[3]])
+        );
+        queue.remove(ids[3]);
+        queue.pop();
+        assert!(queue.partitions.is_empty());
+    }
+
+    #[test]
+    fn test_overflow_victim() {
+        let (queue, ids) = mixed_queue();
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::Oldest),
+            Some(ids[0])
+        );
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::Newest),
+            Some(ids[3])
+        );
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::LowestPriority),
+            Some(ids[0])
+        );
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::NearestExpiry),
+            Some(ids[2])
+        );
+
+        // a per-message ttl expiring first makes its message the nearest expiry
+        let (mut queue, _) = mixed_queue();
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/test")])
+                .mode_push()
+                .expire_at(Utc::now() + TimeDelta::minutes(30))
+                .build(),
+            "payload",
+        );
+        let short_lived = message.id();
+        queue.push_durable_message(DurableMessage {
+            message,
+            status: HashMap::new(),
+            time: Utc::now(),
+        });
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::NearestExpiry),
+            Some(short_lived)
+        );
+
+        let (mut queue, ids) = mixed_queue();
+        let victim = queue
+            .select_victim(TopicOverflowVictim::Newest)
+            .and_then(|id| queue.remove(id))
+            .unwrap();
+        assert_eq!(victim.message.id(), ids[3]);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(
+            queue.select_victim(TopicOverflowVictim::Newest),
+            Some(ids[2])
+        );
     }
 }
@@ -1,7 +1,9 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     future::Future,
+    sync::{Arc, Weak},
     task::Poll,
+    time::Duration,
 };
 
 pub use asteroid_mq_model::{WaitAckError, WaitAckErrorException, WaitAckResult, WaitAckSuccess};
@@ -26,13 +28,101 @@ impl WaitAck {
     }
 }
 
+pub(crate) type AckWaitingPool = tokio::sync::RwLock<AckWaiters>;
+
+/// Report channels of the producers on this node waiting for their messages, in the order they
+/// started waiting.
+#[derive(Debug, Default)]
+pub(crate) struct AckWaiters {
+    senders: HashMap<MessageId, tokio::sync::oneshot::Sender<WaitAckResult>>,
+    /// ids already removed are left behind and skipped, until they outnumber the waiters
+    order: VecDeque<MessageId>,
+}
+
+impl AckWaiters {
+    pub(crate) fn len(&self) -> usize {
+        self.senders.len()
+    }
+    pub(crate) fn insert(
+        &mut self,
+        id: MessageId,
+        sender: tokio::sync::oneshot::Sender<WaitAckResult>,
+    ) {
+        if self.order.len() > 2 * self.senders.len() + 16 {
+            let senders = &self.senders;
+            self.order.retain(|id| senders.contains_key(id));
+        }
+        self.senders.insert(id, sender);
+        self.order.push_back(id);
+    }
+    pub(crate) fn remove(
+        &mut self,
+        id: &MessageId,
+    ) -> Option<tokio::sync::oneshot::Sender<WaitAckResult>> {
+        self.senders.remove(id)
+    }
+    /// every waiter, oldest first
+    pub(crate) fn take_all(
+        &mut self,
+    ) -> impl Iterator<Item = tokio::sync::oneshot::Sender<WaitAckResult>> {
+        let mut senders = std::mem::take(&mut self.senders);
+        std::mem::take(&mut self.order)
+            .into_iter()
+            .filter_map(move |id| senders.remove(&id))
+    }
+    /// Make room for `new` more waiters within `cap`.
+    ///
+    /// Waiters whose handle is gone are dropped first, then the oldest ones are failed with
+    /// [`WaitAckErrorException::Overflow`]. Returns how many were failed.
+    pub(crate) fn evict_for(&mut self, new: usize, cap: usize) -> usize {
+        if self.senders.len() + new <= cap {
+            return 0;
+        }
+        self.senders.retain(|_, sender| !sender.is_closed());
+        let mut evicted = 0;
+        while self.senders.len() + new > cap {
+            let Some(id) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(sender) = self.senders.remove(&id) {
+                let _ = sender.send(Err(WaitAckError::exception(
+                    WaitAckErrorException::Overflow,
+                )));
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+}
+
 pin_project_lite::pin_project! {
     pub struct WaitAckHandle {
         pub(crate) message_id: MessageId,
         #[pin]
         pub(crate) result: tokio::sync::oneshot::Receiver<WaitAckResult>,
+        // the pool the sender is registered in, if any
+        pub(crate) pool: Option<Weak<AckWaitingPool>>,
     }
 
+    impl PinnedDrop for WaitAckHandle {
+        fn drop(this: Pin<&mut Self>) {
+            // a producer that gives up on the handle stops counting as waiting
+            let this = this.project();
+            let Some(pool) = this.pool.as_ref().and_then(Weak::upgrade) else {
+                return;
+            };
+            let message_id = *this.message_id;
+            if let Ok(mut guard) = pool.try_write() {
+                guard.remove(&message_id);
+                return;
+            }
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn(async move {
+                    pool.write().await.remove(&message_id);
+                });
+            }
+        }
+    }
 }
 
 impl WaitAckHandle {
@@ -46,9 +136,30 @@ impl WaitAckHandle {
             WaitAckHandle {
                 message_id: id,
                 result: rx,
+                pool: None,
             },
         )
     }
+    /// a handle already failed with `exception`, for messages rejected before they are proposed
+    pub(crate) fn failed(id: MessageId, exception: WaitAckErrorException) -> Self {
+        let (sender, handle) = Self::new(id);
+        let _ = sender.send(Err(WaitAckError::exception(exception)));
+        handle
+    }
+    pub(crate) fn registered_in(mut self, pool: &Arc<AckWaitingPool>) -> Self {
+        self.pool = Some(Arc::downgrade(pool));
+        self
+    }
+    /// Wait at most `timeout`, then fail with [`WaitAckErrorException::Timeout`].
+    ///
+    /// A timed out handle is taken out of the topic's waiting pool, so late acks of the message
+    /// are not delivered to it. The message itself stays held until it resolves or expires.
+    pub async fn with_timeout(self, timeout: Duration) -> WaitAckResult {
+        // a dropped handle leaves the pool by itself
+        tokio::time::timeout(timeout, self)
+            .await
+            .unwrap_or(Err(WaitAckError::exception(WaitAckErrorException::Timeout)))
+    }
 }
 
 impl Future for WaitAckHandle {
@@ -0,0 +1,94 @@
+//! TLS for the links between cluster members.
+//!
+//! Every member uses the same certificate for both ends of a link: it's presented when accepting
+//! a connection, and when opening one if the peer asks for a client certificate.
+use std::{
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio_rustls::{
+    rustls::{
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        server::WebPkiClientVerifier,
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor, TlsConnector,
+};
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// PEM certificate chain of this node
+    pub cert: PathBuf,
+    /// PEM private key of `cert`
+    pub key: PathBuf,
+    /// PEM certificates peers are verified against
+    pub ca: PathBuf,
+    /// Reject peers connecting without a certificate signed by `ca`, for mutual auth between members.
+    pub require_client_cert: bool,
+    /// Name peer certificates are verified for, defaults to the peer's ip address.
+    pub server_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>, ca: impl Into<PathBuf>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+            ca: ca.into(),
+            require_client_cert: false,
+            server_name: None,
+        }
+    }
+    pub fn with_client_cert_required(mut self) -> Self {
+        self.require_client_cert = true;
+        self
+    }
+    fn certs(path: &Path) -> std::io::Result<Vec<CertificateDer<'static>>> {
+        let mut reader = BufReader::new(std::fs::File::open(path)?);
+        rustls_pemfile::certs(&mut reader).collect()
+    }
+    fn key(path: &Path) -> std::io::Result<PrivateKeyDer<'static>> {
+        let mut reader = BufReader::new(std::fs::File::open(path)?);
+        rustls_pemfile::private_key(&mut reader)?.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "no private key found")
+        })
+    }
+    fn roots(&self) -> std::io::Result<Arc<RootCertStore>> {
+        let mut roots = RootCertStore::empty();
+        for cert in Self::certs(&self.ca)? {
+            roots.add(cert).map_err(std::io::Error::other)?;
+        }
+        Ok(Arc::new(roots))
+    }
+    pub(crate) fn acceptor(&self) -> std::io::Result<TlsAcceptor> {
+        let builder = if self.require_client_cert {
+            let verifier = WebPkiClientVerifier::builder(self.roots()?)
+                .build()
+                .map_err(std::io::Error::other)?;
+            ServerConfig::builder().with_client_cert_verifier(verifier)
+        } else {
+            ServerConfig::builder().with_no_client_auth()
+        };
+        let config = builder
+            .with_single_cert(Self::certs(&self.cert)?, Self::key(&self.key)?)
+            .map_err(std::io::Error::other)?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+    pub(crate) fn connector(&self) -> std::io::Result<TlsConnector> {
+        let config = ClientConfig::builder()
+            .with_root_certificates(self.roots()?)
+            .with_client_auth_cert(Self::certs(&self.cert)?, Self::key(&self.key)?)
+            .map_err(std::io::Error::other)?;
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+    pub(crate) fn server_name(&self, peer: SocketAddr) -> std::io::Result<ServerName<'static>> {
+        match &self.server_name {
+            Some(name) => ServerName::try_from(name.clone())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
+            None => Ok(ServerName::IpAddress(peer.ip().into())),
+        }
+    }
+}
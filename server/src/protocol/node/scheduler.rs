@@ -0,0 +1,128 @@
+//! Fair scheduling of dispatch work across topics.
+//!
+//! Every topic gets its own job queue, and the worker serves the queues round-robin, running at
+//! most `budget` jobs of a topic before moving to the next one. A flooded topic therefore delays
+//! a quiet topic by at most one round, instead of by its whole backlog.
+//!
+//! The jobs of a batch run up to `concurrency` endpoints at a time. Jobs for the same endpoint
+//! always run one after another in the order they were scheduled, so an endpoint never sees its
+//! messages reordered.
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::StreamExt;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::prelude::{EndpointAddr, TopicCode};
+
+pub(crate) type DispatchJob = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+#[derive(Default)]
+struct DispatchQueues {
+    jobs: HashMap<TopicCode, VecDeque<(EndpointAddr, DispatchJob)>>,
+    /// topics with pending jobs, in serving order
+    order: VecDeque<TopicCode>,
+}
+
+struct SchedulerShared {
+    queues: Mutex<DispatchQueues>,
+    notify: Notify,
+    budget: usize,
+    concurrency: usize,
+}
+
+#[derive(Clone)]
+pub(crate) struct DispatchScheduler {
+    shared: Arc<SchedulerShared>,
+}
+
+impl std::fmt::Debug for DispatchScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DispatchScheduler")
+            .field("budget", &self.shared.budget)
+            .field("concurrency", &self.shared.concurrency)
+            .finish()
+    }
+}
+
+impl DispatchScheduler {
+    pub(crate) fn new(budget: usize, concurrency: usize) -> Self {
+        Self {
+            shared: Arc::new(SchedulerShared {
+                queues: Mutex::new(DispatchQueues::default()),
+                notify: Notify::new(),
+                budget: budget.max(1),
+                concurrency: concurrency.max(1),
+            }),
+        }
+    }
+    /// queue a delivery to `endpoint`
+    pub(crate) fn schedule(&self, topic: TopicCode, endpoint: EndpointAddr, job: DispatchJob) {
+        let mut queues = self.shared.queues.lock().unwrap();
+        let queue = queues.jobs.entry(topic.clone()).or_default();
+        let was_idle = queue.is_empty();
+        queue.push_back((endpoint, job));
+        if was_idle {
+            queues.order.push_back(topic);
+        }
+        drop(queues);
+        self.shared.notify.notify_one();
+    }
+    /// take up to `budget` jobs of the next topic in turn
+    fn next_batch(&self) -> Option<Vec<(EndpointAddr, DispatchJob)>> {
+        let mut queues = self.shared.queues.lock().unwrap();
+        let topic = queues.order.pop_front()?;
+        let queue = queues.jobs.get_mut(&topic)?;
+        let take = queue.len().min(self.shared.budget);
+        let batch = queue.drain(..take).collect::<Vec<_>>();
+        if queue.is_empty() {
+            queues.jobs.remove(&topic);
+        } else {
+            queues.order.push_back(topic);
+        }
+        Some(batch)
+    }
+    pub(crate) async fn run(self, ct: CancellationToken) {
+        loop {
+            let Some(batch) = self.next_batch() else {
+                tokio::select! {
+                    _ = ct.cancelled() => return,
+                    _ = self.shared.notify.notified() => continue,
+                }
+            };
+            self.run_batch(batch).await;
+            // let other tasks, including new proposals, make progress between turns
+            tokio::task::yield_now().await;
+        }
+    }
+    /// run the jobs of every endpoint in order, up to `concurrency` endpoints at a time
+    async fn run_batch(&self, batch: Vec<(EndpointAddr, DispatchJob)>) {
+        if self.shared.concurrency == 1 {
+            for (_, job) in batch {
+                job.await;
+            }
+            return;
+        }
+        let mut lanes = Vec::<Vec<DispatchJob>>::new();
+        let mut lane_of = HashMap::<EndpointAddr, usize>::new();
+        for (endpoint, job) in batch {
+            let lane = *lane_of.entry(endpoint).or_insert_with(|| {
+                lanes.push(Vec::new());
+                lanes.len() - 1
+            });
+            lanes[lane].push(job);
+        }
+        futures_util::stream::iter(lanes)
+            .for_each_concurrent(self.shared.concurrency, |lane| async move {
+                for job in lane {
+                    job.await;
+                }
+            })
+            .await;
+    }
+}
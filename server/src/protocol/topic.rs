@@ -4,32 +4,53 @@
 //!
 
 pub mod durable_message;
+pub mod mirror;
+mod payload_cache;
+mod rate_limit;
+pub mod validator;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::Deref,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
 };
 
 use asteroid_mq_model::MessageAck;
-use tokio::sync::oneshot;
+use bytes::Bytes;
 
 use crate::protocol::endpoint::LocalEndpointInner;
+use mirror::{MirrorStats, TopicMirror};
+use payload_cache::PayloadCache;
+use rate_limit::RateLimiter;
 
 use super::{
-    endpoint::{EndpointAddr, LocalEndpoint, LocalEndpointRef},
-    interest::Interest,
+    endpoint::{
+        EndpointAddr, EpInfo, GroupId, LocalEndpoint, LocalEndpointRef, ReplayPolicy, SeekTarget,
+    },
+    interest::{Interest, Subject},
     message::*,
     node::{
+        authorizer::AuthorizeAction,
+        edge::codec::{encode_value, CodecKind},
         raft::{
             proposal::*,
-            state_machine::topic::wait_ack::{WaitAckHandle, WaitAckResult},
+            state_machine::topic::{
+                config::{TopicChecksum, TopicConfig, TopicOverflowConfig, TopicOverflowPolicy},
+                wait_ack::{
+                    AckWaitingPool, WaitAckError, WaitAckErrorException, WaitAckHandle,
+                    WaitAckSuccess,
+                },
+                MessageHeaderView, RoutingExplanation, TopicData, TopicSnapshot,
+            },
         },
         Node,
     },
 };
 
-pub use asteroid_mq_model::TopicCode;
+pub use asteroid_mq_model::{TopicCode, TopicCodeError, TopicCodeErrorReason};
 #[derive(Debug, Default, Clone)]
 pub struct TopicRef {
     inner: Weak<TopicInner>,
@@ -37,7 +58,10 @@ pub struct TopicRef {
 
 impl TopicRef {
     pub fn upgrade(&self) -> Option<Topic> {
-        self.inner.upgrade().map(|inner| Topic { inner })
+        self.inner.upgrade().map(|inner| Topic {
+            inner,
+            credential: None,
+        })
     }
 }
 
@@ -45,14 +69,187 @@ impl TopicRef {
 pub struct TopicInner {
     pub(crate) code: TopicCode,
     pub(crate) node: Node,
-    pub(crate) ack_waiting_pool:
-        Arc<tokio::sync::RwLock<HashMap<MessageId, oneshot::Sender<WaitAckResult>>>>,
+    pub(crate) ack_waiting_pool: Arc<AckWaitingPool>,
     pub(crate) local_endpoints: Arc<std::sync::RwLock<HashMap<EndpointAddr, LocalEndpointRef>>>,
+    pub(crate) corrupted_messages: Arc<AtomicU64>,
+    /// counter of the ids built by [`TopicInner::new_message_id`]
+    pub(crate) id_sequence: Arc<AtomicU32>,
+    pub(crate) mirror: Option<TopicMirror>,
+    pub(crate) ephemeral: bool,
+    /// follows the topic's config, see `Node::update_topic_config`
+    pub(crate) dead_letter: Arc<std::sync::RwLock<Option<TopicCode>>>,
+    pub(crate) max_message_bytes: Option<u32>,
+    /// codec of [`Topic::send_typed`] and [`LocalEndpoint::recv_typed`]
+    pub(crate) payload_codec: CodecKind,
+    /// woken when the queue shrinks, for producers of a `Block` topic
+    pub(crate) space_notify: Arc<tokio::sync::Notify>,
+    pub(crate) payload_cache: Option<Arc<Mutex<PayloadCache>>>,
+    /// deliveries held back while the topic is paused on this node, `None` when it isn't
+    pub(crate) paused: Arc<Mutex<Option<Vec<PausedDispatch>>>>,
+    /// acks waiting to be committed together, see `NodeConfig::ack_flush_interval`
+    pub(crate) ack_batch: Arc<Mutex<AckBatch>>,
+    /// admission of the messages this node proposes, see `TopicConfig::rate_limit`
+    pub(crate) rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
+/// Settings of an endpoint created by [`Topic::create_endpoint_with`].
+///
+/// ```ignore
+/// let options = EndpointOptions::new().prefetch(16).endpoint_key("invoice-worker");
+/// let ep = topic.create_endpoint_with([Interest::new("invoice/*")], options).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EndpointOptions {
+    weight: u32,
+    group: Option<GroupId>,
+    replay: ReplayPolicy,
+    mailbox_capacity: Option<usize>,
+    auto_ack: bool,
+    filter: Option<MessageFilter>,
+    prefetch: Option<u32>,
+    endpoint_key: Option<Bytes>,
+    exactly_once: bool,
+}
+
+impl Default for EndpointOptions {
+    fn default() -> Self {
+        Self {
+            weight: EpInfo::DEFAULT_WEIGHT,
+            group: None,
+            replay: ReplayPolicy::None,
+            mailbox_capacity: None,
+            auto_ack: false,
+            filter: None,
+            prefetch: None,
+            endpoint_key: None,
+            exactly_once: false,
+        }
+    }
+}
+
+impl EndpointOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Get `weight` times the share of push messages of a default endpoint.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+    /// Join consumer group `group`, see [`GroupId`].
+    pub fn group(mut self, group: GroupId) -> Self {
+        self.group = Some(group);
+        self
+    }
+    /// First receive the matching durable messages picked by `replay`, in time order, then the
+    /// live ones.
+    ///
+    /// Messages still held by the topic are delivered as usual and not replayed twice.
+    pub fn replay(mut self, replay: ReplayPolicy) -> Self {
+        self.replay = replay;
+        self
+    }
+    /// Hold at most `mailbox_capacity` undelivered messages in the mailbox.
+    ///
+    /// Messages dispatched while the mailbox is full aren't queued, their delivery to this endpoint
+    /// fails as [`MessageStatusKind::Unreachable`], so a slow consumer can't make the node buffer
+    /// messages without bound.
+    pub fn mailbox_capacity(mut self, mailbox_capacity: usize) -> Self {
+        self.mailbox_capacity = Some(mailbox_capacity);
+        self
+    }
+    /// Ack every message as processed once it's in the mailbox, for consumers that don't need
+    /// processing guarantees.
+    ///
+    /// Producers waiting for [`MessageAckExpectKind::Processed`] are resolved without the consumer
+    /// acking anything, even if it never takes the message out of the mailbox.
+    pub fn auto_ack(mut self) -> Self {
+        self.auto_ack = true;
+        self
+    }
+    /// Only receive the messages `filter` lets through, see [`MessageFilter`].
+    ///
+    /// A message it filters out is never waited for, producers resolve as if the endpoint didn't
+    /// exist for that message.
+    pub fn filter(mut self, filter: MessageFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+    /// Hold at most `prefetch` messages sent to the endpoint and not acked yet.
+    ///
+    /// Further messages wait for it until it acks one of them, a slow consumer is never sent more
+    /// than it can take. An endpoint at its limit stays a target of the messages picked for it.
+    pub fn prefetch(mut self, prefetch: u32) -> Self {
+        self.prefetch = Some(prefetch);
+        self
+    }
+    /// Let a restarting consumer reclaim the endpoint by `endpoint_key`.
+    ///
+    /// If the topic still has an endpoint created with this key, the new one takes over its
+    /// address and interests, the given interests are ignored, and gets the messages sent to it
+    /// and not acked yet again. Dropping a keyed endpoint leaves it in the topic for its key to
+    /// reclaim, until it goes stale past
+    /// [`NodeConfig::endpoint_ttl`](crate::prelude::NodeConfig::endpoint_ttl) or its node leaves,
+    /// deliveries to it meanwhile fail as unreachable.
+    pub fn endpoint_key(mut self, endpoint_key: impl Into<Bytes>) -> Self {
+        self.endpoint_key = Some(endpoint_key.into());
+        self
+    }
+    /// Never get a message again once the endpoint acked it as processed.
+    ///
+    /// Its processed acks are recorded with the node's durable service before they are proposed.
+    /// If such an ack is lost on its way and the message is redelivered, the recorded ack is
+    /// committed in place of the delivery. An ack that fails to be recorded is still proposed,
+    /// only that one falls back to at-least-once delivery.
+    ///
+    /// It needs a durable service that records acks, see
+    /// [`Durable::records_acks`](crate::prelude::Durable::records_acks), and a topic that isn't
+    /// ephemeral, [`Topic::create_endpoint_with`] refuses it otherwise.
+    pub fn exactly_once(mut self) -> Self {
+        self.exactly_once = true;
+        self
+    }
+}
+
+/// A delivery to a local endpoint held back by [`Topic::pause`].
+#[derive(Debug)]
+pub(crate) struct PausedDispatch {
+    pub(crate) message: Message,
+    pub(crate) endpoint: EndpointAddr,
+    pub(crate) offloaded: Option<TopicChecksum>,
+}
+
+type AckWaiter = tokio::sync::oneshot::Sender<Result<(), Arc<crate::Error>>>;
+
+/// Acks of a topic collected during one flush window.
+#[derive(Debug, Default)]
+pub(crate) struct AckBatch {
+    /// bumped when a new window opens, so a stale timer can't flush a later window early
+    generation: u64,
+    updates: HashMap<MessageId, HashMap<EndpointAddr, MessageStatusKind>>,
+    waiters: Vec<AckWaiter>,
+    /// the window is over, or an ack in it resolves a message
+    due: bool,
+    /// a flush is being committed, the next one waits for it
+    flushing: bool,
+}
+
+impl AckBatch {
+    fn take(&mut self) -> (Vec<MessageStateUpdate>, Vec<AckWaiter>) {
+        self.due = false;
+        let updates = std::mem::take(&mut self.updates)
+            .into_iter()
+            .map(|(message_id, status)| MessageStateUpdate::new(message_id, status))
+            .collect();
+        (updates, std::mem::take(&mut self.waiters))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Topic {
     pub(crate) inner: Arc<TopicInner>,
+    /// presented to the node's authorizer, from the node handle the topic was got from
+    credential: Option<Bytes>,
 }
 impl Topic {}
 impl Deref for Topic {
@@ -70,26 +267,606 @@ impl TopicInner {
     pub(crate) fn get_local_ep(&self, ep: &EndpointAddr) -> Option<LocalEndpointRef> {
         self.local_endpoints.read().unwrap().get(ep).cloned()
     }
+    /// end the mailboxes of local endpoints, the topic is going away
+    pub(crate) fn close_local_endpoints(&self) {
+        for local in self.local_endpoints.read().unwrap().values() {
+            if let Some(local) = local.upgrade() {
+                local.close();
+            }
+        }
+    }
+    /// fail the producers on this node still waiting for acks, the node is shutting down or the
+    /// topic is gone
+    pub(crate) async fn abandon_waiting(&self, exception: WaitAckErrorException) {
+        let waiting = self
+            .ack_waiting_pool
+            .write()
+            .await
+            .take_all()
+            .collect::<Vec<_>>();
+        for sender in waiting {
+            let _ = sender.send(Err(WaitAckError::exception(exception)));
+        }
+    }
+    /// Resolve the fire and forget messages among `ids` right after they are enqueued, unless the
+    /// state machine already rejected them.
+    async fn resolve_enqueued(&self, ids: impl IntoIterator<Item = MessageId>) {
+        let mut pool = self.ack_waiting_pool.write().await;
+        for id in ids {
+            if let Some(sender) = pool.remove(&id) {
+                let _ = sender.send(Ok(WaitAckSuccess {
+                    status: Default::default(),
+                    delivered: None,
+                }));
+            }
+        }
+    }
+    /// A message proposed to a topic unloaded before it was applied is dropped by the state
+    /// machine, fail its producer instead of leaving it waiting.
+    async fn fail_if_unloaded(&self, ids: impl IntoIterator<Item = MessageId>) {
+        if self.node.has_topic(self.code()) {
+            return;
+        }
+        let mut pool = self.ack_waiting_pool.write().await;
+        for id in ids {
+            if let Some(sender) = pool.remove(&id) {
+                let _ = sender.send(Err(WaitAckError::exception(
+                    WaitAckErrorException::TopicNotFound,
+                )));
+            }
+        }
+    }
+    /// unregister local endpoints that never came online
+    pub(crate) fn forget_local_endpoints(&self, eps: impl IntoIterator<Item = EndpointAddr>) {
+        let mut local_endpoints = self.local_endpoints.write().unwrap();
+        for ep in eps {
+            local_endpoints.remove(&ep);
+        }
+    }
+    /// end the mailbox of a local endpoint that went offline
+    pub(crate) fn close_local_endpoint(&self, ep: &EndpointAddr) {
+        let local = self.local_endpoints.write().unwrap().remove(ep);
+        if let Some(local) = local.and_then(|local| local.upgrade()) {
+            local.close();
+        }
+    }
+    /// Whether `message`, header and payload as they are proposed, fits the topic's and the
+    /// node's `max_message_bytes`.
+    pub(crate) fn fits_size_limit(&self, message: &Message) -> bool {
+        let limit = [
+            self.max_message_bytes.map(u64::from),
+            self.node
+                .config()
+                .max_message_bytes
+                .map(|limit| limit as u64),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let Some(limit) = limit else {
+            return true;
+        };
+        bincode::serialized_size(message).is_ok_and(|size| size <= limit)
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused.lock().unwrap().is_some()
+    }
+    /// Hold the delivery back if the topic is paused, `false` if it should go ahead.
+    pub(crate) fn park_if_paused(
+        &self,
+        message: &Message,
+        endpoint: EndpointAddr,
+        offloaded: Option<TopicChecksum>,
+    ) -> bool {
+        let mut paused = self.paused.lock().unwrap();
+        let Some(parked) = paused.as_mut() else {
+            return false;
+        };
+        parked.push(PausedDispatch {
+            message: message.clone(),
+            endpoint,
+            offloaded,
+        });
+        true
+    }
+    /// count of messages rejected because of a payload checksum mismatch
+    pub fn corrupted_message_count(&self) -> u64 {
+        self.corrupted_messages.load(Ordering::Relaxed)
+    }
+    pub fn mirror_stats(&self) -> Option<MirrorStats> {
+        self.mirror.as_ref().map(TopicMirror::stats)
+    }
+    /// A message id for this topic, see [`MessageId::new_sequenced`].
+    ///
+    /// Ids built here sort in the order they were built in. Held messages are ordered by their
+    /// replicated sequence first, the id decides between messages held without one, e.g. the
+    /// ones of an imported snapshot.
+    pub fn new_message_id(&self) -> MessageId {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::hash::DefaultHasher::new();
+        self.node.id().hash(&mut hasher);
+        self.code.hash(&mut hasher);
+        let origin = hasher.finish() as u32;
+        MessageId::new_sequenced(origin, self.id_sequence.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 impl Topic {
+    pub(crate) fn new(config: &TopicConfig, node: Node) -> Self {
+        Topic {
+            credential: None,
+            inner: Arc::new(TopicInner {
+                code: config.code.clone(),
+                node,
+                ack_waiting_pool: Default::default(),
+                local_endpoints: Default::default(),
+                corrupted_messages: Default::default(),
+                id_sequence: Default::default(),
+                mirror: config
+                    .mirror
+                    .clone()
+                    .map(|mirror| TopicMirror::spawn(&config.code, mirror)),
+                ephemeral: config.ephemeral,
+                dead_letter: Arc::new(std::sync::RwLock::new(config.dead_letter.clone())),
+                max_message_bytes: config.max_message_bytes,
+                payload_codec: config.payload_codec.unwrap_or(CodecKind::JSON),
+                space_notify: Default::default(),
+                payload_cache: config.lazy_payloads.then(|| {
+                    Arc::new(Mutex::new(PayloadCache::new(
+                        PayloadCache::DEFAULT_CAPACITY,
+                    )))
+                }),
+                paused: Default::default(),
+                ack_batch: Default::default(),
+                rate_limiter: Default::default(),
+            }),
+        }
+    }
+    /// Stop delivering messages of this topic to the endpoints hosted by this node.
+    ///
+    /// Messages are still accepted and held, deliveries due in the meantime are kept back and show
+    /// as being sent. The pause isn't replicated, other nodes keep delivering to their endpoints,
+    /// and it lasts until [`Topic::resume`] or the process exits.
+    pub fn pause(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        if paused.is_none() {
+            *paused = Some(Vec::new());
+        }
+    }
+    /// Resume delivery, deliveries kept back by [`Topic::pause`] go out first.
+    pub fn resume(&self) {
+        let Some(parked) = self.paused.lock().unwrap().take() else {
+            return;
+        };
+        let mut ctx = ProposalContext::new(self.node(), self.node().now());
+        ctx.set_topic_code(self.code().clone());
+        for dispatch in parked {
+            ctx.schedule_dispatch(&dispatch.message, dispatch.endpoint, dispatch.offloaded);
+        }
+    }
+    pub(crate) fn presenting(mut self, credential: Option<Bytes>) -> Self {
+        self.credential = credential;
+        self
+    }
+    async fn authorize(&self, action: AuthorizeAction) -> Result<(), crate::Error> {
+        self.node()
+            .authorize(self.code(), action, self.credential.as_ref())
+            .await
+    }
+    /// Fails with `Unauthorized` if an interest isn't allowed by the topic's `allowed_interests`.
+    pub(crate) async fn check_interests(&self, interests: &[Interest]) -> Result<(), crate::Error> {
+        let uncovered = self
+            .node()
+            .with_topic_data(self.code(), |topic| topic.uncovered_interest(interests))
+            .await
+            .flatten();
+        match uncovered {
+            Some(interest) => Err(crate::Error::new(
+                format!(
+                    "interest {interest:?} is not allowed on topic {}",
+                    self.code()
+                ),
+                crate::error::ErrorKind::Unauthorized,
+            )),
+            None => Ok(()),
+        }
+    }
+    /// reject messages with more attribute bytes than [`NodeConfig::max_attributes_size`](crate::prelude::NodeConfig::max_attributes_size)
+    fn check_attributes(&self, message: &Message) -> Result<(), crate::Error> {
+        let size = message.header.attributes_size();
+        let limit = self.node.config().max_attributes_size;
+        if size > limit {
+            return Err(crate::Error::new(
+                format!(
+                    "message {} has {size} bytes of attributes, over the limit of {limit}",
+                    message.id()
+                ),
+                crate::error::ErrorKind::HeaderTooLarge,
+            ));
+        }
+        Ok(())
+    }
+    /// Whether the topic's payload validator on this node accepts `message`, which must not be
+    /// compressed yet.
+    fn validate_payload(&self, message: &Message) -> bool {
+        let Some(validator) = self.node().payload_validator(self.code()) else {
+            return true;
+        };
+        match validator.validate(&message.payload.0) {
+            Ok(()) => true,
+            Err(reason) => {
+                tracing::debug!(message_id = %message.id(), %reason, "payload validation failed");
+                false
+            }
+        }
+    }
+    /// checksum the payload as it will be replicated, a checksum set by the producer is kept
+    fn with_checksum(mut message: Message, checksum: TopicChecksum) -> Message {
+        if message.header.checksum.is_none() {
+            message.header.checksum = checksum.digest(&message.payload.0);
+        }
+        message
+    }
+    /// reject malformed subjects before they're routed, see [`Subject::parse`](crate::prelude::Subject::parse)
+    fn parse_subjects(mut message: Message) -> Result<Message, crate::Error> {
+        message.header.subjects = message
+            .header
+            .subjects
+            .iter()
+            .map(|subject| Subject::parse(subject.as_bytes().to_vec()))
+            .collect::<Result<_, _>>()
+            .map_err(crate::Error::contextual_custom("publish"))?;
+        Ok(message)
+    }
     pub async fn send_message(&self, message: Message) -> Result<WaitAckHandle, crate::Error> {
-        let handle = self.wait_ack(message.id()).await;
+        self.authorize(AuthorizeAction::SendMessage).await?;
+        self.publish(message).await
+    }
+    /// [`Topic::send_message`] with `value` serialized by the topic's `payload_codec` as the payload.
+    ///
+    /// Receive it with [`LocalEndpoint::recv_typed`].
+    pub async fn send_typed<T: serde::Serialize>(
+        &self,
+        value: &T,
+        header: MessageHeader,
+    ) -> Result<WaitAckHandle, crate::Error> {
+        let payload = encode_value(self.payload_codec, value)
+            .map_err(crate::Error::contextual("encode typed payload"))?;
+        self.send_message(Message::new(header, payload)).await
+    }
+    /// [`Topic::send_message`] without asking the authorizer
+    pub(crate) async fn publish(&self, message: Message) -> Result<WaitAckHandle, crate::Error> {
+        self.check_attributes(&message)?;
+        let message = Self::parse_subjects(message)?;
+        if !self.validate_payload(&message) {
+            return Ok(WaitAckHandle::failed(
+                message.id(),
+                WaitAckErrorException::ValidationFailed,
+            ));
+        }
+        let message = compression::compress(message, self.node.config().compression_threshold);
+        let checksum = self.checksum().await;
+        self.send_compressed_message(Self::with_checksum(message, checksum))
+            .await
+    }
+    /// checksum algorithm of the topic, published payloads are checksummed with it
+    async fn checksum(&self) -> TopicChecksum {
+        self.node()
+            .with_topic_data(self.code(), |topic| topic.config.checksum)
+            .await
+            .unwrap_or_default()
+    }
+    /// [`Topic::send_message`] for a message already compressed by the node it was published on
+    pub(crate) async fn send_compressed_message(
+        &self,
+        message: Message,
+    ) -> Result<WaitAckHandle, crate::Error> {
+        // the topic may have been unloaded while idle, acks are resolved on the live handle
+        let topic = self.node().get_or_reload_topic(self.code()).await?;
+        if !topic.fits_size_limit(&message) {
+            return Ok(WaitAckHandle::failed(
+                message.id(),
+                WaitAckErrorException::MessageTooLarge,
+            ));
+        }
+        if !topic.wait_for_space().await {
+            return Ok(WaitAckHandle::failed(
+                message.id(),
+                WaitAckErrorException::Overflow,
+            ));
+        }
+        // the leader's view of the message is the one that counts, followers only mirror it
+        let leader = self.node().leader().await?;
+        if leader != self.node().id() {
+            return self
+                .node()
+                .forward_message(
+                    leader,
+                    DelegateMessage {
+                        topic: self.code().clone(),
+                        message,
+                        now: self.node().now(),
+                    },
+                )
+                .await;
+        }
+        let message_id = message.id();
+        if topic.admit(1).await.contains(&false) {
+            tracing::debug!(%message_id, "rate limited");
+            return Ok(WaitAckHandle::failed(
+                message_id,
+                WaitAckErrorException::RateLimited,
+            ));
+        }
+        let fire_and_forget = message.ack_kind() == MessageAckExpectKind::None;
+        let handle = topic.wait_ack(message_id).await;
         self.node()
             .propose(Proposal::DelegateMessage(DelegateMessage {
                 topic: self.code().clone(),
                 message,
+                now: self.node().now(),
             }))
             .await?;
+        topic.fail_if_unloaded([message_id]).await;
+        if fire_and_forget {
+            topic.resolve_enqueued([message_id]).await;
+        }
         Ok(handle)
     }
+    /// Publish `messages` in one raft log entry, one handle for each message in the same order.
+    ///
+    /// The batch is committed as a whole, then every message is held on its own, so a full queue
+    /// may reject or drop some messages of the batch and admit others. On a follower the batch is
+    /// forwarded to the leader in one request and committed there the same way, so the handles
+    /// resolve with the leader's view of them.
+    pub async fn send_messages(
+        &self,
+        messages: Vec<Message>,
+    ) -> Result<Vec<WaitAckHandle>, crate::Error> {
+        self.authorize(AuthorizeAction::SendMessage).await?;
+        for message in &messages {
+            self.check_attributes(message)?;
+        }
+        let messages = messages
+            .into_iter()
+            .map(Self::parse_subjects)
+            .collect::<Result<Vec<_>, _>>()?;
+        let threshold = self.node.config().compression_threshold;
+        let checksum = self.checksum().await;
+        // invalid messages fail here, the others get their handles from the leader
+        let mut handles = Vec::with_capacity(messages.len());
+        let mut valid = Vec::with_capacity(messages.len());
+        for message in messages {
+            if self.validate_payload(&message) {
+                handles.push(None);
+                valid.push(Self::with_checksum(
+                    compression::compress(message, threshold),
+                    checksum,
+                ));
+            } else {
+                handles.push(Some(WaitAckHandle::failed(
+                    message.id(),
+                    WaitAckErrorException::ValidationFailed,
+                )));
+            }
+        }
+        let mut held = self.send_compressed_messages(valid).await?.into_iter();
+        Ok(handles
+            .into_iter()
+            .map(|handle| {
+                handle.unwrap_or_else(|| held.next().expect("a handle for every valid message"))
+            })
+            .collect())
+    }
+    /// [`Topic::send_messages`] for messages already compressed by the node they were published on
+    pub(crate) async fn send_compressed_messages(
+        &self,
+        messages: Vec<Message>,
+    ) -> Result<Vec<WaitAckHandle>, crate::Error> {
+        let topic = self.node().get_or_reload_topic(self.code()).await?;
+        let leader = self.node().leader().await?;
+        if leader != self.node().id() {
+            return self
+                .node()
+                .forward_messages(
+                    leader,
+                    DelegateMessages {
+                        topic: self.code().clone(),
+                        messages,
+                        now: self.node().now(),
+                    },
+                )
+                .await;
+        }
+        if !topic.wait_for_space().await {
+            return Ok(messages
+                .iter()
+                .map(|message| WaitAckHandle::failed(message.id(), WaitAckErrorException::Overflow))
+                .collect());
+        }
+        let fits = messages
+            .iter()
+            .map(|message| topic.fits_size_limit(message))
+            .collect::<Vec<_>>();
+        let candidates = fits.iter().filter(|fits| **fits).count();
+        let mut admitted = topic.admit(candidates).await.into_iter();
+        let mut handles = Vec::with_capacity(messages.len());
+        let mut accepted = Vec::with_capacity(messages.len());
+        for (message, fits) in messages.into_iter().zip(fits) {
+            if !fits {
+                handles.push(WaitAckHandle::failed(
+                    message.id(),
+                    WaitAckErrorException::MessageTooLarge,
+                ));
+            } else if admitted.next() == Some(false) {
+                handles.push(WaitAckHandle::failed(
+                    message.id(),
+                    WaitAckErrorException::RateLimited,
+                ));
+            } else {
+                handles.push(topic.wait_ack(message.id()).await);
+                accepted.push(message);
+            }
+        }
+        if accepted.is_empty() {
+            return Ok(handles);
+        }
+        let messages = accepted;
+        let ids = messages.iter().map(Message::id).collect::<Vec<_>>();
+        let fire_and_forget = messages
+            .iter()
+            .filter(|message| message.ack_kind() == MessageAckExpectKind::None)
+            .map(Message::id)
+            .collect::<Vec<_>>();
+        self.node()
+            .propose(Proposal::DelegateMessages(DelegateMessages {
+                topic: self.code().clone(),
+                messages,
+                now: self.node().now(),
+            }))
+            .await?;
+        topic.fail_if_unloaded(ids).await;
+        topic.resolve_enqueued(fire_and_forget).await;
+        Ok(handles)
+    }
+    /// Publish `message` as a request and wait for its reply.
+    ///
+    /// The message gets a generated [`MessageHeader::CORRELATION_ID`] and a [`MessageHeader::REPLY_TO`]
+    /// naming `reply_interest`, which responders publish to, so it should be a plain subject. A temporary
+    /// endpoint interested in it takes the first message with the same correlation id, and is deleted once
+    /// the reply comes or `timeout` passes, the latter failing with [`ErrorKind::Timeout`](crate::error::ErrorKind::Timeout).
+    ///
+    /// Replies to other requests on the same interest don't hold their responders up. A push or
+    /// available one is nacked, so it's offered to another requester, a broadcast one is acked.
+    pub async fn request(
+        &self,
+        mut message: Message,
+        reply_interest: Interest,
+        timeout: std::time::Duration,
+    ) -> Result<Message, crate::Error> {
+        let correlation_id = Bytes::from(MessageId::new_snowflake().to_string());
+        let reply_to = Bytes::copy_from_slice(reply_interest.as_bytes());
+        let attributes = Arc::make_mut(&mut message.header.attributes);
+        attributes.insert(
+            MessageHeader::CORRELATION_ID.to_owned(),
+            crate::util::MaybeBase64Bytes(correlation_id.clone()),
+        );
+        attributes.insert(
+            MessageHeader::REPLY_TO.to_owned(),
+            crate::util::MaybeBase64Bytes(reply_to),
+        );
+        // subscribed before publishing, so a quick reply isn't missed
+        let endpoint = self.create_endpoint([reply_interest]).await?;
+        let reply = self
+            .wait_reply(&endpoint, message, &correlation_id, timeout)
+            .await;
+        let deleted = self.delete_endpoint(endpoint.address).await;
+        let reply = reply?;
+        deleted?;
+        Ok(reply)
+    }
+    async fn wait_reply(
+        &self,
+        endpoint: &LocalEndpoint,
+        message: Message,
+        correlation_id: &Bytes,
+        timeout: std::time::Duration,
+    ) -> Result<Message, crate::Error> {
+        let _handle = self.send_message(message).await?;
+        let reply = async {
+            while let Some(reply) = endpoint.next_message().await {
+                if reply.correlation_id() == Some(correlation_id) {
+                    endpoint.ack_processed(&reply.header).await?;
+                    return Ok(reply);
+                }
+                // a reply to another request on the same interest
+                match reply.header.target_kind {
+                    MessageTargetKind::Push | MessageTargetKind::Available => {
+                        endpoint.nack(&reply.header).await?
+                    }
+                    MessageTargetKind::Online | MessageTargetKind::Durable => {
+                        endpoint.ack_processed(&reply.header).await?
+                    }
+                }
+            }
+            Err(crate::Error::new(
+                "reply endpoint closed",
+                crate::error::ErrorKind::Offline,
+            ))
+        };
+        tokio::time::timeout(timeout, reply)
+            .await
+            .map_err(|_| crate::Error::new("no reply in time", crate::error::ErrorKind::Timeout))?
+    }
     pub fn node(&self) -> Node {
         self.node.clone()
     }
+    /// Take a token of the topic's rate limit for `count` messages about to be proposed, `false`
+    /// for each one that is over it.
+    async fn admit(&self, count: usize) -> Vec<bool> {
+        let limit = self
+            .node()
+            .with_topic_data(self.code(), |topic| topic.config.rate_limit)
+            .await
+            .flatten();
+        let Some(limit) = limit else {
+            return vec![true; count];
+        };
+        let mut limiter = self.rate_limiter.lock().unwrap();
+        let now = std::time::Instant::now();
+        (0..count)
+            .map(|_| limiter.try_acquire(&limit, now))
+            .collect()
+    }
+    /// With [`TopicOverflowPolicy::Block`], wait until the queue has room.
+    ///
+    /// Returns false if it stayed full for the whole `block_timeout`.
+    async fn wait_for_space(&self) -> bool {
+        let node = self.node();
+        let timeout = node
+            .with_topic_data(self.code(), |topic| {
+                topic
+                    .config
+                    .overflow_config
+                    .as_ref()
+                    .filter(|config| matches!(config.policy, TopicOverflowPolicy::Block))
+                    .map(TopicOverflowConfig::block_timeout)
+            })
+            .await
+            .flatten();
+        let Some(timeout) = timeout else {
+            return true;
+        };
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // register before checking, so a wake up in between isn't missed
+            let notified = self.space_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let has_space = node
+                .with_topic_data(self.code(), |topic| topic.has_space())
+                .await
+                .unwrap_or(true);
+            if has_space {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return false;
+            }
+        }
+    }
     pub async fn wait_ack(&self, id: MessageId) -> WaitAckHandle {
         let (sender, handle) = WaitAckHandle::new(id);
-        self.ack_waiting_pool.write().await.insert(id, sender);
-        handle
+        let mut pool = self.ack_waiting_pool.write().await;
+        if let Some(cap) = self.node.config().max_waiting_handles {
+            let evicted = pool.evict_for(1, cap);
+            if evicted > 0 {
+                tracing::warn!(topic = %self.code(), evicted, cap, "too many waiting handles, oldest ones failed");
+            }
+        }
+        pool.insert(id, sender);
+        drop(pool);
+        handle.registered_in(&self.ack_waiting_pool)
     }
     pub fn reference(&self) -> TopicRef {
         TopicRef {
@@ -97,57 +874,668 @@ impl Topic {
         }
     }
 
+    /// Create an endpoint with the default [`EndpointOptions`].
     pub async fn create_endpoint(
         &self,
         interests: impl IntoIterator<Item = Interest>,
     ) -> Result<LocalEndpoint, crate::Error> {
-        let channel = flume::unbounded();
-        let topic_code = self.code().clone();
-        let ep = LocalEndpoint {
-            inner: Arc::new(LocalEndpointInner {
-                attached_node: self.node.node_ref(),
-                address: EndpointAddr::new_snowflake(),
-                mail_box: channel.1,
-                mail_addr: channel.0,
-                interest: interests.into_iter().collect(),
-                topic_code: topic_code.clone(),
-                attached_topic: self.reference(),
-            }),
+        self.create_endpoint_with(interests, EndpointOptions::new())
+            .await
+    }
+    /// Create an endpoint that gets `weight` times the share of push messages of a default one.
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::weight`")]
+    pub async fn create_weighted_endpoint(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        weight: u32,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(interests, EndpointOptions::new().weight(weight))
+            .await
+    }
+    /// Create an endpoint whose mailbox holds at most `mailbox_capacity` undelivered messages,
+    /// see [`EndpointOptions::mailbox_capacity`].
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::mailbox_capacity`")]
+    pub async fn create_bounded_endpoint(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        mailbox_capacity: usize,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(
+            interests,
+            EndpointOptions::new().mailbox_capacity(mailbox_capacity),
+        )
+        .await
+    }
+    /// Create an endpoint in consumer group `group`, see [`GroupId`].
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::group`")]
+    pub async fn create_grouped_endpoint(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        group: GroupId,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(interests, EndpointOptions::new().group(group))
+            .await
+    }
+    /// Create an endpoint that first receives the matching durable messages picked by `replay`,
+    /// see [`EndpointOptions::replay`].
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::replay`")]
+    pub async fn create_endpoint_with_replay(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        replay: ReplayPolicy,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(interests, EndpointOptions::new().replay(replay))
+            .await
+    }
+    /// Create an endpoint that acks every message as processed once it's in the mailbox, see
+    /// [`EndpointOptions::auto_ack`].
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::auto_ack`")]
+    pub async fn create_auto_ack_endpoint(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(interests, EndpointOptions::new().auto_ack())
+            .await
+    }
+    /// Create an endpoint that only receives the messages `filter` lets through, see
+    /// [`EndpointOptions::filter`].
+    #[deprecated(note = "use `create_endpoint_with` and `EndpointOptions::filter`")]
+    pub async fn create_filtered_endpoint(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        filter: MessageFilter,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        self.create_endpoint_with(interests, EndpointOptions::new().filter(filter))
+            .await
+    }
+    /// Create an endpoint with `options`, see [`EndpointOptions`].
+    pub async fn create_endpoint_with(
+        &self,
+        interests: impl IntoIterator<Item = Interest>,
+        options: EndpointOptions,
+    ) -> Result<LocalEndpoint, crate::Error> {
+        let EndpointOptions {
+            weight,
+            group,
+            replay,
+            mailbox_capacity,
+            auto_ack,
+            filter,
+            prefetch,
+            endpoint_key,
+            exactly_once,
+        } = options;
+        if exactly_once {
+            let records_acks = self
+                .node
+                .config()
+                .durable
+                .as_ref()
+                .is_some_and(durable_message::DurableService::records_acks);
+            if self.ephemeral || !records_acks {
+                return Err(crate::Error::unknown(
+                    "exactly once endpoints need a durable topic and a durable service recording acks",
+                ));
+            }
+        }
+        self.authorize(AuthorizeAction::CreateEndpoint).await?;
+        let mut interests = interests.into_iter().collect::<Vec<_>>();
+        for interest in &interests {
+            interest
+                .validate()
+                .map_err(crate::Error::contextual_custom("create endpoint"))?;
+        }
+        // the topic may have been unloaded while idle, attach to the live handle
+        let topic = self.node().get_or_reload_topic(self.code()).await?;
+        topic.check_interests(&interests).await?;
+        let reclaimed = match &endpoint_key {
+            Some(key) => topic
+                .node()
+                .with_topic_data(topic.code(), |data| data.keyed_endpoint(key))
+                .await
+                .flatten(),
+            None => None,
         };
-        self.node()
+        let is_reclaimed = reclaimed.is_some();
+        let address = match reclaimed {
+            Some((address, adopted)) => {
+                tracing::debug!(topic = %topic.code(), ?address, "endpoint reclaimed by its key");
+                interests = adopted;
+                address
+            }
+            None => EndpointAddr::new_snowflake(),
+        };
+        let ep = topic.new_local_endpoint(
+            address,
+            interests,
+            mailbox_capacity,
+            auto_ack,
+            endpoint_key.clone(),
+            exactly_once,
+        );
+        // registered before it comes online, messages are dispatched to it while that's applied
+        topic
+            .local_endpoints
+            .write()
+            .unwrap()
+            .insert(ep.address, ep.reference());
+        topic
+            .node()
             .propose(Proposal::EpOnline(EndpointOnline {
-                topic_code: topic_code.clone(),
+                topic_code: topic.code().clone(),
                 endpoint: ep.address,
                 interests: ep.interest.clone(),
-                host: self.node.id(),
+                host: topic.node.id(),
+                weight,
+                group,
+                filter,
+                prefetch,
+                key: endpoint_key,
+                now: topic.node().now(),
             }))
-            .await?;
-        self.local_endpoints
+            .await
+            .inspect_err(|_| topic.forget_local_endpoints([ep.address]))?;
+        if is_reclaimed {
+            topic.redeliver_unacked(&ep).await;
+        }
+        if let Some(since) = replay.since() {
+            topic.replay_durable(&ep, since).await?;
+        }
+        Ok(ep)
+    }
+    /// Create one endpoint for each set of interests, all of them in a single log entry.
+    ///
+    /// Every endpoint gets its own mailbox and address, as if created with
+    /// [`Topic::create_endpoint`]. They come online together: if any set of interests is invalid
+    /// or not allowed on this topic, none of them is created.
+    pub async fn create_endpoints(
+        &self,
+        interests: Vec<Vec<Interest>>,
+    ) -> Result<Vec<LocalEndpoint>, crate::Error> {
+        self.authorize(AuthorizeAction::CreateEndpoint).await?;
+        for interest in interests.iter().flatten() {
+            interest
+                .validate()
+                .map_err(crate::Error::contextual_custom("create endpoints"))?;
+        }
+        let topic = self.node().get_or_reload_topic(self.code()).await?;
+        for interests in &interests {
+            topic.check_interests(interests).await?;
+        }
+        let eps = interests
+            .into_iter()
+            .map(|interests| {
+                topic.new_local_endpoint(
+                    EndpointAddr::new_snowflake(),
+                    interests,
+                    None,
+                    false,
+                    None,
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+        topic
+            .local_endpoints
             .write()
             .unwrap()
-            .insert(ep.address, ep.reference());
-        Ok(ep)
+            .extend(eps.iter().map(|ep| (ep.address, ep.reference())));
+        topic
+            .node()
+            .propose(Proposal::EpsOnline(EndpointsOnline {
+                topic_code: topic.code().clone(),
+                endpoints: eps
+                    .iter()
+                    .map(|ep| EndpointOnline {
+                        topic_code: topic.code().clone(),
+                        endpoint: ep.address,
+                        interests: ep.interest.clone(),
+                        host: topic.node.id(),
+                        weight: EpInfo::DEFAULT_WEIGHT,
+                        group: None,
+                        filter: None,
+                        prefetch: None,
+                        key: None,
+                        now: topic.node().now(),
+                    })
+                    .collect(),
+                now: topic.node().now(),
+            }))
+            .await
+            .inspect_err(|_| topic.forget_local_endpoints(eps.iter().map(|ep| ep.address)))?;
+        Ok(eps)
+    }
+    fn new_local_endpoint(
+        &self,
+        address: EndpointAddr,
+        interests: Vec<Interest>,
+        mailbox_capacity: Option<usize>,
+        auto_ack: bool,
+        key: Option<Bytes>,
+        exactly_once: bool,
+    ) -> LocalEndpoint {
+        let channel = match mailbox_capacity {
+            Some(capacity) => flume::bounded(capacity),
+            None => flume::unbounded(),
+        };
+        LocalEndpoint {
+            inner: Arc::new(LocalEndpointInner {
+                attached_node: self.node.node_ref(),
+                address,
+                mail_box: channel.1,
+                mail_addr: channel.0,
+                closed: Default::default(),
+                interest: interests,
+                attached_topic: self.reference(),
+                other_topics: Vec::new(),
+                auto_ack,
+                key,
+                exactly_once,
+            }),
+        }
+    }
+    /// push the messages sent to a reclaimed endpoint and not acked yet to its new mailbox
+    async fn redeliver_unacked(&self, ep: &LocalEndpoint) {
+        let Some((unacked, checksum)) = self
+            .node()
+            .with_topic_data(self.code(), |topic| {
+                (topic.unacked_for(&ep.address), topic.config.checksum)
+            })
+            .await
+        else {
+            return;
+        };
+        let mut messages = Vec::with_capacity(unacked.len());
+        for (message, offloaded) in unacked {
+            let message = if offloaded {
+                self.load_payload(message, checksum).await
+            } else {
+                Some(message)
+            };
+            messages.extend(message);
+        }
+        Self::push_replayed(ep, messages);
+    }
+    async fn replay_durable(
+        &self,
+        ep: &LocalEndpoint,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), crate::Error> {
+        let Some(durable) = self.node.config().durable.clone() else {
+            return Ok(());
+        };
+        let messages = durable
+            .retrieve_since(self.code().clone(), since)
+            .await
+            .map_err(crate::Error::contextual("replay durable messages"))?;
+        let messages = self
+            .node()
+            .with_topic_data(self.code(), |topic| {
+                messages
+                    .into_iter()
+                    .map(|stored| stored.message)
+                    .filter(|message| {
+                        !topic.is_waiting(&message.id())
+                            && topic.is_interested(&ep.address, message)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .unwrap_or_default();
+        Self::push_replayed(ep, messages);
+        Ok(())
+    }
+    /// Redeliver messages to `ep` from `target` on, see [`LocalEndpoint::seek`].
+    pub(crate) async fn seek_for(
+        &self,
+        ep: &LocalEndpoint,
+        target: SeekTarget,
+    ) -> Result<(), crate::Error> {
+        let from = match target {
+            SeekTarget::Latest => return Ok(()),
+            SeekTarget::Earliest => 0,
+            SeekTarget::Sequence(sequence) => sequence,
+            SeekTarget::Time(time) => {
+                let Some(durable) = self.node.config().durable.clone() else {
+                    return Ok(());
+                };
+                let stored = durable
+                    .retrieve_since(self.code().clone(), time.as_datetime())
+                    .await
+                    .map_err(crate::Error::contextual("seek durable messages"))?;
+                let messages = self
+                    .node()
+                    .with_topic_data(self.code(), |topic| {
+                        stored
+                            .into_iter()
+                            .map(|stored| stored.message)
+                            .filter(|message| topic.is_interested(&ep.address, message))
+                            .collect::<Vec<_>>()
+                    })
+                    .await
+                    .ok_or_else(|| {
+                        crate::Error::new("topic not found", crate::error::ErrorKind::TopicNotFound)
+                    })?;
+                Self::push_replayed(ep, messages);
+                return Ok(());
+            }
+        };
+        let stored = match self.node.config().durable.clone() {
+            Some(durable) => durable
+                .retrieve_from_sequence(self.code().clone(), from)
+                .await
+                .map_err(crate::Error::contextual("seek durable messages"))?,
+            None => Vec::new(),
+        };
+        let (retained, stored) = self
+            .node()
+            .with_topic_data(self.code(), |topic| {
+                let stored = stored
+                    .into_iter()
+                    .map(|stored| stored.message)
+                    .filter(|message| topic.is_interested(&ep.address, message))
+                    .collect::<Vec<_>>();
+                (topic.replay_for(&ep.address, from), stored)
+            })
+            .await
+            .ok_or_else(|| {
+                crate::Error::new("topic not found", crate::error::ErrorKind::TopicNotFound)
+            })?;
+        // retained and stored messages overlap, keep one of each in sequence order
+        let mut messages = BTreeMap::new();
+        for message in retained.into_iter().chain(stored) {
+            messages
+                .entry((message.header.sequence, message.id()))
+                .or_insert(message);
+        }
+        Self::push_replayed(ep, messages.into_values());
+        Ok(())
+    }
+    /// push replayed messages to `ep`, decompressed
+    fn push_replayed(ep: &LocalEndpoint, messages: impl IntoIterator<Item = Message>) {
+        for message in messages {
+            match compression::decompress(message) {
+                Ok(message) => {
+                    if let Err(err) = ep.push_message(message) {
+                        tracing::warn!(%err, "replayed message is dropped");
+                    }
+                }
+                Err(err) => tracing::warn!(%err, "replayed message can't be decompressed"),
+            }
+        }
     }
     pub async fn delete_endpoint(&self, addr: EndpointAddr) -> Result<(), crate::Error> {
         let node = self.node();
-        self.local_endpoints.write().unwrap().remove(&addr);
+        self.close_local_endpoint(&addr);
         let ep_offline = EndpointOffline {
             endpoint: addr,
             host: self.node.id(),
             topic_code: self.code().clone(),
+            now: node.now(),
         };
         node.propose(Proposal::EpOffline(ep_offline)).await?;
         Ok(())
     }
+    /// Delete an endpoint without losing the messages it's handling.
+    ///
+    /// The endpoint stops getting new messages at once, and keeps its mailbox until every message
+    /// dispatched to it is acked or `timeout` passes. Messages still pending then are nacked on its
+    /// behalf, see [`LocalEndpoint::nack`], before it goes offline.
+    pub async fn drain_endpoint(
+        &self,
+        addr: EndpointAddr,
+        timeout: std::time::Duration,
+    ) -> Result<(), crate::Error> {
+        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+        let node = self.node();
+        node.propose(Proposal::EpInterest(EndpointInterest {
+            topic_code: self.code().clone(),
+            endpoint: addr,
+            interests: Vec::new(),
+            now: node.now(),
+        }))
+        .await?;
+        let deadline = tokio::time::Instant::now() + timeout;
+        let pending = loop {
+            let pending = node
+                .with_topic_data(self.code(), |topic| topic.pending_for(&addr))
+                .await
+                .unwrap_or_default();
+            if pending.is_empty() || tokio::time::Instant::now() >= deadline {
+                break pending;
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(deadline - tokio::time::Instant::now())).await;
+        };
+        for message_id in pending {
+            tracing::debug!(%message_id, ?addr, "still pending after drain, requeue");
+            node.propose(Proposal::SetState(SetState {
+                topic: self.code().clone(),
+                update: MessageStateUpdate::new(
+                    message_id,
+                    HashMap::from([(addr, MessageStatusKind::Unsent)]),
+                ),
+                now: node.now(),
+            }))
+            .await?;
+        }
+        self.delete_endpoint(addr).await
+    }
 
+    /// Host, interests and latest activity of an endpoint, `None` if it isn't online in this topic.
+    pub async fn endpoint_info(&self, ep: &EndpointAddr) -> Option<EpInfo> {
+        self.node()
+            .with_topic_data(self.code(), |topic| topic.ep_info(ep))
+            .await
+            .flatten()
+    }
+    /// Every endpoint online in this topic, with what [`Topic::endpoint_info`] tells about it.
+    pub async fn endpoints(&self) -> Vec<(EndpointAddr, EpInfo)> {
+        self.node()
+            .with_topic_data(self.code(), TopicData::ep_infos)
+            .await
+            .unwrap_or_default()
+    }
+    /// The config the topic was loaded with, `None` once it's unloaded.
+    pub async fn config(&self) -> Option<TopicConfig> {
+        self.node()
+            .with_topic_data(self.code(), |topic| topic.config.clone())
+            .await
+    }
+    /// How `message` would be routed if it were sent now, without sending it.
+    ///
+    /// Lists the interests each subject matches, the endpoints they collect and the ones its
+    /// target kind finally picks. `None` once the topic is unloaded.
+    pub async fn explain_routing(&self, message: &Message) -> Option<RoutingExplanation> {
+        self.node()
+            .with_topic_data(self.code(), |topic| {
+                topic.explain_routing(message, self.node().now())
+            })
+            .await
+    }
+    /// Metadata of up to `n` held messages at the head of the queue, in dispatch order.
+    ///
+    /// Read-only: nothing is acked, removed or dispatched, and payloads are left out, see
+    /// [`Topic::peek_with_payload`]. Empty once the topic is unloaded.
+    pub async fn peek(&self, n: usize) -> Vec<MessageHeaderView> {
+        self.node()
+            .with_topic_data(self.code(), |topic| {
+                topic.peek(n).into_iter().map(|(view, _)| view).collect()
+            })
+            .await
+            .unwrap_or_default()
+    }
+    /// [`Topic::peek`] with the decompressed payloads, offloaded ones are loaded back.
+    ///
+    /// A payload that can't be loaded or decompressed is left out.
+    pub async fn peek_with_payload(&self, n: usize) -> Vec<MessageHeaderView> {
+        let Some((peeked, checksum)) = self
+            .node()
+            .with_topic_data(self.code(), |topic| {
+                let peeked = topic
+                    .peek(n)
+                    .into_iter()
+                    .map(|(view, hold_message)| {
+                        (view, hold_message.message.clone(), hold_message.offloaded)
+                    })
+                    .collect::<Vec<_>>();
+                (peeked, topic.config.checksum)
+            })
+            .await
+        else {
+            return Vec::new();
+        };
+        let mut views = Vec::with_capacity(peeked.len());
+        for (mut view, message, offloaded) in peeked {
+            let message = if offloaded {
+                self.load_payload(message, checksum).await
+            } else {
+                Some(message)
+            };
+            view.payload = message
+                .and_then(|message| compression::decompress(message).ok())
+                .map(|message| message.payload.0);
+            views.push(view);
+        }
+        views
+    }
+    /// A point-in-time backup of the topic, taken without stopping it.
+    ///
+    /// Resolved messages are left out and offloaded payloads are loaded back, so the snapshot
+    /// holds everything needed to bring the messages back in [`Topic::import_snapshot`].
+    pub async fn export_snapshot(&self) -> Result<TopicSnapshot, crate::Error> {
+        let now = self.node().now();
+        let mut data = self
+            .node()
+            .with_topic_data(self.code(), |topic| topic.snapshot(now))
+            .await
+            .ok_or_else(|| {
+                crate::Error::new("topic not found", crate::error::ErrorKind::TopicNotFound)
+            })?;
+        let checksum = data.config.checksum;
+        for hold_message in data.queue.hold_messages.values_mut() {
+            if !hold_message.offloaded {
+                continue;
+            }
+            let message = self
+                .load_payload(hold_message.message.clone(), checksum)
+                .await
+                .ok_or_else(|| {
+                    crate::Error::new(
+                        format!("payload of message {} is lost", hold_message.message.id()),
+                        crate::error::ErrorKind::MessageNotFound,
+                    )
+                })?;
+            hold_message.message = message;
+            hold_message.offloaded = false;
+        }
+        Ok(TopicSnapshot {
+            version: TopicSnapshot::VERSION,
+            taken_at: now,
+            data,
+        })
+    }
+    /// Merge a snapshot exported from this or another topic into this one.
+    ///
+    /// Its endpoints are added and its messages not held here yet are held and dispatched again,
+    /// this topic's config is kept. Only the leader imports, through the raft log, so every node
+    /// ends up with the same state.
+    pub async fn import_snapshot(&self, snapshot: TopicSnapshot) -> Result<(), crate::Error> {
+        self.authorize(AuthorizeAction::LoadTopic).await?;
+        if snapshot.version > TopicSnapshot::VERSION {
+            return Err(crate::Error::new(
+                "import snapshot",
+                crate::error::ErrorKind::Custom(
+                    format!("unsupported snapshot version {}", snapshot.version).into(),
+                ),
+            ));
+        }
+        self.node().ensure_leader().await?;
+        let topic = self.node().get_or_reload_topic(self.code()).await?;
+        topic
+            .node()
+            .propose(Proposal::ImportSnapshot(ImportSnapshot {
+                topic: topic.code().clone(),
+                snapshot: Box::new(snapshot),
+                now: topic.node().now(),
+            }))
+            .await
+    }
+    pub(crate) fn cache_payload(&self, message: &Message) {
+        if let Some(cache) = &self.payload_cache {
+            cache
+                .lock()
+                .unwrap()
+                .put(message.id(), message.payload.0.clone());
+        }
+    }
+    /// put back the payload of an offloaded message, from the cache or the durable service
+    pub(crate) async fn load_payload(
+        &self,
+        mut message: Message,
+        checksum: TopicChecksum,
+    ) -> Option<Message> {
+        let id = message.id();
+        let cached = self
+            .payload_cache
+            .as_ref()
+            .and_then(|cache| cache.lock().unwrap().get(&id));
+        if let Some(payload) = cached {
+            message.payload = crate::util::MaybeBase64Bytes(payload);
+            return Some(message);
+        }
+        let Some(durable) = self.node.config().durable.clone() else {
+            tracing::error!(%id, "lazy payloads need a durable service");
+            return None;
+        };
+        let stored = match durable.retrieve(self.code().clone(), id).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                tracing::warn!(?err, %id, "load payload failed");
+                return None;
+            }
+        };
+        // against the checksum replicated with the header, not the stored one
+        if !checksum.verify(&stored.message.payload.0, message.header.checksum) {
+            tracing::error!(topic = %self.code(), message_id = %id, "payload checksum mismatch");
+            self.corrupted_messages.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.cache_payload(&stored.message);
+        message.payload = stored.message.payload;
+        Some(message)
+    }
     pub(crate) async fn dispatch_message(
         &self,
         message: Message,
         ep: &EndpointAddr,
     ) -> Option<MessageStatusKind> {
+        let mut message = match compression::decompress(message) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::error!(%err, topic = %self.code(), "dispatched message can't be decompressed");
+                return Some(MessageStatusKind::Unreachable);
+            }
+        };
         // message is local or edge?
         if let Some(local) = self.get_local_ep(ep) {
-            local.upgrade()?.push_message(message);
+            let local = local.upgrade()?;
+            if local.exactly_once && self.is_acked(*ep, message.id()).await {
+                // its ack was lost, commit the recorded one instead of delivering again
+                tracing::debug!(topic = %self.code(), message_id = %message.id(), ?ep, "processed ack recovered");
+                return Some(MessageStatusKind::Processed);
+            }
+            // endpoints in more than one topic tell them apart by this attribute
+            if !local.other_topics.is_empty() {
+                Arc::make_mut(&mut message.header.attributes).insert(
+                    MessageHeader::TOPIC.to_owned(),
+                    crate::util::MaybeBase64Bytes(Bytes::from(self.code().to_string())),
+                );
+            }
+            // a full mailbox fails the delivery instead of blocking the dispatch loop
+            if let Err(err) = local.push_message(message) {
+                tracing::warn!(%err, ?ep, "push message failed");
+                return Some(MessageStatusKind::Unreachable);
+            }
             Some(MessageStatusKind::Sent)
         } else {
             // message is edge
@@ -164,12 +1552,150 @@ impl Topic {
             Some(MessageStatusKind::Sent)
         }
     }
+    /// Break-glass tool: resolve a message stuck waiting for acks, reporting `outcome` to its producer.
+    ///
+    /// The message is removed from the queue and archived, acks arriving later are ignored.
+    /// Only the leader accepts this, it's meant for incident response and never for normal flow.
+    pub async fn force_resolve_wait(
+        &self,
+        message_id: MessageId,
+        outcome: ForceResolveOutcome,
+    ) -> Result<(), crate::Error> {
+        let node = self.node();
+        node.ensure_leader().await?;
+        let is_waiting = node
+            .with_topic_data(self.code(), |topic| topic.is_waiting(&message_id))
+            .await
+            .unwrap_or(false);
+        if !is_waiting {
+            return Err(crate::Error::new(
+                "message is not waiting in topic",
+                crate::error::ErrorKind::MessageNotFound,
+            ));
+        }
+        tracing::warn!(topic = %self.code(), %message_id, ?outcome, "force resolving message");
+        node.propose(Proposal::ForceResolve(ForceResolve {
+            topic: self.code().clone(),
+            message_id,
+            outcome,
+            now: node.now(),
+        }))
+        .await
+    }
+    /// record that `ep` processed `message_id`, see [`EndpointOptions::exactly_once`]
+    pub(crate) async fn record_ack(
+        &self,
+        ep: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<(), crate::Error> {
+        let Some(durable) = &self.node.config().durable else {
+            return Ok(());
+        };
+        durable
+            .record_ack(self.code().clone(), ep, message_id)
+            .await
+            .map_err(crate::Error::contextual("record ack"))
+    }
+    async fn is_acked(&self, ep: EndpointAddr, message_id: MessageId) -> bool {
+        let Some(durable) = &self.node.config().durable else {
+            return false;
+        };
+        durable
+            .is_acked(self.code().clone(), ep, message_id)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!(%err, topic = %self.code(), %message_id, "recorded ack unknown");
+                false
+            })
+    }
     pub(crate) async fn single_ack(&self, ack: MessageAck) -> Result<(), crate::Error> {
-        self.node()
-            .propose(Proposal::SetState(SetState {
-                topic: self.code().clone(),
-                update: MessageStateUpdate::new(ack.ack_to, HashMap::from([(ack.from, ack.kind)])),
-            }))
+        let Some(interval) = self.node().config().ack_flush_interval else {
+            return self
+                .node()
+                .propose(Proposal::SetState(SetState {
+                    topic: self.code().clone(),
+                    update: MessageStateUpdate::new(
+                        ack.ack_to,
+                        HashMap::from([(ack.from, ack.kind)]),
+                    ),
+                    now: self.node().now(),
+                }))
+                .await;
+        };
+        // the producer's handle shouldn't wait for the window to end
+        let resolves = self
+            .node()
+            .with_topic_data(self.code(), |topic| topic.is_resolved_by(&ack))
             .await
+            .unwrap_or(false);
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let opened = {
+            let mut batch = self.ack_batch.lock().unwrap();
+            let opened = batch.updates.is_empty().then(|| {
+                batch.generation += 1;
+                batch.generation
+            });
+            batch
+                .updates
+                .entry(ack.ack_to)
+                .or_default()
+                .insert(ack.from, ack.kind);
+            batch.waiters.push(sender);
+            batch.due |= resolves;
+            opened
+        };
+        if let Some(generation) = opened.filter(|_| !resolves) {
+            let topic = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(interval).await;
+                {
+                    let mut batch = topic.ack_batch.lock().unwrap();
+                    if batch.generation != generation {
+                        return;
+                    }
+                    batch.due = true;
+                }
+                topic.flush_acks().await;
+            });
+        } else if resolves {
+            // not tied to this future, a dropped caller mustn't leave the batch stuck flushing
+            let topic = self.clone();
+            tokio::spawn(async move { topic.flush_acks().await });
+        }
+        receiver
+            .await
+            .map_err(|_| crate::Error::unknown("ack batch dropped"))?
+            .map_err(|err| crate::Error::custom("flush acks", err))
+    }
+    /// Commit the due ack batch as one log entry, then any batch that became due meanwhile.
+    async fn flush_acks(&self) {
+        let (mut updates, mut waiters) = {
+            let mut batch = self.ack_batch.lock().unwrap();
+            if batch.flushing || !batch.due || batch.updates.is_empty() {
+                return;
+            }
+            batch.flushing = true;
+            batch.take()
+        };
+        loop {
+            let result = self
+                .node()
+                .propose(Proposal::SetStates(SetStates {
+                    topic: self.code().clone(),
+                    updates,
+                    now: self.node().now(),
+                }))
+                .await
+                .map_err(Arc::new);
+            for waiter in waiters {
+                let _ = waiter.send(result.clone());
+            }
+            let mut batch = self.ack_batch.lock().unwrap();
+            if !batch.due || batch.updates.is_empty() {
+                batch.flushing = false;
+                return;
+            }
+            (updates, waiters) = batch.take();
+        }
     }
 }
@@ -6,10 +6,20 @@ use serde::{Deserialize, Serialize};
 
 use crate::protocol::message::*;
 use crate::protocol::{
-    endpoint::EndpointAddr, node::raft::state_machine::topic::config::TopicConfig,
+    endpoint::EndpointAddr,
+    node::{
+        edge::codec::{encoded_len, CodecError, CodecKind},
+        raft::state_machine::topic::config::TopicConfig,
+    },
 };
 
 use super::{MessageStateUpdate, TopicCode};
+pub(crate) mod memory;
+pub use memory::MemoryDurability;
+#[cfg(feature = "rocksdb")]
+pub(crate) mod rocksdb;
+#[cfg(feature = "rocksdb")]
+pub use rocksdb::RocksDbDurability;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DurableMessage {
     pub message: Message,
@@ -17,6 +27,29 @@ pub struct DurableMessage {
     pub time: DateTime<Utc>,
 }
 
+impl DurableMessage {
+    /// per-topic sequence number, assigned when the message is held and saved with its header
+    pub fn sequence(&self) -> Option<u64> {
+        self.message.header.sequence
+    }
+    /// Key sorting messages in the order they were held: by time, then by sequence, ties between
+    /// messages without one go to the id.
+    pub fn hold_order(&self) -> (DateTime<Utc>, Option<u64>, MessageId) {
+        (self.time, self.sequence(), self.message.id())
+    }
+    /// Byte size of this message encoded with bincode, the encoding used by snapshots and the
+    /// binary edge codec, computed without allocating the encoded buffer.
+    pub fn encoded_size(&self) -> usize {
+        self.encoded_size_with(CodecKind::BINCODE)
+            .expect("durable message is always serializable")
+    }
+    /// Byte size of this message encoded with `codec`, e.g. the one a durable service stores it
+    /// with, computed without allocating the encoded buffer.
+    pub fn encoded_size_with(&self, codec: CodecKind) -> Result<usize, CodecError> {
+        encoded_len(codec, self)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct DurableMessageQuery {
     pub limit: u32,
@@ -75,7 +108,8 @@ impl DurableError {
 
 #[derive(Debug, Clone)]
 pub enum DurableCommand {
-    Create(Message),
+    /// boxed, a held message is much larger than the other commands
+    Create(Box<DurableMessage>),
     UpdateStatus(MessageStateUpdate),
     Archive(MessageId),
 }
@@ -155,6 +189,22 @@ impl DurableService {
         self.inner.batch_retrieve(topic, query).await
     }
     #[inline(always)]
+    pub async fn retrieve_since(
+        &self,
+        topic: TopicCode,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        self.inner.retrieve_since(topic, since).await
+    }
+    #[inline(always)]
+    pub async fn retrieve_from_sequence(
+        &self,
+        topic: TopicCode,
+        from: u64,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        self.inner.retrieve_from_sequence(topic, from).await
+    }
+    #[inline(always)]
     pub async fn create_topic(&self, topic: TopicConfig) -> Result<(), DurableError> {
         self.inner.create_topic(topic).await
     }
@@ -169,6 +219,28 @@ impl DurableService {
     pub async fn topic_list(&self) -> Result<Vec<TopicConfig>, DurableError> {
         self.inner.topic_list().await
     }
+    #[inline(always)]
+    pub async fn record_ack(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<(), DurableError> {
+        self.inner.record_ack(topic, endpoint, message_id).await
+    }
+    #[inline(always)]
+    pub async fn is_acked(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<bool, DurableError> {
+        self.inner.is_acked(topic, endpoint, message_id).await
+    }
+    #[inline(always)]
+    pub fn records_acks(&self) -> bool {
+        self.inner.records_acks()
+    }
 }
 
 pub trait Durable: Send + Sync + 'static {
@@ -193,6 +265,59 @@ pub trait Durable: Send + Sync + 'static {
         topic: TopicCode,
         query: DurableMessageQuery,
     ) -> impl Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send;
+    /// Messages of `topic` saved at or after `since`, in time order.
+    ///
+    /// The default implementation pages through [`Durable::batch_retrieve`], so it misses archived
+    /// messages. Override it if archived messages are kept and should be replayed too.
+    fn retrieve_since(
+        &self,
+        topic: TopicCode,
+        since: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send {
+        async move {
+            let mut query = DurableMessageQuery::new(256, 0);
+            let mut messages = Vec::new();
+            loop {
+                let page = self.batch_retrieve(topic.clone(), query).await?;
+                let page_size = page.len();
+                messages.extend(page.into_iter().filter(|message| message.time >= since));
+                if page_size < query.limit as usize {
+                    break;
+                }
+                query = query.next_page();
+            }
+            messages.sort_by_key(|message| message.time);
+            Ok(messages)
+        }
+    }
+    /// Messages of `topic` with a sequence number of at least `from`, in sequence order.
+    ///
+    /// Like [`Durable::retrieve_since`], the default implementation pages through
+    /// [`Durable::batch_retrieve`] and misses archived messages.
+    fn retrieve_from_sequence(
+        &self,
+        topic: TopicCode,
+        from: u64,
+    ) -> impl Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send {
+        async move {
+            let mut query = DurableMessageQuery::new(256, 0);
+            let mut messages = Vec::new();
+            loop {
+                let page = self.batch_retrieve(topic.clone(), query).await?;
+                let page_size = page.len();
+                messages.extend(
+                    page.into_iter()
+                        .filter(|message| message.sequence().is_some_and(|seq| seq >= from)),
+                );
+                if page_size < query.limit as usize {
+                    break;
+                }
+                query = query.next_page();
+            }
+            messages.sort_by_key(DurableMessage::sequence);
+            Ok(messages)
+        }
+    }
     fn archive(
         &self,
         topic: TopicCode,
@@ -208,16 +333,47 @@ pub trait Durable: Send + Sync + 'static {
     ) -> impl Future<Output = Result<(), DurableError>> + Send;
     fn topic_code_list(&self) -> impl Future<Output = Result<Vec<TopicCode>, DurableError>> + Send;
     fn topic_list(&self) -> impl Future<Output = Result<Vec<TopicConfig>, DurableError>> + Send;
+    /// Remember that `endpoint` processed `message_id`, for endpoints created with
+    /// [`EndpointOptions::exactly_once`](crate::prelude::EndpointOptions::exactly_once).
+    ///
+    /// Records of a message can go once it's archived. The default implementation keeps nothing
+    /// and fails, implementations that record acks also override [`Durable::records_acks`].
+    fn record_ack(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> impl Future<Output = Result<(), DurableError>> + Send {
+        let _ = (topic, endpoint, message_id);
+        async { Err(DurableError::new_local("acks are not recorded")) }
+    }
+    /// Whether [`Durable::record_ack`] recorded `endpoint` processing `message_id`.
+    fn is_acked(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> impl Future<Output = Result<bool, DurableError>> + Send {
+        let _ = (topic, endpoint, message_id);
+        async { Ok(false) }
+    }
+    /// Whether [`Durable::record_ack`] is implemented, exactly-once endpoints can't be created
+    /// on a node whose durable service doesn't record acks.
+    fn records_acks(&self) -> bool {
+        false
+    }
 }
 
 mod sealed {
     use std::{future::Future, pin::Pin};
 
+    use chrono::{DateTime, Utc};
+
     use crate::{
         prelude::TopicCode,
         protocol::{
-            message::*, node::raft::state_machine::topic::config::TopicConfig,
-            topic::MessageStateUpdate,
+            endpoint::EndpointAddr, message::*,
+            node::raft::state_machine::topic::config::TopicConfig, topic::MessageStateUpdate,
         },
     };
 
@@ -244,6 +400,16 @@ mod sealed {
             topic: TopicCode,
             query: DurableMessageQuery,
         ) -> Pin<Box<dyn Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send + '_>>;
+        fn retrieve_since(
+            &self,
+            topic: TopicCode,
+            since: DateTime<Utc>,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send + '_>>;
+        fn retrieve_from_sequence(
+            &self,
+            topic: TopicCode,
+            from: u64,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send + '_>>;
         fn archive(
             &self,
             topic: TopicCode,
@@ -263,6 +429,19 @@ mod sealed {
         fn topic_list(
             &self,
         ) -> Pin<Box<dyn Future<Output = Result<Vec<TopicConfig>, DurableError>> + Send + '_>>;
+        fn record_ack(
+            &self,
+            topic: TopicCode,
+            endpoint: EndpointAddr,
+            message_id: MessageId,
+        ) -> Pin<Box<dyn Future<Output = Result<(), DurableError>> + Send + '_>>;
+        fn is_acked(
+            &self,
+            topic: TopicCode,
+            endpoint: EndpointAddr,
+            message_id: MessageId,
+        ) -> Pin<Box<dyn Future<Output = Result<bool, DurableError>> + Send + '_>>;
+        fn records_acks(&self) -> bool;
     }
 
     impl<T> DurabilityObjectTrait for T
@@ -270,7 +449,6 @@ mod sealed {
         T: Durable,
     {
         #[inline(always)]
-
         fn save(
             &self,
             topic: TopicCode,
@@ -280,7 +458,6 @@ mod sealed {
         }
 
         #[inline(always)]
-
         fn retrieve(
             &self,
             topic: TopicCode,
@@ -290,7 +467,6 @@ mod sealed {
             Box::pin(self.retrieve(topic, message_id))
         }
         #[inline(always)]
-
         fn batch_retrieve(
             &self,
             topic: TopicCode,
@@ -300,7 +476,24 @@ mod sealed {
             Box::pin(self.batch_retrieve(topic, query))
         }
         #[inline(always)]
-
+        fn retrieve_since(
+            &self,
+            topic: TopicCode,
+            since: DateTime<Utc>,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send + '_>>
+        {
+            Box::pin(self.retrieve_since(topic, since))
+        }
+        #[inline(always)]
+        fn retrieve_from_sequence(
+            &self,
+            topic: TopicCode,
+            from: u64,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<DurableMessage>, DurableError>> + Send + '_>>
+        {
+            Box::pin(self.retrieve_from_sequence(topic, from))
+        }
+        #[inline(always)]
         fn archive(
             &self,
             topic: TopicCode,
@@ -345,5 +538,66 @@ mod sealed {
         {
             Box::pin(self.topic_list())
         }
+
+        fn record_ack(
+            &self,
+            topic: TopicCode,
+            endpoint: EndpointAddr,
+            message_id: MessageId,
+        ) -> Pin<Box<dyn Future<Output = Result<(), DurableError>> + Send + '_>> {
+            Box::pin(self.record_ack(topic, endpoint, message_id))
+        }
+
+        fn is_acked(
+            &self,
+            topic: TopicCode,
+            endpoint: EndpointAddr,
+            message_id: MessageId,
+        ) -> Pin<Box<dyn Future<Output = Result<bool, DurableError>> + Send + '_>> {
+            Box::pin(self.is_acked(topic, endpoint, message_id))
+        }
+
+        fn records_acks(&self) -> bool {
+            self.records_acks()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::Utc;
+
+    use super::DurableMessage;
+    use crate::{
+        prelude::{Message, MessageHeader, Subject},
+        protocol::node::edge::codec::{encode_value, CodecKind},
+    };
+
+    #[test]
+    fn test_encoded_size() {
+        let message = DurableMessage {
+            message: Message::new(
+                MessageHeader::builder([Subject::new("event/size"), Subject::new("event/other")])
+                    .mode_online()
+                    .build(),
+                vec![7u8; 1024],
+            ),
+            status: HashMap::new(),
+            time: Utc::now(),
+        };
+        let encoded = bincode::serialize(&message).expect("encode");
+        assert_eq!(message.encoded_size(), encoded.len());
+        let codecs = [
+            CodecKind::BINCODE,
+            CodecKind::JSON,
+            #[cfg(feature = "cbor")]
+            CodecKind::CBOR,
+        ];
+        for codec in codecs {
+            let encoded = encode_value(codec, &message).expect("encode");
+            assert_eq!(message.encoded_size_with(codec).ok(), Some(encoded.len()));
+        }
     }
 }
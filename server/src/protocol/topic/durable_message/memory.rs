@@ -0,0 +1,189 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::RwLock,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::protocol::{
+    endpoint::EndpointAddr,
+    message::*,
+    node::raft::state_machine::topic::config::TopicConfig,
+    topic::{MessageStateUpdate, TopicCode},
+};
+
+use super::{Durable, DurableError, DurableMessage, DurableMessageQuery};
+
+#[derive(Debug, Default)]
+struct MemoryTopic {
+    config: Option<TopicConfig>,
+    /// by save time, so scans are in time order
+    messages: BTreeMap<(DateTime<Utc>, MessageId), DurableMessage>,
+    saved_at: HashMap<MessageId, DateTime<Utc>>,
+    /// endpoints that processed a message, see [`Durable::record_ack`]
+    acked: HashMap<MessageId, HashSet<EndpointAddr>>,
+}
+
+impl MemoryTopic {
+    fn get(&self, message_id: MessageId) -> Option<&DurableMessage> {
+        let time = *self.saved_at.get(&message_id)?;
+        self.messages.get(&(time, message_id))
+    }
+    fn get_mut(&mut self, message_id: MessageId) -> Option<&mut DurableMessage> {
+        let time = *self.saved_at.get(&message_id)?;
+        self.messages.get_mut(&(time, message_id))
+    }
+}
+
+/// [`Durable`] kept in the node's memory, the default when [`NodeConfig`](crate::prelude::NodeConfig)
+/// has no durable service.
+///
+/// Nothing survives a restart. Archived messages are dropped instead of kept, so memory only
+/// holds messages that are still waiting, and replay only sees those.
+#[derive(Debug, Default)]
+pub struct MemoryDurability {
+    topics: RwLock<HashMap<TopicCode, MemoryTopic>>,
+}
+
+impl MemoryDurability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Durable for MemoryDurability {
+    async fn save(&self, topic: TopicCode, message: DurableMessage) -> Result<(), DurableError> {
+        let mut topics = self.topics.write().unwrap();
+        let topic = topics.entry(topic).or_default();
+        let message_id = message.message.id();
+        if let Some(time) = topic.saved_at.insert(message_id, message.time) {
+            topic.messages.remove(&(time, message_id));
+        }
+        topic.messages.insert((message.time, message_id), message);
+        Ok(())
+    }
+    async fn update_status(
+        &self,
+        topic: TopicCode,
+        update: MessageStateUpdate,
+    ) -> Result<(), DurableError> {
+        let mut topics = self.topics.write().unwrap();
+        if let Some(message) = topics
+            .get_mut(&topic)
+            .and_then(|topic| topic.get_mut(update.message_id))
+        {
+            message.status.extend(update.status);
+        }
+        Ok(())
+    }
+    async fn retrieve(
+        &self,
+        topic: TopicCode,
+        message_id: MessageId,
+    ) -> Result<DurableMessage, DurableError> {
+        let topics = self.topics.read().unwrap();
+        topics
+            .get(&topic)
+            .and_then(|topic| topic.get(message_id))
+            .cloned()
+            .ok_or(DurableError::new_local("message not found"))
+    }
+    async fn batch_retrieve(
+        &self,
+        topic: TopicCode,
+        query: DurableMessageQuery,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        let topics = self.topics.read().unwrap();
+        Ok(topics
+            .get(&topic)
+            .into_iter()
+            .flat_map(|topic| topic.messages.values())
+            .skip(query.offset as usize)
+            .take(query.limit as usize)
+            .cloned()
+            .collect())
+    }
+    async fn retrieve_since(
+        &self,
+        topic: TopicCode,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        let topics = self.topics.read().unwrap();
+        Ok(topics
+            .get(&topic)
+            .into_iter()
+            .flat_map(|topic| topic.messages.iter())
+            .skip_while(|((time, _), _)| *time < since)
+            .map(|(_, message)| message.clone())
+            .collect())
+    }
+    async fn archive(&self, topic: TopicCode, message_id: MessageId) -> Result<(), DurableError> {
+        let mut topics = self.topics.write().unwrap();
+        let topic = topics
+            .get_mut(&topic)
+            .ok_or(DurableError::new_local("topic not found"))?;
+        let time = topic
+            .saved_at
+            .remove(&message_id)
+            .ok_or(DurableError::new_local("message not found"))?;
+        topic.messages.remove(&(time, message_id));
+        topic.acked.remove(&message_id);
+        Ok(())
+    }
+    async fn create_topic(&self, topic: TopicConfig) -> Result<(), DurableError> {
+        let mut topics = self.topics.write().unwrap();
+        let code = topic.code.clone();
+        topics.entry(code).or_default().config = Some(topic);
+        Ok(())
+    }
+    async fn delete_topic(&self, topic: TopicCode) -> Result<(), DurableError> {
+        self.topics.write().unwrap().remove(&topic);
+        Ok(())
+    }
+    async fn topic_code_list(&self) -> Result<Vec<TopicCode>, DurableError> {
+        let topics = self.topics.read().unwrap();
+        Ok(topics
+            .iter()
+            .filter(|(_, topic)| topic.config.is_some())
+            .map(|(code, _)| code.clone())
+            .collect())
+    }
+    async fn topic_list(&self) -> Result<Vec<TopicConfig>, DurableError> {
+        let topics = self.topics.read().unwrap();
+        Ok(topics
+            .values()
+            .filter_map(|topic| topic.config.clone())
+            .collect())
+    }
+    async fn record_ack(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<(), DurableError> {
+        let mut topics = self.topics.write().unwrap();
+        topics
+            .entry(topic)
+            .or_default()
+            .acked
+            .entry(message_id)
+            .or_default()
+            .insert(endpoint);
+        Ok(())
+    }
+    async fn is_acked(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<bool, DurableError> {
+        let topics = self.topics.read().unwrap();
+        Ok(topics
+            .get(&topic)
+            .and_then(|topic| topic.acked.get(&message_id))
+            .is_some_and(|endpoints| endpoints.contains(&endpoint)))
+    }
+    fn records_acks(&self) -> bool {
+        true
+    }
+}
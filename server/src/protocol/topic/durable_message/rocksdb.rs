@@ -0,0 +1,367 @@
+//! [`Durable`] backed by a local RocksDB database.
+//!
+//! Messages are keyed by `(topic, time, message id)`, so a topic is a contiguous key range
+//! and scanning it yields messages in time order.
+use std::{path::Path, sync::Arc};
+
+use ::rocksdb::{
+    ColumnFamily, ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB,
+};
+use chrono::{DateTime, Utc};
+
+use crate::protocol::{
+    endpoint::EndpointAddr,
+    message::*,
+    node::raft::state_machine::topic::config::TopicConfig,
+    topic::{MessageStateUpdate, TopicCode},
+};
+
+use super::{Durable, DurableError, DurableMessage, DurableMessageQuery};
+
+/// topic | time | message id -> message
+const MESSAGES: &str = "messages";
+/// same layout as `messages`
+const ARCHIVED: &str = "archived";
+/// topic | message id -> key of the message in `messages`
+const INDEX: &str = "index";
+/// topic -> topic config
+const TOPICS: &str = "topics";
+/// topic | message id | endpoint -> nothing, see [`Durable::record_ack`]
+const ACKED: &str = "acked";
+
+#[derive(Clone)]
+pub struct RocksDbDurability {
+    db: Arc<DB>,
+}
+
+impl std::fmt::Debug for RocksDbDurability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RocksDbDurability")
+            .field("path", &self.db.path())
+            .finish()
+    }
+}
+
+/// length prefixed, so no topic is a prefix of another
+fn topic_prefix(topic: &TopicCode) -> Vec<u8> {
+    let code = topic.to_string().into_bytes();
+    let mut prefix = Vec::with_capacity(4 + code.len());
+    prefix.extend_from_slice(&(code.len() as u32).to_be_bytes());
+    prefix.extend_from_slice(&code);
+    prefix
+}
+
+/// microseconds with the sign bit flipped, so byte order is time order
+fn time_bytes(time: DateTime<Utc>) -> [u8; 8] {
+    ((time.timestamp_micros() as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+fn message_key(topic: &TopicCode, time: DateTime<Utc>, message_id: MessageId) -> Vec<u8> {
+    let mut key = topic_prefix(topic);
+    key.extend_from_slice(&time_bytes(time));
+    key.extend_from_slice(&message_id.bytes);
+    key
+}
+
+fn index_key(topic: &TopicCode, message_id: MessageId) -> Vec<u8> {
+    let mut key = topic_prefix(topic);
+    key.extend_from_slice(&message_id.bytes);
+    key
+}
+
+fn ack_key(topic: &TopicCode, message_id: MessageId, endpoint: EndpointAddr) -> Vec<u8> {
+    let mut key = index_key(topic, message_id);
+    key.extend_from_slice(&endpoint.bytes);
+    key
+}
+
+fn cf<'a>(db: &'a DB, name: &'static str) -> Result<&'a ColumnFamily, DurableError> {
+    db.cf_handle(name)
+        .ok_or(DurableError::new_local("rocksdb column family missing"))
+}
+
+fn encode(message: &DurableMessage) -> Result<Vec<u8>, DurableError> {
+    bincode::serialize(message).map_err(|e| DurableError::with_source("encode message", e))
+}
+
+fn decode(bytes: &[u8]) -> Result<DurableMessage, DurableError> {
+    bincode::deserialize(bytes).map_err(|e| DurableError::with_source("decode message", e))
+}
+
+fn rocksdb_error(e: ::rocksdb::Error) -> DurableError {
+    DurableError::with_source("rocksdb", e)
+}
+
+/// values of `cf` from `from` on, as long as their key starts with `prefix`
+fn scan<'a>(
+    db: &'a DB,
+    cf: &'a ColumnFamily,
+    prefix: &'a [u8],
+    from: &'a [u8],
+) -> impl Iterator<Item = Result<Box<[u8]>, DurableError>> + 'a {
+    db.iterator_cf(cf, IteratorMode::From(from, Direction::Forward))
+        .map(|item| item.map_err(rocksdb_error))
+        .take_while(move |item| match item {
+            Ok((key, _)) => key.starts_with(prefix),
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(_, value)| value))
+}
+
+/// key of a live message in `messages`
+fn locate(db: &DB, topic: &TopicCode, message_id: MessageId) -> Result<Vec<u8>, DurableError> {
+    db.get_cf(cf(db, INDEX)?, index_key(topic, message_id))
+        .map_err(rocksdb_error)?
+        .ok_or(DurableError::new_local("message not found"))
+}
+
+impl RocksDbDurability {
+    /// open or create the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DurableError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let column_families = [MESSAGES, ARCHIVED, INDEX, TOPICS, ACKED]
+            .into_iter()
+            .map(|name| ColumnFamilyDescriptor::new(name, Options::default()));
+        let db = DB::open_cf_descriptors(&options, path, column_families).map_err(rocksdb_error)?;
+        Ok(Self { db: Arc::new(db) })
+    }
+    /// run a blocking database operation off the async runtime
+    async fn blocking<T, F>(&self, f: F) -> Result<T, DurableError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DB) -> Result<T, DurableError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || f(&db))
+            .await
+            .map_err(|e| DurableError::with_source("rocksdb task", e))?
+    }
+}
+
+impl Durable for RocksDbDurability {
+    async fn save(&self, topic: TopicCode, message: DurableMessage) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            let message_id = message.message.id();
+            let key = message_key(&topic, message.time, message_id);
+            let mut batch = WriteBatch::default();
+            batch.put_cf(cf(db, MESSAGES)?, &key, encode(&message)?);
+            batch.put_cf(cf(db, INDEX)?, index_key(&topic, message_id), &key);
+            db.write(batch).map_err(rocksdb_error)
+        })
+        .await
+    }
+    async fn update_status(
+        &self,
+        topic: TopicCode,
+        update: MessageStateUpdate,
+    ) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            let Ok(key) = locate(db, &topic, update.message_id) else {
+                return Ok(());
+            };
+            let messages = cf(db, MESSAGES)?;
+            let Some(value) = db.get_cf(messages, &key).map_err(rocksdb_error)? else {
+                return Ok(());
+            };
+            let mut message = decode(&value)?;
+            message.status.extend(update.status);
+            db.put_cf(messages, &key, encode(&message)?)
+                .map_err(rocksdb_error)
+        })
+        .await
+    }
+    async fn retrieve(
+        &self,
+        topic: TopicCode,
+        message_id: MessageId,
+    ) -> Result<DurableMessage, DurableError> {
+        self.blocking(move |db| {
+            let key = locate(db, &topic, message_id)?;
+            let value = db
+                .get_cf(cf(db, MESSAGES)?, &key)
+                .map_err(rocksdb_error)?
+                .ok_or(DurableError::new_local("message not found"))?;
+            decode(&value)
+        })
+        .await
+    }
+    async fn batch_retrieve(
+        &self,
+        topic: TopicCode,
+        query: DurableMessageQuery,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        self.blocking(move |db| {
+            let prefix = topic_prefix(&topic);
+            scan(db, cf(db, MESSAGES)?, &prefix, &prefix)
+                .skip(query.offset as usize)
+                .take(query.limit as usize)
+                .map(|value| decode(&value?))
+                .collect()
+        })
+        .await
+    }
+    /// archived messages are included
+    async fn retrieve_since(
+        &self,
+        topic: TopicCode,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        self.blocking(move |db| {
+            let prefix = topic_prefix(&topic);
+            let mut from = prefix.clone();
+            from.extend_from_slice(&time_bytes(since));
+            let mut messages = Vec::new();
+            for name in [MESSAGES, ARCHIVED] {
+                for value in scan(db, cf(db, name)?, &prefix, &from) {
+                    messages.push(decode(&value?)?);
+                }
+            }
+            messages.sort_by_key(DurableMessage::hold_order);
+            Ok(messages)
+        })
+        .await
+    }
+    /// archived messages are kept, so they are replayed too
+    async fn retrieve_from_sequence(
+        &self,
+        topic: TopicCode,
+        from: u64,
+    ) -> Result<Vec<DurableMessage>, DurableError> {
+        self.blocking(move |db| {
+            let prefix = topic_prefix(&topic);
+            let mut messages = Vec::new();
+            for name in [MESSAGES, ARCHIVED] {
+                for value in scan(db, cf(db, name)?, &prefix, &prefix) {
+                    let message = decode(&value?)?;
+                    if message.sequence().is_some_and(|sequence| sequence >= from) {
+                        messages.push(message);
+                    }
+                }
+            }
+            messages.sort_by_key(DurableMessage::sequence);
+            Ok(messages)
+        })
+        .await
+    }
+    async fn archive(&self, topic: TopicCode, message_id: MessageId) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            let key = locate(db, &topic, message_id)?;
+            let messages = cf(db, MESSAGES)?;
+            let value = db
+                .get_cf(messages, &key)
+                .map_err(rocksdb_error)?
+                .ok_or(DurableError::new_local("message not found"))?;
+            let mut batch = WriteBatch::default();
+            batch.delete_cf(messages, &key);
+            batch.put_cf(cf(db, ARCHIVED)?, &key, value);
+            batch.delete_cf(cf(db, INDEX)?, index_key(&topic, message_id));
+            let acked = cf(db, ACKED)?;
+            let prefix = index_key(&topic, message_id);
+            for item in db.iterator_cf(acked, IteratorMode::From(&prefix, Direction::Forward)) {
+                let (key, _) = item.map_err(rocksdb_error)?;
+                if !key.starts_with(&prefix) {
+                    break;
+                }
+                batch.delete_cf(acked, key);
+            }
+            db.write(batch).map_err(rocksdb_error)
+        })
+        .await
+    }
+    async fn create_topic(&self, topic: TopicConfig) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            let config = serde_json::to_vec(&topic)
+                .map_err(|e| DurableError::with_source("encode topic config", e))?;
+            db.put_cf(cf(db, TOPICS)?, topic.code.to_string(), config)
+                .map_err(rocksdb_error)
+        })
+        .await
+    }
+    /// purges the topic's messages, archived ones included, along with its config
+    async fn delete_topic(&self, topic: TopicCode) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            let prefix = topic_prefix(&topic);
+            let mut batch = WriteBatch::default();
+            for name in [MESSAGES, ARCHIVED, INDEX, ACKED] {
+                let cf = cf(db, name)?;
+                for item in db.iterator_cf(cf, IteratorMode::From(&prefix, Direction::Forward)) {
+                    let (key, _) = item.map_err(rocksdb_error)?;
+                    if !key.starts_with(&prefix) {
+                        break;
+                    }
+                    batch.delete_cf(cf, key);
+                }
+            }
+            batch.delete_cf(cf(db, TOPICS)?, topic.to_string());
+            db.write(batch).map_err(rocksdb_error)
+        })
+        .await
+    }
+    async fn topic_code_list(&self) -> Result<Vec<TopicCode>, DurableError> {
+        self.blocking(|db| {
+            db.iterator_cf(cf(db, TOPICS)?, IteratorMode::Start)
+                .map(|item| {
+                    let (key, _) = item.map_err(rocksdb_error)?;
+                    Ok(TopicCode::from(&*key))
+                })
+                .collect()
+        })
+        .await
+    }
+    async fn topic_list(&self) -> Result<Vec<TopicConfig>, DurableError> {
+        self.blocking(|db| {
+            db.iterator_cf(cf(db, TOPICS)?, IteratorMode::Start)
+                .map(|item| {
+                    let (_, value) = item.map_err(rocksdb_error)?;
+                    serde_json::from_slice(&value)
+                        .map_err(|e| DurableError::with_source("decode topic config", e))
+                })
+                .collect()
+        })
+        .await
+    }
+    async fn record_ack(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<(), DurableError> {
+        self.blocking(move |db| {
+            db.put_cf(cf(db, ACKED)?, ack_key(&topic, message_id, endpoint), [])
+                .map_err(rocksdb_error)
+        })
+        .await
+    }
+    async fn is_acked(
+        &self,
+        topic: TopicCode,
+        endpoint: EndpointAddr,
+        message_id: MessageId,
+    ) -> Result<bool, DurableError> {
+        self.blocking(move |db| {
+            db.get_pinned_cf(cf(db, ACKED)?, ack_key(&topic, message_id, endpoint))
+                .map(|value| value.is_some())
+                .map_err(rocksdb_error)
+        })
+        .await
+    }
+    fn records_acks(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_time_key_order() {
+    let topic = TopicCode::const_new("order");
+    let id = MessageId::new_snowflake();
+    let before_epoch = DateTime::from_timestamp_micros(-1).expect("valid time");
+    let epoch = DateTime::from_timestamp_micros(0).expect("valid time");
+    let later = DateTime::from_timestamp_micros(1_000).expect("valid time");
+    assert!(message_key(&topic, before_epoch, id) < message_key(&topic, epoch, id));
+    assert!(message_key(&topic, epoch, id) < message_key(&topic, later, id));
+    // `order` never collides with a longer topic sharing its name as a prefix
+    let other = TopicCode::const_new("orders");
+    assert!(!message_key(&other, epoch, id).starts_with(&topic_prefix(&topic)));
+}
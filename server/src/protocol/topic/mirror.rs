@@ -0,0 +1,301 @@
+//! Best-effort asynchronous mirroring of a topic to a remote cluster.
+//!
+//! The leader forwards every newly held message to the remote cluster over an edge
+//! connection. Messages wait in a bounded local buffer while the remote is unreachable,
+//! and forwarding resumes from the buffer once it reconnects.
+//!
+//! Every forwarded message carries the mirror's [`MirrorConfig::credential`] in its
+//! [`MessageHeader::MIRROR_CREDENTIAL`] attribute, for the remote's `edge_auth` to check, see
+//! [`EdgeMessageHeader::mirror_credential`]. The remote removes it before publishing.
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use chrono::{TimeDelta, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::Instrument;
+
+use crate::{
+    prelude::{compression, Message, MessageHeader, MessageId, TopicCode},
+    protocol::node::edge::{
+        codec::{CodecKind, CodecRegistry},
+        connection::tokio_tcp::TokioTcp,
+        packet::EdgePacket,
+        EdgeMessage, EdgeMessageHeader, EdgePayload, EdgeRequest, EdgeRequestEnum,
+    },
+    util::{MaybeBase64Bytes, Timed},
+};
+
+/// The header a message is published with on the remote cluster, with the mirror's `credential`.
+///
+/// Everything the producer set is carried over. The remote assigns its own id, sequence and
+/// checksum, and compresses again as it sees fit, a dead-letter origin stays local.
+fn mirrored_header(
+    header: MessageHeader,
+    topic: TopicCode,
+    credential: Option<MaybeBase64Bytes>,
+) -> EdgeMessageHeader {
+    let mut attributes = Arc::unwrap_or_clone(header.attributes);
+    if let Some(credential) = credential {
+        attributes.insert(MessageHeader::MIRROR_CREDENTIAL.to_owned(), credential);
+    }
+    EdgeMessageHeader {
+        ack_kind: header.ack_kind,
+        target_kind: header.target_kind,
+        durability: header.durability,
+        subjects: header.subjects.to_vec(),
+        topic,
+        expire_at: header.expire_at,
+        partition_key: header.partition_key,
+        dedup_key: header.dedup_key,
+        deliver_at: header.deliver_at,
+        priority: header.priority,
+        attributes,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorConfig {
+    /// edge address of the remote cluster
+    pub remote: SocketAddr,
+    /// topic on the remote cluster, the same code is used if not set
+    pub remote_topic: Option<TopicCode>,
+    /// max messages buffered while the remote is unavailable, the oldest one is dropped on overflow
+    pub buffer_size: u32,
+    /// presented to the remote's edge auth with every forwarded message
+    #[serde(default)]
+    pub credential: Option<MaybeBase64Bytes>,
+}
+
+impl MirrorConfig {
+    pub const DEFAULT_BUFFER_SIZE: u32 = 4096;
+    pub fn new(remote: SocketAddr) -> Self {
+        Self {
+            remote,
+            remote_topic: None,
+            buffer_size: Self::DEFAULT_BUFFER_SIZE,
+            credential: None,
+        }
+    }
+    pub fn with_credential(mut self, credential: impl Into<Bytes>) -> Self {
+        self.credential = Some(MaybeBase64Bytes::new(credential.into()));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MirrorStats {
+    pub buffered: usize,
+    pub forwarded: u64,
+    pub dropped: u64,
+    /// age of the oldest message not yet forwarded
+    pub lag: TimeDelta,
+}
+
+#[derive(Debug)]
+struct MirrorShared {
+    config: MirrorConfig,
+    remote_topic: TopicCode,
+    buffer: Mutex<VecDeque<Timed<Message>>>,
+    notify: Notify,
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl MirrorShared {
+    fn front(&self) -> Option<Message> {
+        self.buffer
+            .lock()
+            .unwrap()
+            .front()
+            .map(|timed| timed.data.clone())
+    }
+    fn pop_front_if(&self, id: MessageId) {
+        let mut buffer = self.buffer.lock().unwrap();
+        // the front may already have been dropped by an overflow
+        if buffer.front().is_some_and(|timed| timed.data.id() == id) {
+            buffer.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TopicMirror {
+    shared: Arc<MirrorShared>,
+    _guard: Arc<DropGuard>,
+}
+
+impl TopicMirror {
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+    const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+    pub(crate) fn spawn(code: &TopicCode, config: MirrorConfig) -> Self {
+        let ct = CancellationToken::new();
+        let shared = Arc::new(MirrorShared {
+            remote_topic: config.remote_topic.clone().unwrap_or_else(|| code.clone()),
+            buffer: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+            forwarded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            config,
+        });
+        tokio::spawn(
+            Self::forward_loop(shared.clone(), ct.child_token())
+                .instrument(tracing::info_span!("topic mirror", topic = %code)),
+        );
+        Self {
+            shared,
+            _guard: Arc::new(ct.drop_guard()),
+        }
+    }
+    pub(crate) fn push(&self, message: Message) {
+        // the remote cluster gets the original payload, it compresses again as it sees fit
+        let message = match compression::decompress(message) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(%err, "mirrored message can't be decompressed");
+                self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        if buffer.len() >= self.shared.config.buffer_size.max(1) as usize {
+            buffer.pop_front();
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        buffer.push_back(Timed::new(Utc::now(), message));
+        drop(buffer);
+        self.shared.notify.notify_one();
+    }
+    pub fn stats(&self) -> MirrorStats {
+        let buffer = self.shared.buffer.lock().unwrap();
+        let lag = buffer
+            .front()
+            .map(|timed| Utc::now() - timed.time)
+            .unwrap_or(TimeDelta::zero());
+        MirrorStats {
+            buffered: buffer.len(),
+            forwarded: self.shared.forwarded.load(Ordering::Relaxed),
+            dropped: self.shared.dropped.load(Ordering::Relaxed),
+            lag,
+        }
+    }
+    async fn forward_loop(shared: Arc<MirrorShared>, ct: CancellationToken) {
+        let codec_registry = CodecRegistry::new_preloaded();
+        let mut backoff = Duration::from_millis(100);
+        loop {
+            let connection = tokio::select! {
+                _ = ct.cancelled() => return,
+                connection = Self::connect(&shared) => connection,
+            };
+            let mut connection = match connection {
+                Ok(connection) => {
+                    backoff = Duration::from_millis(100);
+                    connection
+                }
+                Err(e) => {
+                    tracing::warn!(?e, remote = %shared.config.remote, "connect to remote cluster failed");
+                    tokio::select! {
+                        _ = ct.cancelled() => return,
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    backoff = (backoff * 2).min(Self::MAX_RECONNECT_BACKOFF);
+                    continue;
+                }
+            };
+            let mut seq_id = 0u32;
+            loop {
+                let message = loop {
+                    if let Some(message) = shared.front() {
+                        break message;
+                    }
+                    tokio::select! {
+                        _ = ct.cancelled() => return,
+                        _ = shared.notify.notified() => {}
+                    }
+                };
+                let message_id = message.id();
+                seq_id = seq_id.wrapping_add(1);
+                let forward = tokio::select! {
+                    _ = ct.cancelled() => return,
+                    forward = tokio::time::timeout(
+                        Self::REQUEST_TIMEOUT,
+                        Self::forward(&shared, &codec_registry, &mut connection, seq_id, message),
+                    ) => forward,
+                };
+                match forward {
+                    Ok(Ok(())) => {
+                        shared.pop_front_if(message_id);
+                        shared.forwarded.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok(Err(e)) => {
+                        // keep the message buffered, and resume after reconnecting
+                        tracing::warn!(?e, "mirror connection broken");
+                        break;
+                    }
+                    Err(_) => {
+                        tracing::warn!("remote cluster didn't respond in time, reconnecting");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    async fn connect(shared: &MirrorShared) -> std::io::Result<TokioTcp> {
+        let stream = tokio::net::TcpStream::connect(shared.config.remote).await?;
+        Ok(TokioTcp::new(stream))
+    }
+    async fn forward(
+        shared: &MirrorShared,
+        codec_registry: &CodecRegistry,
+        connection: &mut TokioTcp,
+        seq_id: u32,
+        message: Message,
+    ) -> std::io::Result<()> {
+        let header = message.header;
+        let request = EdgePayload::Request(EdgeRequest {
+            seq_id,
+            request: EdgeRequestEnum::SendMessage(EdgeMessage {
+                header: mirrored_header(
+                    header,
+                    shared.remote_topic.clone(),
+                    shared.config.credential.clone(),
+                ),
+                payload: message.payload,
+            }),
+        });
+        let payload = codec_registry
+            .encode(CodecKind::JSON, &request)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        connection
+            .send(EdgePacket::new(CodecKind::JSON, payload))
+            .await
+            .map_err(|e| std::io::Error::other(format!("{e:?}")))?;
+        while let Some(packet) = connection.next().await {
+            let packet = packet.map_err(|e| std::io::Error::other(format!("{e:?}")))?;
+            let Ok(EdgePayload::Response(response)) =
+                codec_registry.decode(packet.codec(), &packet.payload)
+            else {
+                continue;
+            };
+            if response.seq_id != seq_id {
+                continue;
+            }
+            // best-effort: a message rejected by the remote is not retried
+            if let Err(e) = response.result.into_std() {
+                tracing::warn!(?e, "remote cluster rejected mirrored message");
+            }
+            return Ok(());
+        }
+        Err(std::io::ErrorKind::UnexpectedEof.into())
+    }
+}
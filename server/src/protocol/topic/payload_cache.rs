@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use crate::protocol::message::MessageId;
+
+#[derive(Debug)]
+struct CacheEntry {
+    id: MessageId,
+    payload: Bytes,
+    /// the entry used just before this one
+    prev: Option<usize>,
+    /// the entry used just after this one
+    next: Option<usize>,
+}
+
+/// Payloads of a lazy topic kept in memory, least recently used are evicted first.
+///
+/// Entries form a doubly linked list by slot index, so every operation is O(1).
+#[derive(Debug)]
+pub(crate) struct PayloadCache {
+    capacity: usize,
+    slots: HashMap<MessageId, usize>,
+    entries: Vec<CacheEntry>,
+    /// least recently used
+    head: Option<usize>,
+    /// most recently used
+    tail: Option<usize>,
+}
+
+impl PayloadCache {
+    pub(crate) const DEFAULT_CAPACITY: usize = 64;
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            slots: HashMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity),
+            head: None,
+            tail: None,
+        }
+    }
+    fn unlink(&mut self, slot: usize) {
+        let CacheEntry { prev, next, .. } = self.entries[slot];
+        match prev {
+            Some(prev) => self.entries[prev].next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => self.entries[next].prev = prev,
+            None => self.tail = prev,
+        }
+    }
+    fn link_back(&mut self, slot: usize) {
+        self.entries[slot].prev = self.tail;
+        self.entries[slot].next = None;
+        match self.tail {
+            Some(tail) => self.entries[tail].next = Some(slot),
+            None => self.head = Some(slot),
+        }
+        self.tail = Some(slot);
+    }
+    fn touch(&mut self, slot: usize) {
+        if self.tail != Some(slot) {
+            self.unlink(slot);
+            self.link_back(slot);
+        }
+    }
+    pub(crate) fn get(&mut self, id: &MessageId) -> Option<Bytes> {
+        let slot = *self.slots.get(id)?;
+        self.touch(slot);
+        Some(self.entries[slot].payload.clone())
+    }
+    pub(crate) fn put(&mut self, id: MessageId, payload: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(&slot) = self.slots.get(&id) {
+            self.entries[slot].payload = payload;
+            self.touch(slot);
+            return;
+        }
+        let entry = CacheEntry {
+            id,
+            payload,
+            prev: None,
+            next: None,
+        };
+        let slot = if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            self.entries.len() - 1
+        } else {
+            // full, the least recently used slot is reused
+            let Some(slot) = self.head else {
+                return;
+            };
+            self.unlink(slot);
+            self.slots.remove(&self.entries[slot].id);
+            self.entries[slot] = entry;
+            slot
+        };
+        self.slots.insert(id, slot);
+        self.link_back(slot);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let ids = [
+            MessageId::new_snowflake(),
+            MessageId::new_snowflake(),
+            MessageId::new_snowflake(),
+        ];
+        let mut cache = PayloadCache::new(2);
+        cache.put(ids[0], Bytes::from_static(b"a"));
+        cache.put(ids[1], Bytes::from_static(b"b"));
+        // touching the first one makes the second the least recently used
+        assert_eq!(cache.get(&ids[0]), Some(Bytes::from_static(b"a")));
+        cache.put(ids[2], Bytes::from_static(b"c"));
+        assert_eq!(cache.get(&ids[1]), None);
+        assert_eq!(cache.get(&ids[0]), Some(Bytes::from_static(b"a")));
+        assert_eq!(cache.get(&ids[2]), Some(Bytes::from_static(b"c")));
+    }
+
+    #[test]
+    fn test_put_refreshes_existing() {
+        let ids = [
+            MessageId::new_snowflake(),
+            MessageId::new_snowflake(),
+            MessageId::new_snowflake(),
+        ];
+        let mut cache = PayloadCache::new(2);
+        cache.put(ids[0], Bytes::from_static(b"a"));
+        cache.put(ids[1], Bytes::from_static(b"b"));
+        cache.put(ids[0], Bytes::from_static(b"A"));
+        cache.put(ids[2], Bytes::from_static(b"c"));
+        assert_eq!(cache.get(&ids[1]), None);
+        assert_eq!(cache.get(&ids[0]), Some(Bytes::from_static(b"A")));
+        assert_eq!(cache.slots.len(), 2);
+        assert_eq!(cache.entries.len(), 2);
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Instant;
+
+use crate::protocol::node::raft::state_machine::topic::config::TopicRateLimit;
+
+/// Token bucket state of [`TopicRateLimit`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimiter {
+    /// tokens left and when they were counted, `None` until the first message
+    bucket: Option<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    /// take a token, false if the bucket is empty
+    pub(crate) fn try_acquire(&mut self, limit: &TopicRateLimit, now: Instant) -> bool {
+        let burst = limit.burst.get() as f64;
+        let tokens = match self.bucket {
+            Some((tokens, counted_at)) => {
+                let elapsed = now.saturating_duration_since(counted_at).as_secs_f64();
+                (tokens + elapsed * limit.per_second.get() as f64).min(burst)
+            }
+            None => burst,
+        };
+        let acquired = tokens >= 1.0;
+        let tokens = if acquired { tokens - 1.0 } else { tokens };
+        self.bucket = Some((tokens, now));
+        acquired
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_refill() {
+        let limit = TopicRateLimit::new(10, 2);
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.try_acquire(&limit, start));
+        assert!(limiter.try_acquire(&limit, start));
+        assert!(!limiter.try_acquire(&limit, start));
+        // one token every 100ms
+        assert!(limiter.try_acquire(&limit, start + Duration::from_millis(100)));
+        assert!(!limiter.try_acquire(&limit, start + Duration::from_millis(150)));
+        // never more than `burst`
+        let later = start + Duration::from_secs(10);
+        assert!(limiter.try_acquire(&limit, later));
+        assert!(limiter.try_acquire(&limit, later));
+        assert!(!limiter.try_acquire(&limit, later));
+    }
+}
@@ -0,0 +1,73 @@
+//! Payload validation at the broker, see [`PayloadValidator`].
+//!
+//! A topic's validator is node-local, it's never replicated: the node a message is published on
+//! checks the payload before proposing it, and rejects it with `ValidationFailed` if it's
+//! malformed. Forwarded messages were checked by the node they were published on.
+use std::{borrow::Cow, sync::Arc};
+
+pub trait PayloadValidator: Send + Sync + 'static {
+    /// `payload` is decoded, as the publisher sent it. The error is the reason it's rejected.
+    fn validate(&self, payload: &[u8]) -> Result<(), String>;
+}
+
+impl<F> PayloadValidator for F
+where
+    F: Fn(&[u8]) -> Result<(), String> + Send + Sync + 'static,
+{
+    fn validate(&self, payload: &[u8]) -> Result<(), String> {
+        (self)(payload)
+    }
+}
+
+#[derive(Clone)]
+pub struct PayloadValidatorService {
+    inner: Arc<dyn PayloadValidator>,
+    source: Cow<'static, str>,
+}
+
+impl std::fmt::Debug for PayloadValidatorService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PayloadValidatorService")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl PayloadValidatorService {
+    pub fn new<T>(inner: T) -> Self
+    where
+        T: PayloadValidator,
+    {
+        Self {
+            inner: Arc::new(inner),
+            source: std::any::type_name::<T>().into(),
+        }
+    }
+    pub fn validate(&self, payload: &[u8]) -> Result<(), String> {
+        self.inner.validate(payload)
+    }
+}
+
+/// Accept JSON payloads conforming to a JSON schema.
+#[cfg(feature = "json-schema")]
+pub struct JsonSchemaValidator {
+    schema: jsonschema::Validator,
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchemaValidator {
+    pub fn new(schema: &serde_json::Value) -> Result<Self, crate::Error> {
+        let schema = jsonschema::validator_for(schema)
+            .map_err(crate::Error::contextual_custom("compile json schema"))?;
+        Ok(Self { schema })
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl PayloadValidator for JsonSchemaValidator {
+    fn validate(&self, payload: &[u8]) -> Result<(), String> {
+        let instance = serde_json::from_slice::<serde_json::Value>(payload)
+            .map_err(|e| format!("payload is not json: {e}"))?;
+        self.schema.validate(&instance).map_err(|e| e.to_string())
+    }
+}
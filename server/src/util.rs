@@ -10,6 +10,13 @@ pub fn timestamp_sec() -> u64 {
         .as_secs()
 }
 
+pub fn timestamp_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("time never goes backward")
+        .as_millis() as u64
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Timed<T> {
     pub time: DateTime<Utc>,
@@ -30,18 +37,17 @@ impl<T: PartialEq> PartialEq for Timed<T> {
 
 impl<T: Eq> Eq for Timed<T> {}
 
-impl<T: PartialEq> PartialOrd for Timed<T> {
+impl<T: Ord> PartialOrd for Timed<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.data != other.data {
-            None
-        } else {
-            self.time.partial_cmp(&other.time)
-        }
+        Some(self.cmp(other))
     }
 }
-impl<T: Eq> Ord for Timed<T> {
+/// Ordered by time, ties are broken by data, so equal timestamps never collide in ordered sets.
+impl<T: Ord> Ord for Timed<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.time.cmp(&other.time)
+        self.time
+            .cmp(&other.time)
+            .then_with(|| self.data.cmp(&other.data))
     }
 }
 
@@ -52,4 +58,33 @@ pub fn hash64<T: Hash>(value: &T) -> u64 {
     Hasher::finish(&hasher)
 }
 
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3) of `bytes`.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
 pub use asteroid_mq_model::{executor_digest, hex, MaybeBase64Bytes};
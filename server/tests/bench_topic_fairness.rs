@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+fn message(subject: &'static str) -> Message {
+    Message::new(
+        MessageHeader::builder([Subject::new(subject)])
+            .mode_online()
+            .build(),
+        "payload",
+    )
+}
+
+/// Ack latency of a quiet topic, while another topic on the same node is flooded.
+///
+/// Run with `cargo test --test bench_topic_fairness -- --ignored --nocapture`.
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "benchmark"]
+async fn bench_topic_fairness() -> Result<(), Box<dyn std::error::Error>> {
+    const HOT: TopicCode = TopicCode::const_new("hot");
+    const QUIET: TopicCode = TopicCode::const_new("quiet");
+    const HOT_PRODUCERS: usize = 16;
+    const SAMPLES: usize = 20;
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19740".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let hot = node.create_new_topic(HOT).await?;
+    let quiet = node.create_new_topic(QUIET).await?;
+    let hot_endpoints = futures_util::future::try_join_all(
+        (0..8).map(|_| hot.create_endpoint([Interest::new("hot/*")])),
+    )
+    .await?;
+    let _quiet_endpoint = quiet.create_endpoint([Interest::new("quiet/*")]).await?;
+    for endpoint in hot_endpoints {
+        tokio::spawn(async move { while endpoint.next_message().await.is_some() {} });
+    }
+
+    let idle_latency = {
+        let start = Instant::now();
+        assert!(quiet
+            .send_message(message("quiet/ping"))
+            .await?
+            .await
+            .is_ok());
+        start.elapsed()
+    };
+
+    let ct = tokio_util::sync::CancellationToken::new();
+    for _ in 0..HOT_PRODUCERS {
+        let hot = hot.clone();
+        let ct = ct.clone();
+        tokio::spawn(async move {
+            while !ct.is_cancelled() {
+                let _ = hot.send_message(message("hot/flood")).await;
+            }
+        });
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut latencies = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        assert!(quiet
+            .send_message(message("quiet/ping"))
+            .await?
+            .await
+            .is_ok());
+        latencies.push(start.elapsed());
+    }
+    ct.cancel();
+    latencies.sort();
+    let p50 = latencies[SAMPLES / 2];
+    let max = latencies[SAMPLES - 1];
+    println!("quiet topic ack latency: idle {idle_latency:?}, under load p50 {p50:?}, max {max:?}");
+    assert!(max < Duration::from_secs(1), "quiet topic was starved");
+    Ok(())
+}
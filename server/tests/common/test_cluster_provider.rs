@@ -2,35 +2,39 @@
 use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 
 use asteroid_mq::{prelude::NodeId, protocol::node::raft::cluster::ClusterProvider};
-use tokio::sync::{Mutex, Notify};
+use tokio::sync::watch;
+// a watch keeps the update for listeners that are busy when it's published
 #[derive(Debug, Clone)]
 pub struct TestClusterProvider {
-    nodes: Arc<Mutex<BTreeMap<NodeId, SocketAddr>>>,
-    notify: Arc<Notify>,
+    nodes: Arc<watch::Sender<BTreeMap<NodeId, SocketAddr>>>,
+    rx: watch::Receiver<BTreeMap<NodeId, SocketAddr>>,
 }
 
 impl TestClusterProvider {
     pub fn new(nodes: BTreeMap<NodeId, SocketAddr>) -> Self {
+        let (nodes, rx) = watch::channel(nodes);
         Self {
-            nodes: Arc::new(Mutex::new(nodes)),
-            notify: Arc::new(Notify::new()),
+            nodes: Arc::new(nodes),
+            rx,
         }
     }
 
     pub async fn update(&self, nodes: BTreeMap<NodeId, SocketAddr>) {
-        *self.nodes.lock().await = nodes;
-        self.notify.notify_waiters();
+        self.nodes.send_replace(nodes);
     }
 }
 
 impl ClusterProvider for TestClusterProvider {
     async fn next_update(&mut self) -> asteroid_mq::Result<BTreeMap<NodeId, SocketAddr>> {
-        self.notify.notified().await;
-        let nodes = self.nodes.lock().await.clone();
+        self.rx
+            .changed()
+            .await
+            .map_err(asteroid_mq::Error::contextual_custom("cluster update"))?;
+        let nodes = self.rx.borrow_and_update().clone();
         Ok(nodes)
     }
     async fn pristine_nodes(&mut self) -> asteroid_mq::Result<BTreeMap<NodeId, SocketAddr>> {
-        let nodes = self.nodes.lock().await.clone();
+        let nodes = self.rx.borrow_and_update().clone();
         Ok(nodes)
     }
 }
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_ack_batching() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("ack-batching-test");
+    const COUNT: usize = 50;
+    const WINDOW: Duration = Duration::from_millis(500);
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19823".parse()?,
+        ack_flush_interval: Some(WINDOW),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(TopicConfig::from(CODE)).await?;
+    let endpoint = topic.create_endpoint([Interest::new("batch/*")]).await?;
+    let mut handles = Vec::new();
+    for index in 0..COUNT {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("batch/ack")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("message {index}"),
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let mut received = Vec::new();
+    while received.len() < COUNT {
+        let message = endpoint
+            .recv_timeout(Duration::from_secs(1))
+            .await
+            .ok_or("message not received")?;
+        received.push(message);
+    }
+    // let the deliveries' own state updates settle
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let applied = || async {
+        node.raft()
+            .await
+            .metrics()
+            .borrow()
+            .last_applied
+            .map(|log_id| log_id.index)
+            .unwrap_or_default()
+    };
+
+    // nothing resolves, the acks wait for the window and go in together
+    let before = applied().await;
+    futures_util::future::try_join_all(
+        received
+            .iter()
+            .map(|message| endpoint.ack_received(&message.header)),
+    )
+    .await?;
+    let received_entries = applied().await - before;
+    assert!(received_entries < 5, "{received_entries} entries");
+
+    // every ack resolves its message, they are flushed without waiting for the window
+    let before = applied().await;
+    let start = Instant::now();
+    futures_util::future::try_join_all(
+        received
+            .iter()
+            .map(|message| endpoint.ack_processed(&message.header)),
+    )
+    .await?;
+    assert!(start.elapsed() < WINDOW);
+    let processed_entries = applied().await - before;
+    assert!(processed_entries < 10, "{processed_entries} entries");
+    for handle in handles {
+        assert!(tokio::time::timeout(Duration::from_millis(100), handle)
+            .await?
+            .is_ok());
+    }
+    Ok(())
+}
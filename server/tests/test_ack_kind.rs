@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageDurableConfig, MessageHeader,
+        MessageStatusKind, Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_ack_kind() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("ack-kind-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19801".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+
+    // fire and forget, resolved while the message is still held for lack of endpoints
+    let held = Message::new(
+        MessageHeader::builder([Subject::new("held/job")])
+            .ack_kind(MessageAckExpectKind::None)
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::hours(1),
+                max_receiver: Some(1),
+            })
+            .build(),
+        "held",
+    );
+    let handle = topic.send_message(held).await?;
+    let success = tokio::time::timeout(Duration::from_millis(200), handle)
+        .await?
+        .expect("enqueued");
+    assert!(success.status.is_empty());
+    assert_eq!(
+        node.topic_stats()
+            .await
+            .get(&CODE)
+            .map(|stats| stats.held_messages),
+        Some(1)
+    );
+
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let new_message = |ack_kind: MessageAckExpectKind| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/job")])
+                .mode_online()
+                .ack_kind(ack_kind)
+                .build(),
+            "job",
+        )
+    };
+
+    // resolved once the endpoint took it off the wire
+    let mut handle = topic
+        .send_message(new_message(MessageAckExpectKind::Received))
+        .await?;
+    let message = endpoint.next_message().await.expect("endpoint is alive");
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), &mut handle)
+            .await
+            .is_err()
+    );
+    endpoint.ack_received(&message.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("received");
+    assert_eq!(
+        success.status.get(&endpoint.address()),
+        Some(&MessageStatusKind::Received)
+    );
+
+    // received is not enough, only the consumer's processed ack resolves it
+    let mut handle = topic
+        .send_message(new_message(MessageAckExpectKind::Processed))
+        .await?;
+    let message = endpoint.next_message().await.expect("endpoint is alive");
+    endpoint.ack_received(&message.header).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), &mut handle)
+            .await
+            .is_err()
+    );
+    endpoint.ack_processed(&message.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("processed");
+    assert_eq!(
+        success.status.get(&endpoint.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    Ok(())
+}
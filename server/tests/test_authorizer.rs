@@ -0,0 +1,56 @@
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::{
+        authorizer::{AuthorizerService, StaticTokenAuthorizer},
+        raft::cluster::StaticClusterProvider,
+    },
+};
+
+#[tokio::test]
+async fn test_static_token() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("authorizer-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19783".parse()?,
+        authorizer: Some(AuthorizerService::new(StaticTokenAuthorizer::new([
+            "right-token",
+        ]))),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let is_unauthorized = |error: asteroid_mq::Error| matches!(error.kind, ErrorKind::Unauthorized);
+
+    let wrong = node.with_credential("wrong-token");
+    assert!(wrong
+        .create_new_topic(CODE)
+        .await
+        .is_err_and(is_unauthorized));
+    assert!(node
+        .create_new_topic(CODE)
+        .await
+        .is_err_and(is_unauthorized));
+
+    let right = node.with_credential("right-token");
+    let topic = right.create_new_topic(CODE).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = || {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/authorized")]).build(),
+            "hello",
+        )
+    };
+    assert!(topic.send_message(message()).await?.await.is_ok());
+
+    let topic = wrong.get_topic(&CODE).expect("topic is loaded");
+    assert!(topic
+        .create_endpoint([Interest::new("event/*")])
+        .await
+        .is_err_and(is_unauthorized));
+    assert!(topic
+        .send_message(message())
+        .await
+        .is_err_and(is_unauthorized));
+    Ok(())
+}
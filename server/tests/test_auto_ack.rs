@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind,
+        Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_auto_ack() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("auto-ack-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19802".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic
+        .create_endpoint_with(
+            [Interest::new("event/*")],
+            EndpointOptions::new().auto_ack(),
+        )
+        .await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/auto")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "auto",
+    );
+    let handle = topic.send_message(message).await?;
+    // nobody acks it by hand
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("acked by the endpoint");
+    assert_eq!(
+        success.status.get(&endpoint.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    let received = endpoint.try_recv().expect("message is in the mailbox");
+    assert_eq!(&received.payload.0[..], b"auto");
+    Ok(())
+}
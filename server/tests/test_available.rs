@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+fn available_message(payload: &'static str) -> Message {
+    Message::new(
+        MessageHeader::builder([Subject::new("event/available")])
+            .mode_available()
+            .build(),
+        payload,
+    )
+}
+
+#[tokio::test]
+async fn test_available_without_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("available-empty-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19753".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    // interested in something else
+    let _endpoint = topic.create_endpoint([Interest::new("other/*")]).await?;
+    let result = topic.send_message(available_message("nobody")).await?.await;
+    let error = result.expect_err("no endpoint is online");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::NoAvailableTarget)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_available_mixed_endpoints() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("available-mixed-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19754".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.available_freshness = Some(Duration::from_millis(300));
+    let topic = node.create_new_topic(config).await?;
+    let stale = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let offline = topic.create_endpoint([Interest::new("event/*")]).await?;
+    drop(offline);
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    let live_a = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let live_b = topic.create_endpoint([Interest::new("event/*")]).await?;
+
+    let success = topic
+        .send_message(available_message("hello"))
+        .await?
+        .await
+        .expect("live endpoints are online");
+    assert_eq!(success.status.len(), 2);
+    assert!(success.status.contains_key(&live_a.address()));
+    assert!(success.status.contains_key(&live_b.address()));
+    assert!(!success.status.contains_key(&stale.address()));
+    for endpoint in [&live_a, &live_b] {
+        let received = endpoint.next_message().await.expect("endpoint is alive");
+        assert_eq!(&received.payload.0[..], b"hello");
+    }
+    Ok(())
+}
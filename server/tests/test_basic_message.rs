@@ -1,7 +1,6 @@
 use std::{
     collections::{BTreeMap, HashMap},
     net::SocketAddr,
-    num::NonZeroU32,
     str::FromStr,
     time::Duration,
 };
@@ -13,7 +12,7 @@ use asteroid_mq::{
     },
     protocol::node::raft::{
         cluster::StaticClusterProvider,
-        state_machine::topic::config::{TopicConfig, TopicOverflowConfig, TopicOverflowPolicy},
+        state_machine::topic::config::{TopicConfig, TopicOverflowConfig},
     },
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
@@ -86,12 +85,8 @@ async fn test_nodes() {
     const CODE: TopicCode = TopicCode::const_new("events");
     fn topic_config() -> TopicConfig {
         TopicConfig {
-            code: CODE,
-            blocking: false,
-            overflow_config: Some(TopicOverflowConfig {
-                policy: TopicOverflowPolicy::RejectNew,
-                size: NonZeroU32::new(500).unwrap(),
-            }),
+            overflow_config: Some(TopicOverflowConfig::new_reject_new(500)),
+            ..TopicConfig::from(CODE)
         }
     }
     let node_server = nodes.get(&node_id_1).unwrap().clone();
@@ -0,0 +1,137 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+mod common;
+
+fn batch(size: usize) -> Vec<Message> {
+    (0..size)
+        .map(|index| {
+            Message::new(
+                MessageHeader::builder([Subject::new("batch/item")])
+                    .mode_online()
+                    .ack_kind(MessageAckExpectKind::Sent)
+                    .build(),
+                format!("item {index}"),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_batch_publish() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("batch-publish-test");
+    const BATCH_SIZE: usize = 50;
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19772".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("batch/*")]).await?;
+    let messages = batch(BATCH_SIZE);
+    let ids = messages.iter().map(Message::id).collect::<Vec<_>>();
+    let handles = topic.send_messages(messages).await?;
+    assert_eq!(handles.len(), BATCH_SIZE);
+    for (handle, id) in handles.into_iter().zip(&ids) {
+        assert_eq!(handle.message_id(), *id);
+        assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+            .await?
+            .is_ok());
+    }
+    let mut received = Vec::new();
+    while received.len() < BATCH_SIZE {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        received.push(message.id());
+    }
+    received.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(received, expected);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_follower_batch_publish() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("follower-batch-publish-test");
+    const BATCH_SIZE: usize = 50;
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "follower-batch-publish".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19849 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let leader = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    let follower = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    leader.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    follower.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(follower.ensure_leader().await.is_err());
+
+    let leader_topic = leader.create_new_topic(CODE).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let endpoint = leader_topic
+        .create_endpoint([Interest::new("batch/*")])
+        .await?;
+    let follower_topic = follower.get_topic(&CODE).expect("topic is replicated");
+    let messages = batch(BATCH_SIZE);
+    let ids = messages.iter().map(Message::id).collect::<Vec<_>>();
+    let handles = follower_topic.send_messages(messages).await?;
+    assert_eq!(handles.len(), BATCH_SIZE);
+    for (handle, id) in handles.into_iter().zip(&ids) {
+        assert_eq!(handle.message_id(), *id);
+        assert!(tokio::time::timeout(Duration::from_secs(2), handle)
+            .await?
+            .is_ok());
+    }
+    let mut received = Vec::new();
+    while received.len() < BATCH_SIZE {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        received.push(message.id());
+    }
+    received.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(received, expected);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::{num::NonZeroU32, time::Duration};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig, TopicOverflowConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+fn message(payload: &'static str) -> Message {
+    Message::new(
+        MessageHeader::builder([Subject::new("event/ingest")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        payload,
+    )
+}
+
+#[tokio::test]
+async fn test_block_until_space() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("block-overflow-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19757".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.overflow_config = Some(TopicOverflowConfig::new_block(1, Duration::from_secs(1)));
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+
+    let first = topic.send_message(message("first")).await?;
+    let first_received = endpoint.next_message().await.expect("endpoint is alive");
+
+    // the queue is full, the second producer waits
+    let second = tokio::spawn({
+        let topic = topic.clone();
+        async move { topic.send_message(message("second")).await }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!second.is_finished());
+
+    endpoint.ack_processed(&first_received.header).await?;
+    assert!(first.await.is_ok());
+    let _second = second.await??;
+    let second_received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(&second_received.payload.0[..], b"second");
+
+    // nobody acks the second one, so the third gives up after the timeout
+    let third = topic.send_message(message("third")).await?;
+    let error = third.await.expect_err("queue stays full");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::Overflow)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parked_messages_capped() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("parked-cap-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19847".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.blocking = true;
+    let mut overflow_config = TopicOverflowConfig::new_reject_new(1);
+    overflow_config.max_parked = NonZeroU32::new(1);
+    config.overflow_config = Some(overflow_config);
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+
+    // nobody acks, the first one fills the queue and the second one is parked
+    let first = topic.send_message(message("first")).await?;
+    let second = topic.send_message(message("second")).await?;
+    let third = topic.send_message(message("third")).await?;
+    let error = third.await.expect_err("no room to park it");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::Overflow)
+    ));
+
+    let first_received = endpoint.next_message().await.expect("endpoint is alive");
+    endpoint.ack_processed(&first_received.header).await?;
+    assert!(first.await.is_ok());
+    let second_received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(&second_received.payload.0[..], b"second");
+    endpoint.ack_processed(&second_received.header).await?;
+    assert!(second.await.is_ok());
+    Ok(())
+}
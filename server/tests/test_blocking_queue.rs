@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, LocalEndpoint, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig,
+        Subject, Topic, TopicCode, TopicConfig, TopicOverflowConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+async fn full_topic(node: &Node, code: TopicCode, blocking: bool) -> (Topic, LocalEndpoint) {
+    let mut config = TopicConfig::from(code);
+    config.blocking = blocking;
+    config.overflow_config = Some(TopicOverflowConfig::new_reject_new(1));
+    let topic = node.create_new_topic(config).await.unwrap();
+    let endpoint = topic
+        .create_endpoint([Interest::new("event/*")])
+        .await
+        .unwrap();
+    (topic, endpoint)
+}
+
+fn new_message(payload: &'static str) -> Message {
+    Message::new(
+        MessageHeader::builder([Subject::new("event/test")])
+            .ack_kind(MessageAckExpectKind::Processed)
+            .mode_online()
+            .build(),
+        payload,
+    )
+}
+
+#[tokio::test]
+async fn test_blocking_queue_at_capacity() -> asteroid_mq::Result<()> {
+    let node = Node::new(NodeConfig::default());
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+
+    // non-blocking queue rejects the overflowing message
+    let (topic, endpoint) = full_topic(&node, TopicCode::const_new("non-blocking"), false).await;
+    let _first = topic.send_message(new_message("first")).await?;
+    let first = endpoint.next_message().await.unwrap();
+    let second = topic.send_message(new_message("second")).await?;
+    let result = tokio::time::timeout(Duration::from_secs(1), second)
+        .await
+        .expect("overflow resolves immediately");
+    assert!(matches!(
+        result,
+        Err(e) if matches!(e.exception, Some(WaitAckErrorException::Overflow))
+    ));
+    endpoint.ack_processed(&first.header).await?;
+
+    // blocking queue parks the overflowing message until there is space
+    let (topic, endpoint) = full_topic(&node, TopicCode::const_new("blocking"), true).await;
+    let first_handle = topic.send_message(new_message("first")).await?;
+    let first = endpoint.next_message().await.unwrap();
+    let mut second_handle = topic.send_message(new_message("second")).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), &mut second_handle)
+            .await
+            .is_err(),
+        "publisher should wait for space"
+    );
+    endpoint.ack_processed(&first.header).await?;
+    assert!(first_handle.await.is_ok());
+    let second = endpoint.next_message().await.unwrap();
+    assert_eq!(&second.payload.0[..], b"second");
+    endpoint.ack_processed(&second.header).await?;
+    assert!(second_handle.await.is_ok());
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, Interest, Message, MessageHeader, MessageStatusKind, Node, NodeConfig,
+        NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_bounded_mailbox() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("bounded-mailbox-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19788".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    // never drained
+    let endpoint = topic
+        .create_endpoint_with(
+            [Interest::new("event/*")],
+            EndpointOptions::new().mailbox_capacity(1),
+        )
+        .await?;
+    let new_message = |payload: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/bounded")])
+                .mode_online()
+                .build(),
+            payload,
+        )
+    };
+    let first = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message("first")).await?,
+    )
+    .await?;
+    assert!(first.is_ok());
+
+    let second = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message("second")).await?,
+    )
+    .await?;
+    let Err(error) = second else {
+        panic!("the mailbox is full");
+    };
+    assert_eq!(
+        error.status.get(&endpoint.address()),
+        Some(&MessageStatusKind::Unreachable)
+    );
+
+    // only the first message was queued
+    let queued = endpoint
+        .try_recv()
+        .expect("first message is in the mailbox");
+    assert_eq!(&queued.payload.0[..], b"first");
+    assert!(endpoint.try_recv().is_none());
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, GroupId, Interest, Message, MessageHeader, Node, NodeConfig, NodeId,
+        Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_consumer_group() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("consumer-group-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19761".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let mut endpoints = Vec::new();
+    for group in ["billing", "billing", "audit", "audit"] {
+        endpoints.push(
+            topic
+                .create_endpoint_with(
+                    [Interest::new("order/*")],
+                    EndpointOptions::new().group(GroupId::from(group)),
+                )
+                .await?,
+        );
+    }
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("order/created")])
+            .mode_online()
+            .build(),
+        "order",
+    );
+    let success = topic
+        .send_message(message)
+        .await?
+        .await
+        .expect("both groups are online");
+    // one ack per group
+    assert_eq!(success.status.len(), 2);
+
+    let mut deliveries = Vec::new();
+    for ep in &endpoints {
+        if let Ok(Some(_)) =
+            tokio::time::timeout(Duration::from_millis(200), ep.next_message()).await
+        {
+            deliveries.push(ep.address());
+        }
+    }
+    assert_eq!(deliveries.len(), 2);
+    let billing = &endpoints[..2];
+    assert_eq!(
+        billing
+            .iter()
+            .filter(|ep| deliveries.contains(&ep.address()))
+            .count(),
+        1
+    );
+    Ok(())
+}
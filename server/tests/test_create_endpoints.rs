@@ -0,0 +1,69 @@
+use std::{collections::HashSet, time::Duration};
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_create_endpoints() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("create-endpoints-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19814".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.allowed_interests = Some(vec![Interest::new("worker/*")]);
+    let topic = node.create_new_topic(config).await?;
+
+    let endpoints = topic
+        .create_endpoints(
+            (0..5)
+                .map(|index| vec![Interest::new(format!("worker/{index}"))])
+                .collect(),
+        )
+        .await?;
+    assert_eq!(endpoints.len(), 5);
+    let addresses = endpoints
+        .iter()
+        .map(|ep| ep.address())
+        .collect::<HashSet<_>>();
+    assert_eq!(addresses.len(), 5);
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.endpoints), Some(5));
+
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new(format!("worker/{index}"))])
+                .mode_online()
+                .build(),
+            format!("job {index}"),
+        );
+        assert!(topic.send_message(message).await?.await.is_ok());
+        let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        assert_eq!(received.payload.0, format!("job {index}").into_bytes());
+    }
+
+    // one set of interests is not allowed, nothing is created
+    let Err(error) = topic
+        .create_endpoints(vec![
+            vec![Interest::new("worker/5")],
+            vec![Interest::new("admin/*")],
+        ])
+        .await
+    else {
+        panic!("`admin/*` is not allowed");
+    };
+    assert!(matches!(error.kind, ErrorKind::Unauthorized));
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.endpoints), Some(5));
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_dead_letter() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("dead-letter-source");
+    const DEAD_LETTER_CODE: TopicCode = TopicCode::const_new("dead-letter-target");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19756".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let dead_letter_topic = node.create_new_topic(DEAD_LETTER_CODE).await?;
+    let dead_letter_endpoint = dead_letter_topic
+        .create_endpoint([Interest::new("event/*")])
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.dead_letter = Some(DEAD_LETTER_CODE);
+    let topic = node.create_new_topic(config).await?;
+
+    // nobody subscribes to the source topic
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/lost")])
+            .mode_push()
+            .build(),
+        "lost",
+    );
+    let message_id = message.id();
+    let error = topic
+        .send_message(message)
+        .await?
+        .await
+        .expect_err("no endpoint in the source topic");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::NoAvailableTarget)
+    ));
+
+    let received =
+        tokio::time::timeout(Duration::from_secs(2), dead_letter_endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+    assert_eq!(received.id(), message_id);
+    assert_eq!(&received.payload.0[..], b"lost");
+    let dead_letter = received
+        .header
+        .dead_letter
+        .expect("message is tagged as dead letter");
+    assert_eq!(dead_letter.topic, CODE);
+    assert!(matches!(
+        dead_letter.exception,
+        Some(WaitAckErrorException::NoAvailableTarget)
+    ));
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_dedup() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("dedup-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19766".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.dedup_window = 16;
+    let topic = node.create_new_topic(config).await?;
+    // never acks, so the first message stays held
+    let _endpoint = topic.create_endpoint([Interest::new("order/*")]).await?;
+    let new_message = || {
+        Message::new(
+            MessageHeader::builder([Subject::new("order/created")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .dedup_key("order-42")
+                .build(),
+            "order",
+        )
+    };
+    let _first = topic.send_message(new_message()).await?;
+    let retried = topic.send_message(new_message()).await?;
+    let retried = tokio::time::timeout(Duration::from_secs(1), retried).await?;
+    assert!(retried.is_ok());
+
+    let stats = node.topic_stats().await;
+    let stats = stats.get(&CODE).expect("topic is loaded");
+    assert_eq!(stats.held_messages, 1);
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::{
+    prelude::{
+        ClockService, Interest, ManualClock, Message, MessageAckExpectKind, MessageHeader, Node,
+        NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+mod common;
+
+#[tokio::test]
+async fn test_delayed_message() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("delayed-message-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19768".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("retry/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("retry/payment")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Sent)
+            .delay(Duration::from_millis(200))
+            .build(),
+        "retry",
+    );
+    let message_id = message.id();
+    let handle = topic.send_message(message).await?;
+
+    let early = tokio::time::timeout(Duration::from_millis(100), endpoint.next_message()).await;
+    assert!(early.is_err(), "delivered before `deliver_at`");
+    let stats = node.topic_stats().await;
+    let stats = stats.get(&CODE).expect("topic is loaded");
+    assert_eq!(stats.delayed_messages, 1);
+    assert_eq!(stats.held_messages, 0);
+
+    let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.id(), message_id);
+    assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .is_ok());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delayed_message_on_skewed_follower() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("skewed-delay-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "skewed-delay".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19843 + index)
+    }
+    let leader_clock = ManualClock::default();
+    let follower_clock = ManualClock::default();
+    // a day ahead, every delay has passed as far as the follower can tell
+    follower_clock.advance(Duration::from_secs(24 * 60 * 60));
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let leader = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        clock: ClockService::new(leader_clock.clone()),
+        ..Default::default()
+    });
+    let follower = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        clock: ClockService::new(follower_clock),
+        ..Default::default()
+    });
+    leader.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    follower.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(follower.ensure_leader().await.is_err());
+
+    let leader_topic = leader.create_new_topic(CODE).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let follower_topic = follower.get_topic(&CODE).expect("topic is replicated");
+    let endpoint = follower_topic
+        .create_endpoint([Interest::new("retry/*")])
+        .await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("retry/payment")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Sent)
+            .delay(Duration::from_secs(60 * 60))
+            .build(),
+        "retry",
+    );
+    let message_id = message.id();
+    let _handle = leader_topic.send_message(message).await?;
+
+    let early = tokio::time::timeout(Duration::from_millis(500), endpoint.next_message()).await;
+    assert!(early.is_err(), "released by the follower's clock");
+
+    leader_clock.advance(Duration::from_secs(2 * 60 * 60));
+    let received = tokio::time::timeout(Duration::from_secs(2), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.id(), message_id);
+    Ok(())
+}
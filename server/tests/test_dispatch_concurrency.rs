@@ -0,0 +1,50 @@
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+/// Every endpoint gets every message exactly once and in order, while deliveries to different
+/// endpoints run concurrently.
+#[tokio::test]
+async fn test_dispatch_concurrency() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("dispatch-concurrency-test");
+    const ENDPOINTS: usize = 200;
+    const MESSAGES: usize = 3;
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19817".parse()?,
+        dispatch_concurrency: 16,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoints = topic
+        .create_endpoints(vec![vec![Interest::new("fanout/*")]; ENDPOINTS])
+        .await?;
+
+    for index in 0..MESSAGES {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("fanout/event")])
+                .mode_online()
+                .build(),
+            format!("message {index}"),
+        );
+        let result = topic.send_message(message).await?.await;
+        let Ok(success) = result else {
+            panic!("every endpoint is reachable");
+        };
+        assert_eq!(success.status.len(), ENDPOINTS);
+    }
+    for endpoint in &endpoints {
+        for index in 0..MESSAGES {
+            let received = endpoint.try_recv().expect("one copy of every message");
+            assert_eq!(
+                &received.payload.0[..],
+                format!("message {index}").as_bytes()
+            );
+        }
+        assert!(endpoint.try_recv().is_none());
+    }
+    Ok(())
+}
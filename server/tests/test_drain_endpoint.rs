@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_drain_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("drain-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19764".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let ep_a = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let ep_b = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("job/run")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "job",
+    );
+    let handle = topic.send_message(message).await?;
+    // the endpoint that got it is drained without acking
+    let (draining, other) = tokio::select! {
+        Some(_) = ep_a.next_message() => (&ep_a, &ep_b),
+        Some(_) = ep_b.next_message() => (&ep_b, &ep_a),
+    };
+    topic
+        .drain_endpoint(draining.address(), Duration::from_millis(200))
+        .await?;
+    assert!(topic.endpoint_info(&draining.address()).await.is_none());
+
+    let message = tokio::time::timeout(Duration::from_secs(1), other.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&message.payload.0[..], b"job");
+    other.ack_processed(&message.header).await?;
+    let success = handle.await.expect("redelivered to the other endpoint");
+    assert!(success.status.contains_key(&other.address()));
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageDurableConfig, MessageHeader, Node,
+        NodeConfig, NodeId, SeekTarget, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+    TimestampMs,
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_durable_seek_by_sequence() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("durable-seek-test");
+    // no retention, everything is replayed from the default in-memory durable service, which only
+    // keeps messages still held, so the messages take no receiver limit
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19785".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let mut handles = Vec::new();
+    for index in 1..=5 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/seek")])
+                .ack_kind(MessageAckExpectKind::Processed)
+                .mode_durable(MessageDurableConfig {
+                    expire: Utc::now() + TimeDelta::hours(1),
+                    max_receiver: None,
+                })
+                .build(),
+            format!("message {index}"),
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let mut sequences = Vec::new();
+    for _ in 0..5 {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        sequences.push(message.header.sequence.expect("sequence is assigned"));
+    }
+    assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]));
+    // durable commands are flushed in the background
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // from the second message on
+    endpoint.seek(SeekTarget::Sequence(sequences[1])).await?;
+    let mut replayed = Vec::new();
+    for _ in 0..4 {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        replayed.push(String::from_utf8(message.payload.0.to_vec())?);
+    }
+    assert_eq!(
+        replayed,
+        ["message 2", "message 3", "message 4", "message 5"]
+    );
+
+    endpoint.seek(SeekTarget::Latest).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), endpoint.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_durable_seek_by_time() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("durable-seek-time-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19834".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let durable_message = |payload: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/seek")])
+                .mode_durable(MessageDurableConfig {
+                    expire: Utc::now() + TimeDelta::hours(1),
+                    max_receiver: None,
+                })
+                .build(),
+            payload,
+        )
+    };
+    topic.send_message(durable_message("before")).await?;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    // most likely within the same second as the first message
+    let mark = TimestampMs::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    topic.send_message(durable_message("after")).await?;
+    for _ in 0..2 {
+        tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+    }
+    // durable commands are flushed in the background
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    endpoint.seek(SeekTarget::Time(mark)).await?;
+    let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&message.payload.0[..], b"after");
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), endpoint.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
@@ -6,9 +6,11 @@ use std::{
 
 use asteroid_mq::{
     prelude::{
-        Durable, DurableMessage, DurableService, Interest, Message, MessageHeader, MessageId, Node,
-        NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+        Durable, DurableMessage, DurableService, EndpointOptions, Interest, MaybeBase64Bytes,
+        Message, MessageHeader, MessageId, Node, NodeConfig, NodeId, ReplayPolicy, Subject,
+        TopicChecksum, TopicCode, TopicConfig,
     },
+    protocol::node::raft::state_machine::topic::wait_ack::WaitAckErrorException,
     DEFAULT_TCP_SOCKET_ADDR,
 };
 use tokio::sync::RwLock;
@@ -100,6 +102,24 @@ impl Durable for MemoryDurable {
             Ok(Vec::new())
         }
     }
+    async fn retrieve_since(
+        &self,
+        topic: TopicCode,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<DurableMessage>, asteroid_mq::prelude::DurableError> {
+        let messages = self.messages.read().await;
+        let archived = self.archived.read().await;
+        let mut found = messages
+            .get(&topic)
+            .into_iter()
+            .chain(archived.get(&topic))
+            .flat_map(|queue| queue.values())
+            .filter(|message| message.time >= since)
+            .cloned()
+            .collect::<Vec<_>>();
+        found.sort_by_key(|message| message.time);
+        Ok(found)
+    }
     async fn retrieve(
         &self,
         topic: TopicCode,
@@ -152,22 +172,18 @@ async fn test_durable_service() -> Result<(), Box<dyn std::error::Error>> {
     durable.topics.write().await.insert(
         PRELOAD_TOPIC_CODE,
         TopicConfig {
-            code: PRELOAD_TOPIC_CODE,
-            blocking: false,
-            overflow_config: Some(asteroid_mq::prelude::TopicOverflowConfig {
-                policy: asteroid_mq::prelude::TopicOverflowPolicy::RejectNew,
-                size: std::num::NonZeroU32::new(500).unwrap(),
-            }),
+            overflow_config: Some(asteroid_mq::prelude::TopicOverflowConfig::new_reject_new(
+                500,
+            )),
+            ..TopicConfig::from(PRELOAD_TOPIC_CODE)
         },
     );
     let service = DurableService::new(durable);
     let topic_config = TopicConfig {
-        code: "test".into(),
-        blocking: false,
-        overflow_config: Some(asteroid_mq::prelude::TopicOverflowConfig {
-            policy: asteroid_mq::prelude::TopicOverflowPolicy::RejectNew,
-            size: std::num::NonZeroU32::new(500).unwrap(),
-        }),
+        overflow_config: Some(asteroid_mq::prelude::TopicOverflowConfig::new_reject_new(
+            500,
+        )),
+        ..TopicConfig::from(TopicCode::const_new("test"))
     };
     let cluster = common::TestClusterProvider::new(map!(
         NodeId::new_indexed(1) => DEFAULT_TCP_SOCKET_ADDR
@@ -190,6 +206,15 @@ async fn test_durable_service() -> Result<(), Box<dyn std::error::Error>> {
     }
     let topic = node.create_new_topic(topic_config.clone()).await?;
 
+    // this durable service doesn't record acks
+    assert!(topic
+        .create_endpoint_with(
+            [Interest::new("event/**")],
+            EndpointOptions::new().exactly_once()
+        )
+        .await
+        .is_err());
+
     let endpoint = topic.create_endpoint([Interest::new("event/**")]).await?;
     tokio::spawn(async move {
         while let Some(message) = endpoint.next_message().await {
@@ -212,3 +237,338 @@ async fn test_durable_service() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_durable_checksum() -> Result<(), Box<dyn std::error::Error>> {
+    const CHECKSUM_TOPIC_CODE: TopicCode = TopicCode::const_new("checksum-test");
+    let durable = MemoryDurable::default();
+    durable.topics.write().await.insert(
+        CHECKSUM_TOPIC_CODE,
+        TopicConfig {
+            checksum: TopicChecksum::Crc32,
+            ..TopicConfig::from(CHECKSUM_TOPIC_CODE)
+        },
+    );
+    let persisted = |payload: &'static str| {
+        let mut message = Message::new(
+            MessageHeader::builder([Subject::new("event/all")]).build(),
+            payload,
+        );
+        message.header.checksum = TopicChecksum::Crc32.digest(payload.as_bytes());
+        DurableMessage {
+            message,
+            status: Default::default(),
+            time: chrono::Utc::now(),
+        }
+    };
+    let intact = persisted("hello");
+    let mut corrupted = persisted("hello");
+    // flip the payload behind the durability layer's back
+    corrupted.message.payload = MaybeBase64Bytes::new("hellO".into());
+    durable.messages.write().await.insert(
+        CHECKSUM_TOPIC_CODE,
+        BTreeMap::from([
+            (intact.message.id(), intact),
+            (corrupted.message.id(), corrupted),
+        ]),
+    );
+    let service = DurableService::new(durable);
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => DEFAULT_TCP_SOCKET_ADDR
+    ));
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: DEFAULT_TCP_SOCKET_ADDR,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(cluster.clone()).await?;
+    node.load_from_durable_service().await?;
+
+    let topic = node
+        .get_topic(&CHECKSUM_TOPIC_CODE)
+        .expect("topic should be loaded");
+    assert_eq!(topic.corrupted_message_count(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_corrupted_in_transit() -> Result<(), Box<dyn std::error::Error>> {
+    const TRANSIT_TOPIC_CODE: TopicCode = TopicCode::const_new("transit-checksum-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19843".parse()?,
+        durable: Some(DurableService::new(MemoryDurable::default())),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    let topic = node
+        .create_new_topic(TopicConfig {
+            checksum: TopicChecksum::Crc32,
+            ..TopicConfig::from(TRANSIT_TOPIC_CODE)
+        })
+        .await?;
+    let _ep = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let mut message = Message::new(
+        MessageHeader::builder([Subject::new("event/hello")]).build(),
+        "hello",
+    );
+    // checksummed at publish, flipped before the topic holds it
+    message.header.checksum = TopicChecksum::Crc32.digest(b"hellO");
+    let error = topic
+        .send_message(message)
+        .await?
+        .await
+        .expect_err("corrupted payload should not be delivered");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::PayloadCorrupted)
+    ));
+    assert_eq!(topic.corrupted_message_count(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ephemeral_topic() -> Result<(), Box<dyn std::error::Error>> {
+    const EPHEMERAL_TOPIC_CODE: TopicCode = TopicCode::const_new("ephemeral-test");
+    const PERSISTENT_TOPIC_CODE: TopicCode = TopicCode::const_new("persistent-test");
+    let service = DurableService::new(MemoryDurable::default());
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19710".parse()?,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    let mut ephemeral_config = TopicConfig::from(EPHEMERAL_TOPIC_CODE);
+    ephemeral_config.ephemeral = true;
+    let ephemeral = node.create_new_topic(ephemeral_config).await?;
+    let persistent = node.create_new_topic(PERSISTENT_TOPIC_CODE).await?;
+    let _ephemeral_ep = ephemeral
+        .create_endpoint([Interest::new("event/*")])
+        .await?;
+    let _persistent_ep = persistent
+        .create_endpoint([Interest::new("event/*")])
+        .await?;
+    for topic in [&ephemeral, &persistent] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/hello")])
+                .mode_online()
+                .build(),
+            "hello",
+        );
+        assert!(topic.send_message(message).await?.await.is_ok());
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let inner = service.downcast_ref::<MemoryDurable>().unwrap();
+    let messages = inner.messages.read().await;
+    let archived = inner.archived.read().await;
+    assert!(!messages.contains_key(&EPHEMERAL_TOPIC_CODE));
+    assert!(!archived.contains_key(&EPHEMERAL_TOPIC_CODE));
+    assert!(
+        messages.contains_key(&PERSISTENT_TOPIC_CODE)
+            || archived.contains_key(&PERSISTENT_TOPIC_CODE)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idle_unload() -> Result<(), Box<dyn std::error::Error>> {
+    const IDLE_TOPIC_CODE: TopicCode = TopicCode::const_new("idle-test");
+    let service = DurableService::new(MemoryDurable::default());
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19711".parse()?,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    let mut config = TopicConfig::from(IDLE_TOPIC_CODE);
+    config.idle_unload_after = Some(std::time::Duration::from_millis(300));
+    let topic = node.create_new_topic(config).await?;
+
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    assert!(node.get_topic(&IDLE_TOPIC_CODE).is_none());
+    let inner = service.downcast_ref::<MemoryDurable>().unwrap();
+    assert!(inner.topics.read().await.contains_key(&IDLE_TOPIC_CODE));
+
+    // publishing through the old handle reloads the topic
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    assert!(node.get_topic(&IDLE_TOPIC_CODE).is_some());
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/hello")])
+            .mode_online()
+            .build(),
+        "hello",
+    );
+    assert!(topic.send_message(message).await?.await.is_ok());
+    let received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"hello");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lazy_payloads() -> Result<(), Box<dyn std::error::Error>> {
+    const LAZY_TOPIC_CODE: TopicCode = TopicCode::const_new("lazy-test");
+    let durable = MemoryDurable::default();
+    let mut config = TopicConfig::from(LAZY_TOPIC_CODE);
+    config.checksum = TopicChecksum::Crc32;
+    config.lazy_payloads = true;
+    durable.topics.write().await.insert(LAZY_TOPIC_CODE, config);
+    // a backlog message, its payload is only in the durable service after loading
+    let mut backlog = Message::new(
+        MessageHeader::builder([Subject::new("event/backlog")])
+            .mode_durable(asteroid_mq::prelude::MessageDurableConfig {
+                expire: chrono::Utc::now() + chrono::TimeDelta::hours(1),
+                max_receiver: None,
+            })
+            .build(),
+        "from durable",
+    );
+    backlog.header.checksum = TopicChecksum::Crc32.digest(b"from durable");
+    durable.messages.write().await.insert(
+        LAZY_TOPIC_CODE,
+        BTreeMap::from([(
+            backlog.id(),
+            DurableMessage {
+                message: backlog,
+                status: Default::default(),
+                time: chrono::Utc::now(),
+            },
+        )]),
+    );
+    let service = DurableService::new(durable);
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19752".parse()?,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    node.load_from_durable_service().await?;
+    let topic = node
+        .get_topic(&LAZY_TOPIC_CODE)
+        .expect("topic should be loaded");
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"from durable");
+
+    // a fresh message is served from the payload cache
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/fresh")])
+            .mode_online()
+            .build(),
+        "fresh",
+    );
+    assert!(topic.send_message(message).await?.await.is_ok());
+    let received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"fresh");
+    assert_eq!(topic.corrupted_message_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lazy_payloads_burst() -> Result<(), Box<dyn std::error::Error>> {
+    const BURST_TOPIC_CODE: TopicCode = TopicCode::const_new("lazy-burst-test");
+    // well past the 64 payloads the cache keeps
+    const BURST: usize = 256;
+    let service = DurableService::new(MemoryDurable::default());
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19846".parse()?,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    let mut config = TopicConfig::from(BURST_TOPIC_CODE);
+    config.lazy_payloads = true;
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let mut handles = Vec::with_capacity(BURST);
+    for index in 0..BURST {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/burst")])
+                .mode_online()
+                .build(),
+            format!("burst-{index}"),
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let mut received = std::collections::HashSet::new();
+    for _ in 0..BURST {
+        let message = endpoint.next_message().await.expect("endpoint is alive");
+        received.insert(String::from_utf8(message.payload.0.to_vec())?);
+    }
+    assert_eq!(received.len(), BURST);
+    for handle in handles {
+        let success = handle.await.expect("delivered");
+        assert!(success
+            .status
+            .values()
+            .all(|status| *status != asteroid_mq::prelude::MessageStatusKind::Unreachable));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_from_beginning() -> Result<(), Box<dyn std::error::Error>> {
+    const REPLAY_TOPIC_CODE: TopicCode = TopicCode::const_new("replay-test");
+    let service = DurableService::new(MemoryDurable::default());
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19759".parse()?,
+        durable: Some(service.clone()),
+        ..Default::default()
+    });
+    node.init_raft(
+        asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider::singleton(node.config()),
+    )
+    .await?;
+    let topic = node
+        .create_new_topic(TopicConfig::from(REPLAY_TOPIC_CODE))
+        .await?;
+    let early = topic.create_endpoint([Interest::new("event/*")]).await?;
+    for payload in ["first", "second", "third"] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/replay")])
+                .mode_durable(asteroid_mq::prelude::MessageDurableConfig {
+                    expire: chrono::Utc::now() + chrono::TimeDelta::hours(1),
+                    max_receiver: Some(1),
+                })
+                .build(),
+            payload,
+        );
+        assert!(topic.send_message(message).await?.await.is_ok());
+        early.next_message().await.expect("endpoint is alive");
+    }
+    // wait for the acked messages to be archived
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    let late = topic
+        .create_endpoint_with(
+            [Interest::new("event/*")],
+            EndpointOptions::new().replay(ReplayPolicy::FromBeginning),
+        )
+        .await?;
+    for payload in ["first", "second", "third"] {
+        let received = late.next_message().await.expect("endpoint is alive");
+        assert_eq!(&received.payload.0[..], payload.as_bytes());
+    }
+    Ok(())
+}
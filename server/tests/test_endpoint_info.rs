@@ -0,0 +1,34 @@
+use asteroid_mq::{
+    prelude::{EndpointAddr, Interest, Node, NodeConfig, NodeId, TopicCode},
+    protocol::{endpoint::EpInfo, node::raft::cluster::StaticClusterProvider},
+};
+
+#[tokio::test]
+async fn test_endpoint_info() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("endpoint-info-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19751".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let before = chrono::Utc::now();
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let info = topic
+        .endpoint_info(&endpoint.address())
+        .await
+        .expect("endpoint is online");
+    assert_eq!(info.host, node.id());
+    assert_eq!(info.interests, vec![Interest::new("event/*")]);
+    assert_eq!(info.weight, EpInfo::DEFAULT_WEIGHT);
+    assert!(info.latest_active >= before);
+    assert!(info.latest_active <= chrono::Utc::now());
+
+    assert!(topic
+        .endpoint_info(&EndpointAddr::new_snowflake())
+        .await
+        .is_none());
+    Ok(())
+}
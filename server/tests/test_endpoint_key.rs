@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig,
+        NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_endpoint_key_reclaim() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("endpoint-key-test");
+    const KEY: &[u8] = b"billing-worker";
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19826".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(TopicConfig::from(CODE)).await?;
+    let endpoint = topic
+        .create_endpoint_with(
+            [Interest::new("invoice/*")],
+            EndpointOptions::new().endpoint_key(KEY),
+        )
+        .await?;
+    let address = endpoint.address();
+    let handle = topic
+        .send_message(Message::new(
+            MessageHeader::builder([Subject::new("invoice/created")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            "invoice 1",
+        ))
+        .await?;
+    let message = endpoint
+        .recv_timeout(Duration::from_secs(1))
+        .await
+        .ok_or("message not received")?;
+    // the consumer goes away without acking
+    drop(endpoint);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // interests given on reclaim are ignored, the old ones are adopted
+    let endpoint = topic
+        .create_endpoint_with(
+            [Interest::new("other/*")],
+            EndpointOptions::new().endpoint_key(KEY),
+        )
+        .await?;
+    assert_eq!(endpoint.address(), address);
+    let redelivered = endpoint
+        .recv_timeout(Duration::from_secs(1))
+        .await
+        .ok_or("pending message not reclaimed")?;
+    assert_eq!(redelivered.id(), message.id());
+    assert_eq!(redelivered.payload.0, message.payload.0);
+    endpoint.ack_processed(&redelivered.header).await?;
+    assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .is_ok());
+
+    // new messages matching the adopted interests reach it
+    topic
+        .send_message(Message::new(
+            MessageHeader::builder([Subject::new("invoice/paid")])
+                .mode_online()
+                .build(),
+            "invoice 2",
+        ))
+        .await?;
+    assert!(endpoint
+        .recv_timeout(Duration::from_secs(1))
+        .await
+        .is_some());
+
+    // another key gets an endpoint of its own
+    let other = topic
+        .create_endpoint_with(
+            [Interest::new("invoice/*")],
+            EndpointOptions::new().endpoint_key(&b"audit-worker"[..]),
+        )
+        .await?;
+    assert_ne!(other.address(), address);
+    Ok(())
+}
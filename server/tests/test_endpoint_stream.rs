@@ -0,0 +1,41 @@
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use futures_util::StreamExt;
+
+#[tokio::test]
+async fn test_endpoint_stream() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("endpoint-stream-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19765".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let stream = endpoint.stream();
+    tokio::pin!(stream);
+    for payload in ["first", "second"] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/stream")])
+                .mode_online()
+                .build(),
+            payload,
+        );
+        assert!(topic.send_message(message).await?.await.is_ok());
+    }
+    assert_eq!(&stream.next().await.expect("first").payload.0[..], b"first");
+    assert_eq!(
+        &stream.next().await.expect("second").payload.0[..],
+        b"second"
+    );
+    assert!(endpoint.try_recv().is_none());
+
+    topic.delete_endpoint(endpoint.address()).await?;
+    assert!(stream.next().await.is_none());
+    assert!(endpoint.next_message().await.is_none());
+    Ok(())
+}
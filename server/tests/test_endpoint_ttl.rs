@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{ClockService, Interest, ManualClock, Node, NodeConfig, NodeId, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_stale_endpoint_evicted() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("endpoint-ttl-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19774".parse()?,
+        endpoint_ttl: Some(Duration::from_millis(300)),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let alive = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let stale = topic.create_endpoint([Interest::new("event/*")]).await?;
+    for _ in 0..10 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        alive.heartbeat().await?;
+    }
+    assert!(topic.endpoint_info(&stale.address()).await.is_none());
+    assert!(topic.endpoint_info(&alive.address()).await.is_some());
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.endpoints), Some(1));
+    // the evicted endpoint is closed on its host
+    assert!(stale
+        .recv_timeout(Duration::from_millis(100))
+        .await
+        .is_none());
+    assert!(stale.next_message().await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_endpoint_evicted_by_manual_clock() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("endpoint-ttl-clock-test");
+    let clock = ManualClock::default();
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19812".parse()?,
+        endpoint_ttl: Some(Duration::from_secs(60)),
+        clock: ClockService::new(clock.clone()),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let alive = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let stale = topic.create_endpoint([Interest::new("event/*")]).await?;
+
+    clock.advance(Duration::from_secs(30));
+    alive.heartbeat().await?;
+    // past the ttl for `stale` only, no real time has to pass
+    clock.advance(Duration::from_secs(45));
+    tokio::time::timeout(Duration::from_secs(3), async {
+        while topic.endpoint_info(&stale.address()).await.is_some() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await?;
+    assert!(topic.endpoint_info(&alive.address()).await.is_some());
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{Node, NodeConfig, NodeId},
+};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ensure_leader_on_follower() -> Result<(), Box<dyn std::error::Error>> {
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "ensure-leader".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19839 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+        NodeId::new_indexed(2) => node_addr(2),
+        NodeId::new_indexed(3) => node_addr(3),
+    ));
+    let mut nodes = Vec::new();
+    for index in 1..=3 {
+        let node = Node::new(NodeConfig {
+            id: NodeId::new_indexed(index),
+            addr: node_addr(index as u16),
+            raft: raft_config(),
+            ..Default::default()
+        });
+        node.init_raft(cluster.clone()).await?;
+        nodes.push(node);
+    }
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let leader = nodes
+        .iter()
+        .find(|node| node.is_leader())
+        .expect("leader elected");
+    leader.ensure_leader().await?;
+    let follower = nodes
+        .iter()
+        .find(|node| !node.is_leader())
+        .expect("follower exists");
+    let error = follower
+        .ensure_leader()
+        .await
+        .expect_err("follower is not the leader");
+    let ErrorKind::NotLeader(forward) = error.kind() else {
+        panic!("expect NotLeader, got {error}");
+    };
+    assert_eq!(forward.leader_id, Some(leader.id()));
+    assert_eq!(
+        forward.leader_node.as_ref().map(|node| node.addr.clone()),
+        Some(leader.config().addr.to_string())
+    );
+    Ok(())
+}
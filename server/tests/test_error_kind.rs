@@ -0,0 +1,34 @@
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_send_to_missing_topic() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("error-kind-test");
+    // without a durable service, an unloaded topic can't come back
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19798".parse()?,
+        durable: None,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    node.unload_topic(&CODE).await?;
+
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/lost")])
+            .mode_online()
+            .build(),
+        "lost",
+    );
+    let Err(error) = topic.send_message(message).await else {
+        panic!("the topic is gone");
+    };
+    assert!(matches!(error.kind(), ErrorKind::TopicNotFound));
+    assert!(error.to_string().contains("topic not found"));
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        DurableService, EndpointOptions, Interest, MemoryDurability, Message, MessageAckExpectKind,
+        MessageHeader, MessageStatusKind, Node, NodeConfig, NodeId, Subject, TopicCode,
+        TopicConfig, TopicRedeliveryConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_lost_ack_is_recovered() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("exactly-once-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19829".parse()?,
+        durable: Some(DurableService::new(MemoryDurability::new())),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.redelivery = Some(TopicRedeliveryConfig::new(Duration::from_millis(200), 3));
+    let topic = node.create_new_topic(config).await?;
+    let ep = topic
+        .create_endpoint_with(
+            [Interest::new("job/*")],
+            EndpointOptions::new().exactly_once(),
+        )
+        .await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("job/queued")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "job",
+    );
+    let id = message.id();
+    let handle = topic.send_message(message).await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), ep.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.header.message_id, id);
+
+    // the ack is recorded but never reaches the cluster
+    node.config()
+        .durable
+        .as_ref()
+        .expect("durable service")
+        .record_ack(CODE, ep.address(), id)
+        .await?;
+
+    // the redelivery commits the recorded ack instead of processing the job again
+    let success = tokio::time::timeout(Duration::from_secs(2), handle)
+        .await?
+        .expect("recorded ack recovered");
+    assert_eq!(
+        success.status.get(&ep.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), ep.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
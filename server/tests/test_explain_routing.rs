@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, MessageTargetKind, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_explain_routing() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("explain-routing-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19808".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    // overlapping wildcard and literal interests on the same endpoint
+    let both = topic
+        .create_endpoint([Interest::new("event/*"), Interest::new("event/created")])
+        .await?;
+    let deep = topic.create_endpoint([Interest::new("event/**")]).await?;
+    let other = topic.create_endpoint([Interest::new("user/*")]).await?;
+
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/created")])
+            .mode_online()
+            .build(),
+        "{}",
+    );
+    let explanation = topic
+        .explain_routing(&message)
+        .await
+        .expect("topic is loaded");
+    assert_eq!(explanation.target_kind, MessageTargetKind::Online);
+    assert_eq!(explanation.subjects.len(), 1);
+    let routing = &explanation.subjects[0];
+    assert_eq!(routing.subject, Subject::new("event/created"));
+    assert_eq!(routing.matches.len(), 3);
+    assert!(routing
+        .matches
+        .contains(&(Interest::new("event/*"), both.address())));
+    assert!(routing
+        .matches
+        .contains(&(Interest::new("event/created"), both.address())));
+    assert!(routing
+        .matches
+        .contains(&(Interest::new("event/**"), deep.address())));
+    let expected = HashSet::from([both.address(), deep.address()]);
+    assert_eq!(explanation.collected, expected);
+    assert_eq!(explanation.targets, expected);
+    assert!(!explanation.collected.contains(&other.address()));
+
+    // a push message goes to exactly one of them
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/created")])
+            .mode_push()
+            .build(),
+        "{}",
+    );
+    let explanation = topic
+        .explain_routing(&message)
+        .await
+        .expect("topic is loaded");
+    assert_eq!(explanation.collected, expected);
+    assert_eq!(explanation.targets.len(), 1);
+    assert!(explanation.targets.is_subset(&expected));
+
+    // nothing matches, nothing is sent
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("order/created")])
+            .mode_push()
+            .build(),
+        "{}",
+    );
+    let explanation = topic
+        .explain_routing(&message)
+        .await
+        .expect("topic is loaded");
+    assert!(explanation.subjects[0].matches.is_empty());
+    assert!(explanation.targets.is_empty());
+    assert_eq!(node.topic_stats().await[&CODE].held_messages, 0);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{Node, NodeConfig, NodeId, TopicCode};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_follower_creates_topic() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("follower-propose-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "follower-propose".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19792 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let leader = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    let follower = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    leader.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    follower.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(!follower.is_leader());
+
+    // committed through the leader, and applied on the follower before it returns
+    follower.create_new_topic(CODE).await?;
+    assert!(follower.get_topic(&CODE).is_some());
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(leader.get_topic(&CODE).is_some());
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind, Node, NodeConfig,
+    NodeId, Subject, TopicCode,
+};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_follower_publish_resolves() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("follower-publish-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "follower-publish".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19774 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let leader = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    let follower = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    leader.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    follower.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(follower.ensure_leader().await.is_err());
+
+    let leader_topic = leader.create_new_topic(CODE).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let endpoint = leader_topic
+        .create_endpoint([Interest::new("event/*")])
+        .await?;
+    let handler = tokio::spawn(async move {
+        while let Some(message) = endpoint.next_message().await {
+            endpoint.ack_processed(&message.header).await?;
+        }
+        Ok::<_, asteroid_mq::Error>(())
+    });
+
+    let follower_topic = follower.get_topic(&CODE).expect("topic is replicated");
+    for index in 0..5 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/published")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("from follower {index}"),
+        );
+        let handle = follower_topic.send_message(message).await?;
+        let success = tokio::time::timeout(Duration::from_secs(2), handle)
+            .await?
+            .map_err(asteroid_mq::Error::contextual("wait ack"))?;
+        assert_eq!(success.status.len(), 1);
+        assert!(success
+            .status
+            .values()
+            .all(|status| *status == MessageStatusKind::Processed));
+    }
+    // batches are forwarded just the same
+    let batch = (0..3)
+        .map(|index| {
+            Message::new(
+                MessageHeader::builder([Subject::new("event/published")])
+                    .mode_online()
+                    .ack_kind(MessageAckExpectKind::Processed)
+                    .build(),
+                format!("batched from follower {index}"),
+            )
+        })
+        .collect();
+    for handle in follower_topic.send_messages(batch).await? {
+        let success = tokio::time::timeout(Duration::from_secs(2), handle)
+            .await?
+            .map_err(asteroid_mq::Error::contextual("wait ack"))?;
+        assert_eq!(
+            success.status.values().collect::<Vec<_>>(),
+            [&MessageStatusKind::Processed]
+        );
+    }
+    handler.abort();
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::{cluster::StaticClusterProvider, proposal::ForceResolveOutcome},
+};
+
+#[tokio::test]
+async fn test_force_resolve_wait() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("force-resolve-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19680".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/stuck")])
+            .ack_kind(MessageAckExpectKind::Processed)
+            .mode_online()
+            .build(),
+        "stuck",
+    );
+    let message_id = message.id();
+    let handle = topic.send_message(message).await?;
+    // received, but never acked
+    let _received = endpoint.next_message().await.expect("endpoint is alive");
+
+    topic
+        .force_resolve_wait(message_id, ForceResolveOutcome::Failure)
+        .await?;
+    let result = tokio::time::timeout(Duration::from_secs(1), handle).await?;
+    assert!(result.is_err());
+
+    // the entry is gone, a second attempt is rejected
+    let error = topic
+        .force_resolve_wait(message_id, ForceResolveOutcome::Success)
+        .await
+        .expect_err("message is already resolved");
+    assert!(matches!(error.kind, ErrorKind::MessageNotFound));
+    Ok(())
+}
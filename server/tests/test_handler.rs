@@ -1,8 +1,13 @@
 use asteroid_mq::event_handler::json::Json;
-use asteroid_mq::event_handler::EventAttribute;
-use asteroid_mq::prelude::{Interest, MessageAckExpectKind, Node, NodeConfig, Subject, TopicCode};
+use asteroid_mq::event_handler::{EventAttribute, ServeConfig, Transient};
+use asteroid_mq::prelude::{
+    Interest, MessageAckExpectKind, Node, NodeConfig, NodeId, Subject, TopicCode,
+};
 use asteroid_mq::protocol::node::raft::cluster::StaticClusterProvider;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HelloWorld {
     pub number: u32,
@@ -59,3 +64,88 @@ async fn test_create_handler_loop() -> asteroid_mq::Result<()> {
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_handler_retry() -> asteroid_mq::Result<()> {
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19750".parse().unwrap(),
+        ..Default::default()
+    });
+    let cluster_provider = StaticClusterProvider::singleton(node.config());
+    node.init_raft(cluster_provider).await?;
+    let topic = node
+        .create_new_topic(TopicCode::const_new("test-retry"))
+        .await?;
+    let calls = Arc::new(AtomicU32::new(0));
+    let handler_calls = calls.clone();
+    let _evt_loop_handle = topic
+        .create_endpoint([Interest::new("*")])
+        .await?
+        .create_event_loop_with_config(ServeConfig {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+        })
+        .with_handler(move |Json(_): Json<HelloWorld>| {
+            let handler_calls = handler_calls.clone();
+            async move {
+                // fail twice before succeeding
+                if handler_calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(Transient::new(asteroid_mq::Error::unknown(
+                        "transient failure",
+                    )));
+                }
+                Ok(())
+            }
+        })
+        .spawn();
+    topic
+        .send_event(Json(HelloWorld {
+            number: 1,
+            text: "retry".to_string(),
+        }))
+        .await?;
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_handler_no_retry_for_permanent_errors() -> asteroid_mq::Result<()> {
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19835".parse().unwrap(),
+        ..Default::default()
+    });
+    let cluster_provider = StaticClusterProvider::singleton(node.config());
+    node.init_raft(cluster_provider).await?;
+    let topic = node
+        .create_new_topic(TopicCode::const_new("test-no-retry"))
+        .await?;
+    let calls = Arc::new(AtomicU32::new(0));
+    let handler_calls = calls.clone();
+    let _evt_loop_handle = topic
+        .create_endpoint([Interest::new("*")])
+        .await?
+        .create_event_loop_with_config(ServeConfig {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+        })
+        .with_handler(move |Json(_): Json<HelloWorld>| {
+            let handler_calls = handler_calls.clone();
+            async move {
+                handler_calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(asteroid_mq::Error::unknown("permanent failure"))
+            }
+        })
+        .spawn();
+    let result = topic
+        .send_event(Json(HelloWorld {
+            number: 1,
+            text: "no retry".to_string(),
+        }))
+        .await;
+    // acked as failed after the first attempt
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
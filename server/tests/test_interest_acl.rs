@@ -0,0 +1,63 @@
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{Interest, Node, NodeConfig, NodeId, TopicCode, TopicConfig},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_allowed_interests() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("interest-acl-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19784".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.allowed_interests = Some(vec![
+        Interest::new("orders/eu/**"),
+        Interest::new("audit/*"),
+    ]);
+    let topic = node.create_new_topic(config).await?;
+    let is_unauthorized = |error: asteroid_mq::Error| matches!(error.kind, ErrorKind::Unauthorized);
+
+    // narrow interests under an allowed one
+    let endpoint = topic
+        .create_endpoint([Interest::new("orders/eu/created")])
+        .await?;
+    // out of scope
+    assert!(topic
+        .create_endpoint([Interest::new("orders/us/created")])
+        .await
+        .is_err_and(is_unauthorized));
+    // one interest out of scope rejects the whole endpoint
+    assert!(topic
+        .create_endpoint([
+            Interest::new("orders/eu/created"),
+            Interest::new("orders/*/created"),
+        ])
+        .await
+        .is_err_and(is_unauthorized));
+
+    // wildcards are covered by wider wildcards only
+    let _wildcard = topic
+        .create_endpoint([
+            Interest::new("orders/eu/*/shipped"),
+            Interest::new("audit/*"),
+        ])
+        .await?;
+    assert!(topic
+        .create_endpoint([Interest::new("audit/**")])
+        .await
+        .is_err_and(is_unauthorized));
+
+    endpoint
+        .update_interests(vec![Interest::new("orders/eu/>")])
+        .await?;
+    assert!(endpoint
+        .update_interests(vec![Interest::new("orders/>")])
+        .await
+        .is_err_and(is_unauthorized));
+    Ok(())
+}
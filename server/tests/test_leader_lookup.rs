@@ -0,0 +1,55 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{Node, NodeConfig, NodeId};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_leader_lookup() -> Result<(), Box<dyn std::error::Error>> {
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "leader-lookup".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19789 + index)
+    }
+    let members = || {
+        map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+            NodeId::new_indexed(3) => node_addr(3),
+        )
+    };
+    let cluster = common::TestClusterProvider::new(members());
+    let mut nodes = Vec::new();
+    for index in 1..=3 {
+        let node = Node::new(NodeConfig {
+            id: NodeId::new_indexed(index),
+            addr: node_addr(index as u16),
+            raft: raft_config(),
+            ..Default::default()
+        });
+        node.init_raft(cluster.clone()).await?;
+        nodes.push(node);
+    }
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let leaders = nodes
+        .iter()
+        .filter(|node| node.is_leader())
+        .collect::<Vec<_>>();
+    assert_eq!(leaders.len(), 1);
+    let leader = leaders[0];
+    for node in &nodes {
+        assert_eq!(node.leader_id(), Some(leader.id()));
+        assert_eq!(node.leader_addr(), Some(leader.config().addr));
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, LifecycleEvent, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind,
+        Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use futures_util::StreamExt;
+
+#[tokio::test]
+async fn test_published_then_acked() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("lifecycle-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19780".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let events = node.subscribe_events();
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    tokio::spawn(async move {
+        while let Some(message) = endpoint.next_message().await {
+            endpoint.ack_processed(&message.header).await.unwrap();
+        }
+    });
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/observed")])
+            .ack_kind(MessageAckExpectKind::Processed)
+            .mode_online()
+            .build(),
+        "observed",
+    );
+    let message_id = message.id();
+    assert!(topic.send_message(message).await?.await.is_ok());
+
+    // delivery is reported from the dispatch task, so only the state machine events are ordered
+    let observed = tokio::time::timeout(
+        Duration::from_secs(1),
+        events
+            .filter(|event| {
+                std::future::ready(
+                    event.message_id() == Some(message_id)
+                        && !matches!(event, LifecycleEvent::Delivered { .. }),
+                )
+            })
+            .take(2)
+            .collect::<Vec<_>>(),
+    )
+    .await?;
+    assert!(matches!(
+        &observed[0],
+        LifecycleEvent::Published { topic, .. } if *topic == CODE
+    ));
+    assert!(matches!(
+        &observed[1],
+        LifecycleEvent::Acked {
+            status: MessageStatusKind::Processed,
+            ..
+        }
+    ));
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use std::collections::HashSet;
+
+use asteroid_mq::{
+    prelude::{Interest, Node, NodeConfig, NodeId, TopicCode, TopicConfig},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_list_topics() -> Result<(), Box<dyn std::error::Error>> {
+    const ORDERS: TopicCode = TopicCode::const_new("list-topics-orders");
+    const EVENTS: TopicCode = TopicCode::const_new("list-topics-events");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19806".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(ORDERS);
+    config.retention = 16;
+    let orders = node.create_new_topic(config).await?;
+    node.create_new_topic(EVENTS).await?;
+    let topics = node.list_topics().into_iter().collect::<HashSet<_>>();
+    assert_eq!(topics, HashSet::from([ORDERS, EVENTS]));
+
+    let config = orders.config().await.expect("topic is loaded");
+    assert_eq!(config.code, ORDERS);
+    assert_eq!(config.retention, 16);
+
+    let endpoint = orders.create_endpoint([Interest::new("order/*")]).await?;
+    let endpoints = orders.endpoints().await;
+    assert_eq!(endpoints.len(), 1);
+    let (address, info) = &endpoints[0];
+    assert_eq!(*address, endpoint.address());
+    assert_eq!(info.host, node.id());
+    assert_eq!(info.interests, [Interest::new("order/*")]);
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_max_subjects_per_message() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("max-subjects-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19796".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.max_subjects_per_message = Some(3);
+    let topic = node.create_new_topic(config).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let new_message = |count: usize| {
+        Message::new(
+            MessageHeader::builder((0..count).map(|index| Subject::new(format!("event/{index}"))))
+                .mode_online()
+                .build(),
+            "subjects",
+        )
+    };
+
+    let at_limit = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message(3)).await?,
+    )
+    .await?;
+    assert!(at_limit.is_ok());
+
+    let over_limit = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message(4)).await?,
+    )
+    .await?;
+    assert!(matches!(
+        over_limit.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::TooManySubjects))
+    ));
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use asteroid_mq::{
+    prelude::{Node, NodeConfig, NodeId},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_membership_change() -> Result<(), Box<dyn std::error::Error>> {
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "membership-change".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    let leader = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19810".parse()?,
+        raft: raft_config(),
+        ..Default::default()
+    });
+    leader
+        .init_raft(StaticClusterProvider::singleton(leader.config()))
+        .await?;
+    // not a pristine member, it waits to be added
+    let joining = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: "127.0.0.1:19811".parse()?,
+        raft: raft_config(),
+        ..Default::default()
+    });
+    joining
+        .init_raft(StaticClusterProvider::new(BTreeMap::from([(
+            leader.id(),
+            leader.config().addr,
+        )])))
+        .await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(leader.is_leader());
+
+    let voters = |node: &Node| {
+        node.raft_opt()
+            .expect("raft is initialized")
+            .metrics()
+            .borrow()
+            .membership_config
+            .membership()
+            .voter_ids()
+            .collect::<BTreeSet<_>>()
+    };
+    leader
+        .add_learner(joining.id(), joining.config().addr)
+        .await?;
+    assert_eq!(voters(&leader), BTreeSet::from([leader.id()]));
+    leader.promote_to_voter(joining.id()).await?;
+    assert_eq!(voters(&leader), BTreeSet::from([leader.id(), joining.id()]));
+
+    // the new voter learns about the membership through replication
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        voters(&joining),
+        BTreeSet::from([leader.id(), joining.id()])
+    );
+    assert_eq!(joining.leader_id(), Some(leader.id()));
+
+    // only the leader changes the membership
+    assert!(joining.remove_node(leader.id()).await.is_err());
+    Ok(())
+}
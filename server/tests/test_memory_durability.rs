@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_memory_durability_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("memory-durability-test");
+    // no durable service configured, the in-memory one is used
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19770".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/persisted")])
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::hours(1),
+                max_receiver: Some(1),
+            })
+            .build(),
+        "kept",
+    );
+    let message_id = message.id();
+    let _handle = topic.send_message(message).await?;
+    // durable commands are flushed in the background
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    node.unload_topic(&CODE).await?;
+    assert!(node.get_topic(&CODE).is_none());
+
+    let topic = node.get_or_reload_topic(&CODE).await?;
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.held_messages), Some(1));
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.id(), message_id);
+    assert_eq!(&received.payload.0[..], b"kept");
+    Ok(())
+}
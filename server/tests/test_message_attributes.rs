@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_message_attributes() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("attributes-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19786".parse()?,
+        max_attributes_size: 64,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/attributed")])
+            .mode_online()
+            .attribute("content-type", "application/json")
+            .attribute("correlation-id", "42")
+            .build(),
+        "{}",
+    );
+    assert!(topic.send_message(message).await?.await.is_ok());
+    let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(
+        received.attribute("content-type").map(|value| &value[..]),
+        Some(&b"application/json"[..])
+    );
+    assert_eq!(
+        received.attribute("correlation-id").map(|value| &value[..]),
+        Some(&b"42"[..])
+    );
+    assert!(received.attribute("missing").is_none());
+
+    let oversize = Message::new(
+        MessageHeader::builder([Subject::new("event/attributed")])
+            .mode_online()
+            .attribute("trace", vec![0u8; 128])
+            .build(),
+        "{}",
+    );
+    let Err(error) = topic.send_message(oversize).await else {
+        panic!("attributes are over the limit");
+    };
+    assert!(matches!(error.kind, ErrorKind::HeaderTooLarge));
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, Interest, Message, MessageAckExpectKind, MessageFilter, MessageHeader,
+        MessageStatusKind, Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_message_filter() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("message-filter-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19809".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let all = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let us_only = topic
+        .create_endpoint_with(
+            [Interest::new("event/*")],
+            EndpointOptions::new().filter(MessageFilter::eq("region", "us")),
+        )
+        .await?;
+    let new_message = |region: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/created")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .attribute("region", region)
+                .build(),
+            region,
+        )
+    };
+
+    // filtered out, only the unfiltered endpoint has to ack
+    let handle = topic.send_message(new_message("eu")).await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), all.next_message())
+        .await?
+        .expect("endpoint is alive");
+    all.ack_processed(&received.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("acked by the unfiltered endpoint");
+    assert_eq!(
+        success.status.get(&all.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    assert!(!success.status.contains_key(&us_only.address()));
+    assert!(us_only.try_recv().is_none());
+
+    // both endpoints get it and both have to ack
+    let mut handle = topic.send_message(new_message("us")).await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), all.next_message())
+        .await?
+        .expect("endpoint is alive");
+    all.ack_processed(&received.header).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), &mut handle)
+            .await
+            .is_err(),
+        "the filtered endpoint hasn't acked yet"
+    );
+    let received = tokio::time::timeout(Duration::from_secs(1), us_only.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"us");
+    us_only.ack_processed(&received.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("acked by both endpoints");
+    assert_eq!(
+        success.status.get(&us_only.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    Ok(())
+}
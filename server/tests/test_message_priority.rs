@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig, TopicOverflowConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_message_priority() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("priority-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19773".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.overflow_config = Some(TopicOverflowConfig::new_drop_old(3));
+    let topic = node.create_new_topic(config).await?;
+    let new_message = |priority: u8| {
+        Message::new(
+            MessageHeader::builder([Subject::new("job/queued")])
+                .mode_durable(MessageDurableConfig {
+                    expire: Utc::now() + TimeDelta::hours(1),
+                    max_receiver: Some(1),
+                })
+                .priority(priority)
+                .build(),
+            format!("priority {priority}"),
+        )
+    };
+    // nobody is interested yet, so every message is held
+    let mut handles = Vec::new();
+    for priority in [1, 0, 2] {
+        handles.push(topic.send_message(new_message(priority)).await?);
+    }
+    // the queue is full, the lowest priority message is dropped even though it isn't the oldest
+    let _last = topic.send_message(new_message(3)).await?;
+    let dropped = tokio::time::timeout(Duration::from_secs(1), handles.remove(1)).await?;
+    assert!(matches!(
+        dropped.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::Overflow))
+    ));
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.held_messages), Some(3));
+
+    let endpoint = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        received.push(message.header.priority);
+    }
+    assert_eq!(received, [3, 2, 1]);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_message_size_limit() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("message-size-limit-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19805".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let new_message = |size: usize| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/sized")])
+                .mode_online()
+                .build(),
+            vec![b'x'; size],
+        )
+    };
+    // the limit counts the header too
+    let at_limit = new_message(512);
+    let limit = bincode::serialized_size(&at_limit)?;
+    assert!(limit > 512);
+    let mut config = TopicConfig::from(CODE);
+    config.max_message_bytes = Some(limit as u32);
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+
+    let accepted =
+        tokio::time::timeout(Duration::from_secs(1), topic.send_message(at_limit).await?).await?;
+    assert!(accepted.is_ok());
+    let received = endpoint.next_message().await.expect("endpoint is alive");
+    assert_eq!(received.payload.0.len(), 512);
+
+    let rejected = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message(513)).await?,
+    )
+    .await?;
+    assert!(matches!(
+        rejected.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::MessageTooLarge))
+    ));
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), endpoint.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
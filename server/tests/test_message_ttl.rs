@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, proposal::ForceResolveOutcome,
+        state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_message_ttl() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("ttl-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19755".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    // receives the message, but never acks it
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/command")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .ttl(Duration::from_millis(100))
+            .build(),
+        "do it now",
+    );
+    let message_id = message.id();
+    let handle = topic.send_message(message).await?;
+    let result = tokio::time::timeout(Duration::from_secs(2), handle).await?;
+    let error = result.expect_err("nobody acks the message");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::Expired)
+    ));
+    // no longer held
+    let error = topic
+        .force_resolve_wait(message_id, ForceResolveOutcome::Failure)
+        .await
+        .expect_err("expired message is removed");
+    assert!(matches!(error.kind, ErrorKind::MessageNotFound));
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "metrics")]
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig, TopicOverflowConfig,
+    },
+    protocol::{node::raft::cluster::StaticClusterProvider, topic::mirror::MirrorConfig},
+};
+
+#[tokio::test]
+async fn test_dropped_metric() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("metrics-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19763".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.overflow_config = Some(TopicOverflowConfig::new_reject_new(1));
+    let topic = node.create_new_topic(config).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let mut handles = Vec::new();
+    for _ in 0..2 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("job/run")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            "job",
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let rejected = handles.pop().expect("two messages sent").await;
+    assert!(rejected.is_err());
+
+    let text = node.metrics_text();
+    assert!(text.contains("asteroid_messages_published_total 2"));
+    assert!(text.contains("asteroid_messages_dropped_total{reason=\"overflow\"} 1"));
+    assert!(text.contains("asteroid_topic_queue_len{topic=\"metrics-test\"} 1"));
+    assert!(text.contains("asteroid_endpoints_online{topic=\"metrics-test\"} 1"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mirror_metrics() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("metrics-mirror-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19849".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    // nothing listens there, the messages stay buffered
+    let mut config = TopicConfig::from(CODE);
+    config.mirror = Some(MirrorConfig::new("127.0.0.1:1".parse()?));
+    let topic = node.create_new_topic(config).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    for _ in 0..2 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/mirror")])
+                .mode_online()
+                .build(),
+            "event",
+        );
+        topic.send_message(message).await?.await.ok();
+    }
+
+    let text = node.metrics_text();
+    assert!(text.contains("asteroid_mirror_buffered{topic=\"metrics-mirror-test\"} 2"));
+    assert!(text.contains("asteroid_mirror_lag_seconds{topic=\"metrics-mirror-test\"}"));
+    Ok(())
+}
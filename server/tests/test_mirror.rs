@@ -0,0 +1,154 @@
+use std::{collections::HashSet, net::SocketAddr, time::Duration};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, MirrorConfig, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::{
+        edge::{
+            auth::{EdgeAuth, EdgeAuthError, EdgeAuthService},
+            codec::CodecKind,
+            connection::tokio_tcp::TokioTcp,
+            packet::Auth,
+            EdgeConfig, EdgeRequestEnum,
+        },
+        raft::cluster::StaticClusterProvider,
+    },
+};
+
+/// lets in messages of mirrors presenting `secret`
+struct MirrorAuth;
+
+impl EdgeAuth for MirrorAuth {
+    async fn check(&self, _from: NodeId, request: &EdgeRequestEnum) -> Result<(), EdgeAuthError> {
+        match request {
+            EdgeRequestEnum::SendMessage(message)
+                if message.header.mirror_credential().map(|c| &c[..]) == Some(b"secret") =>
+            {
+                Ok(())
+            }
+            _ => Err(EdgeAuthError::new_local("not a trusted mirror")),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mirror_topic() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("mirror-test");
+    const UNTRUSTED: TopicCode = TopicCode::const_new("mirror-test-untrusted");
+    let remote_edge_addr: SocketAddr = "127.0.0.1:19660".parse()?;
+
+    // remote cluster, accepting edge connections over tcp
+    let remote = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: "127.0.0.1:19661".parse()?,
+        edge_auth: Some(EdgeAuthService::new(MirrorAuth)),
+        ..Default::default()
+    });
+    remote
+        .init_raft(StaticClusterProvider::singleton(remote.config()))
+        .await?;
+    let remote_topic = remote.create_new_topic(CODE).await?;
+    let remote_endpoint = remote_topic
+        .create_endpoint([Interest::new("event/*")])
+        .await?;
+    let listener = tokio::net::TcpListener::bind(remote_edge_addr).await?;
+    {
+        let remote = remote.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let _ = remote
+                    .create_edge_connection(
+                        TokioTcp::new(stream),
+                        EdgeConfig {
+                            supported_codec_kinds: HashSet::from([CodecKind::JSON]),
+                            peer_id: NodeId::snowflake(),
+                            peer_auth: Auth {},
+                        },
+                    )
+                    .await;
+            }
+        });
+    }
+
+    // local cluster, mirroring the topic
+    let local = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19662".parse()?,
+        ..Default::default()
+    });
+    local
+        .init_raft(StaticClusterProvider::singleton(local.config()))
+        .await?;
+    // rejected by the remote's edge auth
+    let mut config = TopicConfig::from(UNTRUSTED);
+    config.mirror = Some(MirrorConfig {
+        remote_topic: Some(CODE),
+        ..MirrorConfig::new(remote_edge_addr).with_credential("guess")
+    });
+    let untrusted = local.create_new_topic(config).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/untrusted")])
+            .mode_online()
+            .build(),
+        "untrusted",
+    );
+    untrusted.send_message(message).await?.await.ok();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while untrusted
+            .mirror_stats()
+            .expect("mirror is configured")
+            .forwarded
+            == 0
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await?;
+
+    let mut config = TopicConfig::from(CODE);
+    config.mirror = Some(MirrorConfig::new(remote_edge_addr).with_credential("secret"));
+    let topic = local.create_new_topic(config).await?;
+    let now = chrono::Utc::now();
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/hello")])
+            .mode_online()
+            .expire_at(now + chrono::TimeDelta::seconds(60))
+            .deliver_at(now + chrono::TimeDelta::milliseconds(200))
+            .partition_key("partition")
+            .dedup_key("dedup")
+            .priority(3)
+            .attribute("content-type", "text/plain")
+            .build(),
+        "hello",
+    );
+    let sent = message.header.clone();
+    let result = topic.send_message(message).await?.await;
+    assert!(result.is_ok());
+
+    let mirrored = tokio::time::timeout(Duration::from_secs(5), remote_endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    // the first message that got through
+    assert_eq!(&mirrored.payload.0[..], b"hello");
+    assert!(mirrored
+        .attribute(MessageHeader::MIRROR_CREDENTIAL)
+        .is_none());
+    // the remote publishes it with the header the producer set
+    let header = &mirrored.header;
+    assert_eq!(header.expire_at, sent.expire_at);
+    assert_eq!(header.deliver_at, sent.deliver_at);
+    assert_eq!(header.partition_key, sent.partition_key);
+    assert_eq!(header.dedup_key, sent.dedup_key);
+    assert_eq!(header.priority, 3);
+    assert_eq!(
+        mirrored.attribute("content-type").map(|value| &value[..]),
+        Some(&b"text/plain"[..])
+    );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let stats = topic.mirror_stats().expect("mirror is configured");
+    assert_eq!(stats.forwarded, 1);
+    assert_eq!(stats.buffered, 0);
+    Ok(())
+}
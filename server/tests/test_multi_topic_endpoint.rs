@@ -0,0 +1,59 @@
+use std::{collections::HashMap, time::Duration};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_multi_topic_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+    const ORDERS: TopicCode = TopicCode::const_new("multi-topic-orders");
+    const PAYMENTS: TopicCode = TopicCode::const_new("multi-topic-payments");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19820".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let orders = node.create_new_topic(ORDERS).await?;
+    let payments = node.create_new_topic(PAYMENTS).await?;
+    let endpoint = node
+        .create_multi_topic_endpoint(vec![ORDERS, PAYMENTS], [Interest::new("event/*")])
+        .await?;
+
+    let new_message = |payload: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/created")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            payload,
+        )
+    };
+    let order = orders.send_message(new_message("order")).await?;
+    let payment = payments.send_message(new_message("payment")).await?;
+
+    let mut received = HashMap::new();
+    for _ in 0..2 {
+        let message = endpoint
+            .recv_timeout(Duration::from_secs(1))
+            .await
+            .expect("a message from each topic");
+        endpoint.ack_processed(&message.header).await?;
+        received.insert(message.topic().expect("topic is set"), message.payload.0);
+    }
+    assert_eq!(&received[&ORDERS][..], b"order");
+    assert_eq!(&received[&PAYMENTS][..], b"payment");
+    // acks went back to the topic each message came from
+    assert!(tokio::time::timeout(Duration::from_secs(1), order)
+        .await?
+        .is_ok());
+    assert!(tokio::time::timeout(Duration::from_secs(1), payment)
+        .await?
+        .is_ok());
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind, Node,
+        NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_nack() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("nack-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19760".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let ep_a = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let ep_b = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/job")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "job",
+    );
+    let handle = topic.send_message(message).await?;
+    // whoever gets it first gives it back, the other one takes it
+    let (nacking, taking) = tokio::select! {
+        Some(message) = ep_a.next_message() => {
+            ep_a.nack(&message.header).await?;
+            (ep_a.address(), &ep_b)
+        }
+        Some(message) = ep_b.next_message() => {
+            ep_b.nack(&message.header).await?;
+            (ep_b.address(), &ep_a)
+        }
+    };
+    let message = taking.next_message().await.expect("endpoint is alive");
+    assert_eq!(&message.payload.0[..], b"job");
+    taking.ack_processed(&message.header).await?;
+    let success = handle.await.expect("taken by the other endpoint");
+    assert!(!success.status.contains_key(&nacking));
+    assert_eq!(
+        success.status.get(&taking.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+
+    // nobody else to take it, the producer still gets an answer
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/job")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "job",
+    );
+    drop(ep_b);
+    drop(ep_a);
+    let ep_c = topic.create_endpoint([Interest::new("event/*")]).await?;
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let handle = topic.send_message(message).await?;
+    let message = ep_c.next_message().await.expect("endpoint is alive");
+    ep_c.nack(&message.header).await?;
+    let error = handle.await.expect_err("nacked by the only endpoint");
+    assert_eq!(
+        error.status.get(&ep_c.address()),
+        Some(&MessageStatusKind::Failed)
+    );
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_warn_on_no_subscribers() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("no-subscribers-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19827".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.warn_on_no_subscribers = true;
+    let topic = node.create_new_topic(config).await?;
+    let new_message = |subject: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new(subject)])
+                .mode_online()
+                .build(),
+            "nobody listens",
+        )
+    };
+
+    let handle = topic.send_message(new_message("alert/fired")).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("no subscribers isn't an error");
+    assert!(success.status.is_empty());
+    assert_eq!(success.delivered, Some(0));
+    assert!(topic.peek(10).await.is_empty());
+
+    // with a subscriber it's delivered as usual
+    let endpoint = topic.create_endpoint([Interest::new("alert/*")]).await?;
+    let handle = topic.send_message(new_message("alert/fired")).await?;
+    let message = endpoint
+        .recv_timeout(Duration::from_secs(1))
+        .await
+        .ok_or("message not received")?;
+    endpoint.ack_processed(&message.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("delivered to the endpoint");
+    assert_eq!(success.delivered, Some(1));
+    Ok(())
+}
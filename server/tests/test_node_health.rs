@@ -0,0 +1,72 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{Node, NodeConfig, NodeId, TopicCode, TopicConfig};
+use openraft::ServerState;
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_node_health() -> Result<(), Box<dyn std::error::Error>> {
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "node-health".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19830 + index)
+    }
+    const CODE: TopicCode = TopicCode::const_new("health-test");
+    let members = || {
+        map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+            NodeId::new_indexed(3) => node_addr(3),
+        )
+    };
+    let cluster = common::TestClusterProvider::new(members());
+    let mut nodes = Vec::new();
+    for index in 1..=3 {
+        let node = Node::new(NodeConfig {
+            id: NodeId::new_indexed(index),
+            addr: node_addr(index as u16),
+            raft: raft_config(),
+            ..Default::default()
+        });
+        assert!(!node.health().is_ready());
+        node.init_raft(cluster.clone()).await?;
+        nodes.push(node);
+    }
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    nodes[0].create_new_topic(TopicConfig::from(CODE)).await?;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !nodes.iter().all(|node| node.health().is_ready()) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await?;
+    let reports = nodes.iter().map(Node::health).collect::<Vec<_>>();
+    let leaders = reports
+        .iter()
+        .filter(|health| health.role == Some(ServerState::Leader))
+        .count();
+    assert_eq!(leaders, 1);
+    for health in &reports {
+        assert_eq!(health.leader, reports[0].leader);
+        assert_eq!(health.member_peers, 2);
+        // followers only dial each other when they vote, but always hold the leader's connection
+        if health.role == Some(ServerState::Leader) {
+            assert_eq!(health.connected_peers, 2);
+        } else {
+            assert!(health.connected_peers >= 1);
+        }
+    }
+    assert_eq!(reports[0].topics, 1);
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use asteroid_mq::prelude::NodeId;
+
+#[test]
+fn test_node_id_from_host_identity() {
+    let id = NodeId::from_host_identity("node-1.example.com");
+    // pinned, the id must not change between runs or releases
+    assert_eq!(id.to_base64(), "AYTib9UZToyyzOZJf4_3Jg==");
+    assert_eq!(id, NodeId::from_host_identity(" Node-1.Example.com\n"));
+    assert_ne!(id, NodeId::from_host_identity("node-2.example.com"));
+}
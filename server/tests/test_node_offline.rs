@@ -0,0 +1,101 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageAckExpectKind, MessageDurableConfig, MessageHeader, Node, NodeConfig,
+    NodeId, Subject, TopicCode,
+};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_node_offline_hands_off_durable_messages() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("node-offline-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "node-offline".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19700 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let node_1 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    let node_2 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    node_1.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    // started before the update, so it joins through the leader instead of bootstrapping
+    node_2.init_raft(cluster.clone()).await?;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    node_1
+        .raft()
+        .await
+        .wait(Some(Duration::from_secs(10)))
+        .voter_ids(
+            [NodeId::new_indexed(1), NodeId::new_indexed(2)],
+            "node 2 joins",
+        )
+        .await?;
+
+    let topic_1 = node_1.create_new_topic(CODE).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let topic_2 = node_2.get_topic(&CODE).expect("topic is replicated");
+    let endpoint_2 = topic_2.create_endpoint([Interest::new("event/*")]).await?;
+
+    // room for two receivers, the one on node 2 takes a slot but never acks
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/durable")])
+            .ack_kind(MessageAckExpectKind::Processed)
+            .mode_durable(MessageDurableConfig {
+                expire: chrono::Utc::now() + chrono::TimeDelta::seconds(60),
+                max_receiver: Some(2),
+            })
+            .build(),
+        "durable",
+    );
+    let _handle = topic_1.send_message(message).await?;
+    let received = tokio::time::timeout(Duration::from_secs(2), endpoint_2.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"durable");
+
+    // node 2 leaves the cluster, its slot is handed off
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+        ))
+        .await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let endpoint_a = topic_1.create_endpoint([Interest::new("event/*")]).await?;
+    let endpoint_b = topic_1.create_endpoint([Interest::new("event/*")]).await?;
+    for endpoint in [&endpoint_a, &endpoint_b] {
+        let redelivered = tokio::time::timeout(Duration::from_secs(2), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        assert_eq!(redelivered.id(), received.id());
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject,
+    TopicCode,
+};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_leader_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("node-shutdown-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "node-shutdown".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19776 + index)
+    }
+    let new_node = |index: u16| {
+        Node::new(NodeConfig {
+            id: NodeId::new_indexed(index as u64),
+            addr: node_addr(index),
+            raft: raft_config(),
+            ..Default::default()
+        })
+    };
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let node_1 = new_node(1);
+    let node_2 = new_node(2);
+    let node_3 = new_node(3);
+    node_1.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    // started before the update, so they join through the leader instead of bootstrapping
+    node_2.init_raft(cluster.clone()).await?;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    node_1
+        .raft()
+        .await
+        .wait(Some(Duration::from_secs(10)))
+        .voter_ids(
+            [NodeId::new_indexed(1), NodeId::new_indexed(2)],
+            "node 2 joins",
+        )
+        .await?;
+    node_3.init_raft(cluster.clone()).await?;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+            NodeId::new_indexed(3) => node_addr(3),
+        ))
+        .await;
+    node_1
+        .raft()
+        .await
+        .wait(Some(Duration::from_secs(10)))
+        .voter_ids(
+            [
+                NodeId::new_indexed(1),
+                NodeId::new_indexed(2),
+                NodeId::new_indexed(3),
+            ],
+            "node 3 joins",
+        )
+        .await?;
+    node_1.ensure_leader().await?;
+
+    // held until somebody is interested
+    let topic_1 = node_1.create_new_topic(CODE).await?;
+    let mut message_ids = Vec::new();
+    for index in 0..3 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/kept")])
+                .mode_durable(MessageDurableConfig {
+                    expire: chrono::Utc::now() + chrono::TimeDelta::seconds(60),
+                    max_receiver: Some(1),
+                })
+                .build(),
+            format!("kept {index}"),
+        );
+        message_ids.push(message.id());
+        let _handle = topic_1.send_message(message).await?;
+    }
+
+    node_1.shutdown(Duration::from_secs(10)).await?;
+    // handed over before node 1 left, no election timeout to wait for
+    assert!(node_2.is_leader() || node_3.is_leader());
+    tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let leader = node_2.raft().await.current_leader().await;
+            if let Some(leader) = leader.filter(|leader| *leader != NodeId::new_indexed(1)) {
+                if node_3.raft().await.current_leader().await == Some(leader) {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("a new leader is elected");
+
+    let stats = node_2.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.held_messages), Some(3));
+    let topic_2 = node_2.get_topic(&CODE).expect("topic is replicated");
+    let endpoint = topic_2.create_endpoint([Interest::new("event/*")]).await?;
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let message = tokio::time::timeout(Duration::from_secs(2), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        received.push(message.id());
+    }
+    received.sort();
+    message_ids.sort();
+    assert_eq!(received, message_ids);
+    Ok(())
+}
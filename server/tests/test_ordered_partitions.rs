@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_ordered_partitions() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("ordered-partitions-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19822".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.ordered_partitions = true;
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("order/*")]).await?;
+    let new_message = |key: &'static str, payload: String| {
+        Message::new(
+            MessageHeader::builder([Subject::new("order/updated")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .partition_key(key)
+                .build(),
+            payload,
+        )
+    };
+    let mut handles = Vec::new();
+    for index in 0..3 {
+        handles.push(
+            topic
+                .send_message(new_message("order-1", format!("order-1 {index}")))
+                .await?,
+        );
+    }
+    handles.push(
+        topic
+            .send_message(new_message("order-2", "order-2 0".to_owned()))
+            .await?,
+    );
+
+    // whatever arrives without acking anything
+    let drain = || async {
+        let mut received = Vec::new();
+        while let Some(message) = endpoint.recv_timeout(Duration::from_millis(200)).await {
+            received.push(message);
+        }
+        received
+    };
+    let payloads = |messages: &[Message]| {
+        let mut payloads = messages
+            .iter()
+            .map(|message| String::from_utf8_lossy(&message.payload.0).into_owned())
+            .collect::<Vec<_>>();
+        payloads.sort();
+        payloads
+    };
+    // the other key isn't held back by the first one
+    let first = drain().await;
+    assert_eq!(payloads(&first), ["order-1 0", "order-2 0"]);
+    for message in &first {
+        endpoint.ack_processed(&message.header).await?;
+    }
+    let second = drain().await;
+    assert_eq!(payloads(&second), ["order-1 1"]);
+    endpoint.ack_processed(&second[0].header).await?;
+    let third = drain().await;
+    assert_eq!(payloads(&third), ["order-1 2"]);
+    endpoint.ack_processed(&third[0].header).await?;
+    for handle in handles {
+        assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+            .await?
+            .is_ok());
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind, Node,
+        NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_partial_ack() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("partial-ack-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19800".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let ep_ok = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let ep_fail = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/fanout")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "fanout",
+    );
+    let handle = topic.send_message(message).await?;
+    let message = ep_ok.next_message().await.expect("endpoint is alive");
+    ep_ok.ack_processed(&message.header).await?;
+    let message = ep_fail.next_message().await.expect("endpoint is alive");
+    ep_fail.ack_failed(&message.header).await?;
+
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("one endpoint processed it");
+    assert!(!success.is_fully_acked());
+    assert_eq!(success.status.len(), 2);
+    assert_eq!(
+        success.status.get(&ep_ok.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    assert_eq!(
+        success.status.get(&ep_fail.address()),
+        Some(&MessageStatusKind::Failed)
+    );
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_partition_key() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("partition-key-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19758".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let mut endpoints = Vec::new();
+    for _ in 0..3 {
+        endpoints.push(topic.create_endpoint([Interest::new("event/*")]).await?);
+    }
+    let mut selected = Vec::new();
+    for payload in ["login", "update", "logout"] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/session")])
+                .mode_push()
+                .partition_key("client-42")
+                .build(),
+            payload,
+        );
+        let success = topic
+            .send_message(message)
+            .await?
+            .await
+            .expect("endpoints are online");
+        assert_eq!(success.status.len(), 1);
+        selected.extend(success.status.into_keys());
+    }
+    assert!(endpoints.iter().any(|ep| ep.address() == selected[0]));
+    assert!(selected.iter().all(|ep| *ep == selected[0]));
+    Ok(())
+}
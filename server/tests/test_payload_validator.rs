@@ -0,0 +1,61 @@
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, PayloadValidatorService,
+        Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_payload_validator() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("payload-validator-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19815".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.validator = Some(PayloadValidatorService::new(|payload: &[u8]| {
+        if payload.is_empty() {
+            Err("empty payload".to_string())
+        } else {
+            Ok(())
+        }
+    }));
+    let topic = node.create_new_topic(config).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let new_message = |payload: &'static str| {
+        Message::new(
+            MessageHeader::builder([Subject::new("event/validated")])
+                .mode_online()
+                .build(),
+            payload,
+        )
+    };
+
+    let Err(error) = topic.send_message(new_message("")).await?.await else {
+        panic!("empty payloads are rejected");
+    };
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::ValidationFailed)
+    ));
+    assert!(topic.send_message(new_message("{}")).await?.await.is_ok());
+
+    let handles = topic
+        .send_messages(vec![new_message("first"), new_message("")])
+        .await?;
+    let Ok([first, empty]) = <[_; 2]>::try_from(handles) else {
+        panic!("one handle per message");
+    };
+    assert!(first.await.is_ok());
+    assert!(matches!(
+        empty.await.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::ValidationFailed))
+    ));
+    Ok(())
+}
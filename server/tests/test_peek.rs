@@ -0,0 +1,61 @@
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, MessageTargetKind, Node,
+        NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_peek() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("peek-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19821".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    // never acks, the messages stay held
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let mut sent = Vec::new();
+    let mut handles = Vec::new();
+    for index in 0..3 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/peek")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("message {index}"),
+        );
+        sent.push(message.id());
+        handles.push(topic.send_message(message).await?);
+    }
+
+    let peeked = topic.peek(2).await;
+    assert_eq!(
+        peeked.iter().map(|view| view.id).collect::<Vec<_>>(),
+        sent[..2]
+    );
+    for view in &peeked {
+        assert_eq!(view.target_kind, MessageTargetKind::Online);
+        assert_eq!(&view.subjects[..], [Subject::new("event/peek")]);
+        assert!(view.status.contains_key(&endpoint.address()));
+        assert!(view.payload.is_none());
+    }
+    let with_payload = topic.peek_with_payload(1).await;
+    assert_eq!(with_payload[0].id, sent[0]);
+    assert_eq!(with_payload[0].payload.as_deref(), Some(&b"message 0"[..]));
+
+    // peeking leaves the queue as it was
+    assert_eq!(topic.peek(10).await.len(), 3);
+    assert_eq!(
+        node.topic_stats()
+            .await
+            .get(&CODE)
+            .map(|stats| stats.held_messages),
+        Some(3)
+    );
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_poison_message() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("poison-message-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19813".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.max_delivery_attempts = Some(3);
+    let topic = node.create_new_topic(config).await?;
+    // every consumer crashes on the message
+    let deliveries = Arc::new(AtomicUsize::new(0));
+    for _ in 0..5 {
+        let endpoint = topic.create_endpoint([Interest::new("job/*")]).await?;
+        let deliveries = deliveries.clone();
+        tokio::spawn(async move {
+            while let Some(message) = endpoint.next_message().await {
+                deliveries.fetch_add(1, Ordering::SeqCst);
+                let _ = endpoint.nack(&message.header).await;
+            }
+        });
+    }
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("job/poison")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "poison",
+    );
+    let result =
+        tokio::time::timeout(Duration::from_secs(2), topic.send_message(message).await?).await?;
+    assert!(matches!(
+        result.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::PoisonMessage))
+    ));
+    // quarantined before the remaining endpoints got it
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(deliveries.load(Ordering::SeqCst), 3);
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.held_messages), Some(0));
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        EndpointOptions, Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig,
+        NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_prefetch() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("prefetch-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19818".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic
+        .create_endpoint_with(
+            [Interest::new("work/*")],
+            EndpointOptions::new().prefetch(1),
+        )
+        .await?;
+    let info = topic
+        .endpoint_info(&endpoint.address())
+        .await
+        .expect("endpoint is online");
+    assert_eq!(info.prefetch, Some(1));
+
+    let mut handles = Vec::new();
+    for index in 0..3 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("work/job")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("job {index}"),
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+
+    for index in 0..3 {
+        let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        assert_eq!(&received.payload.0[..], format!("job {index}").as_bytes());
+        // the next one waits for this ack
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(endpoint.try_recv().is_none());
+        endpoint.ack_processed(&received.header).await?;
+    }
+    for handle in handles {
+        assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+            .await?
+            .is_ok());
+    }
+    Ok(())
+}
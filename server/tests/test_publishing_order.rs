@@ -0,0 +1,93 @@
+use asteroid_mq::{
+    prelude::{
+        ClockService, Interest, ManualClock, Message, MessageAckExpectKind, MessageHeader,
+        MessageId, Node, NodeConfig, NodeId, Subject, TopicCode, TopicSnapshot,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_publishing_order_at_same_instant() -> Result<(), Box<dyn std::error::Error>> {
+    const SOURCE: TopicCode = TopicCode::const_new("publishing-order-source");
+    const RESTORED: TopicCode = TopicCode::const_new("publishing-order-restored");
+    // every message is held at the same instant
+    let clock = ManualClock::default();
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19819".parse()?,
+        clock: ClockService::new(clock.clone()),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let source = node.create_new_topic(SOURCE).await?;
+    // never acks, the messages stay held
+    let _endpoint = source.create_endpoint([Interest::new("event/*")]).await?;
+    // ids sort against the publishing order, the replicated sequence decides
+    let mut ids = (0..100)
+        .map(|_| MessageId::new_snowflake())
+        .collect::<Vec<_>>();
+    ids.sort_by(|a, b| b.cmp(a));
+    let mut sent = Vec::new();
+    let mut handles = Vec::new();
+    for (index, id) in ids.into_iter().enumerate() {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/ordered")])
+                .message_id(id)
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("message {index}"),
+        );
+        sent.push(message.id());
+        handles.push(source.send_message(message).await?);
+    }
+    let exported = source.export_snapshot().await?;
+    assert_eq!(exported.held_messages(), sent);
+    let exported: TopicSnapshot = bincode::deserialize(&bincode::serialize(&exported)?)?;
+    let restored = node.create_new_topic(RESTORED).await?;
+    restored.import_snapshot(exported).await?;
+    assert_eq!(restored.export_snapshot().await?.held_messages(), sent);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_topic_message_id() -> Result<(), Box<dyn std::error::Error>> {
+    const SOURCE: TopicCode = TopicCode::const_new("topic-message-id-source");
+    const RESTORED: TopicCode = TopicCode::const_new("topic-message-id-restored");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19848".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let source = node.create_new_topic(SOURCE).await?;
+    let _endpoint = source.create_endpoint([Interest::new("event/*")]).await?;
+    let mut sent = Vec::new();
+    let mut handles = Vec::new();
+    for index in 0..100 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/ordered")])
+                .message_id(source.new_message_id())
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("message {index}"),
+        );
+        sent.push(message.id());
+        handles.push(source.send_message(message).await?);
+    }
+    // built within the same second, still in publishing order
+    let mut sorted = sent.clone();
+    sorted.sort();
+    assert_eq!(sorted, sent);
+
+    let exported = source.export_snapshot().await?;
+    assert_eq!(exported.held_messages(), sent);
+    let exported: TopicSnapshot = bincode::deserialize(&bincode::serialize(&exported)?)?;
+    let restored = node.create_new_topic(RESTORED).await?;
+    restored.import_snapshot(exported).await?;
+    assert_eq!(restored.export_snapshot().await?.held_messages(), sent);
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig, TopicRateLimit,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_rate_limit() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("rate-limit-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19767".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.rate_limit = Some(TopicRateLimit::new(1, 3));
+    let topic = node.create_new_topic(config).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("tick/*")]).await?;
+    let mut handles = Vec::new();
+    for _ in 0..10 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("tick/now")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Sent)
+                .build(),
+            "tick",
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let mut succeeded = 0;
+    let mut limited = 0;
+    for handle in handles {
+        match handle.await {
+            Ok(_) => succeeded += 1,
+            Err(error) => {
+                assert!(matches!(
+                    error.exception,
+                    Some(WaitAckErrorException::RateLimited)
+                ));
+                limited += 1;
+            }
+        }
+    }
+    // the burst always gets through, the rest mostly doesn't
+    assert!(succeeded >= 3);
+    assert!(limited > 0);
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_recent_log_entries() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("recent-log-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19730".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let _endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/hello")])
+            .mode_online()
+            .build(),
+        "hello",
+    );
+    assert!(topic.send_message(message).await?.await.is_ok());
+
+    let entries = node.recent_log_entries(16);
+    assert!(entries.len() <= 16);
+    assert!(entries.windows(2).all(|pair| pair[0].index < pair[1].index));
+    let kinds = entries.iter().map(|entry| entry.kind).collect::<Vec<_>>();
+    let position = |kind: &str| kinds.iter().position(|k| *k == kind);
+    let load = position("LoadTopic").expect("topic load is logged");
+    let online = position("EpOnline").expect("endpoint online is logged");
+    let delegate = position("DelegateMessage").expect("message is logged");
+    assert!(load < online && online < delegate);
+    assert_eq!(node.recent_log_entries(1).len(), 1);
+    Ok(())
+}
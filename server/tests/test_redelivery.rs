@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind, Node,
+        NodeConfig, NodeId, Subject, TopicCode, TopicConfig, TopicRedeliveryConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_redelivery() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("redelivery-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19803".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.redelivery = Some(TopicRedeliveryConfig::new(Duration::from_millis(200), 3));
+    let topic = node.create_new_topic(config).await?;
+    let ep_a = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let ep_b = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("job/queued")])
+            .mode_push()
+            .ack_kind(MessageAckExpectKind::Processed)
+            .build(),
+        "job",
+    );
+    let handle = topic.send_message(message).await?;
+    // whoever gets it first crashes without acking
+    let taking = tokio::select! {
+        Some(_) = ep_a.next_message() => &ep_b,
+        Some(_) = ep_b.next_message() => &ep_a,
+    };
+    let redelivered = tokio::time::timeout(Duration::from_secs(2), taking.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&redelivered.payload.0[..], b"job");
+    assert_eq!(redelivered.delivery_attempt(), 2);
+    taking.ack_processed(&redelivered.header).await?;
+    let success = tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .expect("processed by the other endpoint");
+    assert_eq!(
+        success.status.get(&taking.address()),
+        Some(&MessageStatusKind::Processed)
+    );
+    Ok(())
+}
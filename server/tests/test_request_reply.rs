@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_request_reply() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("request-reply-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19787".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+
+    // echoes the payload and the correlation id to the reply subject
+    let responder = topic.create_endpoint([Interest::new("rpc/echo")]).await?;
+    let responder_topic = topic.clone();
+    tokio::spawn(async move {
+        while let Some(request) = responder.next_message().await {
+            let (Some(reply_to), Some(correlation_id)) =
+                (request.reply_to(), request.correlation_id().cloned())
+            else {
+                continue;
+            };
+            let reply = Message::new(
+                MessageHeader::builder([reply_to])
+                    .mode_online()
+                    .attribute(MessageHeader::CORRELATION_ID, correlation_id)
+                    .build(),
+                request.payload.0.clone(),
+            );
+            let _ = responder_topic.send_message(reply).await;
+        }
+    });
+
+    let request = Message::new(
+        MessageHeader::builder([Subject::new("rpc/echo")])
+            .mode_online()
+            .build(),
+        "ping",
+    );
+    let reply = topic
+        .request(
+            request,
+            Interest::new("rpc/reply/client"),
+            Duration::from_secs(1),
+        )
+        .await?;
+    assert_eq!(&reply.payload.0[..], b"ping");
+    assert!(reply.correlation_id().is_some());
+
+    // nobody answers on this subject
+    let unanswered = Message::new(
+        MessageHeader::builder([Subject::new("rpc/silent")])
+            .mode_online()
+            .build(),
+        "ping",
+    );
+    let Err(error) = topic
+        .request(
+            unanswered,
+            Interest::new("rpc/reply/client"),
+            Duration::from_millis(200),
+        )
+        .await
+    else {
+        panic!("nobody replies");
+    };
+    assert!(matches!(error.kind, ErrorKind::Timeout));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_requests() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("concurrent-request-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19836".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+
+    // pushes every reply, and waits for the requester to process it
+    let responder = topic.create_endpoint([Interest::new("rpc/echo")]).await?;
+    let responder_topic = topic.clone();
+    let (acked, mut acked_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(request) = responder.next_message().await {
+            let (Some(reply_to), Some(correlation_id)) =
+                (request.reply_to(), request.correlation_id().cloned())
+            else {
+                continue;
+            };
+            let reply = Message::new(
+                MessageHeader::builder([reply_to])
+                    .mode_push()
+                    .ack_kind(MessageAckExpectKind::Processed)
+                    .attribute(MessageHeader::CORRELATION_ID, correlation_id)
+                    .build(),
+                request.payload.0.clone(),
+            );
+            let Ok(handle) = responder_topic.send_message(reply).await else {
+                continue;
+            };
+            let acked = acked.clone();
+            tokio::spawn(async move {
+                let result = tokio::time::timeout(Duration::from_secs(2), handle).await;
+                let _ = acked.send(matches!(result, Ok(Ok(_))));
+            });
+        }
+    });
+
+    let request = |payload: &'static str| {
+        let topic = topic.clone();
+        async move {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("rpc/echo")])
+                    .mode_online()
+                    .build(),
+                payload,
+            );
+            topic
+                .request(
+                    message,
+                    Interest::new("rpc/reply/shared"),
+                    Duration::from_secs(2),
+                )
+                .await
+        }
+    };
+    let (first, second) = tokio::join!(request("first"), request("second"));
+    assert_eq!(&first?.payload.0[..], b"first");
+    assert_eq!(&second?.payload.0[..], b"second");
+    // both replies are processed by their own requester
+    for _ in 0..2 {
+        let acked = tokio::time::timeout(Duration::from_secs(3), acked_rx.recv()).await?;
+        assert_eq!(acked, Some(true));
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "rocksdb")]
+use asteroid_mq::{
+    prelude::{
+        Durable, DurableMessage, DurableService, Message, MessageDurableConfig, MessageHeader,
+        Node, NodeConfig, NodeId, RocksDbDurability, Subject, TopicCode, TopicConfig,
+    },
+    protocol::{
+        node::raft::cluster::StaticClusterProvider, topic::durable_message::DurableMessageQuery,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_rocksdb_reload() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("rocksdb-test");
+    let path = std::env::temp_dir().join(format!("asteroid-mq-rocksdb-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+
+    // saved out of time order
+    let now = Utc::now();
+    let mut saved = Vec::new();
+    {
+        let durable = RocksDbDurability::open(&path)?;
+        durable.create_topic(TopicConfig::from(CODE)).await?;
+        for (payload, offset) in [("second", 2), ("first", 1), ("third", 3)] {
+            let message = Message::new(
+                MessageHeader::builder([Subject::new("event/persisted")])
+                    .mode_durable(MessageDurableConfig {
+                        expire: now + TimeDelta::hours(1),
+                        max_receiver: None,
+                    })
+                    .build(),
+                payload,
+            );
+            saved.push((offset, message.id()));
+            durable
+                .save(
+                    CODE,
+                    DurableMessage {
+                        message,
+                        status: Default::default(),
+                        time: now + TimeDelta::seconds(offset),
+                    },
+                )
+                .await?;
+        }
+    }
+    saved.sort_by_key(|(offset, _)| *offset);
+    let in_time_order = saved.into_iter().map(|(_, id)| id).collect::<Vec<_>>();
+
+    // reopened, the in-memory state is gone
+    let service = DurableService::new(RocksDbDurability::open(&path)?);
+    let reloaded = service
+        .batch_retrieve(CODE, DurableMessageQuery::new(16, 0))
+        .await?;
+    assert_eq!(
+        reloaded.iter().map(|m| m.message.id()).collect::<Vec<_>>(),
+        in_time_order
+    );
+
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19769".parse()?,
+        durable: Some(service),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    node.load_from_durable_service().await?;
+    let stats = node.topic_stats().await;
+    let stats = stats.get(&CODE).expect("topic is reloaded");
+    assert_eq!(stats.held_messages, 3);
+
+    drop(node);
+    let _ = std::fs::remove_dir_all(&path);
+    Ok(())
+}
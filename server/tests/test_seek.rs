@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_seek_backward() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("seek-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19670".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.retention = 16;
+    let topic = node.create_new_topic(config).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    for payload in ["a", "b", "c"] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/seek")])
+                .mode_online()
+                .build(),
+            payload,
+        );
+        assert!(topic.send_message(message).await?.await.is_ok());
+    }
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let message = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        received.push(message);
+    }
+    let sequences = received
+        .iter()
+        .map(|message| message.header.sequence.expect("sequence is assigned"))
+        .collect::<Vec<_>>();
+    assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]));
+
+    endpoint.seek(sequences[0]).await?;
+    for expected in &received {
+        let replayed = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+            .await?
+            .expect("endpoint is alive");
+        assert_eq!(replayed.header.sequence, expected.header.sequence);
+        assert_eq!(replayed.payload.0, expected.payload.0);
+    }
+    Ok(())
+}
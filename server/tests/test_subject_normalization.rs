@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, Topic, TopicCode,
+        TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+async fn publish(topic: &Topic, subject: &'static str) -> Result<(), Box<dyn std::error::Error>> {
+    let message = Message::new(
+        MessageHeader::builder([Subject::new(subject)])
+            .mode_online()
+            .build(),
+        subject,
+    );
+    assert!(topic.send_message(message).await?.await.is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subject_normalization() -> Result<(), Box<dyn std::error::Error>> {
+    const NORMALIZED: TopicCode = TopicCode::const_new("normalized");
+    const PLAIN: TopicCode = TopicCode::const_new("plain");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19690".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+
+    let plain_config = TopicConfig::from(PLAIN);
+    assert!(!plain_config.normalize_subjects);
+    let mut normalized_config = TopicConfig::from(NORMALIZED);
+    normalized_config.normalize_subjects = true;
+
+    let normalized = node.create_new_topic(normalized_config).await?;
+    let plain = node.create_new_topic(plain_config).await?;
+    let normalized_ep = normalized
+        .create_endpoint([Interest::new("orders/eu")])
+        .await?;
+    let plain_ep = plain.create_endpoint([Interest::new("orders/eu")]).await?;
+
+    publish(&normalized, "orders/EU/").await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), normalized_ep.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"orders/EU/");
+    assert_eq!(received.header.subjects[0].as_str(), "orders/eu");
+
+    // off by default, the case mismatch doesn't route
+    publish(&plain, "orders/EU/").await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), plain_ep.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_malformed_subject_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("malformed-subject");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19795".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("orders/*")]).await?;
+    for malformed in ["orders//created", "orders/*", ""] {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new(malformed)])
+                .mode_online()
+                .build(),
+            malformed,
+        );
+        assert!(topic.send_message(message).await.is_err());
+    }
+    // redundant slashes around the subject are trimmed
+    publish(&topic, "/orders/created/").await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.header.subjects[0].as_str(), "orders/created");
+    Ok(())
+}
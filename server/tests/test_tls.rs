@@ -0,0 +1,108 @@
+#![cfg(feature = "tls")]
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::Path,
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+    TlsConfig, TopicCode,
+};
+mod common;
+
+/// a ca, and a certificate signed by it shared by every member
+fn write_certificates(dir: &Path) -> Result<TlsConfig, Box<dyn std::error::Error>> {
+    let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new())?;
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let ca_key = rcgen::KeyPair::generate()?;
+    let ca = ca_params.self_signed(&ca_key)?;
+    let mut node_params = rcgen::CertificateParams::new(vec!["127.0.0.1".to_string()])?;
+    node_params.extended_key_usages = vec![
+        rcgen::ExtendedKeyUsagePurpose::ServerAuth,
+        rcgen::ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+    let node_key = rcgen::KeyPair::generate()?;
+    let node_cert = node_params.signed_by(&node_key, &ca, &ca_key)?;
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("ca.pem"), ca.pem())?;
+    std::fs::write(dir.join("node.pem"), node_cert.pem())?;
+    std::fs::write(dir.join("node.key"), node_key.serialize_pem())?;
+    Ok(TlsConfig::new(
+        dir.join("node.pem"),
+        dir.join("node.key"),
+        dir.join("ca.pem"),
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tls_cluster() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("tls-test");
+    let dir = std::env::temp_dir().join(format!("asteroid-mq-tls-{}", std::process::id()));
+    let tls = write_certificates(&dir)?.with_client_cert_required();
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "tls".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19780 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let node_1 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        tls: Some(tls.clone()),
+        ..Default::default()
+    });
+    let node_2 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        tls: Some(tls),
+        ..Default::default()
+    });
+    node_1.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    node_2.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // the topic and the endpoint replicate to node 2 over tls
+    let topic_1 = node_1.create_new_topic(CODE).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let topic_2 = node_2.get_topic(&CODE).expect("topic is replicated");
+    let endpoint = topic_2.create_endpoint([Interest::new("event/*")]).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/encrypted")])
+            .mode_online()
+            .ack_kind(MessageAckExpectKind::Received)
+            .build(),
+        "over tls",
+    );
+    let handle = topic_1.send_message(message).await?;
+    let received = tokio::time::timeout(Duration::from_secs(2), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"over tls");
+    endpoint.ack_received(&received.header).await?;
+    assert!(tokio::time::timeout(Duration::from_secs(2), handle)
+        .await?
+        .is_ok());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
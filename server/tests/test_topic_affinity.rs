@@ -0,0 +1,89 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use asteroid_mq::prelude::{
+    Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode, TopicConfig,
+};
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_topic_affinity() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("affinity-test");
+    fn raft_config() -> openraft::Config {
+        openraft::Config {
+            cluster_name: "affinity".to_string(),
+            heartbeat_interval: 200,
+            election_timeout_max: 1000,
+            election_timeout_min: 500,
+            ..Default::default()
+        }
+    }
+    const fn node_addr(index: u16) -> SocketAddr {
+        SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 19720 + index)
+    }
+    let cluster = common::TestClusterProvider::new(map!(
+        NodeId::new_indexed(1) => node_addr(1),
+    ));
+    let node_1 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: node_addr(1),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    let node_2 = Node::new(NodeConfig {
+        id: NodeId::new_indexed(2),
+        addr: node_addr(2),
+        raft: raft_config(),
+        ..Default::default()
+    });
+    node_1.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    cluster
+        .update(map!(
+            NodeId::new_indexed(1) => node_addr(1),
+            NodeId::new_indexed(2) => node_addr(2),
+        ))
+        .await;
+    node_2.init_raft(cluster.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // consumers are concentrated on node 2
+    let mut config = TopicConfig::from(CODE);
+    config.affinity = Some(NodeId::new_indexed(2));
+    let topic_1 = node_1.create_new_topic(config).await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let topic_2 = node_2.get_topic(&CODE).expect("topic is replicated");
+    let endpoint_1 = topic_1.create_endpoint([Interest::new("event/*")]).await?;
+    let endpoint_2a = topic_2.create_endpoint([Interest::new("event/*")]).await?;
+    let endpoint_2b = topic_2.create_endpoint([Interest::new("event/*")]).await?;
+
+    const COUNT: usize = 16;
+    for index in 0..COUNT {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/push")])
+                .mode_push()
+                .build(),
+            format!("message-{index}"),
+        );
+        assert!(topic_1.send_message(message).await?.await.is_ok());
+    }
+    let mut on_node_2 = 0;
+    for endpoint in [&endpoint_2a, &endpoint_2b] {
+        while tokio::time::timeout(Duration::from_millis(200), endpoint.next_message())
+            .await
+            .is_ok()
+        {
+            on_node_2 += 1;
+        }
+    }
+    assert_eq!(on_node_2, COUNT);
+    // nothing was routed away from the affinity node
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), endpoint_1.next_message())
+            .await
+            .is_err()
+    );
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use asteroid_mq::prelude::{TopicCode, TopicCodeError};
+use asteroid_mq::protocol::topic::TopicCodeErrorReason;
+
+#[test]
+fn test_topic_code_validation() {
+    let reason = |code: String| TopicCode::try_new(code).map_err(|error| error.reason);
+
+    assert_eq!(
+        TopicCode::try_new("orders.eu-west"),
+        Ok(TopicCode::const_new("orders.eu-west"))
+    );
+    assert_eq!(
+        TopicCode::try_new("a".repeat(TopicCode::MAX_LEN)),
+        Ok(TopicCode::new("a".repeat(TopicCode::MAX_LEN)))
+    );
+
+    assert_eq!(reason(String::new()), Err(TopicCodeErrorReason::Empty));
+    assert_eq!(
+        reason("a".repeat(TopicCode::MAX_LEN + 1)),
+        Err(TopicCodeErrorReason::TooLong)
+    );
+    assert_eq!(
+        reason("orders\0".to_owned()),
+        Err(TopicCodeErrorReason::NonPrintable)
+    );
+    assert_eq!(
+        reason("orders\ncreated".to_owned()),
+        Err(TopicCodeErrorReason::NonPrintable)
+    );
+
+    let error: TopicCodeError = TopicCode::try_new("").unwrap_err();
+    assert_eq!(error.to_string(), "topic code is empty");
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+fn message(subject: &'static str) -> Message {
+    Message::new(
+        MessageHeader::builder([Subject::new(subject)])
+            .mode_online()
+            .build(),
+        "payload",
+    )
+}
+
+/// A quiet topic keeps getting its turn while another topic on the node has a dispatch backlog,
+/// see [`NodeConfig::dispatch_budget`].
+#[tokio::test(flavor = "multi_thread")]
+async fn test_quiet_topic_not_starved() -> Result<(), Box<dyn std::error::Error>> {
+    const HOT: TopicCode = TopicCode::const_new("fairness-hot");
+    const QUIET: TopicCode = TopicCode::const_new("fairness-quiet");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19797".parse()?,
+        dispatch_budget: 4,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let hot = node.create_new_topic(HOT).await?;
+    let quiet = node.create_new_topic(QUIET).await?;
+    let hot_endpoints = futures_util::future::try_join_all(
+        (0..4).map(|_| hot.create_endpoint([Interest::new("hot/*")])),
+    )
+    .await?;
+    let quiet_endpoint = quiet.create_endpoint([Interest::new("quiet/*")]).await?;
+    for endpoint in hot_endpoints {
+        tokio::spawn(async move { while endpoint.next_message().await.is_some() {} });
+    }
+
+    // one batch leaves thousands of dispatch jobs queued for the hot topic
+    let _hot_handles = hot
+        .send_messages((0..2000).map(|_| message("hot/flood")).collect())
+        .await?;
+    let _quiet_handle = quiet.send_message(message("quiet/ping")).await?;
+    let received = tokio::time::timeout(Duration::from_secs(1), quiet_endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(received.header.subjects[0].as_str(), "quiet/ping");
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_unloaded_topic_fails_waiting_producers() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("topic-not-found-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19799".parse()?,
+        durable: None,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    assert!(node.has_topic(&CODE));
+    // nobody is interested, so the message is held until the topic goes away
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/held")])
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::hours(1),
+                max_receiver: Some(1),
+            })
+            .build(),
+        "held",
+    );
+    let handle = topic.send_message(message).await?;
+    node.unload_topic(&CODE).await?;
+    assert!(!node.has_topic(&CODE));
+    let result = tokio::time::timeout(Duration::from_secs(1), handle).await?;
+    assert!(matches!(
+        result.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::TopicNotFound))
+    ));
+
+    // sending through the stale handle fails up front
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/lost")])
+            .mode_online()
+            .build(),
+        "lost",
+    );
+    let Err(error) = topic.send_message(message).await else {
+        panic!("the topic is gone");
+    };
+    assert!(matches!(error.kind(), ErrorKind::TopicNotFound));
+    Ok(())
+}
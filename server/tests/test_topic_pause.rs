@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{Interest, Message, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode},
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_topic_pause() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("topic-pause-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19804".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/*")]).await?;
+    topic.pause();
+    assert!(topic.is_paused());
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/paused")])
+            .mode_online()
+            .build(),
+        "paused",
+    );
+    let mut handle = topic.send_message(message).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), endpoint.next_message())
+            .await
+            .is_err()
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), &mut handle)
+            .await
+            .is_err()
+    );
+
+    topic.resume();
+    assert!(!topic.is_paused());
+    let received = tokio::time::timeout(Duration::from_secs(1), endpoint.next_message())
+        .await?
+        .expect("endpoint is alive");
+    assert_eq!(&received.payload.0[..], b"paused");
+    let result = tokio::time::timeout(Duration::from_secs(1), handle).await?;
+    assert!(result.is_ok());
+    Ok(())
+}
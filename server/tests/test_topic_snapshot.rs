@@ -0,0 +1,58 @@
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicSnapshot,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_topic_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+    const SOURCE: TopicCode = TopicCode::const_new("snapshot-source");
+    const RESTORED: TopicCode = TopicCode::const_new("snapshot-restored");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19816".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let source = node.create_new_topic(SOURCE).await?;
+    // never acks, the messages stay held
+    let _endpoint = source.create_endpoint([Interest::new("event/*")]).await?;
+    let mut handles = Vec::new();
+    for index in 0..3 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("event/backup")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("message {index}"),
+        );
+        handles.push(source.send_message(message).await?);
+    }
+
+    let exported = source.export_snapshot().await?;
+    assert_eq!(exported.version, TopicSnapshot::VERSION);
+    assert_eq!(exported.code(), &SOURCE);
+    assert_eq!(exported.held_messages().len(), 3);
+    assert_eq!(exported.endpoints().len(), 1);
+    // through the on-disk format
+    let exported: TopicSnapshot = bincode::deserialize(&bincode::serialize(&exported)?)?;
+
+    let restored = node.create_new_topic(RESTORED).await?;
+    restored.import_snapshot(exported.clone()).await?;
+    let imported = restored.export_snapshot().await?;
+    assert_eq!(imported.held_messages(), exported.held_messages());
+    assert_eq!(imported.endpoints(), exported.endpoints());
+    assert_eq!(restored.endpoints().await, source.endpoints().await);
+
+    // importing again merges, nothing is held twice
+    restored.import_snapshot(exported.clone()).await?;
+    let stats = node.topic_stats().await;
+    assert_eq!(
+        stats.get(&RESTORED).map(|stats| stats.held_messages),
+        Some(3)
+    );
+    Ok(())
+}
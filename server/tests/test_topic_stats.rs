@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, MessageStatusKind, Node,
+        NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+
+#[tokio::test]
+async fn test_topic_stats() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("topic-stats-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19762".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic
+        .create_endpoint([Interest::new("job/*"), Interest::new("audit/**")])
+        .await?;
+    let mut handles = Vec::new();
+    for _ in 0..3 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("job/run")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            "job",
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let first = endpoint.next_message().await.expect("endpoint is alive");
+    endpoint.ack_received(&first.header).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let stats = node.topic_stats().await;
+    let stats = stats.get(&CODE).expect("topic is loaded");
+    assert_eq!(stats.held_messages, 3);
+    assert_eq!(stats.waiting_handles, 3);
+    assert_eq!(stats.endpoints, 1);
+    assert_eq!(stats.interests, 2);
+    assert_eq!(stats.status.get(&MessageStatusKind::Received), Some(&1));
+    assert_eq!(stats.status.get(&MessageStatusKind::Sent), Some(&2));
+    Ok(())
+}
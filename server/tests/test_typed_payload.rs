@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    error::ErrorKind,
+    prelude::{
+        CodecKind, Interest, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode,
+        TopicConfig,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Order {
+    id: u64,
+    item: String,
+    quantity: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Invoice {
+    #[allow(dead_code)]
+    amount: f64,
+}
+
+fn order() -> Order {
+    Order {
+        id: 42,
+        item: "coffee".to_owned(),
+        quantity: 3,
+    }
+}
+
+#[tokio::test]
+async fn test_typed_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19824".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    for (codec, code) in [
+        (CodecKind::JSON, "typed-json-test"),
+        (CodecKind::BINCODE, "typed-bincode-test"),
+    ] {
+        let mut config = TopicConfig::from(TopicCode::const_new(code));
+        config.payload_codec = Some(codec);
+        let topic = node.create_new_topic(config).await?;
+        let endpoint = topic.create_endpoint([Interest::new("order/*")]).await?;
+        let handle = topic
+            .send_typed(
+                &order(),
+                MessageHeader::builder([Subject::new("order/placed")])
+                    .mode_online()
+                    .build(),
+            )
+            .await?;
+        let (header, received) =
+            tokio::time::timeout(Duration::from_secs(1), endpoint.recv_typed::<Order>())
+                .await?
+                .ok_or("message not received")?;
+        assert_eq!(received?, order());
+        endpoint.ack_processed(&header).await?;
+        assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+            .await?
+            .is_ok());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_typed_decode_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("typed-decode-mismatch-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19825".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(TopicConfig::from(CODE)).await?;
+    let endpoint = topic.create_endpoint([Interest::new("order/*")]).await?;
+    topic
+        .send_typed(
+            &order(),
+            MessageHeader::builder([Subject::new("order/placed")])
+                .mode_online()
+                .build(),
+        )
+        .await?;
+    let (header, received) =
+        tokio::time::timeout(Duration::from_secs(1), endpoint.recv_typed::<Invoice>())
+            .await?
+            .ok_or("message not received")?;
+    let err = received.expect_err("an order isn't an invoice");
+    assert!(matches!(err.kind(), ErrorKind::Codec(_)));
+    assert!(err.to_string().contains("amount"), "{err}");
+    // the message can still be handled
+    endpoint.ack_failed(&header).await?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode,
+    },
+    protocol::node::raft::cluster::StaticClusterProvider,
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_update_interests_delivers_held() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("update-interests-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19789".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(CODE).await?;
+    let endpoint = topic.create_endpoint([Interest::new("event/a")]).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/b")])
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::hours(1),
+                max_receiver: Some(1),
+            })
+            .build(),
+        "held",
+    );
+    let message_id = message.id();
+    let handle = topic.send_message(message).await?;
+    // nobody is interested yet, so the message is held
+    assert!(endpoint
+        .recv_timeout(Duration::from_millis(200))
+        .await
+        .is_none());
+
+    endpoint
+        .update_interests(vec![Interest::new("event/b")])
+        .await?;
+    let received = endpoint
+        .recv_timeout(Duration::from_secs(1))
+        .await
+        .expect("held message is delivered");
+    assert_eq!(received.id(), message_id);
+    assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+        .await?
+        .is_ok());
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode,
+        TopicConfig, TopicConfigPatch, TopicOverflowConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_update_topic_config() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("update-topic-config-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19807".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let mut config = TopicConfig::from(CODE);
+    config.overflow_config = Some(TopicOverflowConfig::new_reject_new(1));
+    let topic = node.create_new_topic(config).await?;
+    let new_message = || {
+        Message::new(
+            MessageHeader::builder([Subject::new("job/queued")])
+                .mode_durable(MessageDurableConfig {
+                    expire: Utc::now() + TimeDelta::hours(1),
+                    max_receiver: Some(1),
+                })
+                .build(),
+            "job",
+        )
+    };
+    // nobody is interested, the first message fills the queue
+    let _first = topic.send_message(new_message()).await?;
+    let rejected = tokio::time::timeout(
+        Duration::from_secs(1),
+        topic.send_message(new_message()).await?,
+    )
+    .await?;
+    assert!(matches!(
+        rejected.map_err(|error| error.exception),
+        Err(Some(WaitAckErrorException::Overflow))
+    ));
+
+    node.update_topic_config(
+        &CODE,
+        TopicConfigPatch::default().overflow_config(Some(TopicOverflowConfig::new_reject_new(3))),
+    )
+    .await?;
+    let config = topic.config().await.expect("topic is loaded");
+    assert_eq!(
+        config.overflow_config.map(|overflow| overflow.size()),
+        Some(3)
+    );
+    // held instead of rejected now, the first message is still there
+    let mut handles = Vec::new();
+    for _ in 0..2 {
+        handles.push(topic.send_message(new_message()).await?);
+    }
+    for handle in &mut handles {
+        assert!(tokio::time::timeout(Duration::from_millis(100), handle)
+            .await
+            .is_err());
+    }
+    let stats = node.topic_stats().await;
+    assert_eq!(stats.get(&CODE).map(|stats| stats.held_messages), Some(3));
+
+    let Err(error) = node
+        .update_topic_config(
+            &TopicCode::const_new("missing-topic"),
+            TopicConfigPatch::default(),
+        )
+        .await
+    else {
+        panic!("the topic doesn't exist");
+    };
+    assert!(matches!(
+        error.kind(),
+        asteroid_mq::error::ErrorKind::TopicNotFound
+    ));
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Message, MessageDurableConfig, MessageHeader, Node, NodeConfig, NodeId, Subject, TopicCode,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+#[tokio::test]
+async fn test_wait_ack_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("wait-ack-timeout-test");
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19771".parse()?,
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    // no consumers, the durable message waits until it expires
+    let topic = node.create_new_topic(CODE).await?;
+    let message = Message::new(
+        MessageHeader::builder([Subject::new("event/unheard")])
+            .mode_durable(MessageDurableConfig {
+                expire: Utc::now() + TimeDelta::hours(1),
+                max_receiver: None,
+            })
+            .build(),
+        "anyone?",
+    );
+    let handle = topic.send_message(message).await?;
+    let error = handle
+        .with_timeout(Duration::from_millis(100))
+        .await
+        .expect_err("nobody acks the message");
+    assert!(matches!(
+        error.exception,
+        Some(WaitAckErrorException::Timeout)
+    ));
+
+    let stats = node.topic_stats().await;
+    let stats = stats.get(&CODE).expect("topic is loaded");
+    // the message is still held, but nobody waits for it anymore
+    assert_eq!(stats.held_messages, 1);
+    assert_eq!(stats.waiting_handles, 0);
+    Ok(())
+}
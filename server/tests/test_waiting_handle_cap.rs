@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use asteroid_mq::{
+    prelude::{
+        Interest, Message, MessageAckExpectKind, MessageHeader, Node, NodeConfig, NodeId, Subject,
+        TopicCode, TopicConfig,
+    },
+    protocol::node::raft::{
+        cluster::StaticClusterProvider, state_machine::topic::wait_ack::WaitAckErrorException,
+    },
+};
+
+#[tokio::test]
+async fn test_waiting_handle_cap() -> Result<(), Box<dyn std::error::Error>> {
+    const CODE: TopicCode = TopicCode::const_new("waiting-handle-cap-test");
+    const CAP: usize = 3;
+    let node = Node::new(NodeConfig {
+        id: NodeId::new_indexed(1),
+        addr: "127.0.0.1:19828".parse()?,
+        max_waiting_handles: Some(CAP),
+        ..Default::default()
+    });
+    node.init_raft(StaticClusterProvider::singleton(node.config()))
+        .await?;
+    let topic = node.create_new_topic(TopicConfig::from(CODE)).await?;
+    // it never acks, every producer keeps waiting
+    let _endpoint = topic.create_endpoint([Interest::new("job/*")]).await?;
+    let mut handles = Vec::new();
+    for index in 0..CAP + 2 {
+        let message = Message::new(
+            MessageHeader::builder([Subject::new("job/queued")])
+                .mode_online()
+                .ack_kind(MessageAckExpectKind::Processed)
+                .build(),
+            format!("job {index}"),
+        );
+        handles.push(topic.send_message(message).await?);
+    }
+    let waiting_handles = || async { node.topic_stats().await[&CODE].waiting_handles };
+    assert_eq!(waiting_handles().await, CAP);
+
+    let mut handles = handles.into_iter();
+    // the oldest ones made room for the last two
+    for handle in handles.by_ref().take(2) {
+        let err = tokio::time::timeout(Duration::from_secs(1), handle)
+            .await?
+            .expect_err("evicted");
+        assert!(matches!(
+            err.exception,
+            Some(WaitAckErrorException::Overflow)
+        ));
+    }
+    let mut handles = handles.collect::<Vec<_>>();
+    for handle in &mut handles {
+        assert!(tokio::time::timeout(Duration::from_millis(100), handle)
+            .await
+            .is_err());
+    }
+
+    // a dropped handle stops counting right away
+    handles.pop();
+    assert_eq!(waiting_handles().await, CAP - 1);
+    Ok(())
+}